@@ -0,0 +1,173 @@
+//! System appearance: dark mode, accent color and accessibility preferences.
+//!
+//! The webview only exposes `prefers-color-scheme` and friends, which some
+//! platforms don't update live and which carry no accent color. The backend
+//! reads the settings from the platform instead (`gsettings` on Linux,
+//! `defaults` on macOS, the registry on Windows) and emits
+//! `appearance://changed` with the full state whenever anything differs.
+//! A `ThemeChanged` window event triggers an immediate re-read; the other
+//! settings have no event, so a slow poll catches changes to those.
+
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime, State, Window, WindowEvent};
+
+const CHANGED_EVENT: &str = "appearance://changed";
+/// Reading the settings starts processes on Linux and macOS, so this is
+/// only a fallback for what `ThemeChanged` doesn't cover.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Appearance {
+    pub dark: bool,
+    /// Accent color as `#rrggbb`, when the platform has one.
+    pub accent_color: Option<String>,
+    pub high_contrast: bool,
+    pub reduced_motion: bool,
+}
+
+#[derive(Default)]
+pub struct AppearanceWatcher {
+    current: Mutex<Appearance>,
+}
+
+impl AppearanceWatcher {
+    /// Re-reads the system appearance and emits it if it changed.
+    fn refresh<R: Runtime>(&self, app: &AppHandle<R>) {
+        let appearance = read();
+        let mut current = self.current.lock().unwrap();
+        if *current != appearance {
+            *current = appearance;
+            let _ = app.emit(CHANGED_EVENT, &*current);
+        }
+    }
+}
+
+/// Starts polling the system appearance in the background.
+pub fn watch(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        if let Some(watcher) = app.try_state::<AppearanceWatcher>() {
+            watcher.refresh(&app);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
+
+/// Window event hook registered with the builder.
+pub fn on_window_event<R: Runtime>(window: &Window<R>, event: &WindowEvent) {
+    if let WindowEvent::ThemeChanged(_) = event {
+        if let Some(watcher) = window.try_state::<AppearanceWatcher>() {
+            watcher.refresh(window.app_handle());
+        }
+    }
+}
+
+#[tauri::command]
+pub fn appearance_get(state: State<'_, AppearanceWatcher>) -> Appearance {
+    state.current.lock().unwrap().clone()
+}
+
+/// Runs `program` and returns its trimmed stdout, or `None` if it failed.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program).args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn read() -> Appearance {
+    // One process per schema. Lines look like
+    // `org.gnome.desktop.interface color-scheme 'prefer-dark'`, the value
+    // printed as GVariant text
+    let settings = |schema: &str| -> HashMap<String, String> {
+        let listing = output("gsettings", &["list-recursively", schema]).unwrap_or_default();
+        listing
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, ' ').skip(1);
+                Some((fields.next()?.to_string(), fields.next()?.trim_matches('\'').to_string()))
+            })
+            .collect()
+    };
+    let interface = settings("org.gnome.desktop.interface");
+    let get = |key: &str| interface.get(key).map(String::as_str);
+    let theme = get("gtk-theme").unwrap_or_default();
+
+    let dark = match get("color-scheme") {
+        Some("prefer-dark") => true,
+        Some("prefer-light") => false,
+        _ => theme.to_lowercase().ends_with("-dark"),
+    };
+    let accent_color = get("accent-color").and_then(|name| {
+        let hex = match name {
+            "blue" => "#3584e4",
+            "teal" => "#2190a4",
+            "green" => "#3a944a",
+            "yellow" => "#c88800",
+            "orange" => "#ed5b00",
+            "red" => "#e62d42",
+            "pink" => "#d56199",
+            "purple" => "#9141ac",
+            "slate" => "#6f8396",
+            _ => return None,
+        };
+        Some(hex.to_string())
+    });
+    let a11y = settings("org.gnome.desktop.a11y.interface");
+    let high_contrast =
+        a11y.get("high-contrast").is_some_and(|value| value == "true") || theme.contains("HighContrast");
+    let reduced_motion = get("enable-animations") == Some("false");
+
+    Appearance { dark, accent_color, high_contrast, reduced_motion }
+}
+
+#[cfg(target_os = "macos")]
+fn read() -> Appearance {
+    let get = |domain: &str, key: &str| output("defaults", &["read", domain, key]);
+
+    let dark = get("-g", "AppleInterfaceStyle").as_deref() == Some("Dark");
+    // Blue is the default and leaves the key unset
+    let accent_color = match get("-g", "AppleAccentColor").as_deref() {
+        Some("-1") => "#8c8c8c",
+        Some("0") => "#ff5257",
+        Some("1") => "#f7821b",
+        Some("2") => "#ffc600",
+        Some("3") => "#62ba46",
+        Some("5") => "#a550a7",
+        Some("6") => "#f74f9e",
+        _ => "#007aff",
+    };
+    let high_contrast = get("com.apple.universalaccess", "increaseContrast").as_deref() == Some("1");
+    let reduced_motion = get("com.apple.universalaccess", "reduceMotion").as_deref() == Some("1");
+
+    Appearance { dark, accent_color: Some(accent_color.to_string()), high_contrast, reduced_motion }
+}
+
+#[cfg(target_os = "windows")]
+fn read() -> Appearance {
+    let key = |path: &str| windows_registry::CURRENT_USER.open(path).ok();
+    let dword = |path: &str, name: &str| key(path)?.get_u32(name).ok();
+    let string = |path: &str, name: &str| key(path)?.get_string(name).ok();
+
+    let dark = dword(r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize", "AppsUseLightTheme") == Some(0);
+    // Stored as 0xAABBGGRR
+    let accent_color = dword(r"Software\Microsoft\Windows\DWM", "AccentColor")
+        .map(|abgr| format!("#{:02x}{:02x}{:02x}", abgr & 0xff, (abgr >> 8) & 0xff, (abgr >> 16) & 0xff));
+    // HCF_HIGHCONTRASTON, in flags stored as a string
+    let high_contrast = string(r"Control Panel\Accessibility\HighContrast", "Flags")
+        .and_then(|flags| flags.parse::<u32>().ok())
+        .is_some_and(|flags| flags & 1 != 0);
+    let reduced_motion = string(r"Control Panel\Desktop\WindowMetrics", "MinAnimate").as_deref() == Some("0");
+
+    Appearance { dark, accent_color, high_contrast, reduced_motion }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read() -> Appearance {
+    Appearance::default()
+}
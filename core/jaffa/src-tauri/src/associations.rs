@@ -0,0 +1,187 @@
+//! "Open with ecmaOS" handling for files associated with the app.
+//!
+//! The OS hands us files either as launch arguments (Windows, Linux) or
+//! through `RunEvent::Opened` (macOS). On Windows and Linux, opening a file
+//! while ecmaOS is running launches a second instance; the single-instance
+//! plugin hands its arguments to the running one and it exits. Files are
+//! queued here until the kernel pulls them: on boot it calls
+//! `open_files_pending`, later arrivals are announced with an
+//! `open-file://pending` event. The kernel then streams each file into its
+//! filesystem with `open_file_read`, acknowledges it with `open_file_done`,
+//! and launches the app mapped to the file's extension.
+//!
+//! Which extensions the OS associates with the app is fixed when it's
+//! bundled, by `bundle.fileAssociations` in `tauri.conf.json`. What can be
+//! configured at runtime is which command handles each extension; files
+//! with other extensions can still reach the app through "Open with".
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Response;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::error::{Error, Result};
+
+const PENDING_EVENT: &str = "open-file://pending";
+const CONFIG_FILE: &str = "associations.json";
+
+/// Upper bound for a single `open_file_read` chunk.
+const MAX_CHUNK: u64 = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AssociationConfig {
+    /// Lowercase extension (without the dot) to the command line launched
+    /// once the file is imported. `%f` is replaced with the file's path in
+    /// the ecmaOS filesystem, like `Exec` lines in desktop entries. The line
+    /// is split on whitespace and run directly rather than by the shell.
+    pub apps: BTreeMap<String, String>,
+    /// Directory in the ecmaOS filesystem files are imported into; the
+    /// kernel falls back to the user's home directory when unset.
+    pub destination: Option<String>,
+}
+
+impl Default for AssociationConfig {
+    fn default() -> Self {
+        let apps = [("js", "load %f"), ("json", "edit %f"), ("md", "edit %f"), ("txt", "edit %f"), ("wasm", "%f")]
+            .into_iter()
+            .map(|(ext, app)| (ext.to_string(), app.to_string()))
+            .collect();
+        Self { apps, destination: None }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingOpen {
+    pub id: String,
+    pub name: String,
+    pub size: u64,
+    /// Command line to launch once imported, if the extension is mapped.
+    pub app: Option<String>,
+    pub destination: Option<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+pub struct Associations {
+    config_path: PathBuf,
+    config: Mutex<AssociationConfig>,
+    pending: Mutex<HashMap<String, PendingOpen>>,
+}
+
+impl Associations {
+    pub fn load(app: &AppHandle) -> Result<Self> {
+        let config_path = app.path().app_config_dir()?.join(CONFIG_FILE);
+        let config = match std::fs::read(&config_path) {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => AssociationConfig::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { config_path, config: Mutex::new(config), pending: Mutex::new(HashMap::new()) })
+    }
+
+    /// Queues files the OS asked us to open and lets a running kernel know.
+    /// Anything that isn't a readable regular file is ignored, which also
+    /// skips unrelated launch arguments.
+    pub fn queue<P: AsRef<Path>>(&self, app: &AppHandle, paths: impl IntoIterator<Item = P>) {
+        let config = self.config.lock().unwrap().clone();
+        let mut pending = self.pending.lock().unwrap();
+        let mut queued = false;
+
+        for path in paths {
+            let path = path.as_ref();
+            let Ok(metadata) = std::fs::metadata(path) else { continue };
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let extension = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+            let open = PendingOpen {
+                id: uuid::Uuid::new_v4().to_string(),
+                name: name.to_string(),
+                size: metadata.len(),
+                app: extension.and_then(|ext| config.apps.get(&ext).cloned()),
+                destination: config.destination.clone(),
+                path: path.to_path_buf(),
+            };
+            pending.insert(open.id.clone(), open);
+            queued = true;
+        }
+
+        if queued {
+            let _ = app.emit(PENDING_EVENT, ());
+        }
+    }
+}
+
+/// Called in the running instance when a second one is launched, with the
+/// second one's arguments and working directory. Queues the files it was
+/// given and brings the window to the front.
+#[cfg(any(target_os = "macos", windows, target_os = "linux"))]
+pub fn on_second_instance(app: &AppHandle, argv: Vec<String>, cwd: String) {
+    if let Some(associations) = app.try_state::<Associations>() {
+        let cwd = Path::new(&cwd);
+        associations.queue(app, argv.iter().skip(1).map(|arg| cwd.join(arg)));
+    }
+    if let Some(window) = app.webview_windows().into_values().next() {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+#[tauri::command]
+pub fn associations_get(state: State<'_, Associations>) -> AssociationConfig {
+    state.config.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn associations_set(state: State<'_, Associations>, mut config: AssociationConfig) -> Result<()> {
+    config.apps = config
+        .apps
+        .into_iter()
+        .map(|(ext, app)| (ext.trim_start_matches('.').to_lowercase(), app))
+        .collect();
+
+    if let Some(dir) = state.config_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&state.config_path, serde_json::to_vec_pretty(&config)?)?;
+    *state.config.lock().unwrap() = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn open_files_pending(state: State<'_, Associations>) -> Vec<PendingOpen> {
+    state.pending.lock().unwrap().values().cloned().collect()
+}
+
+/// Reads up to `length` bytes of a pending file starting at `offset`,
+/// returned to the webview as a raw `ArrayBuffer`.
+#[tauri::command]
+pub fn open_file_read(state: State<'_, Associations>, id: String, offset: u64, length: u64) -> Result<Response> {
+    let path = state
+        .pending
+        .lock()
+        .unwrap()
+        .get(&id)
+        .map(|open| open.path.clone())
+        .ok_or_else(|| Error::NotFound(format!("no pending file with id {id}")))?;
+
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::new();
+    file.take(length.min(MAX_CHUNK)).read_to_end(&mut data)?;
+    Ok(Response::new(data))
+}
+
+#[tauri::command]
+pub fn open_file_done(state: State<'_, Associations>, id: String) {
+    state.pending.lock().unwrap().remove(&id);
+}
@@ -0,0 +1,181 @@
+//! Traffic accounting and rate limiting for connections made through the
+//! network bridge.
+//!
+//! Each `net_fetch` call is tracked as a connection attributed to the app
+//! that issued it. Byte counts cover request and response bodies; totals per
+//! app accumulate for the lifetime of the backend and are exposed to the
+//! kernel's network monitor through `network_usage`. Rate limits are token
+//! buckets with a one second burst, applied per app across all of its
+//! connections.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::State;
+
+/// App name used when the kernel doesn't attribute a request.
+pub const DEFAULT_APP: &str = "kernel";
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppUsage {
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub connections: u64,
+    /// Limit in bytes per second, if one is set.
+    pub rate_limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionUsage {
+    pub id: u64,
+    pub app: String,
+    pub url: String,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Milliseconds since the Unix epoch.
+    pub started: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkUsage {
+    pub apps: BTreeMap<String, AppUsage>,
+    pub connections: Vec<ConnectionUsage>,
+}
+
+struct Limiter {
+    rate: u64,
+    /// Available tokens (bytes) and when they were last refilled. The count
+    /// goes negative when a chunk larger than the balance is let through;
+    /// the debt is paid off by sleeping.
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl Limiter {
+    fn new(rate: u64) -> Self {
+        Self { rate, bucket: Mutex::new((rate as f64, Instant::now())) }
+    }
+
+    async fn acquire(&self, bytes: usize) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            let rate = self.rate as f64;
+            let tokens = (bucket.0 + now.duration_since(bucket.1).as_secs_f64() * rate).min(rate) - bytes as f64;
+            *bucket = (tokens, now);
+            if tokens < 0.0 {
+                Duration::from_secs_f64(-tokens / rate)
+            } else {
+                Duration::ZERO
+            }
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Bandwidth {
+    next_id: AtomicU64,
+    totals: Mutex<HashMap<String, AppUsage>>,
+    connections: Mutex<HashMap<u64, ConnectionUsage>>,
+    limits: Mutex<HashMap<String, Arc<Limiter>>>,
+}
+
+impl Bandwidth {
+    /// Registers a new connection for `app`; it's accounted until the
+    /// returned guard is dropped.
+    pub fn open(&self, app: &str, url: &str) -> Connection<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        self.connections.lock().unwrap().insert(
+            id,
+            ConnectionUsage { id, app: app.to_string(), url: url.to_string(), bytes_in: 0, bytes_out: 0, started },
+        );
+        self.totals.lock().unwrap().entry(app.to_string()).or_default().connections += 1;
+        Connection { bandwidth: self, id, app: app.to_string(), limiter: self.limits.lock().unwrap().get(app).cloned() }
+    }
+
+    fn record(&self, id: u64, app: &str, bytes_in: usize, bytes_out: usize) {
+        if let Some(connection) = self.connections.lock().unwrap().get_mut(&id) {
+            connection.bytes_in += bytes_in as u64;
+            connection.bytes_out += bytes_out as u64;
+        }
+        let mut totals = self.totals.lock().unwrap();
+        let usage = totals.entry(app.to_string()).or_default();
+        usage.bytes_in += bytes_in as u64;
+        usage.bytes_out += bytes_out as u64;
+    }
+}
+
+pub struct Connection<'a> {
+    bandwidth: &'a Bandwidth,
+    id: u64,
+    app: String,
+    limiter: Option<Arc<Limiter>>,
+}
+
+impl Connection<'_> {
+    /// Accounts `bytes` received, waiting first if the app is over its limit.
+    pub async fn received(&self, bytes: usize) {
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire(bytes).await;
+        }
+        self.bandwidth.record(self.id, &self.app, bytes, 0);
+    }
+
+    /// Accounts `bytes` about to be sent, waiting first if the app is over
+    /// its limit.
+    pub async fn sending(&self, bytes: usize) {
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire(bytes).await;
+        }
+        self.bandwidth.record(self.id, &self.app, 0, bytes);
+    }
+}
+
+impl Drop for Connection<'_> {
+    fn drop(&mut self) {
+        self.bandwidth.connections.lock().unwrap().remove(&self.id);
+    }
+}
+
+#[tauri::command]
+pub fn network_usage(state: State<'_, Bandwidth>) -> NetworkUsage {
+    let mut apps: BTreeMap<String, AppUsage> =
+        state.totals.lock().unwrap().iter().map(|(app, usage)| (app.clone(), usage.clone())).collect();
+    for (app, limiter) in state.limits.lock().unwrap().iter() {
+        apps.entry(app.clone()).or_default().rate_limit = Some(limiter.rate);
+    }
+    let mut connections: Vec<_> = state.connections.lock().unwrap().values().cloned().collect();
+    connections.sort_by_key(|connection| connection.id);
+    NetworkUsage { apps, connections }
+}
+
+/// Limits the combined upload and download rate of `app` to
+/// `bytes_per_second`, or removes its limit when `None`. Applies to
+/// connections opened after the call.
+#[tauri::command]
+pub fn network_set_rate_limit(state: State<'_, Bandwidth>, app: String, bytes_per_second: Option<u64>) {
+    let mut limits = state.limits.lock().unwrap();
+    match bytes_per_second.filter(|rate| *rate > 0) {
+        Some(rate) => {
+            limits.insert(app, Arc::new(Limiter::new(rate)));
+        }
+        None => {
+            limits.remove(&app);
+        }
+    }
+}
+
+/// Clears the accumulated totals, keeping rate limits and open connections.
+#[tauri::command]
+pub fn network_reset_usage(state: State<'_, Bandwidth>) {
+    state.totals.lock().unwrap().clear();
+}
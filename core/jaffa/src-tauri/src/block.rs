@@ -0,0 +1,645 @@
+//! Block-device storage backed by a single disk-image file on the host.
+//!
+//! These commands give the webview fixed-size blocks to allocate, read,
+//! write and free; what goes in each block is up to the caller, this module
+//! only stores them and keeps track of which are in use. Nothing in the
+//! kernel calls them yet: a ZenFS backend that keeps a filesystem in them
+//! still has to be written. Images live in `disks/` in the app data
+//! directory and are laid out as:
+//!
+//! ```text
+//! block 0                  header
+//! blocks 1..=B             allocation bitmap, one bit per data block
+//! blocks B+1..             data blocks
+//! ```
+//!
+//! The capacity is fixed when the image is created, but the file only grows
+//! as far as the highest block written, and never-written blocks are left as
+//! holes on filesystems that support sparse files. The header carries a dirty
+//! flag that is set while an image is open and cleared on a clean close; an
+//! image that wasn't closed properly has its allocated count rebuilt from
+//! the bitmap when it's next opened. Each open image holds an advisory lock
+//! on its file, so two processes can't use it at once.
+//!
+//! Images can be snapshotted and rolled back; see [`crate::snapshot`].
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Response;
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::{Error, Result};
+use crate::snapshot::{SnapshotInfo, Snapshots};
+
+pub const MAGIC: [u8; 8] = *b"ECMABLK\0";
+pub const VERSION: u32 = 1;
+
+const HEADER_SIZE: usize = 40;
+const FLAG_DIRTY: u32 = 1;
+
+pub const DEFAULT_BLOCK_SIZE: u32 = 4096;
+/// 4 GiB with the default block size.
+pub const DEFAULT_CAPACITY: u64 = 1 << 20;
+/// 1 TiB with the default block size. The bitmap is held in memory while
+/// an image is open, and at this capacity it's 32 MiB.
+pub const MAX_CAPACITY: u64 = 1 << 28;
+
+/// Upper bound for the blocks moved by a single read or write command.
+const MAX_TRANSFER: u64 = 8 * 1024 * 1024;
+
+const DISKS_DIR: &str = "disks";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub block_size: u32,
+    /// Number of data blocks the image can hold.
+    pub capacity: u64,
+    /// Number of data blocks marked in use in the bitmap.
+    pub allocated: u64,
+    pub flags: u32,
+    pub bitmap_blocks: u32,
+}
+
+impl Header {
+    fn new(block_size: u32, capacity: u64) -> Result<Self> {
+        validate_geometry(block_size, capacity)?;
+        let bitmap_blocks = u32::try_from(capacity.div_ceil(8).div_ceil(block_size as u64))
+            .map_err(|_| Error::Image(format!("capacity {capacity} needs too large a bitmap")))?;
+        Ok(Self { block_size, capacity, allocated: 0, flags: 0, bitmap_blocks })
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_SIZE || data[..8] != MAGIC {
+            return Err(Error::Image("not an ecmaOS block image".into()));
+        }
+        let u32_at = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        if u32_at(8) != VERSION {
+            return Err(Error::Image(format!("unsupported image version {}", u32_at(8))));
+        }
+        let header = Self {
+            block_size: u32_at(12),
+            capacity: u64_at(16),
+            allocated: u64_at(24),
+            flags: u32_at(32),
+            bitmap_blocks: u32_at(36),
+        };
+        if header.bitmap_blocks != Header::new(header.block_size, header.capacity)?.bitmap_blocks {
+            return Err(Error::Image("bitmap size doesn't match capacity".into()));
+        }
+        Ok(header)
+    }
+
+    pub fn to_bytes(self) -> Vec<u8> {
+        let mut data = vec![0; self.block_size as usize];
+        data[..8].copy_from_slice(&MAGIC);
+        data[8..12].copy_from_slice(&VERSION.to_le_bytes());
+        data[12..16].copy_from_slice(&self.block_size.to_le_bytes());
+        data[16..24].copy_from_slice(&self.capacity.to_le_bytes());
+        data[24..32].copy_from_slice(&self.allocated.to_le_bytes());
+        data[32..36].copy_from_slice(&self.flags.to_le_bytes());
+        data[36..40].copy_from_slice(&self.bitmap_blocks.to_le_bytes());
+        data
+    }
+
+    pub fn dirty(&self) -> bool {
+        self.flags & FLAG_DIRTY != 0
+    }
+
+    /// Byte offset of data block `index` in the image file.
+    pub fn data_offset(&self, index: u64) -> u64 {
+        (1 + self.bitmap_blocks as u64 + index) * self.block_size as u64
+    }
+}
+
+/// The error for a header whose allocated count can't be right.
+fn miscounted() -> Error {
+    Error::Image("header's allocated block count doesn't match the bitmap".into())
+}
+
+/// Locks the image file at `path` for this process alone.
+fn lock(file: &File, path: &Path) -> Result<()> {
+    match file.try_lock() {
+        Ok(()) => Ok(()),
+        Err(TryLockError::WouldBlock) => Err(Error::Image(format!("{} is already in use", path.display()))),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+fn validate_geometry(block_size: u32, capacity: u64) -> Result<()> {
+    if !block_size.is_power_of_two() || !(512..=65536).contains(&block_size) {
+        return Err(Error::Image(format!("invalid block size {block_size}")));
+    }
+    if capacity == 0 {
+        return Err(Error::Image("image capacity must be at least one block".into()));
+    }
+    if capacity > MAX_CAPACITY {
+        return Err(Error::Image(format!("image capacity {capacity} exceeds the maximum of {MAX_CAPACITY} blocks")));
+    }
+    Ok(())
+}
+
+pub struct BlockImage {
+    file: File,
+    header: Header,
+    bitmap: Vec<u8>,
+    /// Where the next allocation starts looking for free blocks.
+    cursor: u64,
+    snapshots: Snapshots,
+}
+
+impl BlockImage {
+    pub fn create(path: &Path, block_size: u32, capacity: u64) -> Result<Self> {
+        let header = Header::new(block_size, capacity)?;
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(path)?;
+        lock(&file, path)?;
+        file.set_len(header.data_offset(0))?;
+        let bitmap = vec![0; header.bitmap_blocks as usize * block_size as usize];
+        let snapshots = Snapshots::load(path, block_size)?;
+        let mut image = Self { file, header, bitmap, cursor: 0, snapshots };
+        image.flush()?;
+        Ok(image)
+    }
+
+    /// Opens an existing image. The header and bitmap are taken as stored,
+    /// without checking them against each other, unless the image wasn't
+    /// closed cleanly.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        lock(&file, path)?;
+        let mut data = [0; HEADER_SIZE];
+        file.read_exact(&mut data)?;
+        let header = Header::parse(&data)?;
+
+        let mut bitmap = vec![0; header.bitmap_blocks as usize * header.block_size as usize];
+        file.seek(SeekFrom::Start(header.block_size as u64))?;
+        file.read_exact(&mut bitmap)?;
+        let snapshots = Snapshots::load(path, header.block_size)?;
+        let mut image = Self { file, header, bitmap, cursor: 0, snapshots };
+        // A crash between writing the header and the bitmap leaves them out
+        // of step, and the bitmap is what says which blocks are in use
+        if image.header.dirty() {
+            image.header.allocated = (0..image.header.capacity).filter(|index| image.bit(*index)).count() as u64;
+        }
+        Ok(image)
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn is_allocated(&self, index: u64) -> bool {
+        index < self.header.capacity && self.bit(index)
+    }
+
+    fn set_allocated(&mut self, index: u64, allocated: bool) {
+        let (byte, bit) = ((index / 8) as usize, 1 << (index % 8));
+        if allocated {
+            self.bitmap[byte] |= bit;
+        } else {
+            self.bitmap[byte] &= !bit;
+        }
+    }
+
+    /// Marks the image as in use, so an unclean shutdown leaves it dirty.
+    pub fn mark_dirty(&mut self, dirty: bool) -> Result<()> {
+        if dirty {
+            self.header.flags |= FLAG_DIRTY;
+        } else {
+            self.header.flags &= !FLAG_DIRTY;
+        }
+        self.write_header()?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    fn check_range(&self, index: u64, count: u64) -> Result<()> {
+        if index.checked_add(count).is_none_or(|end| end > self.header.capacity) {
+            return Err(Error::Image(format!(
+                "blocks {index}..{} out of range (capacity {})",
+                index.saturating_add(count),
+                self.header.capacity
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reads `count` consecutive data blocks. Blocks past the end of the
+    /// file read as zeros.
+    pub fn read(&mut self, index: u64, count: u64) -> Result<Vec<u8>> {
+        self.check_range(index, count)?;
+        let mut data = vec![0; (count * self.header.block_size as u64) as usize];
+        self.file.seek(SeekFrom::Start(self.header.data_offset(index)))?;
+        let mut filled = 0;
+        while filled < data.len() {
+            match self.file.read(&mut data[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        Ok(data)
+    }
+
+    /// Writes whole data blocks starting at `index`. Every block written must
+    /// have been allocated.
+    pub fn write(&mut self, index: u64, data: &[u8]) -> Result<()> {
+        let block_size = self.header.block_size as u64;
+        if !(data.len() as u64).is_multiple_of(block_size) {
+            return Err(Error::Image(format!("write of {} bytes isn't a whole number of blocks", data.len())));
+        }
+        let count = data.len() as u64 / block_size;
+        self.check_range(index, count)?;
+        if let Some(free) = (index..index + count).find(|block| !self.is_allocated(*block)) {
+            return Err(Error::Image(format!("block {free} isn't allocated")));
+        }
+        for block in index..index + count {
+            let offset = self.header.data_offset(block);
+            if self.snapshots.needs(block, offset) {
+                let previous = self.read(block, 1)?;
+                self.snapshots.preserve(block, offset, &previous)?;
+            }
+        }
+        self.file.seek(SeekFrom::Start(self.header.data_offset(index)))?;
+        self.file.write_all(data)?;
+        Ok(())
+    }
+
+    pub fn snapshots(&self) -> &Snapshots {
+        &self.snapshots
+    }
+
+    pub fn snapshots_mut(&mut self) -> &mut Snapshots {
+        &mut self.snapshots
+    }
+
+    /// Takes a snapshot of the image as it is now.
+    pub fn snapshot(&mut self, name: String, description: Option<String>) -> Result<SnapshotInfo> {
+        self.flush()?;
+        let mut state = self.header.to_bytes();
+        state.extend_from_slice(&self.bitmap);
+        let file_length = self.file.metadata()?.len();
+        self.snapshots.create(name, description, &state, file_length)
+    }
+
+    /// Returns what's needed to read the image as it was when snapshot `id`
+    /// was taken.
+    pub fn snapshot_view(&self, id: &str) -> Result<SnapshotView> {
+        let (state, file_length) = self.snapshots.state(id)?;
+        Ok(SnapshotView { id: id.to_string(), state, file_length })
+    }
+
+    /// Reads up to `length` bytes of the raw image file starting at `offset`,
+    /// as they were when `view`'s snapshot was taken: the header and bitmap
+    /// come from the snapshot, and so does every block written since.
+    pub fn read_snapshot(&mut self, view: &SnapshotView, offset: u64, length: u64) -> Result<Vec<u8>> {
+        let end = offset.saturating_add(length).min(view.file_length);
+        if offset >= end {
+            return Ok(Vec::new());
+        }
+        let mut data = vec![0; (end - offset) as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut filled = 0;
+        while filled < data.len() {
+            match self.file.read(&mut data[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+
+        let mut overlay = |start: u64, source: &[u8]| {
+            let from = start.max(offset);
+            let to = (start + source.len() as u64).min(end);
+            if from < to {
+                data[(from - offset) as usize..(to - offset) as usize]
+                    .copy_from_slice(&source[(from - start) as usize..(to - start) as usize]);
+            }
+        };
+        overlay(0, &view.state);
+        let block_size = self.header.block_size as u64;
+        let first = self.header.data_offset(0);
+        if end > first {
+            for index in (offset.max(first) - first) / block_size..(end - first).div_ceil(block_size) {
+                if let Some(block) = self.snapshots.preserved_block(&view.id, index)? {
+                    overlay(self.header.data_offset(index), &block);
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    /// Restores the image to snapshot `id`.
+    pub fn rollback(&mut self, id: &str) -> Result<()> {
+        let (state, file_length) = self.snapshots.state(id)?;
+        let header = Header::parse(&state)?;
+        if header.block_size != self.header.block_size || header.capacity != self.header.capacity {
+            return Err(Error::Image(format!("snapshot {id} doesn't match the image geometry")));
+        }
+
+        self.snapshots.for_each_block(id, |index, data| {
+            self.file.seek(SeekFrom::Start(self.header.data_offset(index)))?;
+            self.file.write_all(data)?;
+            Ok(())
+        })?;
+        self.file.set_len(file_length)?;
+        self.header = header;
+        self.bitmap = state[self.header.block_size as usize..].to_vec();
+        self.cursor = 0;
+        self.flush()?;
+        // The image stays open, so keep it marked as in use
+        self.mark_dirty(true)?;
+        self.snapshots.rolled_back(id)
+    }
+
+    /// Allocates `count` blocks, not necessarily consecutive.
+    pub fn allocate(&mut self, count: u64) -> Result<Vec<u64>> {
+        let available = self.header.capacity.checked_sub(self.header.allocated).ok_or_else(miscounted)?;
+        if count > available {
+            return Err(Error::Image(format!("not enough free blocks: {count} requested, {available} available")));
+        }
+        let mut blocks = Vec::with_capacity(count as usize);
+        let mut index = self.cursor;
+        while (blocks.len() as u64) < count {
+            if index >= self.header.capacity {
+                index = 0;
+            }
+            if !self.is_allocated(index) {
+                self.set_allocated(index, true);
+                blocks.push(index);
+            }
+            index += 1;
+        }
+        self.cursor = index;
+        self.header.allocated += count;
+        Ok(blocks)
+    }
+
+    /// Frees `blocks`. A block listed more than once is only freed once.
+    pub fn free(&mut self, blocks: &[u64]) -> Result<()> {
+        let mut blocks = blocks.to_vec();
+        blocks.sort_unstable();
+        blocks.dedup();
+        if let Some(block) = blocks.iter().find(|block| !self.is_allocated(**block)) {
+            return Err(Error::Image(format!("block {block} isn't allocated")));
+        }
+        let allocated = self.header.allocated.checked_sub(blocks.len() as u64).ok_or_else(miscounted)?;
+        for block in blocks {
+            self.set_allocated(block, false);
+        }
+        self.header.allocated = allocated;
+        Ok(())
+    }
+
+    fn bit(&self, index: u64) -> bool {
+        self.bitmap[(index / 8) as usize] & (1 << (index % 8)) != 0
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&self.header.to_bytes())?;
+        Ok(())
+    }
+
+    /// Writes the header and bitmap and syncs the image to disk.
+    pub fn flush(&mut self) -> Result<()> {
+        self.write_header()?;
+        self.file.write_all(&self.bitmap)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// An image as it was when a snapshot was taken, to read with
+/// [`BlockImage::read_snapshot`] while the image itself stays in use.
+pub struct SnapshotView {
+    id: String,
+    /// The header and bitmap at the time.
+    state: Vec<u8>,
+    file_length: u64,
+}
+
+impl SnapshotView {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Length of the image file at the time.
+    pub fn file_length(&self) -> u64 {
+        self.file_length
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CreateOptions {
+    pub block_size: Option<u32>,
+    pub capacity: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockInfo {
+    pub handle: u32,
+    pub name: String,
+    pub block_size: u32,
+    pub capacity: u64,
+    pub allocated: u64,
+    /// Set when the image wasn't closed cleanly the last time it was used.
+    pub was_dirty: bool,
+}
+
+pub(crate) struct OpenImage {
+    name: String,
+    was_dirty: bool,
+    pub(crate) image: Mutex<BlockImage>,
+}
+
+impl OpenImage {
+    fn info(&self, handle: u32) -> BlockInfo {
+        let image = self.image.lock().unwrap();
+        let header = image.header();
+        BlockInfo {
+            handle,
+            name: self.name.clone(),
+            block_size: header.block_size,
+            capacity: header.capacity,
+            allocated: header.allocated,
+            was_dirty: self.was_dirty,
+        }
+    }
+}
+
+pub struct Disks {
+    dir: PathBuf,
+    next_handle: AtomicU32,
+    open: Mutex<HashMap<u32, Arc<OpenImage>>>,
+    /// Images held open by the host itself, such as for a sync run, that
+    /// the kernel doesn't have open. Always locked after `open`.
+    held: Mutex<HashMap<String, Weak<OpenImage>>>,
+}
+
+impl Disks {
+    pub fn load(app: &AppHandle) -> Result<Self> {
+        let dir = app.path().app_data_dir()?.join(DISKS_DIR);
+        Ok(Self {
+            dir,
+            next_handle: AtomicU32::new(1),
+            open: Mutex::new(HashMap::new()),
+            held: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub(crate) fn get(&self, handle: u32) -> Result<Arc<OpenImage>> {
+        self.open
+            .lock()
+            .unwrap()
+            .get(&handle)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("no open disk image with handle {handle}")))
+    }
+
+    /// Path of the image called `name`.
+    pub fn path(&self, name: &str) -> Result<PathBuf> {
+        image_path(&self.dir, name)
+    }
+
+    /// Returns the image called `name`, opening it if it isn't open yet.
+    /// There's only ever one [`BlockImage`] per image, so one held here that
+    /// the kernel opens meanwhile is shared with it.
+    pub(crate) fn hold(&self, name: &str) -> Result<Arc<OpenImage>> {
+        let open = self.open.lock().unwrap();
+        if let Some(image) = open.values().find(|open| open.name == name) {
+            return Ok(image.clone());
+        }
+        let mut held = self.held.lock().unwrap();
+        if let Some(image) = held.get(name).and_then(Weak::upgrade) {
+            return Ok(image);
+        }
+        let path = self.path(name)?;
+        let image = match BlockImage::open(&path) {
+            Ok(image) => image,
+            Err(Error::Io(e)) if e.kind() == ErrorKind::NotFound => {
+                return Err(Error::NotFound(format!("no disk image {}", path.display())))
+            }
+            Err(e) => return Err(e),
+        };
+        let image =
+            Arc::new(OpenImage { name: name.to_string(), was_dirty: image.header().dirty(), image: Mutex::new(image) });
+        held.insert(name.to_string(), Arc::downgrade(&image));
+        Ok(image)
+    }
+
+    /// Flushes and cleanly closes every open image.
+    pub fn close_all(&self) {
+        for (_, open) in self.open.lock().unwrap().drain() {
+            let mut image = open.image.lock().unwrap();
+            let _ = image.flush().and_then(|_| image.mark_dirty(false));
+        }
+    }
+}
+
+fn image_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.');
+    if !valid {
+        return Err(Error::Config(format!("invalid disk image name {name:?}")));
+    }
+    Ok(dir.join(format!("{name}.img")))
+}
+
+/// Opens the image called `name`, creating it with `options` if it doesn't
+/// exist yet.
+#[tauri::command]
+pub fn block_open(state: State<'_, Disks>, name: String, options: Option<CreateOptions>) -> Result<BlockInfo> {
+    let mut images = state.open.lock().unwrap();
+    if images.values().any(|open| open.name == name) {
+        return Err(Error::Config(format!("disk image {name} is already open")));
+    }
+    let open = match state.held.lock().unwrap().remove(&name).and_then(|held| held.upgrade()) {
+        Some(open) => open,
+        None => {
+            let path = image_path(&state.dir, &name)?;
+            let image = if path.exists() {
+                BlockImage::open(&path)?
+            } else {
+                let options = options.unwrap_or_default();
+                std::fs::create_dir_all(&state.dir)?;
+                BlockImage::create(
+                    &path,
+                    options.block_size.unwrap_or(DEFAULT_BLOCK_SIZE),
+                    options.capacity.unwrap_or(DEFAULT_CAPACITY),
+                )?
+            };
+            let was_dirty = image.header().dirty();
+            Arc::new(OpenImage { name, was_dirty, image: Mutex::new(image) })
+        }
+    };
+    open.image.lock().unwrap().mark_dirty(true)?;
+
+    let handle = state.next_handle.fetch_add(1, Ordering::Relaxed);
+    let info = open.info(handle);
+    images.insert(handle, open);
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn block_info(state: State<'_, Disks>, handle: u32) -> Result<BlockInfo> {
+    Ok(state.get(handle)?.info(handle))
+}
+
+/// Reads `count` blocks starting at `index`, returned as a raw `ArrayBuffer`.
+#[tauri::command]
+pub fn block_read(state: State<'_, Disks>, handle: u32, index: u64, count: u64) -> Result<Response> {
+    let open = state.get(handle)?;
+    let mut image = open.image.lock().unwrap();
+    if count.checked_mul(image.header().block_size as u64).is_none_or(|bytes| bytes > MAX_TRANSFER) {
+        return Err(Error::Config(format!("read of {count} blocks exceeds the transfer limit")));
+    }
+    Ok(Response::new(image.read(index, count)?))
+}
+
+/// Writes base64 encoded `data`, a whole number of blocks, starting at `index`.
+#[tauri::command]
+pub fn block_write(state: State<'_, Disks>, handle: u32, index: u64, data: String) -> Result<()> {
+    let data = BASE64.decode(data)?;
+    if data.len() as u64 > MAX_TRANSFER {
+        return Err(Error::Config(format!("write of {} bytes exceeds the transfer limit", data.len())));
+    }
+    state.get(handle)?.image.lock().unwrap().write(index, &data)
+}
+
+#[tauri::command]
+pub fn block_allocate(state: State<'_, Disks>, handle: u32, count: u64) -> Result<Vec<u64>> {
+    state.get(handle)?.image.lock().unwrap().allocate(count)
+}
+
+#[tauri::command]
+pub fn block_free(state: State<'_, Disks>, handle: u32, blocks: Vec<u64>) -> Result<()> {
+    state.get(handle)?.image.lock().unwrap().free(&blocks)
+}
+
+/// Persists the allocation bitmap and syncs written blocks to disk.
+#[tauri::command]
+pub fn block_flush(state: State<'_, Disks>, handle: u32) -> Result<()> {
+    state.get(handle)?.image.lock().unwrap().flush()
+}
+
+#[tauri::command]
+pub fn block_close(state: State<'_, Disks>, handle: u32) -> Result<()> {
+    let mut images = state.open.lock().unwrap();
+    let open =
+        images.remove(&handle).ok_or_else(|| Error::NotFound(format!("no open disk image with handle {handle}")))?;
+    // Whatever else still holds it, such as a sync run, keeps it open
+    state.held.lock().unwrap().insert(open.name.clone(), Arc::downgrade(&open));
+    let mut image = open.image.lock().unwrap();
+    image.flush()?;
+    image.mark_dirty(false)
+}
@@ -0,0 +1,495 @@
+//! mDNS/DNS-SD discovery of other ecmaOS desktop instances on the LAN.
+//!
+//! Each running instance advertises itself as `_ecmaos._tcp.local` with its
+//! signaling peer id in a TXT record, and browses for the same service type.
+//! Peers are reported to the kernel through `discovery://peer-found` and
+//! `discovery://peer-lost` events; the peer id can then be used to open a
+//! WebRTC session through the signaling relay.
+//!
+//! Only the subset of DNS needed for this is implemented: PTR queries and
+//! PTR/SRV/TXT answers. Peers are addressed by the source address of their
+//! announcements rather than by resolving the SRV target.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, Socket, Type};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::error::{Error, Result};
+use crate::signaling::Signaling;
+
+const SERVICE: &str = "_ecmaos._tcp.local";
+const MDNS_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const MDNS_PORT: u16 = 5353;
+
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+const CACHE_FLUSH: u16 = 0x8000;
+
+/// Longest label a DNS name can hold, in bytes.
+const MAX_LABEL: usize = 63;
+
+const RECORD_TTL: u32 = 120;
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(60);
+
+const FOUND_EVENT: &str = "discovery://peer-found";
+const LOST_EVENT: &str = "discovery://peer-lost";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiscoveryOptions {
+    /// Human readable name shown to other users; defaults to the hostname.
+    pub name: Option<String>,
+    /// Port advertised in the SRV record for services reachable directly.
+    pub port: u16,
+    /// Extra key/value pairs published in the TXT record.
+    pub txt: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Peer {
+    pub id: String,
+    pub name: String,
+    pub address: IpAddr,
+    pub port: u16,
+    pub txt: HashMap<String, String>,
+    #[serde(skip)]
+    expires: Instant,
+}
+
+struct Advertisement {
+    instance: String,
+    host: String,
+    port: u16,
+    txt: Vec<(String, String)>,
+}
+
+/// The packets an advertisement sends, built before the thread starts so
+/// a name DNS can't carry is reported to the caller.
+struct Packets {
+    announce: Vec<u8>,
+    goodbye: Vec<u8>,
+    query: Vec<u8>,
+}
+
+struct Running {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct Discovery {
+    peers: Arc<Mutex<HashMap<String, Peer>>>,
+    running: Mutex<Option<Running>>,
+}
+
+impl Discovery {
+    /// Stops advertising. The thread can be blocked in a read for up to a
+    /// second before it notices, so it's waited for off the async runtime.
+    async fn stop(&self) {
+        let running = self.running.lock().unwrap().take();
+        if let Some(running) = running {
+            running.stop.store(true, Ordering::Relaxed);
+            let _ = tauri::async_runtime::spawn_blocking(move || running.thread.join()).await;
+        }
+        self.peers.lock().unwrap().clear();
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .unwrap_or_else(|_| "ecmaos".into())
+}
+
+fn open_socket() -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, MDNS_PORT).into())?;
+    socket.join_multicast_v4(&MDNS_ADDR, &Ipv4Addr::UNSPECIFIED)?;
+    // Loopback lets several instances on one machine find each other.
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_multicast_ttl_v4(255)?;
+    socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+    Ok(socket.into())
+}
+
+/// The longest prefix of `text` that fits in `max` bytes without splitting
+/// a character.
+fn truncate(text: &str, max: usize) -> &str {
+    let end = (0..=max.min(text.len())).rev().find(|&end| text.is_char_boundary(end)).unwrap_or(0);
+    &text[..end]
+}
+
+/// Writes `name` as a sequence of labels. A label over 63 bytes is an
+/// error rather than cut short, since a shortened name is no longer the one
+/// peers and this instance compare against.
+fn write_name(buf: &mut Vec<u8>, name: &str) -> Result<()> {
+    for label in name.trim_end_matches('.').split('.') {
+        if label.len() > MAX_LABEL {
+            return Err(Error::Config(format!("DNS label {label:?} is longer than {MAX_LABEL} bytes")));
+        }
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label.as_bytes());
+    }
+    buf.push(0);
+    Ok(())
+}
+
+/// Writes a resource record whose data is produced by `data`.
+fn write_record(
+    buf: &mut Vec<u8>,
+    name: &str,
+    rtype: u16,
+    class: u16,
+    ttl: u32,
+    data: impl FnOnce(&mut Vec<u8>) -> Result<()>,
+) -> Result<()> {
+    write_name(buf, name)?;
+    buf.extend_from_slice(&rtype.to_be_bytes());
+    buf.extend_from_slice(&class.to_be_bytes());
+    buf.extend_from_slice(&ttl.to_be_bytes());
+    let len_at = buf.len();
+    buf.extend_from_slice(&[0, 0]);
+    data(buf)?;
+    let len = (buf.len() - len_at - 2) as u16;
+    buf[len_at..len_at + 2].copy_from_slice(&len.to_be_bytes());
+    Ok(())
+}
+
+fn query_packet() -> Result<Vec<u8>> {
+    let mut buf = vec![0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+    write_name(&mut buf, SERVICE)?;
+    buf.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    buf.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(buf)
+}
+
+/// Builds an authoritative response announcing `ad`; a ttl of zero is the
+/// goodbye packet that tells peers to forget us immediately.
+fn announce_packet(ad: &Advertisement, ttl: u32) -> Result<Vec<u8>> {
+    let mut buf = vec![0, 0, 0x84, 0, 0, 0, 0, 3, 0, 0, 0, 0];
+    write_record(&mut buf, SERVICE, TYPE_PTR, CLASS_IN, ttl, |b| write_name(b, &ad.instance))?;
+    write_record(&mut buf, &ad.instance, TYPE_SRV, CLASS_IN | CACHE_FLUSH, ttl, |b| {
+        b.extend_from_slice(&[0, 0, 0, 0]);
+        b.extend_from_slice(&ad.port.to_be_bytes());
+        write_name(b, &ad.host)
+    })?;
+    write_record(&mut buf, &ad.instance, TYPE_TXT, CLASS_IN | CACHE_FLUSH, ttl, |b| {
+        for (key, value) in &ad.txt {
+            let entry = format!("{key}={value}");
+            let entry = &entry.as_bytes()[..entry.len().min(255)];
+            b.push(entry.len() as u8);
+            b.extend_from_slice(entry);
+        }
+        Ok(())
+    })?;
+    Ok(buf)
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Option<u8> {
+        let value = *self.buf.get(self.pos)?;
+        self.pos += 1;
+        Some(value)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_be_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes([self.u8()?, self.u8()?, self.u8()?, self.u8()?]))
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.buf.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    /// Reads a possibly compressed domain name.
+    fn name(&mut self) -> Option<String> {
+        let mut labels = Vec::new();
+        let mut pos = self.pos;
+        let mut resume = None;
+        // Bounds pointer chains so a malicious packet can't loop forever.
+        for _ in 0..128 {
+            let len = *self.buf.get(pos)? as usize;
+            match len {
+                0 => {
+                    self.pos = resume.unwrap_or(pos + 1);
+                    return Some(labels.join("."));
+                }
+                len if len & 0xc0 == 0xc0 => {
+                    let target = ((len & 0x3f) << 8) | *self.buf.get(pos + 1)? as usize;
+                    resume.get_or_insert(pos + 2);
+                    pos = target;
+                }
+                len => {
+                    let label = self.buf.get(pos + 1..pos + 1 + len)?;
+                    labels.push(String::from_utf8_lossy(label).into_owned());
+                    pos += 1 + len;
+                }
+            }
+        }
+        None
+    }
+}
+
+enum Record {
+    Ptr { ttl: u32, target: String },
+    Srv { name: String, port: u16 },
+    Txt { name: String, entries: HashMap<String, String> },
+}
+
+struct Packet {
+    is_response: bool,
+    questions: Vec<(String, u16)>,
+    records: Vec<Record>,
+}
+
+fn parse_packet(buf: &[u8]) -> Option<Packet> {
+    let mut reader = Reader { buf, pos: 0 };
+    reader.u16()?;
+    let flags = reader.u16()?;
+    let qdcount = reader.u16()?;
+    let rrcount = reader.u16()? as usize + reader.u16()? as usize + reader.u16()? as usize;
+
+    let mut questions = Vec::new();
+    for _ in 0..qdcount {
+        let name = reader.name()?;
+        let qtype = reader.u16()?;
+        reader.u16()?;
+        questions.push((name, qtype));
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..rrcount {
+        let name = reader.name()?;
+        let rtype = reader.u16()?;
+        reader.u16()?;
+        let ttl = reader.u32()?;
+        let len = reader.u16()? as usize;
+        let end = reader.pos + len;
+        match rtype {
+            TYPE_PTR if name.eq_ignore_ascii_case(SERVICE) => {
+                records.push(Record::Ptr { ttl, target: reader.name()? });
+            }
+            TYPE_SRV => {
+                reader.bytes(4)?;
+                records.push(Record::Srv { name, port: reader.u16()? });
+            }
+            TYPE_TXT => {
+                let mut entries = HashMap::new();
+                let mut data = Reader { buf: reader.bytes(len)?, pos: 0 };
+                while let Some(entry_len) = data.u8() {
+                    let entry = String::from_utf8_lossy(data.bytes(entry_len as usize)?);
+                    if let Some((key, value)) = entry.split_once('=') {
+                        entries.insert(key.to_string(), value.to_string());
+                    }
+                }
+                records.push(Record::Txt { name, entries });
+            }
+            _ => {}
+        }
+        reader.pos = end;
+    }
+
+    Some(Packet { is_response: flags & 0x8000 != 0, questions, records })
+}
+
+/// Applies the records of an announcement to the peer table, emitting
+/// events for peers that appeared or said goodbye.
+fn handle_response(app: &AppHandle, peers: &Mutex<HashMap<String, Peer>>, own: &str, packet: Packet, from: SocketAddr) {
+    let mut found: HashMap<String, Peer> = HashMap::new();
+    let mut gone = Vec::new();
+
+    for record in &packet.records {
+        if let Record::Ptr { ttl, target } = record {
+            if target == own {
+                continue;
+            }
+            if *ttl == 0 {
+                gone.push(target.clone());
+                continue;
+            }
+            found.insert(
+                target.clone(),
+                Peer {
+                    id: String::new(),
+                    name: target.strip_suffix(SERVICE).unwrap_or(target).trim_end_matches('.').to_string(),
+                    address: from.ip(),
+                    port: 0,
+                    txt: HashMap::new(),
+                    expires: Instant::now() + Duration::from_secs(*ttl as u64),
+                },
+            );
+        }
+    }
+    for record in packet.records {
+        match record {
+            Record::Srv { name, port } => {
+                if let Some(peer) = found.get_mut(&name) {
+                    peer.port = port;
+                }
+            }
+            Record::Txt { name, mut entries } => {
+                if let Some(peer) = found.get_mut(&name) {
+                    peer.id = entries.remove("id").unwrap_or_default();
+                    if let Some(display) = entries.remove("name") {
+                        peer.name = display;
+                    }
+                    peer.txt = entries;
+                }
+            }
+            Record::Ptr { .. } => {}
+        }
+    }
+
+    let mut table = peers.lock().unwrap();
+    for instance in gone {
+        if let Some(peer) = table.remove(&instance) {
+            let _ = app.emit(LOST_EVENT, peer);
+        }
+    }
+    for (instance, peer) in found {
+        // Announcements without our TXT record aren't ecmaOS peers we can reach.
+        if peer.id.is_empty() {
+            continue;
+        }
+        if table.insert(instance, peer.clone()).is_none() {
+            let _ = app.emit(FOUND_EVENT, peer);
+        }
+    }
+}
+
+fn run(
+    app: AppHandle,
+    socket: UdpSocket,
+    instance: String,
+    packets: Packets,
+    peers: Arc<Mutex<HashMap<String, Peer>>>,
+    stop: Arc<AtomicBool>,
+) {
+    let group = SocketAddr::from((MDNS_ADDR, MDNS_PORT));
+    let Packets { announce, goodbye, query } = packets;
+    let mut next_announce = Instant::now();
+    let mut buf = [0u8; 9000];
+
+    while !stop.load(Ordering::Relaxed) {
+        if Instant::now() >= next_announce {
+            let _ = socket.send_to(&announce, group);
+            let _ = socket.send_to(&query, group);
+            next_announce = Instant::now() + ANNOUNCE_INTERVAL;
+        }
+
+        match socket.recv_from(&mut buf) {
+            Ok((len, from)) => {
+                let Some(packet) = parse_packet(&buf[..len]) else { continue };
+                if packet.is_response {
+                    handle_response(&app, &peers, &instance, packet, from);
+                } else if packet
+                    .questions
+                    .iter()
+                    .any(|(name, qtype)| *qtype == TYPE_PTR && name.eq_ignore_ascii_case(SERVICE))
+                {
+                    let _ = socket.send_to(&announce, group);
+                }
+            }
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(_) => std::thread::sleep(Duration::from_secs(1)),
+        }
+
+        let now = Instant::now();
+        peers.lock().unwrap().retain(|_, peer| {
+            let alive = peer.expires > now;
+            if !alive {
+                let _ = app.emit(LOST_EVENT, peer.clone());
+            }
+            alive
+        });
+    }
+
+    let _ = socket.send_to(&goodbye, group);
+}
+
+/// Starts advertising this instance and browsing for peers, replacing any
+/// previous advertisement.
+#[tauri::command]
+pub async fn discovery_start(
+    app: AppHandle,
+    state: State<'_, Discovery>,
+    signaling: State<'_, Signaling>,
+    options: Option<DiscoveryOptions>,
+) -> Result<()> {
+    state.stop().await;
+
+    let options = options.unwrap_or_default();
+    let peer_id = signaling.peer_id();
+    let host = hostname();
+    let name = options.name.unwrap_or_else(|| host.clone());
+
+    let mut txt: Vec<(String, String)> = vec![
+        ("id".into(), peer_id.clone()),
+        ("name".into(), name.clone()),
+        ("version".into(), env!("CARGO_PKG_VERSION").into()),
+    ];
+    txt.extend(options.txt);
+
+    // The id suffix keeps two instances on the same host distinct. The name
+    // is shortened to leave room for it in the label.
+    let suffix = format!(" [{}]", peer_id.chars().take(8).collect::<String>());
+    let label = name.replace('.', " ");
+    let label = truncate(&label, MAX_LABEL.saturating_sub(suffix.len()));
+    let ad = Advertisement {
+        instance: format!("{label}{suffix}.{SERVICE}"),
+        host: format!("{}.local", truncate(&host.replace('.', "-"), MAX_LABEL)),
+        port: options.port,
+        txt,
+    };
+    let packets = Packets {
+        announce: announce_packet(&ad, RECORD_TTL)?,
+        goodbye: announce_packet(&ad, 0)?,
+        query: query_packet()?,
+    };
+
+    let socket = open_socket()?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let peers = state.peers.clone();
+    let thread = {
+        let stop = stop.clone();
+        std::thread::spawn(move || run(app, socket, ad.instance, packets, peers, stop))
+    };
+    *state.running.lock().unwrap() = Some(Running { stop, thread });
+    Ok(())
+}
+
+/// Stops advertising and sends a goodbye so peers drop us right away.
+#[tauri::command]
+pub async fn discovery_stop(state: State<'_, Discovery>) -> Result<()> {
+    state.stop().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn discovery_peers(state: State<'_, Discovery>) -> Vec<Peer> {
+    state.peers.lock().unwrap().values().cloned().collect()
+}
@@ -0,0 +1,141 @@
+//! Host font enumeration, so the kernel's terminal and editor apps can offer
+//! the user's native fonts in desktop mode.
+//!
+//! Installed fonts are scanned on first use (or when a refresh is requested)
+//! and listed per file, since a collection holds several faces. A file can
+//! only be read into the ecmaOS filesystem when it's a TrueType or OpenType
+//! font (or collection) and every face in it allows installation according
+//! to its OS/2 embedding flags; restricted and preview-only fonts are listed
+//! but refused.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::ipc::Response;
+use tauri::State;
+
+use crate::error::{Error, Result};
+
+/// Upper bound for a single `fonts_read` chunk.
+const MAX_CHUNK: u64 = 8 * 1024 * 1024;
+
+/// File extensions of the formats the webview can load with `FontFace`.
+const FORMATS: [&str; 4] = ["ttf", "otf", "ttc", "otc"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontFace {
+    pub family: String,
+    pub post_script_name: String,
+    pub weight: u16,
+    pub italic: bool,
+    pub monospaced: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontFile {
+    pub id: usize,
+    pub name: String,
+    pub path: PathBuf,
+    pub format: String,
+    pub size: u64,
+    pub faces: Vec<FontFace>,
+    /// Whether the format and embedding flags allow copying the file.
+    pub installable: bool,
+}
+
+#[derive(Default)]
+pub struct Fonts {
+    files: Mutex<Option<Vec<FontFile>>>,
+}
+
+fn allows_install(permissions: Option<ttf_parser::Permissions>) -> bool {
+    matches!(permissions, Some(ttf_parser::Permissions::Installable | ttf_parser::Permissions::Editable))
+}
+
+fn scan() -> Vec<FontFile> {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+
+    let mut files: BTreeMap<PathBuf, (Vec<FontFace>, bool)> = BTreeMap::new();
+    for face in db.faces() {
+        let path = match &face.source {
+            fontdb::Source::File(path) | fontdb::Source::SharedFile(path, _) => path.clone(),
+            fontdb::Source::Binary(_) => continue,
+        };
+        let permitted = db
+            .with_face_data(face.id, |data, index| {
+                ttf_parser::Face::parse(data, index).ok().map(|parsed| allows_install(parsed.permissions()))
+            })
+            .flatten()
+            .unwrap_or(false);
+
+        let entry = files.entry(path).or_insert_with(|| (Vec::new(), true));
+        entry.0.push(FontFace {
+            family: face.families.first().map(|(name, _)| name.clone()).unwrap_or_default(),
+            post_script_name: face.post_script_name.clone(),
+            weight: face.weight.0,
+            italic: face.style != fontdb::Style::Normal,
+            monospaced: face.monospaced,
+        });
+        entry.1 &= permitted;
+    }
+
+    files
+        .into_iter()
+        .enumerate()
+        .map(|(id, (path, (faces, permitted)))| {
+            let format = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_lowercase();
+            FontFile {
+                id,
+                name: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+                size: std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0),
+                installable: permitted && FORMATS.contains(&format.as_str()),
+                format,
+                faces,
+                path,
+            }
+        })
+        .collect()
+}
+
+/// Lists the fonts installed on the host. The scan is cached; pass `refresh`
+/// to pick up fonts installed since.
+#[tauri::command]
+pub async fn fonts_list(state: State<'_, Fonts>, refresh: Option<bool>) -> Result<Vec<FontFile>> {
+    if !refresh.unwrap_or(false) {
+        if let Some(files) = state.files.lock().unwrap().as_ref() {
+            return Ok(files.clone());
+        }
+    }
+    let files = tauri::async_runtime::spawn_blocking(scan).await?;
+    *state.files.lock().unwrap() = Some(files.clone());
+    Ok(files)
+}
+
+/// Reads up to `length` bytes of font file `id` starting at `offset`, so the
+/// kernel can stream it into its filesystem.
+#[tauri::command]
+pub fn fonts_read(state: State<'_, Fonts>, id: usize, offset: u64, length: u64) -> Result<Response> {
+    let path = {
+        let files = state.files.lock().unwrap();
+        let file = files
+            .as_ref()
+            .and_then(|files| files.get(id))
+            .ok_or_else(|| Error::NotFound(format!("no font with id {id}")))?;
+        if !file.installable {
+            return Err(Error::Config(format!("{} doesn't permit installation", file.name)));
+        }
+        file.path.clone()
+    };
+
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::new();
+    file.take(length.min(MAX_CHUNK)).read_to_end(&mut data)?;
+    Ok(Response::new(data))
+}
@@ -1,3 +1,18 @@
+mod appearance;
+mod associations;
+mod bandwidth;
+pub mod block;
+mod discovery;
+mod error;
+mod fonts;
+mod network;
+mod signaling;
+mod snapshot;
+mod sync;
+mod window_state;
+
+use tauri::{Manager, RunEvent};
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -6,9 +21,102 @@ fn greet(name: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let builder = tauri::Builder::default();
+    // Registered first, so a second instance exits before anything else
+    // starts
+    #[cfg(any(target_os = "macos", windows, target_os = "linux"))]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(associations::on_second_instance));
+    builder
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![greet])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .manage(discovery::Discovery::default())
+        .manage(bandwidth::Bandwidth::default())
+        .manage(fonts::Fonts::default())
+        .manage(appearance::AppearanceWatcher::default())
+        .setup(|app| {
+            let handle = app.handle();
+            appearance::watch(handle.clone());
+            app.manage(network::Network::load(handle)?);
+            app.manage(signaling::Signaling::load(handle)?);
+            app.manage(block::Disks::load(handle)?);
+            app.manage(sync::DiskSync::load(handle)?);
+            sync::schedule(handle);
+
+            let associations = associations::Associations::load(handle)?;
+            associations.queue(handle, std::env::args_os().skip(1));
+            app.manage(associations);
+
+            let window_states = window_state::WindowStates::load(handle)?;
+            for window in app.webview_windows().values() {
+                window_states.restore(window)?;
+            }
+            app.manage(window_states);
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            window_state::on_window_event(window, event);
+            appearance::on_window_event(window, event);
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            signaling::signaling_configure,
+            signaling::signaling_config,
+            signaling::signaling_ice_servers,
+            signaling::signaling_connect,
+            signaling::signaling_disconnect,
+            signaling::signaling_create_offer,
+            signaling::signaling_create_answer,
+            signaling::signaling_add_candidate,
+            signaling::signaling_hangup,
+            discovery::discovery_start,
+            discovery::discovery_stop,
+            discovery::discovery_peers,
+            associations::associations_get,
+            associations::associations_set,
+            associations::open_files_pending,
+            associations::open_file_read,
+            associations::open_file_done,
+            window_state::window_state_set_zoom,
+            window_state::window_state_reset,
+            network::network_settings_get,
+            network::network_settings_set,
+            network::network_system_proxy,
+            network::net_fetch,
+            bandwidth::network_usage,
+            bandwidth::network_set_rate_limit,
+            bandwidth::network_reset_usage,
+            fonts::fonts_list,
+            fonts::fonts_read,
+            appearance::appearance_get,
+            block::block_open,
+            block::block_info,
+            block::block_read,
+            block::block_write,
+            block::block_allocate,
+            block::block_free,
+            block::block_flush,
+            block::block_close,
+            snapshot::snapshot_create,
+            snapshot::snapshot_list,
+            snapshot::snapshot_delete,
+            snapshot::snapshot_rollback,
+            sync::sync_settings_get,
+            sync::sync_settings_set,
+            sync::sync_status,
+            sync::sync_now,
+            sync::sync_export_key,
+            sync::sync_import_key,
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                let _ = app.state::<window_state::WindowStates>().save();
+                app.state::<block::Disks>().close_all();
+            }
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            if let RunEvent::Opened { urls } = &event {
+                let paths = urls.iter().filter_map(|url| url.to_file_path().ok());
+                app.state::<associations::Associations>().queue(app, paths);
+            }
+        });
 }
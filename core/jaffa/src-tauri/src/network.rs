@@ -0,0 +1,245 @@
+//! Network bridge: HTTP requests made by the backend on the kernel's behalf.
+//!
+//! Requests issued through `net_fetch` aren't subject to the webview's CORS
+//! rules, and every outgoing connection from the backend (including the
+//! signaling relay) goes through [`Network::client_for`], so proxy and TLS
+//! settings apply uniformly. Settings are edited from the kernel's network
+//! settings and stored in `network.json` in the app config directory.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::bandwidth::{Bandwidth, DEFAULT_APP};
+use crate::error::{Error, Result};
+
+const SETTINGS_FILE: &str = "network.json";
+
+/// Environment variables consulted for the system proxy, in lookup order.
+const PROXY_VARS: [&str; 4] = ["https_proxy", "http_proxy", "all_proxy", "no_proxy"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum ProxyMode {
+    /// Use the platform configuration: proxy environment variables, the
+    /// Windows internet settings or the macOS system configuration.
+    #[default]
+    System,
+    /// Connect directly, ignoring any system proxy.
+    Direct,
+    /// Send all traffic through `url`, an `http://` or `https://` proxy.
+    /// reqwest is built without SOCKS support, so `socks5://` URLs are
+    /// refused when the settings are saved.
+    Manual { url: String },
+}
+
+/// Overrides applied to hosts matching a pattern in [`NetworkSettings::hosts`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HostOptions {
+    pub proxy: Option<ProxyMode>,
+    /// PEM files with additional trusted root certificates.
+    pub ca_certificates: Vec<PathBuf>,
+    /// PEM file holding a client certificate chain and its private key.
+    pub client_certificate: Option<PathBuf>,
+    pub accept_invalid_certs: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NetworkSettings {
+    pub proxy: ProxyMode,
+    /// Hosts that bypass a manual proxy, in `NO_PROXY` syntax.
+    pub no_proxy: Vec<String>,
+    /// Per-host overrides keyed by host name; `*.example.com` matches any
+    /// subdomain of `example.com`.
+    pub hosts: BTreeMap<String, HostOptions>,
+}
+
+impl NetworkSettings {
+    fn host_options(&self, host: &str) -> Option<(&str, &HostOptions)> {
+        if let Some((pattern, options)) = self.hosts.get_key_value(host) {
+            return Some((pattern, options));
+        }
+        self.hosts
+            .iter()
+            .filter(|(pattern, _)| {
+                pattern.strip_prefix("*.").is_some_and(|domain| host.ends_with(&format!(".{domain}")))
+            })
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(pattern, options)| (pattern.as_str(), options))
+    }
+}
+
+fn build_client(settings: &NetworkSettings, host: &HostOptions) -> Result<reqwest::Client> {
+    let mut builder =
+        reqwest::Client::builder().user_agent(concat!("ecmaOS-Jaffa/", env!("CARGO_PKG_VERSION")));
+
+    match host.proxy.as_ref().unwrap_or(&settings.proxy) {
+        ProxyMode::System => {}
+        ProxyMode::Direct => builder = builder.no_proxy(),
+        ProxyMode::Manual { url } => {
+            let no_proxy = reqwest::NoProxy::from_string(&settings.no_proxy.join(","));
+            builder = builder.proxy(reqwest::Proxy::all(url)?.no_proxy(no_proxy));
+        }
+    }
+    for path in &host.ca_certificates {
+        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&std::fs::read(path)?)?);
+    }
+    if let Some(path) = &host.client_certificate {
+        builder = builder.identity(reqwest::Identity::from_pem(&std::fs::read(path)?)?);
+    }
+    if host.accept_invalid_certs {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder.build()?)
+}
+
+pub struct Network {
+    path: PathBuf,
+    settings: Mutex<NetworkSettings>,
+    /// Clients keyed by the host pattern whose options they were built with;
+    /// the empty key holds the client for hosts without overrides.
+    clients: Mutex<HashMap<String, reqwest::Client>>,
+}
+
+impl Network {
+    pub fn load(app: &AppHandle) -> Result<Self> {
+        let path = app.path().app_config_dir()?.join(SETTINGS_FILE);
+        let settings = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => NetworkSettings::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, settings: Mutex::new(settings), clients: Mutex::new(HashMap::new()) })
+    }
+
+    /// Returns an HTTP client configured for the host of `url`.
+    pub fn client_for(&self, url: &str) -> Result<reqwest::Client> {
+        let url = reqwest::Url::parse(url).map_err(|e| Error::Config(format!("invalid url {url}: {e}")))?;
+        let settings = self.settings.lock().unwrap();
+        let default = HostOptions::default();
+        let (key, options) = settings.host_options(url.host_str().unwrap_or_default()).unwrap_or(("", &default));
+
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(client) = clients.get(key) {
+            return Ok(client.clone());
+        }
+        let client = build_client(&settings, options)?;
+        clients.insert(key.to_string(), client.clone());
+        Ok(client)
+    }
+}
+
+#[tauri::command]
+pub fn network_settings_get(state: State<'_, Network>) -> NetworkSettings {
+    state.settings.lock().unwrap().clone()
+}
+
+/// Validates and stores new settings. Every host's client is built up front
+/// so a bad certificate path or proxy URL is reported here rather than on
+/// the next request.
+#[tauri::command]
+pub fn network_settings_set(state: State<'_, Network>, settings: NetworkSettings) -> Result<()> {
+    build_client(&settings, &HostOptions::default())?;
+    for options in settings.hosts.values() {
+        build_client(&settings, options)?;
+    }
+
+    if let Some(dir) = state.path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&state.path, serde_json::to_vec_pretty(&settings)?)?;
+    *state.settings.lock().unwrap() = settings;
+    state.clients.lock().unwrap().clear();
+    Ok(())
+}
+
+/// Reports the proxy environment variables the system mode picks up, so the
+/// settings UI can show what "system" currently means.
+#[tauri::command]
+pub fn network_system_proxy() -> BTreeMap<String, String> {
+    PROXY_VARS
+        .iter()
+        .filter_map(|name| {
+            let value = std::env::var(name).or_else(|_| std::env::var(name.to_uppercase())).ok()?;
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchRequest {
+    pub url: String,
+    /// App the request is made for, used for traffic accounting.
+    #[serde(default)]
+    pub app: Option<String>,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    /// Base64 encoded request body.
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// Base64 encoded response body.
+    pub body: String,
+}
+
+/// Performs an HTTP request for the kernel outside the webview's CORS rules.
+#[tauri::command]
+pub async fn net_fetch(
+    state: State<'_, Network>,
+    bandwidth: State<'_, Bandwidth>,
+    request: FetchRequest,
+) -> Result<FetchResponse> {
+    let connection = bandwidth.open(request.app.as_deref().unwrap_or(DEFAULT_APP), &request.url);
+    let method = request.method.as_deref().unwrap_or("GET");
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+        .map_err(|_| Error::Config(format!("invalid method {method}")))?;
+
+    let mut builder = state.client_for(&request.url)?.request(method, &request.url);
+    for (name, value) in &request.headers {
+        builder = builder.header(name, value);
+    }
+    if let Some(body) = request.body {
+        let body = BASE64.decode(body)?;
+        connection.sending(body.len()).await;
+        builder = builder.body(body);
+    }
+
+    let mut response = builder.send().await?;
+    let status = response.status();
+    let url = response.url().to_string();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+        .collect();
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        connection.received(chunk.len()).await;
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(FetchResponse {
+        status: status.as_u16(),
+        status_text: status.canonical_reason().unwrap_or_default().to_string(),
+        url,
+        headers,
+        body: BASE64.encode(body),
+    })
+}
@@ -0,0 +1,377 @@
+//! WebRTC signaling relay for the kernel's peer-to-peer features.
+//!
+//! The webview owns the `RTCPeerConnection`s; this module only moves their
+//! session descriptions and ICE candidates around. Messages addressed to a
+//! peer hosted by this process are delivered locally, everything else is
+//! posted to the configured signaling server and its inbox is polled in the
+//! background. Received messages are emitted to the kernel as
+//! `signaling://message` events.
+//!
+//! Signaling server protocol (JSON over HTTP):
+//!
+//! - `POST {server}/signal` with a [`SignalMessage`] body
+//! - `GET {server}/signal/{peer}?since={cursor}` returning [`Inbox`]
+//!
+//! The configuration is stored in `signaling.json` in the app config
+//! directory, so the instance keeps its peer id across launches.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::error::{Error, Result};
+use crate::network::Network;
+
+const MESSAGE_EVENT: &str = "signaling://message";
+const ERROR_EVENT: &str = "signaling://error";
+const CONFIG_FILE: &str = "signaling.json";
+
+/// Lifetime assumed for fetched TURN credentials that don't state a ttl.
+const DEFAULT_CREDENTIAL_TTL: u64 = 600;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SignalingConfig {
+    /// Identifier other peers use to address this instance. Generated the
+    /// first time the app runs; a configuration without one keeps the
+    /// current id.
+    pub peer_id: String,
+    /// Base URL of the signaling server; local delivery only when unset.
+    pub server_url: Option<String>,
+    /// Endpoint returning short-lived TURN credentials.
+    pub credentials_url: Option<String>,
+    /// ICE servers that are always offered, typically public STUN servers.
+    pub ice_servers: Vec<IceServer>,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for SignalingConfig {
+    fn default() -> Self {
+        Self {
+            peer_id: String::new(),
+            server_url: None,
+            credentials_url: None,
+            ice_servers: vec![IceServer {
+                urls: vec!["stun:stun.l.google.com:19302".into()],
+                username: None,
+                credential: None,
+            }],
+            poll_interval_ms: 1000,
+        }
+    }
+}
+
+/// Mirrors the browser's `RTCIceServer` dictionary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IceServer {
+    pub urls: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
+}
+
+/// Accepted shapes of a credentials endpoint response: either a ready-made
+/// `iceServers` list or the TURN REST API (`username`/`password`/`ttl`/`uris`).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CredentialsResponse {
+    #[serde(rename_all = "camelCase")]
+    List {
+        ice_servers: Vec<IceServer>,
+        ttl: Option<u64>,
+    },
+    TurnRest {
+        username: String,
+        #[serde(alias = "credential")]
+        password: String,
+        ttl: Option<u64>,
+        uris: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalKind {
+    Offer,
+    Answer,
+    Candidate,
+    Bye,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalMessage {
+    pub from: String,
+    pub to: String,
+    pub session: String,
+    pub kind: SignalKind,
+    pub payload: Value,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Inbox {
+    messages: Vec<SignalMessage>,
+    cursor: Option<String>,
+}
+
+pub struct Signaling {
+    path: PathBuf,
+    config: Mutex<SignalingConfig>,
+    ice_cache: Mutex<Option<(Instant, Vec<IceServer>)>>,
+    poller: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Signaling {
+    pub fn load(app: &AppHandle) -> Result<Self> {
+        let path = app.path().app_config_dir()?.join(CONFIG_FILE);
+        let mut config = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => SignalingConfig::default(),
+            Err(e) => return Err(e.into()),
+        };
+        // Saved straight away, so the id stays the same from now on
+        let generated = config.peer_id.is_empty();
+        if generated {
+            config.peer_id = uuid::Uuid::new_v4().to_string();
+        }
+        let state = Self { path, config: Mutex::new(config), ice_cache: Mutex::new(None), poller: Mutex::new(None) };
+        if generated {
+            state.save(&state.config())?;
+        }
+        Ok(state)
+    }
+
+    fn save(&self, config: &SignalingConfig) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_vec_pretty(config)?)?;
+        Ok(())
+    }
+
+    fn config(&self) -> SignalingConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn peer_id(&self) -> String {
+        self.config.lock().unwrap().peer_id.clone()
+    }
+
+    fn stop_polling(&self) {
+        if let Some(handle) = self.poller.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+
+    async fn send(&self, app: &AppHandle, message: SignalMessage) -> Result<()> {
+        let config = self.config();
+        if message.to == config.peer_id {
+            app.emit(MESSAGE_EVENT, &message)?;
+            return Ok(());
+        }
+
+        let server = config.server_url.ok_or_else(|| {
+            Error::Config(format!("no signaling server configured to reach peer {}", message.to))
+        })?;
+        let url = format!("{}/signal", server.trim_end_matches('/'));
+        app.state::<Network>()
+            .client_for(&url)?
+            .post(url)
+            .json(&message)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+async fn fetch_inbox(app: &AppHandle, server: &str, peer_id: &str, cursor: Option<&str>) -> Result<Inbox> {
+    let url = format!("{}/signal/{}", server.trim_end_matches('/'), peer_id);
+    let mut request = app.state::<Network>().client_for(&url)?.get(url);
+    if let Some(cursor) = cursor {
+        request = request.query(&[("since", cursor)]);
+    }
+    Ok(request.send().await?.error_for_status()?.json().await?)
+}
+
+#[tauri::command]
+pub fn signaling_configure(state: State<'_, Signaling>, mut config: SignalingConfig) -> Result<()> {
+    let mut current = state.config.lock().unwrap();
+    if config.peer_id.is_empty() {
+        config.peer_id = current.peer_id.clone();
+    }
+    state.save(&config)?;
+    *current = config;
+    *state.ice_cache.lock().unwrap() = None;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn signaling_config(state: State<'_, Signaling>) -> SignalingConfig {
+    state.config()
+}
+
+/// Returns the ICE servers to hand to `RTCPeerConnection`, including TURN
+/// credentials fetched from the credentials endpoint while they're valid.
+#[tauri::command]
+pub async fn signaling_ice_servers(
+    state: State<'_, Signaling>,
+    network: State<'_, Network>,
+) -> Result<Vec<IceServer>> {
+    if let Some((expires, servers)) = state.ice_cache.lock().unwrap().as_ref() {
+        if Instant::now() < *expires {
+            return Ok(servers.clone());
+        }
+    }
+
+    let config = state.config();
+    let mut servers = config.ice_servers;
+    let Some(url) = config.credentials_url else {
+        return Ok(servers);
+    };
+
+    let response: CredentialsResponse = network
+        .client_for(&url)?
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let ttl = match response {
+        CredentialsResponse::List { ice_servers, ttl } => {
+            servers.extend(ice_servers);
+            ttl
+        }
+        CredentialsResponse::TurnRest { username, password, ttl, uris } => {
+            servers.push(IceServer { urls: uris, username: Some(username), credential: Some(password) });
+            ttl
+        }
+    };
+
+    // Refresh a little early so peers never receive credentials about to lapse.
+    let ttl = ttl.unwrap_or(DEFAULT_CREDENTIAL_TTL);
+    let expires = Instant::now() + Duration::from_secs(ttl - ttl / 10);
+    *state.ice_cache.lock().unwrap() = Some((expires, servers.clone()));
+    Ok(servers)
+}
+
+/// Starts polling the signaling server for messages addressed to this peer.
+/// Returns the peer id other instances should use to reach us.
+#[tauri::command]
+pub fn signaling_connect(app: AppHandle, state: State<'_, Signaling>) -> String {
+    state.stop_polling();
+
+    let config = state.config();
+    let Some(server) = config.server_url else {
+        return config.peer_id;
+    };
+
+    let peer_id = config.peer_id.clone();
+    let interval = Duration::from_millis(config.poll_interval_ms.max(100));
+    let handle = tauri::async_runtime::spawn(async move {
+        let mut cursor = None;
+        loop {
+            match fetch_inbox(&app, &server, &peer_id, cursor.as_deref()).await {
+                Ok(inbox) => {
+                    for message in inbox.messages {
+                        let _ = app.emit(MESSAGE_EVENT, message);
+                    }
+                    cursor = inbox.cursor.or(cursor);
+                }
+                Err(error) => {
+                    let _ = app.emit(ERROR_EVENT, error.to_string());
+                }
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+    *state.poller.lock().unwrap() = Some(handle);
+    config.peer_id
+}
+
+#[tauri::command]
+pub fn signaling_disconnect(state: State<'_, Signaling>) {
+    state.stop_polling();
+}
+
+/// Sends a local session description offer to `to`. A new session id is
+/// generated unless one is given, and returned for the answer to reference.
+#[tauri::command]
+pub async fn signaling_create_offer(
+    app: AppHandle,
+    state: State<'_, Signaling>,
+    to: String,
+    sdp: String,
+    session: Option<String>,
+) -> Result<String> {
+    let session = session.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let message = SignalMessage {
+        from: state.config().peer_id,
+        to,
+        session: session.clone(),
+        kind: SignalKind::Offer,
+        payload: serde_json::json!({ "type": "offer", "sdp": sdp }),
+    };
+    state.send(&app, message).await?;
+    Ok(session)
+}
+
+#[tauri::command]
+pub async fn signaling_create_answer(
+    app: AppHandle,
+    state: State<'_, Signaling>,
+    to: String,
+    session: String,
+    sdp: String,
+) -> Result<()> {
+    let message = SignalMessage {
+        from: state.config().peer_id,
+        to,
+        session,
+        kind: SignalKind::Answer,
+        payload: serde_json::json!({ "type": "answer", "sdp": sdp }),
+    };
+    state.send(&app, message).await
+}
+
+/// Relays an `RTCIceCandidateInit` (as produced by `candidate.toJSON()`).
+#[tauri::command]
+pub async fn signaling_add_candidate(
+    app: AppHandle,
+    state: State<'_, Signaling>,
+    to: String,
+    session: String,
+    candidate: Value,
+) -> Result<()> {
+    let message = SignalMessage {
+        from: state.config().peer_id,
+        to,
+        session,
+        kind: SignalKind::Candidate,
+        payload: candidate,
+    };
+    state.send(&app, message).await
+}
+
+#[tauri::command]
+pub async fn signaling_hangup(
+    app: AppHandle,
+    state: State<'_, Signaling>,
+    to: String,
+    session: String,
+) -> Result<()> {
+    let message = SignalMessage {
+        from: state.config().peer_id,
+        to,
+        session,
+        kind: SignalKind::Bye,
+        payload: Value::Null,
+    };
+    state.send(&app, message).await
+}
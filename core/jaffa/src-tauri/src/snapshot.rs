@@ -0,0 +1,287 @@
+//! Copy-on-write snapshots of block disk images.
+//!
+//! Taking a snapshot only saves the image's header and allocation bitmap.
+//! From then on, the first write to each block copies its previous contents
+//! into the snapshot, so a snapshot takes space in proportion to how much
+//! has changed since it was taken. Rolling back writes those blocks back,
+//! restores the bitmap and discards any snapshots taken later.
+//!
+//! Snapshots are stored next to their image in `<name>.snapshots/`:
+//! `<id>.json` holds the metadata, `<id>.state` the header and bitmap, and
+//! `<id>.blocks` the preserved blocks, each prefixed with its index. The
+//! snapshot is only reset once a rollback has completed, so an interrupted
+//! rollback can simply be run again.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::block::Disks;
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Metadata {
+    id: String,
+    name: String,
+    description: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    created: u64,
+    /// Length of the image file when the snapshot was taken.
+    file_length: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub created: u64,
+    /// Blocks changed since the snapshot was taken.
+    pub changed_blocks: u64,
+    /// Space the snapshot takes up on the host.
+    pub size: u64,
+}
+
+struct Snapshot {
+    metadata: Metadata,
+    /// Where each preserved block's record is in `blocks`, by block index.
+    preserved: HashMap<u64, u64>,
+    blocks: File,
+}
+
+pub struct Snapshots {
+    dir: PathBuf,
+    block_size: u64,
+    /// Oldest first.
+    list: Vec<Snapshot>,
+}
+
+impl Snapshots {
+    /// Loads the snapshots of the image at `image`.
+    pub fn load(image: &Path, block_size: u32) -> Result<Self> {
+        let dir = image.with_extension("snapshots");
+        let block_size = block_size as u64;
+        let mut list = Vec::new();
+
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self { dir, block_size, list }),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let metadata: Metadata = serde_json::from_slice(&std::fs::read(&path)?)?;
+            let mut blocks = OpenOptions::new().read(true).append(true).open(dir.join(format!("{}.blocks", metadata.id)))?;
+
+            // A record cut short by a crash is dropped; the block it was
+            // preserving hadn't been overwritten yet.
+            let record = 8 + block_size;
+            let length = blocks.metadata()?.len();
+            if length % record != 0 {
+                blocks.set_len(length - length % record)?;
+            }
+            let mut preserved = HashMap::new();
+            let mut reader = BufReader::new(&mut blocks);
+            for position in 0..length / record {
+                let mut index = [0; 8];
+                reader.seek(SeekFrom::Start(position * record))?;
+                reader.read_exact(&mut index)?;
+                preserved.insert(u64::from_le_bytes(index), position);
+            }
+            list.push(Snapshot { metadata, preserved, blocks });
+        }
+        list.sort_by_key(|snapshot| snapshot.metadata.created);
+        Ok(Self { dir, block_size, list })
+    }
+
+    fn path(&self, id: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{id}.{extension}"))
+    }
+
+    fn position(&self, id: &str) -> Result<usize> {
+        self.list
+            .iter()
+            .position(|snapshot| snapshot.metadata.id == id)
+            .ok_or_else(|| Error::NotFound(format!("no snapshot with id {id}")))
+    }
+
+    pub fn list(&self) -> Vec<SnapshotInfo> {
+        self.list
+            .iter()
+            .map(|snapshot| SnapshotInfo {
+                id: snapshot.metadata.id.clone(),
+                name: snapshot.metadata.name.clone(),
+                description: snapshot.metadata.description.clone(),
+                created: snapshot.metadata.created,
+                changed_blocks: snapshot.preserved.len() as u64,
+                size: snapshot.preserved.len() as u64 * (8 + self.block_size),
+            })
+            .collect()
+    }
+
+    /// Whether a snapshot still needs the current contents of block `index`,
+    /// stored at `offset` in the image file.
+    pub fn needs(&self, index: u64, offset: u64) -> bool {
+        self.list
+            .iter()
+            .any(|snapshot| offset < snapshot.metadata.file_length && !snapshot.preserved.contains_key(&index))
+    }
+
+    /// Saves the current contents of block `index` into every snapshot that
+    /// doesn't have it yet. Must be called before the block is overwritten.
+    pub fn preserve(&mut self, index: u64, offset: u64, data: &[u8]) -> Result<()> {
+        for snapshot in &mut self.list {
+            if offset >= snapshot.metadata.file_length || snapshot.preserved.contains_key(&index) {
+                continue;
+            }
+            let mut record = Vec::with_capacity(8 + data.len());
+            record.extend_from_slice(&index.to_le_bytes());
+            record.extend_from_slice(data);
+            snapshot.blocks.write_all(&record)?;
+            snapshot.blocks.sync_data()?;
+            let position = snapshot.preserved.len() as u64;
+            snapshot.preserved.insert(index, position);
+        }
+        Ok(())
+    }
+
+    /// Records a new snapshot of an image whose header and bitmap are `state`
+    /// and whose file is `file_length` bytes long.
+    pub fn create(
+        &mut self,
+        name: String,
+        description: Option<String>,
+        state: &[u8],
+        file_length: u64,
+    ) -> Result<SnapshotInfo> {
+        std::fs::create_dir_all(&self.dir)?;
+        let metadata = Metadata {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            description,
+            created: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            file_length,
+        };
+        std::fs::write(self.path(&metadata.id, "state"), state)?;
+        let blocks = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create_new(true)
+            .open(self.path(&metadata.id, "blocks"))?;
+        // Written last: a snapshot only exists once its metadata does
+        std::fs::write(self.path(&metadata.id, "json"), serde_json::to_vec_pretty(&metadata)?)?;
+
+        self.list.push(Snapshot { metadata, preserved: HashMap::new(), blocks });
+        Ok(self.list().pop().unwrap())
+    }
+
+    fn remove_files(&self, id: &str) -> Result<()> {
+        std::fs::remove_file(self.path(id, "json"))?;
+        for extension in ["state", "blocks"] {
+            match std::fs::remove_file(self.path(id, extension)) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, id: &str) -> Result<()> {
+        let position = self.position(id)?;
+        self.remove_files(id)?;
+        self.list.remove(position);
+        Ok(())
+    }
+
+    /// Returns the saved header and bitmap of snapshot `id` and the image
+    /// file length at the time.
+    pub fn state(&self, id: &str) -> Result<(Vec<u8>, u64)> {
+        let position = self.position(id)?;
+        let state = std::fs::read(self.path(id, "state"))?;
+        Ok((state, self.list[position].metadata.file_length))
+    }
+
+    /// Returns what block `index` held when snapshot `id` was taken, or
+    /// `None` if it hasn't been overwritten since and still holds it.
+    pub fn preserved_block(&mut self, id: &str, index: u64) -> Result<Option<Vec<u8>>> {
+        let position = self.position(id)?;
+        let snapshot = &mut self.list[position];
+        let Some(record) = snapshot.preserved.get(&index) else {
+            return Ok(None);
+        };
+        let mut data = vec![0; self.block_size as usize];
+        snapshot.blocks.seek(SeekFrom::Start(record * (8 + self.block_size) + 8))?;
+        snapshot.blocks.read_exact(&mut data)?;
+        Ok(Some(data))
+    }
+
+    /// Calls `apply` with each block snapshot `id` preserved, as `(index,
+    /// data)`. Blocks are read one at a time, so a snapshot of any size can
+    /// be applied.
+    pub fn for_each_block(&mut self, id: &str, mut apply: impl FnMut(u64, &[u8]) -> Result<()>) -> Result<()> {
+        let position = self.position(id)?;
+        let mut record = vec![0; 8 + self.block_size as usize];
+        let blocks = &mut self.list[position].blocks;
+        blocks.seek(SeekFrom::Start(0))?;
+        let mut reader = BufReader::new(blocks);
+        loop {
+            match reader.read_exact(&mut record) {
+                Ok(()) => apply(u64::from_le_bytes(record[..8].try_into().unwrap()), &record[8..])?,
+                // A record cut short is skipped, as it is when loading
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Called once the image has been rolled back to `id`: snapshots taken
+    /// later are discarded and `id` starts over from the restored state.
+    pub fn rolled_back(&mut self, id: &str) -> Result<()> {
+        let position = self.position(id)?;
+        for snapshot in self.list.split_off(position + 1) {
+            self.remove_files(&snapshot.metadata.id)?;
+        }
+        let snapshot = &mut self.list[position];
+        snapshot.blocks.set_len(0)?;
+        snapshot.preserved.clear();
+        Ok(())
+    }
+}
+
+/// Takes a snapshot of an open image, e.g. before installing packages.
+#[tauri::command]
+pub fn snapshot_create(
+    state: State<'_, Disks>,
+    handle: u32,
+    name: String,
+    description: Option<String>,
+) -> Result<SnapshotInfo> {
+    state.get(handle)?.image.lock().unwrap().snapshot(name, description)
+}
+
+#[tauri::command]
+pub fn snapshot_list(state: State<'_, Disks>, handle: u32) -> Result<Vec<SnapshotInfo>> {
+    Ok(state.get(handle)?.image.lock().unwrap().snapshots().list())
+}
+
+#[tauri::command]
+pub fn snapshot_delete(state: State<'_, Disks>, handle: u32, id: String) -> Result<()> {
+    state.get(handle)?.image.lock().unwrap().snapshots_mut().delete(&id)
+}
+
+/// Rolls an open image back to snapshot `id`. Anything the kernel cached
+/// from the image is stale afterwards, so it should remount the disk.
+#[tauri::command]
+pub fn snapshot_rollback(state: State<'_, Disks>, handle: u32, id: String) -> Result<()> {
+    state.get(handle)?.image.lock().unwrap().rollback(&id)
+}
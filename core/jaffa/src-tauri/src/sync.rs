@@ -0,0 +1,615 @@
+//! Background sync of disk images to remote storage.
+//!
+//! Disk images are uploaded to an S3-compatible bucket or a WebDAV
+//! collection in 1 MiB chunks, each stored under a keyed hash of its
+//! contents. Each run uploads the chunks the remote manifest doesn't list
+//! yet, then replaces the manifest, and only then deletes the chunks nothing
+//! refers to any more, so the remote manifest always names chunks that hold
+//! what it says. Chunks and manifests are encrypted with AES-256-GCM before
+//! they leave the host, using a key generated on first use and stored in
+//! `sync.key` in the app config directory; without that key the remote
+//! copy can't be read, so the kernel should offer to export it.
+//!
+//! Every manifest carries a generation number and the id of the device that
+//! wrote it. If the remote generation isn't the one this device last
+//! uploaded, another device has synced the disk in between: the disk is
+//! skipped and a `sync://conflict` event is emitted, until the user resolves
+//! it with a forced sync. Progress, completion and errors are reported as
+//! `sync://progress`, `sync://done` and `sync://error` events.
+//!
+//! Runs start every `intervalSecs` seconds and on `sync_now`. A run takes a
+//! snapshot of each image (see [`crate::snapshot`]) and uploads the image as
+//! it was then, so the kernel can keep writing to it meanwhile; the snapshot
+//! is deleted once the run is done with it.
+//!
+//! Remote layout, below the S3 prefix or WebDAV collection:
+//!
+//! - `{disk}/manifest`: the encrypted [`Manifest`]
+//! - `{disk}/chunks/{id}`: encrypted chunk contents, named by [`chunk_id`]
+
+mod s3;
+mod webdav;
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::bandwidth::Bandwidth;
+use crate::block::{Disks, OpenImage, SnapshotView};
+use crate::error::{Error, Result};
+use crate::network::Network;
+
+pub use s3::S3Remote;
+pub use webdav::WebDavRemote;
+
+const SETTINGS_FILE: &str = "sync.json";
+const STATE_FILE: &str = "sync-state.json";
+const KEY_FILE: &str = "sync.key";
+/// Name of the snapshot a run uploads from.
+const SNAPSHOT_NAME: &str = "Background sync";
+
+const PROGRESS_EVENT: &str = "sync://progress";
+const CONFLICT_EVENT: &str = "sync://conflict";
+const DONE_EVENT: &str = "sync://done";
+const ERROR_EVENT: &str = "sync://error";
+
+/// Traffic is accounted under this app in the bandwidth monitor.
+const BANDWIDTH_APP: &str = "sync";
+const CHUNK_SIZE: u64 = 1 << 20;
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Remote {
+    S3(S3Remote),
+    Webdav(WebDavRemote),
+}
+
+impl Remote {
+    fn url(&self, key: &str) -> Result<reqwest::Url> {
+        match self {
+            Remote::S3(remote) => remote.url(key),
+            Remote::Webdav(remote) => remote.url(key),
+        }
+    }
+
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        method: reqwest::Method,
+        url: reqwest::Url,
+        body: Vec<u8>,
+    ) -> reqwest::RequestBuilder {
+        match self {
+            Remote::S3(remote) => remote.request(client, method, url, body),
+            Remote::Webdav(remote) => remote.request(client, method, url, body),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncSettings {
+    /// Where disks are synced to; sync is off when unset.
+    pub remote: Option<Remote>,
+    /// Names of the disk images to sync.
+    pub disks: Vec<String>,
+    /// Seconds between background runs; 0 only syncs on request.
+    pub interval_secs: u64,
+    /// Identifies this instance in the manifests it writes.
+    pub device_id: String,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self { remote: None, disks: Vec::new(), interval_secs: 900, device_id: uuid::Uuid::new_v4().to_string() }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    device: String,
+    generation: u64,
+    /// Milliseconds since the Unix epoch.
+    created: u64,
+    image_length: u64,
+    chunk_size: u64,
+    /// [`chunk_id`] of each chunk, in order.
+    chunks: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conflict {
+    pub disk: String,
+    /// Generation this device last uploaded.
+    pub local_generation: u64,
+    pub remote_generation: u64,
+    pub remote_device: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiskState {
+    /// Generation of the last manifest this device uploaded.
+    pub generation: u64,
+    /// Milliseconds since the Unix epoch.
+    pub last_synced: Option<u64>,
+    pub conflict: Option<Conflict>,
+    pub last_error: Option<String>,
+    /// Chunks that may be on the remote with no manifest referring to them:
+    /// uploaded by a run that didn't get to write its manifest, or left over
+    /// from the manifest it replaced. Deleted once a manifest is written.
+    pub unreferenced: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub running: bool,
+    pub disks: BTreeMap<String, DiskState>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Progress<'a> {
+    disk: &'a str,
+    chunks_done: u64,
+    chunks_total: u64,
+    bytes_uploaded: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Done<'a> {
+    disk: &'a str,
+    generation: u64,
+    chunks_uploaded: u64,
+    bytes_uploaded: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Failure<'a> {
+    disk: &'a str,
+    message: String,
+}
+
+pub struct DiskSync {
+    settings_path: PathBuf,
+    state_path: PathBuf,
+    key_path: PathBuf,
+    settings: Mutex<SyncSettings>,
+    disks: Mutex<BTreeMap<String, DiskState>>,
+    running: AtomicBool,
+    scheduler: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl DiskSync {
+    pub fn load(app: &AppHandle) -> Result<Self> {
+        let config_dir = app.path().app_config_dir()?;
+        let settings_path = config_dir.join(SETTINGS_FILE);
+        let state_path = app.path().app_data_dir()?.join(STATE_FILE);
+        let settings = read_json(&settings_path)?.unwrap_or_default();
+        let disks = read_json(&state_path)?.unwrap_or_default();
+        Ok(Self {
+            settings_path,
+            state_path,
+            key_path: config_dir.join(KEY_FILE),
+            settings: Mutex::new(settings),
+            disks: Mutex::new(disks),
+            running: AtomicBool::new(false),
+            scheduler: Mutex::new(None),
+        })
+    }
+
+    fn settings(&self) -> SyncSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    fn update_disk(&self, disk: &str, update: impl FnOnce(&mut DiskState)) -> Result<()> {
+        let mut disks = self.disks.lock().unwrap();
+        update(disks.entry(disk.to_string()).or_default());
+        write_file(&self.state_path, &serde_json::to_vec_pretty(&*disks)?)
+    }
+
+    /// Returns the encryption key, generating it on first use.
+    fn key(&self) -> Result<Vec<u8>> {
+        match std::fs::read(&self.key_path) {
+            Ok(key) if key.len() == KEY_SIZE => Ok(key),
+            Ok(_) => Err(Error::Sync(format!("{} isn't a valid sync key", self.key_path.display()))),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let key = Aes256Gcm::generate_key(OsRng).to_vec();
+                write_key(&self.key_path, &key)?;
+                Ok(key)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn cipher(&self) -> Result<Aes256Gcm> {
+        Ok(Aes256Gcm::new_from_slice(&self.key()?).expect("key has the right length"))
+    }
+
+    /// Key that chunks are named with, derived from the encryption key so
+    /// that the two are never used for the same thing.
+    fn id_key(&self) -> Result<Vec<u8>> {
+        Ok(hmac(&self.key()?, b"chunk ids"))
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_file(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, data)?;
+    Ok(())
+}
+
+/// Writes the key readable by the current user only.
+fn write_key(path: &Path, key: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(key)?;
+    Ok(())
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(crate) fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Names a chunk by a keyed hash of its contents, so identical chunks are
+/// stored once and the names reveal nothing to whoever holds the remote.
+fn chunk_id(id_key: &[u8], data: &[u8]) -> String {
+    hex(&hmac(id_key, data))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Encrypts `data` stored under `key` as nonce followed by ciphertext. The
+/// key is authenticated too, so objects can't be swapped around remotely.
+fn seal(cipher: &Aes256Gcm, key: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: data, aad: key.as_bytes() })
+        .map_err(|_| Error::Sync(format!("couldn't encrypt {key}")))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+fn unseal(cipher: &Aes256Gcm, key: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let invalid = || Error::Sync(format!("couldn't decrypt {key}; was it uploaded with a different key?"));
+    if data.len() < NONCE_SIZE {
+        return Err(invalid());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+    cipher.decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: key.as_bytes() }).map_err(|_| invalid())
+}
+
+/// Sends a request for `key`, returning the response body or `None` if the
+/// object doesn't exist.
+async fn transfer(
+    app: &AppHandle,
+    remote: &Remote,
+    method: reqwest::Method,
+    key: &str,
+    body: Vec<u8>,
+) -> Result<Option<Vec<u8>>> {
+    let url = remote.url(key)?;
+    let client = app.state::<Network>().client_for(url.as_str())?;
+    let bandwidth = app.state::<Bandwidth>();
+    let connection = bandwidth.open(BANDWIDTH_APP, url.as_str());
+    connection.sending(body.len()).await;
+
+    let response = remote.request(&client, method, url, body).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let mut response = response.error_for_status()?;
+    let mut data = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        connection.received(chunk.len()).await;
+        data.extend_from_slice(&chunk);
+    }
+    Ok(Some(data))
+}
+
+/// WebDAV servers only accept uploads into existing collections, so the
+/// disk's collections are created before anything is uploaded.
+async fn create_collections(app: &AppHandle, remote: &Remote, disk: &str) -> Result<()> {
+    let Remote::Webdav(webdav) = remote else {
+        return Ok(());
+    };
+    let mkcol = reqwest::Method::from_bytes(b"MKCOL").expect("valid method");
+    for key in [disk.to_string(), format!("{disk}/chunks")] {
+        let mut url = webdav.url(&key)?;
+        url.set_path(&format!("{}/", url.path()));
+        let client = app.state::<Network>().client_for(url.as_str())?;
+        let response = webdav.request(&client, mkcol.clone(), url, Vec::new()).send().await?;
+        // 405 means the collection already exists
+        if response.status() != reqwest::StatusCode::METHOD_NOT_ALLOWED {
+            response.error_for_status()?;
+        }
+    }
+    Ok(())
+}
+
+/// An image as it was when a run started, kept as a snapshot until the
+/// run is done with it.
+struct Source {
+    image: Arc<OpenImage>,
+    view: SnapshotView,
+}
+
+impl Source {
+    fn open(app: &AppHandle, disk: &str) -> Result<Self> {
+        let image = app.state::<Disks>().hold(disk)?;
+        let view = {
+            let mut locked = image.image.lock().unwrap();
+            // Left behind by a run that was cut short
+            for stale in locked.snapshots().list().into_iter().filter(|info| info.name == SNAPSHOT_NAME) {
+                locked.snapshots_mut().delete(&stale.id)?;
+            }
+            let snapshot = locked.snapshot(SNAPSHOT_NAME.into(), None)?;
+            locked.snapshot_view(&snapshot.id)?
+        };
+        Ok(Self { image, view })
+    }
+
+    fn length(&self) -> u64 {
+        self.view.file_length()
+    }
+
+    fn read(&self, offset: u64, length: u64) -> Result<Vec<u8>> {
+        self.image.image.lock().unwrap().read_snapshot(&self.view, offset, length)
+    }
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        if let Ok(mut image) = self.image.image.lock() {
+            let _ = image.snapshots_mut().delete(self.view.id());
+        }
+    }
+}
+
+/// Syncs one disk. Returns `false` if it was skipped because of a conflict.
+async fn sync_disk(app: &AppHandle, remote: &Remote, settings: &SyncSettings, disk: &str, force: bool) -> Result<bool> {
+    let state = app.state::<DiskSync>();
+    let cipher = state.cipher()?;
+    let id_key = state.id_key()?;
+    let local_generation = state.disks.lock().unwrap().get(disk).map_or(0, |disk| disk.generation);
+
+    let manifest_key = format!("{disk}/manifest");
+    let manifest: Option<Manifest> =
+        match transfer(app, remote, reqwest::Method::GET, &manifest_key, Vec::new()).await? {
+            Some(data) => Some(serde_json::from_slice(&unseal(&cipher, &manifest_key, &data)?)?),
+            None => None,
+        };
+    let remote_generation = manifest.as_ref().map_or(0, |manifest| manifest.generation);
+
+    if remote_generation != local_generation && !force {
+        let conflict = Conflict {
+            disk: disk.to_string(),
+            local_generation,
+            remote_generation,
+            remote_device: manifest.map(|manifest| manifest.device).unwrap_or_default(),
+        };
+        app.emit(CONFLICT_EVENT, &conflict)?;
+        state.update_disk(disk, |state| state.conflict = Some(conflict))?;
+        return Ok(false);
+    }
+
+    // Chunks are compared against what's actually on the remote, so a forced
+    // sync only uploads what differs from the other device's copy
+    let replaced = manifest.map(|manifest| manifest.chunks).unwrap_or_default();
+    let stored: HashSet<&String> = replaced.iter().collect();
+
+    create_collections(app, remote, disk).await?;
+    let source = Source::open(app, disk)?;
+    let image_length = source.length();
+    let mut chunks = Vec::with_capacity(image_length.div_ceil(CHUNK_SIZE) as usize);
+    let mut missing = Vec::new();
+    let mut new = BTreeSet::new();
+    for index in 0..image_length.div_ceil(CHUNK_SIZE) {
+        let id = chunk_id(&id_key, &source.read(index * CHUNK_SIZE, CHUNK_SIZE)?);
+        if !stored.contains(&id) && new.insert(id.clone()) {
+            missing.push(index);
+        }
+        chunks.push(id);
+    }
+
+    // Recorded before uploading, so chunks from a run that's cut short
+    // aren't left on the remote for good
+    state.update_disk(disk, |state| state.unreferenced.extend(new))?;
+    let chunks_total = missing.len() as u64;
+    let mut bytes_uploaded = 0;
+    for (done, index) in missing.into_iter().enumerate() {
+        let key = format!("{disk}/chunks/{}", chunks[index as usize]);
+        let sealed = seal(&cipher, &key, &source.read(index * CHUNK_SIZE, CHUNK_SIZE)?)?;
+        bytes_uploaded += sealed.len() as u64;
+        transfer(app, remote, reqwest::Method::PUT, &key, sealed).await?;
+        app.emit(PROGRESS_EVENT, Progress { disk, chunks_done: done as u64 + 1, chunks_total, bytes_uploaded })?;
+    }
+    drop(source);
+
+    let generation = remote_generation + 1;
+    let manifest = Manifest {
+        device: settings.device_id.clone(),
+        generation,
+        created: now(),
+        image_length,
+        chunk_size: CHUNK_SIZE,
+        chunks,
+    };
+    let sealed = seal(&cipher, &manifest_key, &serde_json::to_vec(&manifest)?)?;
+    transfer(app, remote, reqwest::Method::PUT, &manifest_key, sealed).await?;
+
+    // Only now that the new manifest is in place can chunks go
+    let referenced: HashSet<&String> = manifest.chunks.iter().collect();
+    let mut unreferenced = BTreeSet::new();
+    state.update_disk(disk, |state| {
+        unreferenced = std::mem::take(&mut state.unreferenced);
+        unreferenced.extend(replaced);
+        unreferenced.retain(|id| !referenced.contains(id));
+        state.unreferenced = unreferenced.clone();
+        state.generation = generation;
+        state.last_synced = Some(manifest.created);
+        state.conflict = None;
+        state.last_error = None;
+    })?;
+    for id in unreferenced {
+        transfer(app, remote, reqwest::Method::DELETE, &format!("{disk}/chunks/{id}"), Vec::new()).await?;
+        state.update_disk(disk, |state| {
+            state.unreferenced.remove(&id);
+        })?;
+    }
+    app.emit(DONE_EVENT, Done { disk, generation, chunks_uploaded: chunks_total, bytes_uploaded })?;
+    Ok(true)
+}
+
+/// Clears the running flag when a run finishes or its task is aborted.
+struct Running<'a>(&'a AtomicBool);
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Syncs every configured disk. Errors are reported per disk, so one
+/// unreachable image doesn't hold up the others.
+async fn run(app: &AppHandle, force: bool) -> Result<()> {
+    let state = app.state::<DiskSync>();
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Err(Error::Sync("a sync is already running".into()));
+    }
+    let _running = Running(&state.running);
+
+    let settings = state.settings();
+    let Some(remote) = &settings.remote else {
+        return Err(Error::Config("no sync remote configured".into()));
+    };
+    for disk in &settings.disks {
+        if let Err(error) = sync_disk(app, remote, &settings, disk, force).await {
+            let message = error.to_string();
+            let _ = app.emit(ERROR_EVENT, Failure { disk, message: message.clone() });
+            state.update_disk(disk, |state| state.last_error = Some(message))?;
+        }
+    }
+    Ok(())
+}
+
+/// (Re)starts background runs with the current settings.
+pub fn schedule(app: &AppHandle) {
+    let state = app.state::<DiskSync>();
+    if let Some(handle) = state.scheduler.lock().unwrap().take() {
+        handle.abort();
+    }
+
+    let settings = state.settings();
+    if settings.remote.is_none() || settings.disks.is_empty() || settings.interval_secs == 0 {
+        return;
+    }
+    let interval = Duration::from_secs(settings.interval_secs);
+    let app = app.clone();
+    let handle = tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            // Errors here are a run already in progress or no remote, both
+            // of which are fine to skip
+            let _ = run(&app, false).await;
+        }
+    });
+    *state.scheduler.lock().unwrap() = Some(handle);
+}
+
+#[tauri::command]
+pub fn sync_settings_get(state: State<'_, DiskSync>) -> SyncSettings {
+    state.settings()
+}
+
+/// Validates and stores new settings and restarts background runs.
+#[tauri::command]
+pub fn sync_settings_set(
+    app: AppHandle,
+    state: State<'_, DiskSync>,
+    disks: State<'_, Disks>,
+    settings: SyncSettings,
+) -> Result<()> {
+    if let Some(remote) = &settings.remote {
+        remote.url("")?;
+    }
+    for disk in &settings.disks {
+        disks.path(disk)?;
+    }
+    write_file(&state.settings_path, &serde_json::to_vec_pretty(&settings)?)?;
+    *state.settings.lock().unwrap() = settings;
+    schedule(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn sync_status(state: State<'_, DiskSync>) -> SyncStatus {
+    SyncStatus { running: state.running.load(Ordering::SeqCst), disks: state.disks.lock().unwrap().clone() }
+}
+
+/// Syncs every configured disk now. With `force`, disks in conflict are
+/// uploaded anyway, replacing whatever another device synced.
+#[tauri::command]
+pub async fn sync_now(app: AppHandle, force: Option<bool>) -> Result<()> {
+    run(&app, force.unwrap_or(false)).await
+}
+
+/// Returns the encryption key as base64, for the user to keep somewhere safe.
+#[tauri::command]
+pub fn sync_export_key(state: State<'_, DiskSync>) -> Result<String> {
+    Ok(BASE64.encode(state.key()?))
+}
+
+/// Replaces the encryption key, e.g. with one exported before reinstalling.
+#[tauri::command]
+pub fn sync_import_key(state: State<'_, DiskSync>, key: String) -> Result<()> {
+    let key = BASE64.decode(key.trim())?;
+    if key.len() != KEY_SIZE {
+        return Err(Error::Config(format!("sync keys are {KEY_SIZE} bytes, not {}", key.len())));
+    }
+    write_key(&state.key_path, &key)
+}
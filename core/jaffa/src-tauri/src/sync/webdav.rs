@@ -0,0 +1,44 @@
+//! WebDAV storage (Nextcloud, ownCloud, Apache mod_dav and the like).
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavRemote {
+    /// Collection everything is stored under; it must already exist.
+    pub url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl WebDavRemote {
+    pub fn url(&self, key: &str) -> Result<reqwest::Url> {
+        let mut url = self.url.trim_end_matches('/').to_string();
+        for segment in key.split('/').filter(|segment| !segment.is_empty()) {
+            url.push('/');
+            url.push_str(segment);
+        }
+        reqwest::Url::parse(&url).map_err(|e| Error::Config(format!("invalid WebDAV url {url}: {e}")))
+    }
+
+    pub fn request(
+        &self,
+        client: &reqwest::Client,
+        method: reqwest::Method,
+        url: reqwest::Url,
+        body: Vec<u8>,
+    ) -> reqwest::RequestBuilder {
+        let mut request = client.request(method, url);
+        if let Some(username) = &self.username {
+            request = request.basic_auth(username, self.password.as_ref());
+        }
+        if !body.is_empty() {
+            request = request.body(body);
+        }
+        request
+    }
+}
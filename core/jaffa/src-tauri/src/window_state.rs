@@ -0,0 +1,192 @@
+//! Persists window geometry and zoom per window label across restarts.
+//!
+//! Geometry is tracked from window events as it changes and written to
+//! `window-state.json` in the app config directory when a window closes or
+//! the app exits. Saved positions are only restored onto a monitor that is
+//! still connected, so a window never reopens off-screen after a display is
+//! unplugged.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{
+    AppHandle, LogicalSize, Manager, PhysicalPosition, PhysicalSize, Runtime, State, WebviewWindow, Window,
+    WindowEvent,
+};
+
+use crate::error::{Error, Result};
+
+const STATE_FILE: &str = "window-state.json";
+
+/// Size windows are reset to, matching `tauri.conf.json`.
+const DEFAULT_SIZE: LogicalSize<f64> = LogicalSize { width: 1024.0, height: 768.0 };
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    pub monitor: Option<String>,
+    pub zoom: f64,
+}
+
+pub struct WindowStates {
+    path: PathBuf,
+    states: Mutex<HashMap<String, WindowState>>,
+}
+
+impl WindowStates {
+    pub fn load(app: &AppHandle) -> Result<Self> {
+        let path = app.path().app_config_dir()?.join(STATE_FILE);
+        let states = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data).unwrap_or_default(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, states: Mutex::new(states) })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let states = self.states.lock().unwrap();
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&*states)?)?;
+        Ok(())
+    }
+
+    /// Applies the saved state of `window`, if any.
+    pub fn restore<R: Runtime>(&self, window: &WebviewWindow<R>) -> Result<()> {
+        let Some(state) = self.states.lock().unwrap().get(window.label()).cloned() else {
+            return Ok(());
+        };
+
+        let on_screen = window.available_monitors()?.iter().any(|monitor| {
+            let same = state.monitor.is_none() || monitor.name() == state.monitor.as_ref();
+            let origin = monitor.position();
+            let size = monitor.size();
+            same && state.x >= origin.x
+                && state.y >= origin.y
+                && state.x < origin.x + size.width as i32
+                && state.y < origin.y + size.height as i32
+        });
+        if on_screen {
+            window.set_position(PhysicalPosition::new(state.x, state.y))?;
+        }
+        window.set_size(PhysicalSize::new(state.width, state.height))?;
+        if state.maximized {
+            window.maximize()?;
+        }
+        if state.fullscreen {
+            window.set_fullscreen(true)?;
+        }
+        if state.zoom != 1.0 {
+            window.set_zoom(state.zoom)?;
+        }
+        Ok(())
+    }
+
+    /// Records the current geometry of `window`. While maximized or in
+    /// fullscreen the previous normal bounds are kept, so un-maximizing after
+    /// a restore returns to where the user left the window.
+    fn track<R: Runtime>(&self, window: &Window<R>) -> tauri::Result<()> {
+        let maximized = window.is_maximized()?;
+        let fullscreen = window.is_fullscreen()?;
+        if window.is_minimized()? {
+            return Ok(());
+        }
+
+        let mut states = self.states.lock().unwrap();
+        let zoom = states.get(window.label()).map_or(1.0, |state| state.zoom);
+        let state = if maximized || fullscreen {
+            let Some(previous) = states.get(window.label()) else { return Ok(()) };
+            WindowState { maximized, fullscreen, ..previous.clone() }
+        } else {
+            let position = window.outer_position()?;
+            let size = window.inner_size()?;
+            WindowState {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized,
+                fullscreen,
+                monitor: window.current_monitor()?.and_then(|m| m.name().cloned()),
+                zoom,
+            }
+        };
+        states.insert(window.label().to_string(), state);
+        Ok(())
+    }
+}
+
+/// Window event hook registered with the builder.
+pub fn on_window_event<R: Runtime>(window: &Window<R>, event: &WindowEvent) {
+    let Some(states) = window.try_state::<WindowStates>() else { return };
+    match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+            let _ = states.track(window);
+        }
+        WindowEvent::CloseRequested { .. } => {
+            let _ = states.track(window);
+            let _ = states.save();
+        }
+        _ => {}
+    }
+}
+
+/// Sets and remembers the zoom level of a window; the kernel calls this
+/// whenever the user zooms so it can be restored on the next launch.
+#[tauri::command]
+pub fn window_state_set_zoom(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, WindowStates>,
+    zoom: f64,
+) -> Result<()> {
+    if !(0.2..=5.0).contains(&zoom) {
+        return Err(Error::Config(format!("zoom level {zoom} out of range")));
+    }
+    if let Some(webview) = app.get_webview_window(window.label()) {
+        webview.set_zoom(zoom)?;
+    }
+    state.track(&window)?;
+    if let Some(saved) = state.states.lock().unwrap().get_mut(window.label()) {
+        saved.zoom = zoom;
+    }
+    state.save()
+}
+
+/// Forgets the saved layout of `label` (or of every window when omitted) and
+/// returns those windows to their default size, centered, at 100% zoom.
+#[tauri::command]
+pub fn window_state_reset(app: AppHandle, state: State<'_, WindowStates>, label: Option<String>) -> Result<()> {
+    {
+        let mut states = state.states.lock().unwrap();
+        match &label {
+            Some(label) => {
+                states.remove(label);
+            }
+            None => states.clear(),
+        }
+    }
+    state.save()?;
+
+    for (window_label, window) in app.webview_windows() {
+        if label.as_ref().is_some_and(|label| *label != window_label) {
+            continue;
+        }
+        window.set_fullscreen(false)?;
+        window.unmaximize()?;
+        window.set_size(DEFAULT_SIZE)?;
+        window.center()?;
+        window.set_zoom(1.0)?;
+    }
+    Ok(())
+}
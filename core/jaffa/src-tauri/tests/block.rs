@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+
+use ecmaos_jaffa_lib::block::{BlockImage, MAX_CAPACITY};
+
+/// A path for an image in a directory of its own, removed first in case an
+/// earlier run left it behind.
+fn image_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("jaffa-block-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("test.img")
+}
+
+#[test]
+fn duplicate_blocks_are_freed_once() {
+    let path = image_path("free");
+    let mut image = BlockImage::create(&path, 512, 16).unwrap();
+    assert_eq!(image.allocate(2).unwrap(), [0, 1]);
+
+    image.free(&[0, 0]).unwrap();
+    assert_eq!(image.header().allocated, 1);
+    assert!(!image.is_allocated(0));
+    assert!(image.is_allocated(1));
+
+    image.free(&[1, 1, 1]).unwrap();
+    assert_eq!(image.header().allocated, 0);
+    assert!(image.free(&[0]).is_err());
+    assert_eq!(image.header().allocated, 0);
+}
+
+#[test]
+fn capacity_is_bounded() {
+    let path = image_path("capacity");
+    assert!(BlockImage::create(&path, 512, MAX_CAPACITY + 1).is_err());
+    assert!(BlockImage::create(&path, 512, u64::MAX).is_err());
+    assert!(!path.exists());
+}
+
+#[test]
+fn a_corrupt_allocated_count_is_an_error() {
+    let path = image_path("corrupt");
+    let capacity = 16u64;
+    drop(BlockImage::create(&path, 512, capacity).unwrap());
+
+    // The allocated count sits at bytes 24..32 of the header
+    let mut data = std::fs::read(&path).unwrap();
+    data[24..32].copy_from_slice(&(capacity + 5).to_le_bytes());
+    std::fs::write(&path, &data).unwrap();
+    let mut image = BlockImage::open(&path).unwrap();
+    assert!(image.allocate(1).is_err());
+    drop(image);
+
+    // A block marked in the bitmap that the header doesn't count; the
+    // bitmap starts at block 1
+    data[24..32].copy_from_slice(&0u64.to_le_bytes());
+    data[512] = 1;
+    std::fs::write(&path, &data).unwrap();
+    let mut image = BlockImage::open(&path).unwrap();
+    assert!(image.free(&[0]).is_err());
+    assert!(image.is_allocated(0));
+    assert_eq!(image.header().allocated, 0);
+}
+
+#[test]
+fn rollback_restores_every_preserved_block() {
+    let path = image_path("rollback");
+    let mut image = BlockImage::create(&path, 512, 64).unwrap();
+    let blocks = image.allocate(8).unwrap();
+    for &block in &blocks {
+        image.write(block, &[block as u8 + 1; 512]).unwrap();
+    }
+    let snapshot = image.snapshot("before".into(), None).unwrap();
+
+    for &block in &blocks {
+        image.write(block, &[0xee; 512]).unwrap();
+    }
+    image.free(&blocks[4..]).unwrap();
+    image.rollback(&snapshot.id).unwrap();
+
+    assert_eq!(image.header().allocated, 8);
+    for &block in &blocks {
+        assert_eq!(image.read(block, 1).unwrap(), [block as u8 + 1; 512]);
+    }
+}
+
+#[test]
+fn an_image_in_use_cant_be_opened_again() {
+    let path = image_path("lock");
+    let image = BlockImage::create(&path, 512, 16).unwrap();
+    assert!(BlockImage::open(&path).is_err());
+    drop(image);
+    let image = BlockImage::open(&path).unwrap();
+    assert!(BlockImage::open(&path).is_err());
+    drop(image);
+    BlockImage::open(&path).unwrap();
+}
+
+#[test]
+fn an_unclean_close_recounts_allocated_blocks() {
+    let path = image_path("recount");
+    let mut image = BlockImage::create(&path, 512, 16).unwrap();
+    image.allocate(3).unwrap();
+    image.mark_dirty(true).unwrap();
+    drop(image);
+
+    // mark_dirty wrote the header, counting three blocks, but not the
+    // bitmap, as if the crash came between the two
+    let image = BlockImage::open(&path).unwrap();
+    assert_eq!(image.header().allocated, 0);
+    assert!(!image.is_allocated(0));
+}
+
+#[test]
+fn a_snapshot_view_reads_the_image_as_it_was() {
+    let path = image_path("view");
+    let mut image = BlockImage::create(&path, 512, 64).unwrap();
+    let blocks = image.allocate(4).unwrap();
+    for &block in &blocks {
+        image.write(block, &[block as u8 + 1; 512]).unwrap();
+    }
+    let snapshot = image.snapshot("view".into(), None).unwrap();
+    let before = std::fs::read(&path).unwrap();
+    let view = image.snapshot_view(&snapshot.id).unwrap();
+
+    image.write(blocks[1], &[0xee; 512]).unwrap();
+    image.free(&blocks[2..]).unwrap();
+    let more = image.allocate(8).unwrap();
+    image.write(more[7], &[0xdd; 512]).unwrap();
+    image.flush().unwrap();
+
+    assert_eq!(view.file_length(), before.len() as u64);
+    assert_eq!(image.read_snapshot(&view, 0, u64::MAX).unwrap(), before);
+    // Reads that don't start or end on a block boundary
+    assert_eq!(image.read_snapshot(&view, 700, 1000).unwrap(), before[700..1700]);
+    let tail = before.len() as u64 - 300;
+    assert_eq!(image.read_snapshot(&view, tail, 1000).unwrap(), before[tail as usize..]);
+    assert!(image.read_snapshot(&view, before.len() as u64, 10).unwrap().is_empty());
+}
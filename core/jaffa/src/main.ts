@@ -5,6 +5,8 @@ import { Kernel } from '@ecmaos/kernel'
 import type { Kernel as IKernel, Shell, Terminal } from '@ecmaos/types'
 import '@ecmaos/kernel/ui.css'
 
+import { handleOpenFiles } from './open-files'
+
 
 declare global {
   var kernel: IKernel | undefined // eslint-disable-line no-var
@@ -42,5 +44,6 @@ globalThis.kernel = primaryKernel
 
 kernel.terminal.mount(document.getElementById('terminal') as HTMLElement)
 kernel.boot({ silent: import.meta.env.NODE_ENV === 'test', figletFontRandom: false })
+  .then(() => handleOpenFiles(kernel))
 
 
@@ -0,0 +1,115 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import type { Kernel } from '@ecmaos/types'
+
+interface PendingOpen {
+  id: string
+  name: string
+  size: number
+  app: string | null
+  destination: string | null
+}
+
+const CHUNK_SIZE = 1024 * 1024
+
+// Pending lists fetched on boot and on events can overlap
+const importing = new Set<string>()
+
+/**
+ * Creates an empty file for the import in `directory`, numbering the name
+ * when a file by that name is already there rather than overwriting it.
+ */
+async function createTarget (kernel: Kernel, directory: string, name: string) {
+  const dot = name.lastIndexOf('.')
+  const [stem, extension] = dot > 0 ? [name.slice(0, dot), name.slice(dot)] : [name, '']
+  for (let copy = 0; ; copy++) {
+    const target = `${directory}/${copy ? `${stem} (${copy})${extension}` : name}`
+    try {
+      await kernel.filesystem.fs.writeFile(target, new Uint8Array(), { flag: 'wx' })
+      return target
+    } catch (error) {
+      if ((error as { code?: string }).code !== 'EEXIST') throw error
+    }
+  }
+}
+
+/**
+ * Finds `command` the way the shell does: as a path, or in a directory on
+ * `PATH`.
+ */
+async function resolveCommand (kernel: Kernel, command: string) {
+  if (command.includes('/')) return command
+  const env = kernel.shell.env
+  for (const entry of (env.get('PATH') || '/bin:/usr/bin').split(':')) {
+    const directory = entry.replace(/\$([A-Z_]+)/g, (_, name) => env.get(name) || '')
+    const candidate = `${directory}/${command}`
+    if (await kernel.filesystem.exists(candidate)) return candidate
+  }
+  return undefined
+}
+
+/**
+ * Runs the association's command line with the imported file. The line
+ * is split into words and `%f` filled in within each one, and the words
+ * go to the kernel as they are, since a host file name can hold anything
+ * the shell would otherwise expand.
+ */
+async function launch (kernel: Kernel, app: string, target: string) {
+  const words = app.trim().split(/\s+/)
+  const args = app.includes('%f') ? words.map(word => word.replace(/%f/g, target)) : [...words, target]
+  const [name, ...rest] = args
+  const command = name && await resolveCommand(kernel, name)
+  if (!command) throw new Error(`Command not found: ${name}`)
+
+  const { shell, terminal } = kernel
+  await kernel.execute({
+    command,
+    args: rest,
+    kernel,
+    shell,
+    terminal,
+    stdin: terminal.getInputStream(),
+    stdinIsTTY: true,
+    stdout: terminal.stdout,
+    stdoutIsTTY: true,
+    stderr: terminal.stderr
+  })
+}
+
+async function importFile (kernel: Kernel, file: PendingOpen) {
+  const directory = (file.destination || kernel.shell.env.get('HOME') || '/root').replace(/\/$/, '')
+
+  await kernel.filesystem.fs.mkdir(directory, { recursive: true })
+  const target = await createTarget(kernel, directory, file.name)
+  for (let offset = 0; offset < file.size; offset += CHUNK_SIZE) {
+    const chunk = await invoke<ArrayBuffer>('open_file_read', { id: file.id, offset, length: CHUNK_SIZE })
+    await kernel.filesystem.fs.appendFile(target, new Uint8Array(chunk))
+  }
+  await invoke('open_file_done', { id: file.id })
+
+  if (file.app) await launch(kernel, file.app, target)
+}
+
+async function importPending (kernel: Kernel) {
+  const pending = await invoke<PendingOpen[]>('open_files_pending')
+  for (const file of pending) {
+    if (importing.has(file.id)) continue
+    importing.add(file.id)
+    try {
+      await importFile(kernel, file)
+    } catch (error) {
+      kernel.log.error(`Failed to open ${file.name}: ${error}`)
+    } finally {
+      importing.delete(file.id)
+    }
+  }
+}
+
+/**
+ * Imports files the host asked ecmaOS to open, both those passed at launch
+ * and any that arrive while running.
+ */
+export async function handleOpenFiles (kernel: Kernel) {
+  await listen('open-file://pending', () => importPending(kernel))
+  await importPending(kernel)
+}
@@ -2048,6 +2048,7 @@ export class Kernel implements IKernel {
       shell.env.set('HOSTNAME', globalThis.location.hostname || 'localhost')
       process.env = Object.fromEntries(shell.env)
       await shell.loadEnvFile()
+      await shell.loadProfile()
       await shell.loadConfig()
       this.updateLocaleFromEnv()
       return
@@ -2173,6 +2174,8 @@ export class Kernel implements IKernel {
         shell.env.set('HOME', userCred.user.home || '/root')
         shell.env.set('USER', userCred.user.username)
         process.env = Object.fromEntries(shell.env)
+        // The profiles see the session's user and home directory
+        await shell.loadProfile()
         
         const langEnv = shell.env.get('LANG')
         if (langEnv) {
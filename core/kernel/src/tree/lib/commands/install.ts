@@ -1,6 +1,7 @@
 import chalk from 'chalk'
 import path from 'path'
 import semver from 'semver'
+import type { WasmFeature } from '@ecmaos/types'
 
 import { CommandArgs } from './'
 
@@ -26,9 +27,11 @@ const install = async ({ kernel, shell, terminal, args }: CommandArgs) => {
     return 1
   }
 
-  const url = `${registry}/${packageName}`
-  const packageInfo = await globalThis.fetch(url)
-  const data = await packageInfo.json()
+  // A registry on the filesystem, such as a mounted ecmaos-mirror directory
+  const local = registry.startsWith('/')
+  const data = local
+    ? JSON.parse(await kernel.filesystem.fs.readFile(path.join(registry, packageName, 'index.json'), 'utf-8'))
+    : await (await globalThis.fetch(`${registry}/${packageName}`)).json()
 
   if (!data.versions || !data['dist-tags']) {
     terminal.writeln(chalk.red(`No versions found for ${packageName}`))
@@ -54,6 +57,14 @@ const install = async ({ kernel, shell, terminal, args }: CommandArgs) => {
 
   terminal.writeln(`Installing ${data.name} v${version} from ${registry}...`)
 
+  // Modules using WASM features the browser lacks would fail to compile when run
+  const features: WasmFeature[] = data.versions[version]?.ecmaos?.features || []
+  const supported = kernel.wasm.supportedFeatures()
+  const unsupported = features.filter(feature => supported[feature] === false)
+  if (unsupported.length > 0) {
+    terminal.writeln(chalk.yellow(`Warning: ${packageName}@${version} uses WebAssembly features this browser doesn't support: ${unsupported.join(', ')}`))
+  }
+
   const tarballUrl = data.versions[version]?.dist?.tarball
   const tarballChecksum = data.versions[version]?.dist?.shasum?.toLowerCase()
   if (!tarballUrl || !tarballChecksum) {
@@ -61,8 +72,9 @@ const install = async ({ kernel, shell, terminal, args }: CommandArgs) => {
     return 1
   }
 
-  const tarball = await globalThis.fetch(tarballUrl)
-  const arrayBuffer = await tarball.arrayBuffer()
+  const arrayBuffer = local
+    ? Uint8Array.from(await kernel.filesystem.fs.readFile(path.join(registry, packageName, tarballUrl))).buffer
+    : await (await globalThis.fetch(tarballUrl)).arrayBuffer()
   const hashBuffer = await crypto.subtle.digest('SHA-1', arrayBuffer)
   const hashArray = Array.from(new Uint8Array(hashBuffer))
   const downloadChecksum = hashArray.map(b => b.toString(16).padStart(2, '0')).join('')
@@ -15,6 +15,7 @@ import type { Kernel, Shell as IShell, ShellOptions, ShellConfig as IShellConfig
 import { ThemePresets } from '@ecmaos/types'
 
 const DefaultShellPath = '$HOME/bin:/bin:/usr/bin:/usr/local/bin:/usr/local/sbin:/usr/sbin:/sbin'
+const ProfileHelperPath = '/usr/libexec/profile.wasm'
 const DefaultShellOptions = {
   cwd: '/',
   env: {
@@ -107,6 +108,50 @@ export class Shell implements IShell {
     } catch {}
   }
 
+  /**
+   * Applies /etc/profile and ~/.profile, as evaluated by the profile helper (utils/profile) if it's installed
+   */
+  async loadProfile() {
+    if (!await this.context.fs.promises.exists(ProfileHelperPath)) return
+
+    const chunks: Uint8Array[] = []
+    const stdout = new WritableStream<Uint8Array>({
+      write(chunk) {
+        chunks.push(chunk)
+      }
+    })
+
+    // The helper lists what it skipped in the profiles as warnings
+    const stderr = new WritableStream<Uint8Array>({
+      write: (chunk) => {
+        for (const line of new TextDecoder().decode(chunk).split('\n'))
+          if (line) this._kernel.log.warn(`${ProfileHelperPath}: ${line}`)
+      }
+    })
+
+    try {
+      const exitCode = await this._kernel.execute({
+        command: ProfileHelperPath,
+        args: [],
+        kernel: this._kernel,
+        shell: this,
+        terminal: this._terminal,
+        stdin: new ReadableStream<Uint8Array>(),
+        stdout,
+        stderr
+      })
+      if (exitCode !== 0) return
+
+      const output = new TextDecoder().decode(new Uint8Array(chunks.flatMap(chunk => [...chunk])))
+      const { set, unset } = JSON.parse(output) as { set: Record<string, string>, unset: string[] }
+      for (const [key, value] of Object.entries(set)) this._env.set(key, value)
+      for (const key of unset) this._env.delete(key)
+      process.env = Object.fromEntries(this._env)
+    } catch (error) {
+      this._kernel.log.warn(`Couldn't load the profile: ${(error as Error).message}`)
+    }
+  }
+
   /**
    * Loads shell configuration from config files and applies to terminal
    */
@@ -1,4 +1,4 @@
-import type { Kernel, WasmOptions, Wasm as IWasm, Shell } from '@ecmaos/types'
+import type { Kernel, WasmOptions, Wasm as IWasm, WasmFeature, Shell } from '@ecmaos/types'
 
 import createWasiPreview1Bindings from './wasi/preview1'
 import createWasiPreview2Bindings from './wasi/preview2'
@@ -14,9 +14,29 @@ export interface WasiComponentResult {
   exitCode: Promise<number>
 }
 
+// The smallest modules using each feature, which only validate where it's supported
+const probeHeader = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]
+const probeVoidType = [0x01, 0x04, 0x01, 0x60, 0x00, 0x00]
+// One function of type 0, with any sections that go between the function and code sections
+const probeFunction = (body: number[], sections: number[] = []) =>
+  [0x03, 0x02, 0x01, 0x00, ...sections, 0x0a, body.length + 2, 0x01, body.length, ...body]
+const featureProbes: Record<WasmFeature, number[]> = {
+  // memory.fill
+  'bulk-memory': [...probeVoidType, ...probeFunction([0x00, 0x41, 0x00, 0x41, 0x00, 0x41, 0x00, 0xfc, 0x0b, 0x00, 0x0b], [0x05, 0x03, 0x01, 0x00, 0x00])],
+  // A tag and throw
+  'exception-handling': [...probeVoidType, ...probeFunction([0x00, 0x08, 0x00, 0x0b], [0x0d, 0x03, 0x01, 0x00, 0x00])],
+  // ref.null extern
+  'reference-types': [...probeVoidType, ...probeFunction([0x00, 0xd0, 0x6f, 0x1a, 0x0b])],
+  // v128.const
+  'simd': [0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7b, ...probeFunction([0x00, 0xfd, 0x0c, ...new Array(16).fill(0), 0x0b])],
+  // A shared memory
+  'threads': [0x05, 0x04, 0x01, 0x03, 0x01, 0x01]
+}
+
 export class Wasm implements IWasm {
   private _kernel: Kernel
   private _modules: Map<string, { module: WebAssembly.Module; instance: WebAssembly.Instance }> = new Map()
+  private _supportedFeatures?: Record<WasmFeature, boolean>
 
   get modules() { return this._modules }
 
@@ -124,6 +144,27 @@ export class Wasm implements IWasm {
     return version !== null
   }
 
+  /**
+   * Probe which post-MVP features the browser supports by validating a tiny module using each
+   * Shared memories also need cross-origin isolation, without which threads count as unsupported
+   */
+  supportedFeatures(): Record<WasmFeature, boolean> {
+    if (this._supportedFeatures) return this._supportedFeatures
+
+    const supported = {} as Record<WasmFeature, boolean>
+    for (const [feature, sections] of Object.entries(featureProbes) as [WasmFeature, number[]][]) {
+      try {
+        supported[feature] = WebAssembly.validate(new Uint8Array([...probeHeader, ...sections]))
+      } catch {
+        supported[feature] = false
+      }
+    }
+
+    supported.threads &&= typeof SharedArrayBuffer !== 'undefined' && globalThis.crossOriginIsolated !== false
+    this._supportedFeatures = supported
+    return supported
+  }
+
   /**
    * Detect if a WASM module imports memory from a specific module
    * Returns the initial and maximum pages required, or null if not imported
@@ -34,6 +34,12 @@ export interface WasiComponentResult {
   exitCode: Promise<number>
 }
 
+/**
+ * A WebAssembly feature beyond the first version of the standard, as listed
+ * in the `ecmaos.features` field of a package's manifest
+ */
+export type WasmFeature = 'bulk-memory' | 'exception-handling' | 'reference-types' | 'simd' | 'threads'
+
 /**
  * Interface for WebAssembly management functionality
  */
@@ -57,6 +63,12 @@ export interface Wasm {
    */
   detectWasiRequirements(wasmBytes: Uint8Array): Promise<boolean>
 
+  /**
+   * Probe which WebAssembly features the current browser supports
+   * @returns Whether each feature is supported
+   */
+  supportedFeatures(): Record<WasmFeature, boolean>
+
   /**
    * Load a WASI component with stream integration
    * @param path - Path to the WASM file
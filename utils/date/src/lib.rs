@@ -0,0 +1,46 @@
+//! Conversions between days since 1970-01-01 and dates in the proleptic
+//! Gregorian calendar, for code that prints or parses dates in UTC without
+//! a date crate.
+//!
+//! These are Howard Hinnant's `days_from_civil` and `civil_from_days`.
+//! Dates are counted in 400-year eras starting on 0000-03-01, which puts
+//! the leap day at the end of each year, and they hold for any date before
+//! or after the epoch.
+//!
+//! ```
+//! use ecmaos_date::{civil_from_days, days_from_civil};
+//!
+//! assert_eq!(civil_from_days(0), (1970, 1, 1));
+//! assert_eq!(days_from_civil(2000, 2, 29), 11_016);
+//! ```
+
+/// Days in a 400-year era.
+const ERA: i64 = 146_097;
+
+/// Days from 0000-03-01 to 1970-01-01.
+const EPOCH: i64 = 719_468;
+
+/// The year, month (1 to 12) and day (1 to 31) `days` after 1970-01-01.
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + EPOCH;
+    let era = days.div_euclid(ERA);
+    let day_of_era = days - era * ERA;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// The days from 1970-01-01 to a date, negative before it. The month and
+/// day aren't checked against the calendar.
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * ERA + day_of_era - EPOCH
+}
@@ -0,0 +1,44 @@
+use ecmaos_date::{civil_from_days, days_from_civil};
+
+/// Dates and their days since 1970-01-01, from Python's `datetime`.
+const DATES: &[((i64, i64, i64), i64)] = &[
+    ((1970, 1, 1), 0),
+    ((1969, 12, 31), -1),
+    ((2000, 2, 29), 11_016),
+    ((2024, 3, 1), 19_783),
+    ((1900, 3, 1), -25_508),
+    ((1600, 2, 29), -135_081),
+    ((9999, 12, 31), 2_932_896),
+];
+
+#[test]
+fn known_dates() {
+    for &(date, days) in DATES {
+        assert_eq!(civil_from_days(days), date, "{days}");
+        assert_eq!(days_from_civil(date.0, date.1, date.2), days, "{date:?}");
+    }
+}
+
+#[test]
+fn every_day_follows_the_last() {
+    let mut previous = civil_from_days(-800_000);
+    for days in -799_999..800_000 {
+        let date = civil_from_days(days);
+        let (year, month, day) = previous;
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let length = match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+        let expected = match (month, day) {
+            (12, 31) => (year + 1, 1, 1),
+            (_, day) if day == length => (year, month + 1, 1),
+            _ => (year, month, day + 1),
+        };
+        assert_eq!(date, expected, "{days}");
+        assert_eq!(days_from_civil(date.0, date.1, date.2), days);
+        previous = date;
+    }
+}
@@ -0,0 +1,244 @@
+//! The copy itself: input blocks read one at a time and written out as
+//! they came, with a tally of what went through.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+use crate::operands::Options;
+
+/// Records and bytes copied so far. A record is a block read or written;
+/// a partial one is shorter than the block size.
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub full_in: u64,
+    pub partial_in: u64,
+    pub full_out: u64,
+    pub partial_out: u64,
+    pub bytes: u64,
+}
+
+impl Stats {
+    fn records_in(&self) -> u64 {
+        self.full_in + self.partial_in
+    }
+
+    /// The `records in` and `records out` lines.
+    pub fn records(&self) -> String {
+        format!("{}+{} records in\n{}+{} records out", self.full_in, self.partial_in, self.full_out, self.partial_out)
+    }
+
+    /// The bytes copied in `elapsed` and the rate, with the seconds to
+    /// `precision` decimal places: `1048576 bytes (1.0 MB, 1.0 MiB) copied,
+    /// 0.012 s, 87 MB/s`.
+    pub fn transfer(&self, elapsed: Duration, precision: usize) -> String {
+        let mut line = format!("{} byte{}", self.bytes, if self.bytes == 1 { "" } else { "s" });
+        if self.bytes >= 1024 {
+            line += &format!(" ({}, {})", human(self.bytes as f64, 1000.0, SI), human(self.bytes as f64, 1024.0, IEC));
+        } else if self.bytes >= 1000 {
+            line += &format!(" ({})", human(self.bytes as f64, 1000.0, SI));
+        }
+        let seconds = elapsed.as_secs_f64();
+        let rate = match seconds {
+            0.0 => "Infinity B".to_string(),
+            _ => human(self.bytes as f64 / seconds, 1000.0, SI),
+        };
+        format!("{line} copied, {seconds:.precision$} s, {rate}/s")
+    }
+}
+
+const SI: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+const IEC: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// `value` in the largest unit it makes at least one of, to two significant
+/// figures or as a whole number.
+fn human(mut value: f64, base: f64, units: [&str; 5]) -> String {
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    if unit > 0 && value < 10.0 {
+        format!("{value:.1} {}", units[unit])
+    } else {
+        format!("{value:.0} {}", units[unit])
+    }
+}
+
+/// An I/O error, and the file it happened on.
+#[derive(Debug)]
+pub struct Error {
+    pub name: String,
+    pub source: io::Error,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.source)
+    }
+}
+
+fn error(name: &str) -> impl FnOnce(io::Error) -> Error + '_ {
+    move |source| Error { name: name.to_string(), source }
+}
+
+enum Input {
+    Stdin(io::Stdin),
+    File(File),
+}
+
+impl Read for Input {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::Stdin(stdin) => stdin.read(buffer),
+            Input::File(file) => file.read(buffer),
+        }
+    }
+}
+
+impl Input {
+    /// Moves `bytes` further into the input: by seeking if it's a file that
+    /// can, and otherwise by reading them.
+    fn skip(&mut self, bytes: u64) -> io::Result<()> {
+        if let Input::File(file) = self {
+            if let Ok(offset) = i64::try_from(bytes) {
+                if file.seek(SeekFrom::Current(offset)).is_ok() {
+                    return Ok(());
+                }
+            }
+        }
+        io::copy(&mut self.take(bytes), &mut io::sink()).map(drop)
+    }
+}
+
+enum Output {
+    Stdout(io::Stdout),
+    File(File),
+}
+
+impl Write for Output {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buffer),
+            Output::File(file) => file.write(buffer),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::File(file) => file.flush(),
+        }
+    }
+}
+
+/// Opens the output and moves `offset` bytes into it, having truncated it
+/// there unless that's turned off.
+fn open_output(path: &str, options: &Options, offset: u64) -> io::Result<File> {
+    let mut open = OpenOptions::new();
+    open.write(true);
+    if options.conv.excl {
+        open.create_new(true);
+    } else if !options.conv.nocreat {
+        open.create(true);
+    }
+    let mut file = open.open(path)?;
+    // Devices and the like can't be truncated, and needn't be
+    if !options.conv.notrunc && file.metadata()?.is_file() {
+        file.set_len(offset)?;
+    }
+    if offset > 0 {
+        file.seek(SeekFrom::Start(offset))?;
+    }
+    Ok(file)
+}
+
+/// Reads once into `buffer`, trying again if interrupted.
+fn read_block(input: &mut Input, buffer: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match input.read(buffer) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+/// Copies as `options` say, tallying into `stats` (which holds what was
+/// copied even if it fails part way) and calling `progress` after every
+/// block.
+pub fn copy(options: &Options, stats: &mut Stats, mut progress: impl FnMut(&Stats)) -> Result<(), Error> {
+    let block_size = options.block_size;
+    let bytes = |blocks: u64| {
+        let overflow = || io::Error::new(io::ErrorKind::InvalidInput, "offset too large");
+        blocks.checked_mul(block_size as u64).ok_or_else(overflow)
+    };
+    let input_name = options.input.as_deref().unwrap_or("standard input");
+    let output_name = options.output.as_deref().unwrap_or("standard output");
+
+    let mut input = match &options.input {
+        Some(path) => Input::File(File::open(path).map_err(error(input_name))?),
+        None => Input::Stdin(io::stdin()),
+    };
+    if options.skip > 0 {
+        let skip = bytes(options.skip).map_err(error(input_name))?;
+        input.skip(skip).map_err(error(input_name))?;
+    }
+    let seek = bytes(options.seek).map_err(error(output_name))?;
+    let mut output = match &options.output {
+        Some(path) => Output::File(open_output(path, options, seek).map_err(error(output_name))?),
+        None if options.seek > 0 => {
+            let source = io::Error::new(io::ErrorKind::Unsupported, "can't seek in standard output");
+            return Err(error(output_name)(source));
+        }
+        None => Output::Stdout(io::stdout()),
+    };
+
+    let mut buffer = vec![0; block_size];
+    while options.count.is_none_or(|count| stats.records_in() < count) {
+        let mut length = match read_block(&mut input, &mut buffer) {
+            Ok(0) => break,
+            Ok(length) => {
+                if length == block_size {
+                    stats.full_in += 1;
+                } else {
+                    stats.partial_in += 1;
+                }
+                length
+            }
+            // The block that couldn't be read is skipped, and counts as a
+            // partial record with nothing in it
+            Err(e) if options.conv.noerror => {
+                eprintln!("error: {}: {}", input_name, e);
+                stats.partial_in += 1;
+                input.skip(block_size as u64).map_err(error(input_name))?;
+                0
+            }
+            Err(e) => return Err(error(input_name)(e)),
+        };
+        if options.conv.sync && length < block_size {
+            buffer[length..].fill(0);
+            length = block_size;
+        }
+        if length > 0 {
+            output.write_all(&buffer[..length]).map_err(error(output_name))?;
+            if length == block_size {
+                stats.full_out += 1;
+            } else {
+                stats.partial_out += 1;
+            }
+            stats.bytes += length as u64;
+        }
+        progress(stats);
+    }
+
+    output.flush().map_err(error(output_name))?;
+    if let Output::File(file) = &output {
+        if options.conv.fsync {
+            file.sync_all().map_err(error(output_name))?;
+        } else if options.conv.fdatasync {
+            file.sync_data().map_err(error(output_name))?;
+        }
+    }
+    Ok(())
+}
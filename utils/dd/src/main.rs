@@ -0,0 +1,74 @@
+//! Copies data block by block, like dd(1).
+//!
+//! ```text
+//! dd [if=FILE] [of=FILE] [bs=BYTES] [count=N] [skip=N] [seek=N] [conv=FLAG,...] [status=LEVEL]
+//! ```
+//!
+//! Input is read a block of `bs` bytes (512 unless given) at a time and each
+//! block written out as it came, so offsets in the input and output land
+//! exactly where they're asked to: `skip` and `seek` are in blocks, and
+//! `count` is how many input blocks to copy. That makes it a tool both for
+//! working with disk images for the block-image backend and for checking
+//! that the kernel's filesystems read and write at the right offsets. Sizes
+//! take the usual suffixes (see [`operands::size`]).
+//!
+//! `conv` flags: `notrunc` leaves the rest of an existing output as it is,
+//! `sync` pads short input blocks with NULs, `noerror` carries on past read
+//! errors, `fsync` and `fdatasync` flush the output to storage at the end,
+//! and `excl` and `nocreat` insist the output is new or already there.
+//!
+//! When done, or stopped by an error, the records copied (`full+partial`)
+//! and the bytes, time and rate are written to stderr; `status=noxfer`
+//! leaves the rate out and `status=none` everything. `status=progress` adds
+//! a line every second of the copy, timed on the monotonic clock. Exits
+//! with 1 if anything couldn't be read or written, and 2 for bad operands.
+
+mod copy;
+mod operands;
+
+use std::env;
+use std::time::{Duration, Instant};
+
+use copy::Stats;
+use operands::Status;
+
+const USAGE: &str = "usage: dd [if=FILE] [of=FILE] [bs=BYTES] [count=N] [skip=N] [seek=N] \
+                     [conv=notrunc,sync,noerror,fsync,fdatasync,excl,nocreat] [status=none|noxfer|progress]";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = operands::parse(args).unwrap_or_else(|message| usage_error(&message));
+
+    let start = Instant::now();
+    let mut next_progress = Duration::from_secs(1);
+    let mut stats = Stats::default();
+    let result = copy::copy(&options, &mut stats, |stats| {
+        if options.status == Status::Progress && start.elapsed() >= next_progress {
+            eprintln!("{}", stats.transfer(start.elapsed(), 0));
+            next_progress += Duration::from_secs(1);
+        }
+    });
+    let elapsed = start.elapsed();
+
+    if let Err(e) = &result {
+        eprintln!("error: {}", e);
+    }
+    if options.status != Status::None {
+        eprintln!("{}", stats.records());
+        if options.status != Status::NoXfer {
+            eprintln!("{}", stats.transfer(elapsed, 6));
+        }
+    }
+    if result.is_err() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,138 @@
+//! dd's `NAME=VALUE` operands.
+
+/// What to copy, and how, as given on the command line.
+#[derive(Debug)]
+pub struct Options {
+    /// The file to read; standard input if not given.
+    pub input: Option<String>,
+    /// The file to write; standard output if not given.
+    pub output: Option<String>,
+    pub block_size: usize,
+    /// How many input blocks to copy; all of them if not given.
+    pub count: Option<u64>,
+    /// Blocks to skip at the start of the input.
+    pub skip: u64,
+    /// Blocks to skip at the start of the output.
+    pub seek: u64,
+    pub conv: Conv,
+    pub status: Status,
+}
+
+/// The `conv=` flags.
+#[derive(Debug, Default)]
+pub struct Conv {
+    /// Don't truncate the output.
+    pub notrunc: bool,
+    /// Pad short input blocks with NULs to the block size.
+    pub sync: bool,
+    /// Carry on past read errors.
+    pub noerror: bool,
+    /// Flush the output's data and metadata to storage before finishing.
+    pub fsync: bool,
+    /// Flush the output's data to storage before finishing.
+    pub fdatasync: bool,
+    /// Fail if the output already exists.
+    pub excl: bool,
+    /// Fail if the output doesn't exist.
+    pub nocreat: bool,
+}
+
+/// What's written to stderr besides errors (`status=`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    /// The records copied and the transfer statistics at the end.
+    Default,
+    /// Nothing.
+    None,
+    /// The records copied, without the transfer statistics.
+    NoXfer,
+    /// The default, and a line with the progress so far every second.
+    Progress,
+}
+
+/// Parses the operands, or says what's wrong with the first bad one.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        input: None,
+        output: None,
+        block_size: 512,
+        count: None,
+        skip: 0,
+        seek: 0,
+        conv: Conv::default(),
+        status: Status::Default,
+    };
+    for arg in args {
+        let Some((name, value)) = arg.split_once('=') else {
+            return Err(format!("unexpected operand: {arg}"));
+        };
+        let number = || size(value).ok_or_else(|| format!("invalid number: {arg}"));
+        match name {
+            "if" => options.input = Some(value.to_string()),
+            "of" => options.output = Some(value.to_string()),
+            "bs" => match usize::try_from(number()?) {
+                Ok(0) | Err(_) => return Err(format!("invalid block size: {arg}")),
+                Ok(block_size) => options.block_size = block_size,
+            },
+            "count" => options.count = Some(number()?),
+            "skip" | "iseek" => options.skip = number()?,
+            "seek" | "oseek" => options.seek = number()?,
+            "conv" => {
+                for flag in value.split(',') {
+                    let set = match flag {
+                        "notrunc" => &mut options.conv.notrunc,
+                        "sync" => &mut options.conv.sync,
+                        "noerror" => &mut options.conv.noerror,
+                        "fsync" => &mut options.conv.fsync,
+                        "fdatasync" => &mut options.conv.fdatasync,
+                        "excl" => &mut options.conv.excl,
+                        "nocreat" => &mut options.conv.nocreat,
+                        _ => return Err(format!("invalid conversion: {flag}")),
+                    };
+                    *set = true;
+                }
+            }
+            "status" => {
+                options.status = match value {
+                    "none" => Status::None,
+                    "noxfer" => Status::NoXfer,
+                    "progress" => Status::Progress,
+                    _ => return Err(format!("invalid status level: {value}")),
+                }
+            }
+            _ => return Err(format!("unrecognized operand: {arg}")),
+        }
+    }
+    if options.conv.excl && options.conv.nocreat {
+        return Err("conv=excl and conv=nocreat can't be used together".to_string());
+    }
+    Ok(options)
+}
+
+/// A number with an optional multiplier suffix, as dd takes them: `c` (1),
+/// `w` (2), `b` (512), `kB`, `MB`, `GB` and `TB` (powers of 1000), and `K`,
+/// `M`, `G` and `T` with an optional `iB` (powers of 1024). Numbers joined
+/// by `x` are multiplied (`2x512`).
+pub fn size(text: &str) -> Option<u64> {
+    text.split('x').try_fold(1u64, |product, factor| product.checked_mul(factor_size(factor)?))
+}
+
+fn factor_size(text: &str) -> Option<u64> {
+    let digits = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, suffix) = text.split_at(digits);
+    let multiplier: u64 = match suffix {
+        "" | "c" => 1,
+        "w" => 2,
+        "b" => 512,
+        "kB" => 1000,
+        "MB" => 1000u64.pow(2),
+        "GB" => 1000u64.pow(3),
+        "TB" => 1000u64.pow(4),
+        "K" | "k" | "KiB" => 1 << 10,
+        "M" | "MiB" => 1 << 20,
+        "G" | "GiB" => 1 << 30,
+        "T" | "TiB" => 1 << 40,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
@@ -0,0 +1,108 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// A scratch directory with a file of `size` bytes counting up from 0 in it.
+fn scratch(name: &str, size: usize) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ecmaos-dd-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("in"), pattern(size)).unwrap();
+    root
+}
+
+fn pattern(size: usize) -> Vec<u8> {
+    (0..size).map(|i| i as u8).collect()
+}
+
+fn run(root: &PathBuf, args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dd"))
+        .args(args)
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn copies_blocks_and_counts_records() {
+    let root = scratch("copy", 5000);
+    let report = stderr(&run(&root, &["if=in", "of=out", "bs=1K"], b""));
+    assert_eq!(fs::read(root.join("out")).unwrap(), pattern(5000));
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[..2], ["4+1 records in", "4+1 records out"]);
+    assert!(lines[2].starts_with("5000 bytes (5.0 kB, 4.9 KiB) copied, "), "{}", report);
+    assert!(lines[2].ends_with("B/s"), "{}", report);
+
+    // From standard input to standard output
+    let output = run(&root, &["bs=4", "status=noxfer"], b"0123456789");
+    assert_eq!(output.stdout, b"0123456789");
+    assert_eq!(stderr(&output), "2+1 records in\n2+1 records out\n");
+}
+
+#[test]
+fn skips_and_seeks_by_blocks() {
+    let root = scratch("offsets", 1000);
+    stderr(&run(&root, &["if=in", "of=part", "bs=100", "skip=2", "count=3", "status=none"], b""));
+    assert_eq!(fs::read(root.join("part")).unwrap(), pattern(1000)[200..500]);
+
+    // seek truncates there, unless conv=notrunc
+    fs::copy(root.join("in"), root.join("out")).unwrap();
+    stderr(&run(&root, &["of=out", "bs=100", "seek=3", "status=none"], b"abc"));
+    let mut expected = pattern(300);
+    expected.extend(b"abc");
+    assert_eq!(fs::read(root.join("out")).unwrap(), expected);
+
+    fs::copy(root.join("in"), root.join("out")).unwrap();
+    stderr(&run(&root, &["of=out", "bs=1x100", "seek=3", "conv=notrunc", "status=none"], b"abc"));
+    let mut expected = pattern(1000);
+    expected[300..303].copy_from_slice(b"abc");
+    assert_eq!(fs::read(root.join("out")).unwrap(), expected);
+
+    // Past the end, leaving a hole
+    stderr(&run(&root, &["of=sparse", "bs=1K", "seek=2", "status=none"], b"end"));
+    let sparse = fs::read(root.join("sparse")).unwrap();
+    assert_eq!(sparse.len(), 2051);
+    assert!(sparse[..2048].iter().all(|&byte| byte == 0) && sparse.ends_with(b"end"));
+}
+
+#[test]
+fn pads_short_blocks_with_conv_sync() {
+    let root = scratch("sync", 0);
+    let output = run(&root, &["bs=8", "conv=sync", "status=noxfer"], b"0123456789");
+    assert_eq!(output.stdout, b"0123456789\0\0\0\0\0\0");
+    assert_eq!(stderr(&output), "1+1 records in\n2+0 records out\n");
+}
+
+#[test]
+fn refuses_what_it_cannot_do() {
+    let root = scratch("refuse", 10);
+    for args in [&["bs=0"][..], &["count=ten"], &["conv=ucase"], &["status=loud"], &["in"], &["conv=excl,nocreat"]] {
+        let output = run(&root, args, b"");
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+    }
+
+    let output = run(&root, &["if=in", "of=in", "conv=excl"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: in: "));
+    assert_eq!(fs::read(root.join("in")).unwrap(), pattern(10));
+
+    let output = run(&root, &["if=missing", "of=out"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    let report = String::from_utf8_lossy(&output.stderr);
+    assert!(report.starts_with("error: missing: ") && report.contains("0+0 records in\n"), "{}", report);
+    assert!(!root.join("out").exists());
+
+    assert_eq!(run(&root, &["of=out", "conv=nocreat"], b"").status.code(), Some(1));
+    assert_eq!(run(&root, &["seek=1"], b"").status.code(), Some(1));
+}
@@ -0,0 +1,87 @@
+//! Prints the environment, changed as asked.
+//!
+//! ```text
+//! env [-i] [-0] [-u NAME]... [NAME=VALUE]... [COMMAND [ARG]...]
+//! ```
+//!
+//! `-i` (or a lone `-`) starts from an empty environment, each `-u` removes
+//! a variable and each `NAME=VALUE` sets one. The result is printed one
+//! `NAME=VALUE` per line, or ended by NUL bytes with `-0`, in the order the
+//! program was given its environment with new variables last, which shows
+//! exactly what the kernel passes a WASM program.
+//!
+//! env(1) is mostly used to run a command in the changed environment, but a
+//! WASM program in ecmaOS can't start another: WASI has no way to spawn a
+//! process and the kernel offers WASM programs none. A command is refused
+//! with status 126, the status env(1) gives a command it found but couldn't
+//! run; ecmaOS's own `env` command runs one.
+//!
+//! Messages are printed in the language of `LANG`, from the catalogs in
+//! `locales`.
+
+use std::env;
+use std::io::{self, Write};
+
+use ecmaos_i18n::{catalogs, gettext, tr};
+
+const USAGE: &str = "usage: env [-i] [-0] [-u NAME]... [NAME=VALUE]... [COMMAND [ARG]...]";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, gettext(USAGE));
+    std::process::exit(2);
+}
+
+fn main() {
+    ecmaos_i18n::init(catalogs!("es"));
+    let mut vars: Vec<(String, String)> =
+        env::vars_os().map(|(name, value)| (name.to_string_lossy().into(), value.to_string_lossy().into())).collect();
+    let mut null = false;
+    let mut args = env::args().skip(1).peekable();
+
+    while let Some(arg) = args.next_if(|arg| arg.starts_with('-')) {
+        match arg.as_str() {
+            "-" | "-i" | "--ignore-environment" => vars.clear(),
+            "-0" | "--null" => null = true,
+            "-u" | "--unset" => match args.next() {
+                Some(name) => unset(&mut vars, &name),
+                None => usage_error(&tr!("{option} needs a value", option = arg)),
+            },
+            "-h" | "--help" => {
+                println!("{}", gettext(USAGE));
+                return;
+            }
+            _ if arg.starts_with("--unset=") => unset(&mut vars, &arg["--unset=".len()..]),
+            _ => usage_error(&tr!("unknown option: {option}", option = arg)),
+        }
+    }
+    while let Some(arg) = args.next_if(|arg| arg.contains('=')) {
+        let (name, value) = arg.split_once('=').unwrap_or_default();
+        if name.is_empty() {
+            usage_error(&tr!("invalid variable: {variable}", variable = arg));
+        }
+        match vars.iter_mut().find(|(existing, _)| existing == name) {
+            Some(var) => var.1 = value.to_string(),
+            None => vars.push((name.to_string(), value.to_string())),
+        }
+    }
+    if let Some(command) = args.next() {
+        eprintln!("{}", tr!("error: {command}: WASM programs can't start other programs in ecmaOS", command = command));
+        std::process::exit(126);
+    }
+
+    let mut out = io::stdout().lock();
+    let end = if null { '\0' } else { '\n' };
+    for (name, value) in &vars {
+        if let Err(e) = write!(out, "{}={}{}", name, value, end) {
+            eprintln!("{}", tr!("error: {error}", error = e));
+            std::process::exit(1);
+        }
+    }
+}
+
+fn unset(vars: &mut Vec<(String, String)>, name: &str) {
+    if name.is_empty() || name.contains('=') {
+        usage_error(&tr!("invalid variable name: {name}", name = name));
+    }
+    vars.retain(|(existing, _)| existing != name);
+}
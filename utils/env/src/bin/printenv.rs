@@ -0,0 +1,61 @@
+//! Prints environment variables.
+//!
+//! ```text
+//! printenv [-0] [NAME...]
+//! ```
+//!
+//! Without names every variable is printed as `NAME=VALUE`; otherwise the
+//! value of each one named, a line apiece (or ended by NUL bytes with
+//! `-0`). The exit status is 1 if any of them isn't set.
+//!
+//! Messages are printed in the language of `LANG`, from the catalogs in
+//! `locales`.
+
+use std::env;
+use std::io::{self, Write};
+
+use ecmaos_i18n::{catalogs, gettext, tr};
+
+const USAGE: &str = "usage: printenv [-0] [NAME...]";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, gettext(USAGE));
+    std::process::exit(2);
+}
+
+fn main() {
+    ecmaos_i18n::init(catalogs!("es"));
+    let mut null = false;
+    let mut names = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-0" | "--null" => null = true,
+            "-h" | "--help" => {
+                println!("{}", gettext(USAGE));
+                return;
+            }
+            _ if arg.starts_with('-') => usage_error(&tr!("unknown option: {option}", option = arg)),
+            _ => names.push(arg),
+        }
+    }
+
+    let end = if null { '\0' } else { '\n' };
+    let lines: Vec<String> = match names.is_empty() {
+        true => env::vars_os()
+            .map(|(name, value)| format!("{}={}{}", name.to_string_lossy(), value.to_string_lossy(), end))
+            .collect(),
+        false => {
+            names.iter().filter_map(env::var_os).map(|value| format!("{}{}", value.to_string_lossy(), end)).collect()
+        }
+    };
+    let mut out = io::stdout().lock();
+    for line in &lines {
+        if let Err(e) = out.write_all(line.as_bytes()) {
+            eprintln!("{}", tr!("error: {error}", error = e));
+            std::process::exit(1);
+        }
+    }
+    if lines.len() < names.len() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,64 @@
+use std::process::{Command, Output};
+
+/// Runs `program` with only the variables in `vars` set.
+fn run(program: &str, vars: &[(&str, &str)], args: &[&str]) -> Output {
+    Command::new(program).env_clear().envs(vars.iter().copied()).args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+const ENV: &str = env!("CARGO_BIN_EXE_env");
+const PRINTENV: &str = env!("CARGO_BIN_EXE_printenv");
+
+#[test]
+fn env_changes_the_environment_in_order() {
+    let vars = [("A", "1"), ("B", "2"), ("C", "3")];
+    assert_eq!(stdout(&run(ENV, &vars, &[])), "A=1\nB=2\nC=3\n");
+    assert_eq!(stdout(&run(ENV, &vars, &["-u", "B", "A=x", "D=a=b"])), "A=x\nC=3\nD=a=b\n");
+    assert_eq!(stdout(&run(ENV, &vars, &["-i", "E="])), "E=\n");
+    assert_eq!(stdout(&run(ENV, &vars, &["--unset=A", "-0", "-", "F=1"])), "F=1\0");
+}
+
+#[test]
+fn env_refuses_commands_and_bad_names() {
+    let output = run(ENV, &[], &["A=1", "true"]);
+    assert_eq!(output.status.code(), Some(126));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: true: "));
+    assert_eq!(run(ENV, &[], &["=1"]).status.code(), Some(2));
+    assert_eq!(run(ENV, &[], &["-u", "A=1"]).status.code(), Some(2));
+    assert_eq!(run(ENV, &[], &["-u"]).status.code(), Some(2));
+}
+
+#[test]
+fn printenv_prints_values() {
+    let vars = [("A", "1"), ("B", "two words")];
+    assert_eq!(stdout(&run(PRINTENV, &vars, &[])), "A=1\nB=two words\n");
+    assert_eq!(stdout(&run(PRINTENV, &vars, &["B", "A"])), "two words\n1\n");
+    assert_eq!(stdout(&run(PRINTENV, &vars, &["-0", "A"])), "1\0");
+
+    let output = run(PRINTENV, &vars, &["A", "MISSING"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+}
+
+#[test]
+fn messages_follow_lang() {
+    let checked: Vec<_> = ecmaos_i18n::catalogs!("es").iter().map(|catalog| catalog.check()).collect();
+    assert_eq!(checked, [Ok(())]);
+
+    let stderr = |output: Output| String::from_utf8_lossy(&output.stderr).into_owned();
+    let spanish = stderr(run(ENV, &[("LANG", "es_ES.UTF-8")], &["-x"]));
+    assert!(spanish.starts_with("opción desconocida: -x\nuso: env "), "{spanish}");
+    let english = stderr(run(PRINTENV, &[("LANG", "C")], &["-x"]));
+    assert!(english.starts_with("unknown option: -x\nusage: printenv "), "{english}");
+    let untranslated = stderr(run(PRINTENV, &[("LANG", "de_DE")], &["-x"]));
+    assert!(untranslated.starts_with("unknown option: -x\n"), "{untranslated}");
+
+    // The language comes from the program's environment, not the one it
+    // prints
+    let output = run(ENV, &[("LANG", "es")], &["-i", "A=1", "true"]);
+    assert!(stderr(output).starts_with("error: true: los programas WASM"));
+}
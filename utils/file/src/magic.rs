@@ -0,0 +1,221 @@
+//! The magic database: what a file is, judged from its first bytes.
+//!
+//! Formats with a fixed signature are listed in [`SIGNATURES`]. A few are
+//! worth decoding a little further: WebAssembly modules (core module or
+//! component, and the version), ELF objects (class, byte order, type and
+//! machine) and PNG images (size, depth and color type). Anything that
+//! matches none is judged as text: ASCII, UTF-8 or UTF-16 with a byte order
+//! mark, or ISO-8859, with a script's interpreter and unusual line
+//! terminators noted, and as `data` if it doesn't look like text at all.
+
+/// How many bytes of a file are read to identify it. The furthest signature
+/// is tar's, at 257; the rest is for telling text from data.
+pub const HEADER_SIZE: usize = 8192;
+
+/// What a file was found to be.
+#[derive(Debug, PartialEq)]
+pub struct FileType {
+    pub description: String,
+    pub mime: &'static str,
+}
+
+impl FileType {
+    fn new(description: impl Into<String>, mime: &'static str) -> FileType {
+        FileType { description: description.into(), mime }
+    }
+}
+
+struct Signature {
+    offset: usize,
+    bytes: &'static [u8],
+    description: &'static str,
+    mime: &'static str,
+}
+
+impl Signature {
+    fn matches(&self, header: &[u8]) -> bool {
+        header.get(self.offset..).is_some_and(|at| at.starts_with(self.bytes))
+    }
+}
+
+const fn signature(offset: usize, bytes: &'static [u8], description: &'static str, mime: &'static str) -> Signature {
+    Signature { offset, bytes, description, mime }
+}
+
+/// Formats told apart by a few bytes alone, tried in order.
+const SIGNATURES: &[Signature] = &[
+    signature(0, b"\xff\xd8\xff", "JPEG image data", "image/jpeg"),
+    signature(0, b"GIF87a", "GIF image data, version 87a", "image/gif"),
+    signature(0, b"GIF89a", "GIF image data, version 89a", "image/gif"),
+    signature(0, b"%PDF-", "PDF document", "application/pdf"),
+    signature(0, b"PK\x03\x04", "Zip archive data", "application/zip"),
+    signature(0, b"PK\x05\x06", "Zip archive data (empty)", "application/zip"),
+    signature(0, b"\x1f\x8b", "gzip compressed data", "application/gzip"),
+    signature(0, b"BZh", "bzip2 compressed data", "application/x-bzip2"),
+    signature(0, b"\xfd7zXZ\x00", "XZ compressed data", "application/x-xz"),
+    signature(0, b"\x28\xb5\x2f\xfd", "Zstandard compressed data", "application/zstd"),
+    signature(0, b"7z\xbc\xaf\x27\x1c", "7-zip archive data", "application/x-7z-compressed"),
+    signature(257, b"ustar\x0000", "POSIX tar archive", "application/x-tar"),
+    signature(257, b"ustar  \x00", "GNU tar archive", "application/x-tar"),
+    signature(0, b"SQLite format 3\x00", "SQLite 3.x database", "application/vnd.sqlite3"),
+    signature(0, b"wOFF", "Web Open Font Format", "font/woff"),
+    signature(0, b"wOF2", "Web Open Font Format (version 2)", "font/woff2"),
+];
+
+/// Identifies a file from its first [`HEADER_SIZE`] bytes, or all of them
+/// if it's shorter.
+pub fn identify(header: &[u8]) -> FileType {
+    if header.is_empty() {
+        return FileType::new("empty", "inode/x-empty");
+    }
+    if let Some(file_type) = wasm(header).or_else(|| elf(header)).or_else(|| png(header)) {
+        return file_type;
+    }
+    if let Some(signature) = SIGNATURES.iter().find(|signature| signature.matches(header)) {
+        return FileType::new(signature.description, signature.mime);
+    }
+    text(header)
+}
+
+fn wasm(header: &[u8]) -> Option<FileType> {
+    let version = header.strip_prefix(b"\0asm")?.get(..4)?;
+    let (version, layer) = (u16::from_le_bytes([version[0], version[1]]), u16::from_le_bytes([version[2], version[3]]));
+    let description = match (layer, version) {
+        (0, 1) => "WebAssembly (wasm) binary module version 0x1 (MVP)".to_string(),
+        (0, version) => format!("WebAssembly (wasm) binary module version {version:#x}"),
+        (1, version) => format!("WebAssembly (wasm) component version {version:#x}"),
+        (layer, version) => format!("WebAssembly (wasm) binary, layer {layer}, version {version:#x}"),
+    };
+    Some(FileType::new(description, "application/wasm"))
+}
+
+fn elf(header: &[u8]) -> Option<FileType> {
+    let ident = header.strip_prefix(b"\x7fELF")?;
+    let class = match ident.first()? {
+        1 => "32-bit",
+        2 => "64-bit",
+        _ => return Some(FileType::new("ELF, invalid class", "application/octet-stream")),
+    };
+    let little = match ident.get(1)? {
+        1 => true,
+        2 => false,
+        _ => return Some(FileType::new(format!("ELF {class}, invalid byte order"), "application/octet-stream")),
+    };
+    let half = |offset: usize| -> Option<u16> {
+        let bytes = [*header.get(offset)?, *header.get(offset + 1)?];
+        Some(if little { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    };
+    let (kind, mime) = match half(16)? {
+        1 => ("relocatable", "application/x-object"),
+        2 => ("executable", "application/x-executable"),
+        3 => ("shared object", "application/x-sharedlib"),
+        4 => ("core file", "application/x-coredump"),
+        _ => ("unknown type", "application/octet-stream"),
+    };
+    let machine = match half(18)? {
+        0x03 => "Intel 80386",
+        0x08 => "MIPS",
+        0x14 => "PowerPC",
+        0x28 => "ARM",
+        0x3e => "x86-64",
+        0xb7 => "ARM aarch64",
+        0xf3 => "RISC-V",
+        _ => "unknown machine",
+    };
+    let order = if little { "LSB" } else { "MSB" };
+    Some(FileType::new(format!("ELF {class} {order} {kind}, {machine}"), mime))
+}
+
+fn png(header: &[u8]) -> Option<FileType> {
+    let rest = header.strip_prefix(b"\x89PNG\r\n\x1a\n")?;
+    // The IHDR chunk always comes first: length, type, width, height,
+    // bit depth and color type
+    let Some(ihdr) = rest.get(..18).filter(|chunk| &chunk[4..8] == b"IHDR") else {
+        return Some(FileType::new("PNG image data", "image/png"));
+    };
+    let number = |offset: usize| u32::from_be_bytes(ihdr[offset..offset + 4].try_into().unwrap());
+    let color = match ihdr[17] {
+        0 => "grayscale",
+        2 => "RGB",
+        3 => "colormap",
+        4 => "gray+alpha",
+        6 => "RGBA",
+        _ => "unknown color type",
+    };
+    let description = format!("PNG image data, {} x {}, {}-bit/color {}", number(8), number(12), ihdr[16], color);
+    Some(FileType::new(description, "image/png"))
+}
+
+/// Control characters found in text: bell, backspace, tab, line feed,
+/// vertical tab, form feed, carriage return and escape.
+fn is_text_control(byte: u8) -> bool {
+    matches!(byte, 0x07..=0x0d | 0x1b)
+}
+
+fn text(header: &[u8]) -> FileType {
+    if let Some(rest) = header.strip_prefix(b"\xff\xfe") {
+        return utf16(rest, u16::from_le_bytes, "Little-endian", "text/plain; charset=utf-16le");
+    }
+    if let Some(rest) = header.strip_prefix(b"\xfe\xff") {
+        return utf16(rest, u16::from_be_bytes, "Big-endian", "text/plain; charset=utf-16be");
+    }
+    let (bom, body) = match header.strip_prefix(b"\xef\xbb\xbf") {
+        Some(body) => (true, body),
+        None => (false, header),
+    };
+    if body.iter().any(|&byte| byte < 0x20 && !is_text_control(byte) || byte == 0x7f) {
+        return FileType::new("data", "application/octet-stream");
+    }
+    let (encoding, mime) = if body.is_ascii() && !bom {
+        ("ASCII", "text/plain; charset=us-ascii")
+    } else if is_utf8(body) {
+        (if bom { "UTF-8 Unicode (with BOM)" } else { "UTF-8 Unicode" }, "text/plain; charset=utf-8")
+    } else if body.iter().all(|&byte| !(0x80..0xa0).contains(&byte)) {
+        ("ISO-8859", "text/plain; charset=iso-8859-1")
+    } else {
+        return FileType::new("data", "application/octet-stream");
+    };
+
+    let mut description = match interpreter(body) {
+        Some(interpreter) => format!("{interpreter} script, {encoding} text executable"),
+        None => format!("{encoding} text"),
+    };
+    if body.windows(2).any(|pair| pair == b"\r\n") {
+        description.push_str(", with CRLF line terminators");
+    } else if !body.contains(&b'\n') {
+        description.push_str(", with no line terminators");
+    }
+    let mime = if description.contains("script") { "text/x-script" } else { mime };
+    FileType::new(description, mime)
+}
+
+/// Whether `bytes` are UTF-8, allowing for a character cut off at the end
+/// of the header.
+fn is_utf8(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+fn utf16(body: &[u8], decode: fn([u8; 2]) -> u16, order: &str, mime: &'static str) -> FileType {
+    let units = body.chunks_exact(2).map(|pair| decode([pair[0], pair[1]]));
+    let text = char::decode_utf16(units).all(|c| c.is_ok_and(|c| !c.is_control() || c.is_ascii_whitespace()));
+    if text {
+        FileType::new(format!("{order} UTF-16 Unicode text"), mime)
+    } else {
+        FileType::new("data", "application/octet-stream")
+    }
+}
+
+/// The program a `#!` line runs, without its directory, looking past `env`.
+fn interpreter(text: &[u8]) -> Option<String> {
+    let line = text.strip_prefix(b"#!")?.split(|&byte| byte == b'\n').next()?;
+    let line = String::from_utf8_lossy(line);
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    Some(program.to_string())
+}
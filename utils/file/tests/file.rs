@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Writes the files to a scratch directory.
+fn scratch(name: &str, files: &[(&str, Vec<u8>)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ecmaos-file-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    for (path, contents) in files {
+        fs::write(root.join(path), contents).unwrap();
+    }
+    root
+}
+
+fn run(root: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_file")).args(args).current_dir(root).output().unwrap()
+}
+
+/// The lines `file -b` prints for each of the files.
+fn describe(files: &[(&str, Vec<u8>)], extra: &[&str]) -> Vec<String> {
+    let root = scratch(files[0].0, files);
+    let mut args = vec!["-b"];
+    args.extend(extra);
+    args.extend(files.iter().map(|(name, _)| *name));
+    let output = run(&root, &args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap().lines().map(str::to_string).collect()
+}
+
+fn tar() -> Vec<u8> {
+    let mut header = vec![0; 512];
+    header[..9].copy_from_slice(b"hello.txt");
+    header[257..265].copy_from_slice(b"ustar\x0000");
+    header
+}
+
+fn elf() -> Vec<u8> {
+    let mut header = b"\x7fELF\x02\x01\x01".to_vec();
+    header.resize(16, 0);
+    header.extend([2, 0, 0x3e, 0]);
+    header.resize(64, 0);
+    header
+}
+
+#[test]
+fn identifies_binary_formats() {
+    let files = [
+        ("module.wasm", b"\0asm\x01\0\0\0\x01\x04\x01\x60\0\0".to_vec()),
+        ("component.wasm", b"\0asm\x0d\0\x01\0".to_vec()),
+        ("app", elf()),
+        ("image.png", b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\x01\x40\0\0\0\xf0\x08\x02\0\0\0".to_vec()),
+        ("photo", b"\xff\xd8\xff\xe0\0\x10JFIF\0".to_vec()),
+        ("archive.zip", b"PK\x03\x04\x14\0\0\0".to_vec()),
+        ("archive.tgz", b"\x1f\x8b\x08\0\0\0\0\0".to_vec()),
+        ("archive.tar", tar()),
+        ("random.bin", vec![0x13, 0x00, 0xfe, 0x42, 0x01]),
+    ];
+    assert_eq!(
+        describe(&files, &[]),
+        [
+            "WebAssembly (wasm) binary module version 0x1 (MVP)",
+            "WebAssembly (wasm) component version 0xd",
+            "ELF 64-bit LSB executable, x86-64",
+            "PNG image data, 320 x 240, 8-bit/color RGB",
+            "JPEG image data",
+            "Zip archive data",
+            "gzip compressed data",
+            "POSIX tar archive",
+            "data",
+        ]
+    );
+}
+
+#[test]
+fn identifies_text() {
+    let mut cut = "é".repeat(5000).into_bytes();
+    cut.truncate(8191);
+    let files = [
+        ("ascii.txt", b"plain text\n".to_vec()),
+        ("utf8.txt", "naïve café\n".as_bytes().to_vec()),
+        ("bom.txt", b"\xef\xbb\xbfwith a mark\n".to_vec()),
+        ("utf16.txt", b"\xff\xfeh\0i\0\n\0".to_vec()),
+        ("dos.txt", b"one\r\ntwo\r\n".to_vec()),
+        ("line.txt", b"no newline".to_vec()),
+        ("script", b"#!/usr/bin/env -S node --experimental\nconsole.log(1)\n".to_vec()),
+        ("latin1.txt", b"caf\xe9\n".to_vec()),
+        ("long.txt", cut),
+        ("empty", Vec::new()),
+    ];
+    assert_eq!(
+        describe(&files, &[]),
+        [
+            "ASCII text",
+            "UTF-8 Unicode text",
+            "UTF-8 Unicode (with BOM) text",
+            "Little-endian UTF-16 Unicode text",
+            "ASCII text, with CRLF line terminators",
+            "ASCII text, with no line terminators",
+            "node script, ASCII text executable",
+            "ISO-8859 text",
+            "UTF-8 Unicode text, with no line terminators",
+            "empty",
+        ]
+    );
+    assert_eq!(
+        describe(&files[..3], &["-i"]),
+        ["text/plain; charset=us-ascii", "text/plain; charset=utf-8", "text/plain; charset=utf-8"]
+    );
+}
+
+#[test]
+fn describes_directories_and_links() {
+    let root = scratch("links", &[("module.wasm", b"\0asm\x01\0\0\0".to_vec())]);
+    fs::create_dir(root.join("dir")).unwrap();
+    std::os::unix::fs::symlink("module.wasm", root.join("link")).unwrap();
+    let output = run(&root, &["dir", "link", "module.wasm"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "dir:         directory\n\
+         link:        symbolic link to module.wasm\n\
+         module.wasm: WebAssembly (wasm) binary module version 0x1 (MVP)\n"
+    );
+    let output = run(&root, &["-L", "-i", "link"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "link: application/wasm\n");
+}
+
+#[test]
+fn reports_files_it_cannot_open() {
+    let root = scratch("missing", &[("here", b"here\n".to_vec())]);
+    let output = run(&root, &["missing", "here"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("missing: cannot open ("), "{}", stdout);
+    assert!(stdout.ends_with("here:    ASCII text\n"), "{}", stdout);
+}
@@ -0,0 +1,113 @@
+/// Expands the braces in `pattern`, as the shell does before it globs:
+/// `a{b,c}d` gives `abd` and `acd`, braces nest, and `{1..3}` or `{a..c}`
+/// count from one end to the other (`{01..10}` keeps the zeros). Braces with
+/// neither a comma nor a range in them, braces without a partner and
+/// braces escaped with `\` are left as they are.
+pub fn expand_braces(pattern: &str) -> Vec<String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut start = 0;
+    while let Some((open, close)) = group(&chars, start) {
+        let inner: String = chars[open + 1..close].iter().collect();
+        let items = match split(&inner) {
+            items if items.len() > 1 => items,
+            _ => match range(&inner) {
+                Some(items) => items,
+                None => {
+                    start = open + 1;
+                    continue;
+                }
+            },
+        };
+        let prefix: String = chars[..open].iter().collect();
+        let suffix: String = chars[close + 1..].iter().collect();
+        // The suffix may hold more braces, and each item nested ones
+        return items.iter().flat_map(|item| expand_braces(&format!("{prefix}{item}{suffix}"))).collect();
+    }
+    vec![pattern.to_string()]
+}
+
+/// The first brace at or after `start` and its partner, skipping escaped
+/// characters.
+fn group(chars: &[char], start: usize) -> Option<(usize, usize)> {
+    let mut at = start;
+    while at < chars.len() {
+        match chars[at] {
+            '\\' => at += 1,
+            '{' => {
+                let mut depth = 0;
+                let mut end = at;
+                while end < chars.len() {
+                    match chars[end] {
+                        '\\' => end += 1,
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return Some((at, end));
+                            }
+                        }
+                        _ => {}
+                    }
+                    end += 1;
+                }
+                // No partner for this one; a later brace may still have one
+            }
+            _ => {}
+        }
+        at += 1;
+    }
+    None
+}
+
+/// `inner` split at the commas not inside nested braces.
+fn split(inner: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut depth = 0;
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let last = items.last_mut().unwrap();
+                last.push(c);
+                last.extend(chars.next());
+                continue;
+            }
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        items.last_mut().unwrap().push(c);
+    }
+    items
+}
+
+/// The items of a range such as `1..5`, `5..1`, `01..10` or `a..e`.
+fn range(inner: &str) -> Option<Vec<String>> {
+    let (from, to) = inner.split_once("..")?;
+    if let (Ok(first), Ok(last)) = (from.parse::<i64>(), to.parse::<i64>()) {
+        // Zero-padded if either end is
+        let padded = |end: &str| end.trim_start_matches('-').len() > 1 && end.trim_start_matches('-').starts_with('0');
+        let width = if padded(from) || padded(to) { from.len().max(to.len()) } else { 0 };
+        let numbers: Vec<i64> = match first <= last {
+            true => (first..=last).collect(),
+            false => (last..=first).rev().collect(),
+        };
+        return Some(numbers.iter().map(|n| format!("{n:0width$}")).collect());
+    }
+    let (mut from, mut to) = (from.chars(), to.chars());
+    let (Some(first), None, Some(last), None) = (from.next(), from.next(), to.next(), to.next()) else {
+        return None;
+    };
+    if !first.is_ascii_alphabetic() || !last.is_ascii_alphabetic() {
+        return None;
+    }
+    let letters: Vec<char> = match first <= last {
+        true => (first..=last).collect(),
+        false => (last..=first).rev().collect(),
+    };
+    Some(letters.iter().map(char::to_string).collect())
+}
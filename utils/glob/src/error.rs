@@ -0,0 +1,7 @@
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("invalid glob pattern {pattern:?}: {reason}")]
+    Pattern { pattern: String, reason: &'static str },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
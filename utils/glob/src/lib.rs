@@ -0,0 +1,95 @@
+//! Shell glob patterns for ecmaOS's Rust utilities.
+//!
+//! Anything that takes file names from a user should take patterns the same
+//! way, so the matching lives here rather than in each program:
+//!
+//! - `*` matches any run of characters and `?` any one, but neither matches
+//!   a `/`, nor the `.` that starts a hidden file's name
+//! - `[abc]`, `[a-z]` and `[!a-z]` (or `[^a-z]`) match one character that is
+//!   or isn't listed
+//! - `**` on its own between slashes matches any number of directories,
+//!   hidden ones aside
+//! - `\` takes the next character as it is
+//! - braces expand before anything else, as [`expand_braces`] describes:
+//!   `*.{rs,toml}` is `*.rs` and `*.toml`
+//!
+//! [`glob`] finds the files a pattern matches. It only lists directories
+//! where a wildcard has to be matched against their entries: the literal
+//! parts of a pattern are looked up directly, and it only goes into
+//! directories that the rest of the pattern could match something in, which
+//! matters on filesystems such as ZenFS's where listing a directory may mean
+//! fetching it.
+//!
+//! ```
+//! use ecmaos_glob::{expand_braces, Glob};
+//!
+//! assert_eq!(expand_braces("file{1..3}.txt"), ["file1.txt", "file2.txt", "file3.txt"]);
+//!
+//! let sources = Glob::new("src/**/*.{rs,toml}")?;
+//! assert!(sources.matches("src/bin/test.rs"));
+//! assert!(sources.matches("src/Cargo.toml"));
+//! assert!(!sources.matches("src/.hidden/lib.rs"));
+//! # Ok::<(), ecmaos_glob::Error>(())
+//! ```
+
+mod braces;
+mod error;
+mod pattern;
+mod walk;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+pub use braces::expand_braces;
+pub use error::{Error, Result};
+pub use pattern::Pattern;
+
+/// A glob pattern, braces and all: the [`Pattern`]s that its braces expand
+/// to, any of which may match.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    patterns: Vec<Pattern>,
+}
+
+impl Glob {
+    pub fn new(text: &str) -> Result<Glob> {
+        let patterns = expand_braces(text).iter().map(|pattern| Pattern::new(pattern)).collect::<Result<_>>()?;
+        Ok(Glob { patterns })
+    }
+
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    /// Whether `path` matches one of the patterns.
+    pub fn matches(&self, path: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.matches(path))
+    }
+
+    /// The files that match, from the current directory.
+    pub fn search(&self) -> Vec<PathBuf> {
+        self.search_in(Path::new("."))
+    }
+
+    /// The files that match, looking for relative patterns under `base`.
+    /// Paths come back as the pattern gives them, relative ones without
+    /// `base` in front. Like the shell, this puts the matches of each
+    /// pattern from the braces in order of name, one pattern after another,
+    /// and gives each path only once.
+    pub fn search_in(&self, base: &Path) -> Vec<PathBuf> {
+        let mut seen = HashSet::new();
+        let mut found = Vec::new();
+        for pattern in &self.patterns {
+            let mut matches = Vec::new();
+            walk::search(pattern, base, &mut matches);
+            matches.sort();
+            found.extend(matches.into_iter().filter(|path| seen.insert(path.clone())));
+        }
+        found
+    }
+}
+
+/// The files in or under the current directory that `pattern` matches.
+pub fn glob(pattern: &str) -> Result<Vec<PathBuf>> {
+    Ok(Glob::new(pattern)?.search())
+}
@@ -0,0 +1,188 @@
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    Char(char),
+    /// `?`
+    One,
+    /// `*`
+    Many,
+    /// `[...]`: the ranges of characters it takes, or every other character
+    /// if it's negated.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    fn takes(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::One => true,
+            Token::Many => false,
+            Token::Class { negated, ranges } => ranges.iter().any(|&(low, high)| (low..=high).contains(&c)) != *negated,
+        }
+    }
+}
+
+/// One part of a path pattern, between slashes.
+#[derive(Debug, Clone)]
+pub(crate) enum Segment {
+    /// A name without wildcards, which can be looked up rather than
+    /// searched for.
+    Literal(String),
+    Wildcard(Vec<Token>),
+    /// `**`: any number of directories.
+    Recursive,
+}
+
+impl Segment {
+    fn parse(text: &str, pattern: &str) -> Result<Segment> {
+        if text == "**" {
+            return Ok(Segment::Recursive);
+        }
+        let invalid = |reason| Error::Pattern { pattern: pattern.to_string(), reason };
+        let mut tokens = Vec::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '\\' => Token::Char(chars.next().unwrap_or('\\')),
+                '?' => Token::One,
+                // `**` inside a name is the same as `*`
+                '*' if tokens.last() == Some(&Token::Many) => continue,
+                '*' => Token::Many,
+                '[' => {
+                    let negated = chars.next_if(|&c| c == '!' || c == '^').is_some();
+                    let mut ranges = Vec::new();
+                    loop {
+                        let c = chars.next().ok_or(invalid("unclosed ["))?;
+                        // A `]` first is part of the class
+                        if c == ']' && !ranges.is_empty() {
+                            break;
+                        }
+                        let low = if c == '\\' { chars.next().ok_or(invalid("unclosed ["))? } else { c };
+                        let mut ahead = chars.clone();
+                        match (ahead.next(), ahead.next()) {
+                            (Some('-'), Some(high)) if high != ']' => {
+                                chars.next();
+                                chars.next();
+                                if high < low {
+                                    return Err(invalid("range out of order in [...]"));
+                                }
+                                ranges.push((low, high));
+                            }
+                            _ => ranges.push((low, low)),
+                        }
+                    }
+                    Token::Class { negated, ranges }
+                }
+                c => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+        let literal: Option<String> = tokens
+            .iter()
+            .map(|token| match token {
+                Token::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect();
+        Ok(match literal {
+            Some(name) => Segment::Literal(name),
+            None => Segment::Wildcard(tokens),
+        })
+    }
+
+    /// Whether the name of a file matches. As in the shell, a wildcard
+    /// doesn't match the `.` that hidden files start with.
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        let tokens = match self {
+            Segment::Literal(literal) => return literal == name,
+            Segment::Wildcard(tokens) => tokens,
+            Segment::Recursive => return !name.starts_with('.'),
+        };
+        let name: Vec<char> = name.chars().collect();
+        if name.first() == Some(&'.') && tokens.first() != Some(&Token::Char('.')) {
+            return false;
+        }
+        let (mut t, mut n) = (0, 0);
+        // Where to go back to if what follows the last `*` stops matching
+        let mut star: Option<(usize, usize)> = None;
+        while n < name.len() {
+            match tokens.get(t) {
+                Some(Token::Many) => {
+                    star = Some((t, n));
+                    t += 1;
+                }
+                Some(token) if token.takes(name[n]) => {
+                    t += 1;
+                    n += 1;
+                }
+                _ => match star {
+                    // Let the `*` take one more character
+                    Some((star_t, star_n)) => {
+                        star = Some((star_t, star_n + 1));
+                        t = star_t + 1;
+                        n = star_n + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+        tokens[t..].iter().all(|token| *token == Token::Many)
+    }
+}
+
+/// A glob pattern without braces (see [`Glob`](crate::Glob) for one with
+/// them), compiled for matching.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    text: String,
+    pub(crate) absolute: bool,
+    pub(crate) segments: Vec<Segment>,
+    /// Whether the pattern ends in `/`, and so only matches directories.
+    pub(crate) dirs_only: bool,
+}
+
+impl Pattern {
+    pub fn new(text: &str) -> Result<Pattern> {
+        if text.is_empty() {
+            return Err(Error::Pattern { pattern: String::new(), reason: "empty pattern" });
+        }
+        let segments = text.split('/').filter(|part| !part.is_empty()).map(|part| Segment::parse(part, text));
+        Ok(Pattern {
+            text: text.to_string(),
+            absolute: text.starts_with('/'),
+            segments: segments.collect::<Result<_>>()?,
+            dirs_only: text.len() > 1 && text.ends_with('/'),
+        })
+    }
+
+    /// The pattern as it was given.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether `path` matches the whole pattern. Wildcards never match a
+    /// `/`, and only `**` crosses directories.
+    pub fn matches(&self, path: &str) -> bool {
+        if path.starts_with('/') != self.absolute {
+            return false;
+        }
+        let names: Vec<&str> = path.split('/').filter(|name| !name.is_empty()).collect();
+        matches(&self.segments, &names)
+    }
+}
+
+fn matches(segments: &[Segment], names: &[&str]) -> bool {
+    match segments.split_first() {
+        None => names.is_empty(),
+        Some((Segment::Recursive, rest)) => (0..=names.len())
+            .take_while(|&skipped| skipped == 0 || Segment::Recursive.matches(names[skipped - 1]))
+            .any(|skipped| matches(rest, &names[skipped..])),
+        Some((segment, rest)) => {
+            names.split_first().is_some_and(|(name, names)| segment.matches(name) && matches(rest, names))
+        }
+    }
+}
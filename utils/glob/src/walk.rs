@@ -0,0 +1,73 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::pattern::{Pattern, Segment};
+
+/// Finds the files under `base` that `pattern` matches, adding them to
+/// `found` in the order of their names.
+pub(crate) fn search(pattern: &Pattern, base: &Path, found: &mut Vec<PathBuf>) {
+    let (real, shown) = match pattern.absolute {
+        true => (Path::new("/"), Path::new("/")),
+        false => (base, Path::new("")),
+    };
+    visit(real, shown, &pattern.segments, pattern.dirs_only, found);
+}
+
+/// `real` is where `shown` is on disk: the same path for absolute patterns,
+/// or relative ones joined to the base.
+fn visit(real: &Path, shown: &Path, segments: &[Segment], dirs_only: bool, found: &mut Vec<PathBuf>) {
+    let Some((segment, rest)) = segments.split_first() else {
+        // The base itself isn't a match, even for `**`
+        if !shown.as_os_str().is_empty() && (!dirs_only || real.is_dir()) {
+            found.push(shown.to_path_buf());
+        }
+        return;
+    };
+    // Only go on into directories, unless this is the last segment
+    let next = |name: &str, found: &mut Vec<PathBuf>| {
+        let real = real.join(name);
+        if rest.is_empty() || real.is_dir() {
+            visit(&real, &shown.join(name), rest, dirs_only, found);
+        }
+    };
+    match segment {
+        // Looked up without listing the directory, so that a long path of
+        // literal names costs a lookup per name rather than a listing
+        Segment::Literal(name) => {
+            if fs::symlink_metadata(real.join(name)).is_ok() {
+                next(name, found);
+            }
+        }
+        Segment::Wildcard(_) => {
+            for name in names(real).iter().filter(|name| segment.matches(name)) {
+                next(name, found);
+            }
+        }
+        Segment::Recursive => {
+            visit(real, shown, rest, dirs_only, found);
+            for name in names(real).iter().filter(|name| segment.matches(name)) {
+                let below = real.join(name);
+                // Links aren't followed, so a link back up can't go round
+                // forever
+                if fs::symlink_metadata(&below).is_ok_and(|metadata| metadata.is_dir()) {
+                    visit(&below, &shown.join(name), segments, dirs_only, found);
+                } else if rest.is_empty() {
+                    // A trailing `**` matches the files in each directory too
+                    visit(&below, &shown.join(name), rest, dirs_only, found);
+                }
+            }
+        }
+    }
+}
+
+/// The sorted names in a directory. As in the shell, a directory that can't
+/// be listed has nothing in it that matches, and names that aren't UTF-8
+/// can't be matched.
+fn names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries.filter_map(|entry| entry.ok()?.file_name().into_string().ok()).collect();
+    names.sort();
+    names
+}
@@ -0,0 +1,144 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ecmaos_glob::{expand_braces, Error, Glob, Pattern};
+
+/// Patterns with paths that match them and paths that don't, checked
+/// against bash with `extglob` off and `globstar` on.
+const CASES: &[(&str, &[&str], &[&str])] = &[
+    ("abc", &["abc"], &["ab", "abcd", "ABC"]),
+    ("*", &["a", "abc", "a.b"], &["", ".hidden", "a/b"]),
+    ("*.rs", &["lib.rs", "x.y.rs"], &["lib.rs.bak", "src/lib.rs", ".lib.rs"]),
+    ("a*b*c", &["abc", "aXbYc", "abbbc", "abcbc"], &["acb", "abcd"]),
+    ("?", &["a", "?"], &["", "ab", "."]),
+    ("??.txt", &["ab.txt"], &["a.txt", "abc.txt"]),
+    ("[abc]", &["a", "c"], &["d", "ab"]),
+    ("[a-c][0-9]", &["b7"], &["d7", "bb"]),
+    ("[!a-c]*", &["dog", "-"], &["apple", ".dog"]),
+    ("[^a-c]", &["z"], &["a"]),
+    ("[]]", &["]"], &["["]),
+    ("[!]]", &["["], &["]"]),
+    ("[a-]", &["a", "-"], &["b"]),
+    ("\\*", &["*"], &["a"]),
+    ("a\\?c", &["a?c"], &["abc"]),
+    (".*", &[".hidden", ".a.b"], &["visible"]),
+    ("a/*/c", &["a/b/c", "a//x/c"], &["a/c", "a/b/x/c", "a/.b/c"]),
+    ("**", &["a", "a/b/c"], &[".a", "a/.b/c"]),
+    ("a/**/c", &["a/c", "a/b/c", "a/b/b/c"], &["a/.b/c", "b/c", "a/b/cd"]),
+    ("**/*.rs", &["lib.rs", "src/bin/test.rs"], &["src/.cache/x.rs", "src/lib.rc"]),
+    ("a**b", &["ab", "axxb"], &["a/b"]),
+    ("/usr/*", &["/usr/bin"], &["usr/bin", "/usr/bin/env"]),
+    ("a/", &["a", "a/"], &["b"]),
+];
+
+#[test]
+fn patterns_match_like_the_shell() {
+    for (pattern, matching, other) in CASES {
+        let glob = Glob::new(pattern).unwrap();
+        for path in *matching {
+            assert!(glob.matches(path), "{pattern} should match {path:?}");
+        }
+        for path in *other {
+            assert!(!glob.matches(path), "{pattern} shouldn't match {path:?}");
+        }
+    }
+}
+
+#[test]
+fn bad_patterns_are_errors() {
+    for (pattern, reason) in
+        [("", "empty pattern"), ("[ab", "unclosed ["), ("a/[]", "unclosed ["), ("[z-a]", "range out of order in [...]")]
+    {
+        assert_eq!(Pattern::new(pattern).unwrap_err(), Error::Pattern { pattern: pattern.to_string(), reason });
+    }
+    // Braces expand first, so the error names the pattern that was broken
+    assert_eq!(Glob::new("{a,[b}").unwrap_err().to_string(), "invalid glob pattern \"[b\": unclosed [");
+}
+
+#[test]
+fn braces_expand_like_the_shell() {
+    let cases: &[(&str, &[&str])] = &[
+        ("a{b,c}d", &["abd", "acd"]),
+        ("{a,b}{1,2}", &["a1", "a2", "b1", "b2"]),
+        ("x{a,b{c,d}}y", &["xay", "xbcy", "xbdy"]),
+        ("{,s}", &["", "s"]),
+        ("{a}", &["{a}"]),
+        ("{}", &["{}"]),
+        ("{a,b", &["{a,b"]),
+        ("a}{b,c}", &["a}b", "a}c"]),
+        ("\\{a,b}", &["\\{a,b}"]),
+        ("{a\\,b,c}", &["a\\,b", "c"]),
+        ("{1..4}", &["1", "2", "3", "4"]),
+        ("{3..1}", &["3", "2", "1"]),
+        ("{-1..1}", &["-1", "0", "1"]),
+        ("{08..11}", &["08", "09", "10", "11"]),
+        ("{a..c}", &["a", "b", "c"]),
+        ("{c..a}", &["c", "b", "a"]),
+        ("{1..b}", &["{1..b}"]),
+        ("{a{1..2},b}", &["a1", "a2", "b"]),
+    ];
+    for (pattern, expanded) in cases {
+        assert_eq!(expand_braces(pattern), *expanded, "{pattern}");
+    }
+}
+
+/// A tree of files to search:
+///
+/// ```text
+/// a.rs  b.txt  .hidden.rs
+/// src/lib.rs  src/bin/main.rs  src/bin/notes.txt
+/// src/.cache/cached.rs
+/// docs/
+/// ```
+fn tree(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ecmaos-glob-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for dir in ["src/bin", "src/.cache", "docs"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    for file in
+        ["a.rs", "b.txt", ".hidden.rs", "src/lib.rs", "src/bin/main.rs", "src/bin/notes.txt", "src/.cache/cached.rs"]
+    {
+        fs::write(root.join(file), file).unwrap();
+    }
+    root
+}
+
+fn search(root: &Path, pattern: &str) -> Vec<String> {
+    let found = Glob::new(pattern).unwrap().search_in(root);
+    found.iter().map(|path| path.to_str().unwrap().to_string()).collect()
+}
+
+#[test]
+fn search_finds_what_matches() {
+    let root = tree("search");
+    assert_eq!(search(&root, "*.rs"), ["a.rs"]);
+    assert_eq!(search(&root, ".*.rs"), [".hidden.rs"]);
+    assert_eq!(search(&root, "*"), ["a.rs", "b.txt", "docs", "src"]);
+    assert_eq!(search(&root, "*/"), ["docs", "src"]);
+    assert_eq!(search(&root, "src/*/*"), ["src/bin/main.rs", "src/bin/notes.txt"]);
+    assert_eq!(search(&root, "**/*.rs"), ["a.rs", "src/bin/main.rs", "src/lib.rs"]);
+    assert_eq!(search(&root, "src/**"), ["src", "src/bin", "src/bin/main.rs", "src/bin/notes.txt", "src/lib.rs"]);
+    assert_eq!(search(&root, "src/.cache/*"), ["src/.cache/cached.rs"]);
+    assert_eq!(search(&root, "src/bin/main.rs"), ["src/bin/main.rs"]);
+    assert_eq!(search(&root, "src/lib.rs/*"), Vec::<String>::new());
+    assert_eq!(search(&root, "missing/*"), Vec::<String>::new());
+    // In the order of the braces, each path once
+    assert_eq!(search(&root, "{b.txt,*.rs,a.rs}"), ["b.txt", "a.rs"]);
+    assert_eq!(search(&root, "src/{bin/*.txt,lib.rs}"), ["src/bin/notes.txt", "src/lib.rs"]);
+
+    let absolute = format!("{}/src/*.rs", root.display());
+    assert_eq!(search(Path::new("/nowhere"), &absolute), [format!("{}/src/lib.rs", root.display())]);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn search_doesnt_follow_links_round_in_circles() {
+    let root = tree("links");
+    std::os::unix::fs::symlink("..", root.join("src/up")).unwrap();
+    assert_eq!(search(&root, "src/**/lib.rs"), ["src/lib.rs"]);
+    // A link is followed when it's named, though
+    assert_eq!(search(&root, "src/up/src/lib.rs"), ["src/up/src/lib.rs"]);
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,13 @@
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("{language} catalog, line {line}: {reason}")]
+    Syntax { language: &'static str, line: usize, reason: &'static str },
+    #[error("{language} catalog: {msgid:?} has {found} plural forms, where {language} has {expected}")]
+    PluralForms { language: &'static str, msgid: String, found: usize, expected: usize },
+    #[error("{language} catalog: the translation of {msgid:?} has a {{{name}}} the message doesn't")]
+    Placeholder { language: &'static str, msgid: String, name: String },
+    #[error("{language} catalog: {msgid:?} is translated twice")]
+    Duplicate { language: &'static str, msgid: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,325 @@
+//! Translated messages for ecmaOS's Rust utilities.
+//!
+//! The kernel and the TypeScript coreutils speak the language in `LANG`,
+//! and the Rust ones should too. Each program builds its translations in,
+//! a catalog per language in `locales/<language>.po`, so a WASM command is
+//! still a single file. The catalogs are written in the part of gettext's
+//! PO format that people write by hand, which translators and their tools
+//! already know:
+//!
+//! ```text
+//! msgid "unknown option: {option}"
+//! msgstr "opción desconocida: {option}"
+//!
+//! msgid "{n} file"
+//! msgid_plural "{n} files"
+//! msgstr[0] "{n} archivo"
+//! msgstr[1] "{n} archivos"
+//! ```
+//!
+//! Messages are written in English in the source, which is what's printed
+//! when no catalog matches. The language is chosen as gettext chooses it:
+//! the first of `LC_ALL`, `LC_MESSAGES` and `LANG` that's set names the
+//! locale, unless it's `C` or `POSIX`, and `LANGUAGE` can list languages
+//! to try before it. `es_ES.UTF-8` uses an `es_ES` catalog if there is one
+//! and an `es` one otherwise. Which plural form goes with a number follows
+//! the language's rule; a catalog whose plural messages don't have as many
+//! forms as its language needs is one [`Catalog::check`] turns down.
+//!
+//! Messages name the values that go in them, so that a translation can put
+//! them in another order, and [`format`] fills them in:
+//!
+//! ```
+//! use ecmaos_i18n::{format, Catalog, Messages};
+//!
+//! const ES: Catalog = Catalog {
+//!     language: "es",
+//!     source: r#"
+//! msgid "{n} file in {dir}"
+//! msgid_plural "{n} files in {dir}"
+//! msgstr[0] "{n} archivo en {dir}"
+//! msgstr[1] "{n} archivos en {dir}"
+//! "#,
+//! };
+//!
+//! let messages = Messages::for_locales(&[ES], &["es_ES.UTF-8"]);
+//! let message = messages.get_plural("{n} file in {dir}", "{n} files in {dir}", 3);
+//! assert_eq!(format(message, &[("n", &3), ("dir", &"/tmp")]), "3 archivos en /tmp");
+//! ```
+//!
+//! A program chooses its messages once, with [`init`] and the catalogs it
+//! builds in with [`catalogs!`], and then prints them with [`tr!`] and
+//! [`trn!`], which look a message up and fill it in; `trn!` passes the
+//! number it's given as `{n}`:
+//!
+//! ```ignore
+//! use ecmaos_i18n::{catalogs, tr, trn};
+//!
+//! ecmaos_i18n::init(catalogs!("es"));
+//! eprintln!("{}", tr!("unknown option: {option}", option = arg));
+//! println!("{}", trn!("{n} file", "{n} files", files.len()));
+//! ```
+
+mod error;
+mod plural;
+mod po;
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+pub use error::{Error, Result};
+
+use plural::Rule;
+
+/// A catalog built into a program: the translations of its messages into a
+/// language, in PO form.
+#[derive(Debug, Clone, Copy)]
+pub struct Catalog {
+    /// The language's code, such as `es` or `pt_BR`.
+    pub language: &'static str,
+    pub source: &'static str,
+}
+
+/// Builds the catalogs in the calling crate's `locales` directory in, one
+/// per language named.
+#[macro_export]
+macro_rules! catalogs {
+    ($($language:literal),* $(,)?) => {
+        &[$($crate::Catalog {
+            language: $language,
+            source: include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/locales/", $language, ".po")),
+        }),*]
+    };
+}
+
+impl Catalog {
+    /// Checks that the catalog reads, and that its translations fit its
+    /// language and the messages they translate. A catalog that doesn't is
+    /// never used, so each program's tests should check its catalogs.
+    pub fn check(&self) -> Result<()> {
+        self.entries().map(|_| ())
+    }
+
+    fn entries(&self) -> Result<Vec<po::Entry>> {
+        let entries = po::parse(self.language, self.source)?;
+        let forms = Rule::of(self.language).forms();
+        let mut seen = HashSet::new();
+        for entry in &entries {
+            if !seen.insert(&entry.msgid) {
+                return Err(Error::Duplicate { language: self.language, msgid: entry.msgid.clone() });
+            }
+            if entry.plural.is_some() && entry.translations.len() != forms {
+                return Err(Error::PluralForms {
+                    language: self.language,
+                    msgid: entry.msgid.clone(),
+                    found: entry.translations.len(),
+                    expected: forms,
+                });
+            }
+            let known = |name: &str| {
+                placeholders(&entry.msgid).any(|known| known == name)
+                    || entry.plural.as_deref().is_some_and(|plural| placeholders(plural).any(|known| known == name))
+            };
+            if let Some(name) = entry.translations.iter().flat_map(|text| placeholders(text)).find(|name| !known(name))
+            {
+                return Err(Error::Placeholder {
+                    language: self.language,
+                    msgid: entry.msgid.clone(),
+                    name: name.to_string(),
+                });
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// The messages of one language, or none to print them as they're written.
+#[derive(Debug)]
+pub struct Messages {
+    language: Option<&'static str>,
+    rule: Rule,
+    translations: HashMap<String, Vec<String>>,
+}
+
+impl Messages {
+    /// Messages printed as they're written in the source.
+    pub fn untranslated() -> Messages {
+        Messages { language: None, rule: Rule::One, translations: HashMap::new() }
+    }
+
+    /// The messages of the language the environment asks for.
+    pub fn from_env(catalogs: &[Catalog]) -> Messages {
+        let var = |name| env::var(name).ok().filter(|value: &String| !value.is_empty());
+        let Some(locale) = var("LC_ALL").or_else(|| var("LC_MESSAGES")).or_else(|| var("LANG")) else {
+            return Messages::untranslated();
+        };
+        if matches!(locale.as_str(), "C" | "POSIX") || locale.starts_with("C.") {
+            return Messages::untranslated();
+        }
+        let mut locales: Vec<String> =
+            var("LANGUAGE").iter().flat_map(|list| list.split(':')).map(String::from).collect();
+        locales.push(locale);
+        Messages::for_locales(catalogs, &locales)
+    }
+
+    /// The messages of the first of `locales` there's a catalog for. English
+    /// needs none, so one that asks for it gets the messages as written.
+    pub fn for_locales<S: AsRef<str>>(catalogs: &[Catalog], locales: &[S]) -> Messages {
+        for locale in locales {
+            // `es_ES.UTF-8@euro` or `es-ES` is tried as `es_ES`, then `es`
+            let locale = locale.as_ref();
+            let locale = locale.split(['.', '@']).next().unwrap_or(locale).replace('-', "_");
+            let base = locale.split('_').next().unwrap_or(&locale);
+            for name in [locale.as_str(), base] {
+                if let Some(catalog) = catalogs.iter().find(|catalog| catalog.language.eq_ignore_ascii_case(name)) {
+                    if let Ok(entries) = catalog.entries() {
+                        return Messages::from_entries(catalog.language, entries);
+                    }
+                }
+            }
+            if base.eq_ignore_ascii_case("en") {
+                break;
+            }
+        }
+        Messages::untranslated()
+    }
+
+    fn from_entries(language: &'static str, entries: Vec<po::Entry>) -> Messages {
+        let translations = entries
+            .into_iter()
+            // Untranslated and fuzzy messages are printed as written
+            .filter(|entry| {
+                !entry.fuzzy && !entry.msgid.is_empty() && entry.translations.iter().all(|text| !text.is_empty())
+            })
+            .map(|entry| (entry.msgid, entry.translations))
+            .collect();
+        Messages { language: Some(language), rule: Rule::of(language), translations }
+    }
+
+    /// The language of the catalog in use, if there is one.
+    pub fn language(&self) -> Option<&'static str> {
+        self.language
+    }
+
+    /// The translation of `msgid`, or `msgid` if it has none.
+    pub fn get<'a>(&'a self, msgid: &'a str) -> &'a str {
+        match self.translations.get(msgid) {
+            Some(translations) => &translations[0],
+            None => msgid,
+        }
+    }
+
+    /// The form of a message that goes with `n`: `singular` or `plural` if
+    /// it isn't translated, as in English.
+    pub fn get_plural<'a>(&'a self, singular: &'a str, plural: &'a str, n: u64) -> &'a str {
+        match self.translations.get(singular).and_then(|translations| translations.get(self.rule.index(n))) {
+            Some(translation) => translation,
+            None if n == 1 => singular,
+            None => plural,
+        }
+    }
+}
+
+static MESSAGES: OnceLock<Messages> = OnceLock::new();
+
+/// Chooses the messages a program prints from `catalogs`, by the language
+/// the environment asks for. Until it's called, and in programs that never
+/// call it, messages are printed as they're written.
+pub fn init(catalogs: &[Catalog]) {
+    let _ = MESSAGES.set(Messages::from_env(catalogs));
+}
+
+/// The translation of `msgid` into the program's language.
+pub fn gettext(msgid: &'static str) -> &'static str {
+    match MESSAGES.get() {
+        Some(messages) => messages.get(msgid),
+        None => msgid,
+    }
+}
+
+/// The form of a message that goes with `n` in the program's language.
+pub fn ngettext(singular: &'static str, plural: &'static str, n: u64) -> &'static str {
+    match MESSAGES.get() {
+        Some(messages) => messages.get_plural(singular, plural, n),
+        None if n == 1 => singular,
+        None => plural,
+    }
+}
+
+/// Fills in the `{name}` placeholders of a message with the values given
+/// for them. `{{` and `}}` stand for braces, and placeholders with no value
+/// are left as they are.
+pub fn format(message: &str, values: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        let value = placeholder(rest).and_then(|name| values.iter().find(|(known, _)| *known == name));
+        match value {
+            Some((name, value)) => {
+                out.push_str(&value.to_string());
+                rest = &rest[name.len() + 2..];
+            }
+            None => {
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The name of the placeholder `text` starts with, if it starts with one.
+fn placeholder(text: &str) -> Option<&str> {
+    let name = text.strip_prefix('{')?.split('}').next()?;
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    (valid && text.len() > name.len() + 1).then_some(name)
+}
+
+/// The names of the placeholders in a message.
+fn placeholders(message: &str) -> impl Iterator<Item = &str> {
+    let mut rest = message;
+    std::iter::from_fn(move || loop {
+        let start = rest.find('{')?;
+        rest = &rest[start..];
+        if rest.starts_with("{{") {
+            rest = &rest[2..];
+            continue;
+        }
+        let name = placeholder(rest);
+        rest = &rest[1..];
+        if name.is_some() {
+            return name;
+        }
+    })
+}
+
+/// Looks up a message and fills in its placeholders with the values named.
+#[macro_export]
+macro_rules! tr {
+    ($msgid:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::format($crate::gettext($msgid), &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*])
+    };
+}
+
+/// Looks up the form of a message that goes with a number and fills it in,
+/// the number as `{n}` and the other placeholders with the values named.
+#[macro_export]
+macro_rules! trn {
+    ($singular:literal, $plural:literal, $n:expr $(, $name:ident = $value:expr)* $(,)?) => {{
+        let n = $n;
+        $crate::format(
+            $crate::ngettext($singular, $plural, n as u64),
+            &[("n", &n as &dyn ::std::fmt::Display), $((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    }};
+}
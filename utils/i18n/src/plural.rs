@@ -0,0 +1,61 @@
+/// How a language picks between the plural forms of a message, as gettext
+/// numbers them: `msgstr[0]` is the form for one in most languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rule {
+    /// One form for every number: Japanese, Chinese, Korean and the like.
+    Single,
+    /// One form for 1 and another for everything else: English, Spanish,
+    /// German and most other languages.
+    One,
+    /// One form for 0 and 1 and another for the rest: French and
+    /// Brazilian Portuguese.
+    ZeroOne,
+    /// Russian, Ukrainian and the other East Slavic and Serbo-Croatian
+    /// languages: 1, 21, 31…; 2–4, 22–24…; and the rest.
+    Slavic,
+    /// Polish: 1; 2–4, 22–24… but not 12–14; and the rest.
+    Polish,
+    /// Czech and Slovak: 1; 2–4; and the rest.
+    Czech,
+}
+
+impl Rule {
+    /// The rule for a language code such as `es` or `pt_BR`.
+    pub(crate) fn of(language: &str) -> Rule {
+        let base = language.split('_').next().unwrap_or(language);
+        match (base, language) {
+            (_, "pt_BR") | ("fr", _) => Rule::ZeroOne,
+            ("ja" | "zh" | "ko" | "vi" | "th" | "id" | "ms", _) => Rule::Single,
+            ("ru" | "uk" | "be" | "sr" | "hr" | "bs", _) => Rule::Slavic,
+            ("pl", _) => Rule::Polish,
+            ("cs" | "sk", _) => Rule::Czech,
+            _ => Rule::One,
+        }
+    }
+
+    /// How many forms a plural message has under this rule.
+    pub(crate) fn forms(self) -> usize {
+        match self {
+            Rule::Single => 1,
+            Rule::One | Rule::ZeroOne => 2,
+            Rule::Slavic | Rule::Polish | Rule::Czech => 3,
+        }
+    }
+
+    /// Which form goes with `n`.
+    pub(crate) fn index(self, n: u64) -> usize {
+        let (tens, hundreds) = (n % 10, n % 100);
+        match self {
+            Rule::Single => 0,
+            Rule::One => usize::from(n != 1),
+            Rule::ZeroOne => usize::from(n > 1),
+            Rule::Slavic if tens == 1 && hundreds != 11 => 0,
+            Rule::Slavic if (2..=4).contains(&tens) && !(12..=14).contains(&hundreds) => 1,
+            Rule::Polish if n == 1 => 0,
+            Rule::Polish if (2..=4).contains(&tens) && !(12..=14).contains(&hundreds) => 1,
+            Rule::Czech if n == 1 => 0,
+            Rule::Czech if (2..=4).contains(&n) => 1,
+            Rule::Slavic | Rule::Polish | Rule::Czech => 2,
+        }
+    }
+}
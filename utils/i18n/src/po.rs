@@ -0,0 +1,128 @@
+use crate::error::{Error, Result};
+
+/// A message and its translations, as a catalog gives them.
+#[derive(Debug)]
+pub(crate) struct Entry {
+    pub(crate) msgid: String,
+    pub(crate) plural: Option<String>,
+    /// One translation, or one per plural form.
+    pub(crate) translations: Vec<String>,
+    /// Marked `#, fuzzy`: a guess waiting for a translator to check, which
+    /// gettext doesn't use and neither does this.
+    pub(crate) fuzzy: bool,
+    /// The line its `msgid` is on.
+    line: usize,
+}
+
+/// Which string a continuation line adds to.
+#[derive(Clone, Copy)]
+enum Field {
+    Id,
+    Plural,
+    Translation(usize),
+}
+
+/// Reads the entries of a catalog in the subset of gettext's PO format that
+/// hand-written catalogs use: comments, `msgid`, `msgid_plural`, `msgstr`
+/// and `msgstr[N]`, with strings continued on the lines after. Contexts
+/// (`msgctxt`) and obsolete entries (`#~`) aren't read.
+pub(crate) fn parse(language: &'static str, source: &str) -> Result<Vec<Entry>> {
+    let error = |line, reason| Error::Syntax { language, line, reason };
+    let mut entries = Vec::new();
+    let mut entry: Option<Entry> = None;
+    let mut field = Field::Id;
+    let mut fuzzy = false;
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            if let Some(flags) = comment.strip_prefix(',') {
+                fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            }
+            continue;
+        }
+        if line.starts_with('"') {
+            let text = unquote(line).ok_or(error(number, "badly quoted string"))?;
+            let Some(entry) = &mut entry else {
+                return Err(error(number, "string outside an entry"));
+            };
+            match field {
+                Field::Id => entry.msgid.push_str(&text),
+                Field::Plural => entry.plural.get_or_insert_with(String::new).push_str(&text),
+                Field::Translation(index) => entry.translations[index].push_str(&text),
+            }
+            continue;
+        }
+
+        let (keyword, rest) = line.split_once(char::is_whitespace).ok_or(error(number, "keyword without a string"))?;
+        let text = unquote(rest.trim_start()).ok_or(error(number, "badly quoted string"))?;
+        if keyword == "msgid" {
+            entries.extend(entry.take().map(|entry| finish(language, entry)).transpose()?);
+            entry = Some(Entry { msgid: text, plural: None, translations: Vec::new(), fuzzy, line: number });
+            field = Field::Id;
+            fuzzy = false;
+            continue;
+        }
+        if keyword == "msgctxt" {
+            return Err(error(number, "msgctxt isn't supported"));
+        }
+        let Some(entry) = &mut entry else {
+            return Err(error(number, "keyword outside an entry"));
+        };
+        match keyword {
+            "msgid_plural" if entry.plural.is_none() && entry.translations.is_empty() => {
+                entry.plural = Some(text);
+                field = Field::Plural;
+            }
+            "msgstr" if entry.plural.is_none() && entry.translations.is_empty() => {
+                entry.translations.push(text);
+                field = Field::Translation(0);
+            }
+            _ if keyword.starts_with("msgstr[") && keyword.ends_with(']') => {
+                let index = keyword["msgstr[".len()..keyword.len() - 1].parse().ok();
+                if entry.plural.is_none() || index != Some(entry.translations.len()) {
+                    return Err(error(number, "plural forms out of order, or in a message without msgid_plural"));
+                }
+                entry.translations.push(text);
+                field = Field::Translation(entry.translations.len() - 1);
+            }
+            "msgid_plural" | "msgstr" => return Err(error(number, "keyword out of place")),
+            _ => return Err(error(number, "unknown keyword")),
+        }
+    }
+    entries.extend(entry.map(|entry| finish(language, entry)).transpose()?);
+    Ok(entries)
+}
+
+/// Checks that an entry that has ended was given its translations.
+fn finish(language: &'static str, entry: Entry) -> Result<Entry> {
+    match entry.translations.is_empty() {
+        true => Err(Error::Syntax { language, line: entry.line, reason: "msgid without msgstr" }),
+        false => Ok(entry),
+    }
+}
+
+/// The text of a quoted string with its escapes undone; `None` if it isn't
+/// one quoted string.
+fn unquote(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return None,
+            '\\' => text.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                c @ ('"' | '\\') => c,
+                _ => return None,
+            }),
+            c => text.push(c),
+        }
+    }
+    Some(text)
+}
@@ -0,0 +1,176 @@
+use std::env;
+
+use ecmaos_i18n::{format, Catalog, Error, Messages};
+
+const ES: Catalog = Catalog {
+    language: "es",
+    source: r#"
+# The header gettext's tools write is read like any other entry
+msgid ""
+msgstr "Content-Type: text/plain; charset=UTF-8\n"
+
+msgid "unknown option: {option}"
+msgstr "opción desconocida: {option}"
+
+msgid "{n} file"
+msgid_plural "{n} files"
+msgstr[0] "{n} archivo"
+msgstr[1] "{n} archivos"
+
+msgid "a message split "
+"across lines"
+msgstr "un mensaje partido "
+"en líneas, con \"comillas\""
+
+msgid "not translated yet"
+msgstr ""
+
+#, fuzzy
+msgid "a guess"
+msgstr "una suposición"
+"#,
+};
+
+const ES_MX: Catalog = Catalog {
+    language: "es_MX",
+    source: "msgid \"unknown option: {option}\"\nmsgstr \"opción no conocida: {option}\"",
+};
+
+/// A catalog with a plural message translated into `language`, each form
+/// of it naming its index.
+fn plural_catalog(language: &'static str, forms: usize) -> Catalog {
+    let mut source = String::from("msgid \"{n} file\"\nmsgid_plural \"{n} files\"\n");
+    for index in 0..forms {
+        source.push_str(&format!("msgstr[{index}] \"{index}\"\n"));
+    }
+    Catalog { language, source: Box::leak(source.into_boxed_str()) }
+}
+
+#[test]
+fn messages_are_translated() {
+    let messages = Messages::for_locales(&[ES], &["es"]);
+    assert_eq!(messages.language(), Some("es"));
+    assert_eq!(messages.get("unknown option: {option}"), "opción desconocida: {option}");
+    assert_eq!(messages.get("a message split across lines"), "un mensaje partido en líneas, con \"comillas\"");
+    assert_eq!(messages.get_plural("{n} file", "{n} files", 1), "{n} archivo");
+    assert_eq!(messages.get_plural("{n} file", "{n} files", 0), "{n} archivos");
+
+    // What the catalog doesn't translate is printed as it's written
+    assert_eq!(messages.get("not in the catalog"), "not in the catalog");
+    assert_eq!(messages.get("not translated yet"), "not translated yet");
+    assert_eq!(messages.get("a guess"), "a guess");
+    assert_eq!(messages.get_plural("{n} dir", "{n} dirs", 1), "{n} dir");
+    assert_eq!(messages.get_plural("{n} dir", "{n} dirs", 2), "{n} dirs");
+}
+
+#[test]
+fn plural_forms_follow_the_language() {
+    // The form each language uses for 0, 1, 2, 5, 11, 12, 21, 22, 25, 101, 112 and 122
+    let numbers = [0, 1, 2, 5, 11, 12, 21, 22, 25, 101, 112, 122];
+    let cases: [(&'static str, usize, [&str; 12]); 7] = [
+        ("de", 2, ["1", "0", "1", "1", "1", "1", "1", "1", "1", "1", "1", "1"]),
+        ("fr", 2, ["0", "0", "1", "1", "1", "1", "1", "1", "1", "1", "1", "1"]),
+        ("pt_BR", 2, ["0", "0", "1", "1", "1", "1", "1", "1", "1", "1", "1", "1"]),
+        ("ja", 1, ["0", "0", "0", "0", "0", "0", "0", "0", "0", "0", "0", "0"]),
+        ("ru", 3, ["2", "0", "1", "2", "2", "2", "0", "1", "2", "0", "2", "1"]),
+        ("pl", 3, ["2", "0", "1", "2", "2", "2", "2", "1", "2", "2", "2", "1"]),
+        ("cs", 3, ["2", "0", "1", "2", "2", "2", "2", "2", "2", "2", "2", "2"]),
+    ];
+    for (language, forms, expected) in cases {
+        let messages = Messages::for_locales(&[plural_catalog(language, forms)], &[language]);
+        assert_eq!(messages.language(), Some(language));
+        let chosen: Vec<&str> = numbers.iter().map(|&n| messages.get_plural("{n} file", "{n} files", n)).collect();
+        assert_eq!(chosen, expected, "plural forms in {language}");
+    }
+}
+
+#[test]
+fn locales_choose_the_catalog() {
+    let catalogs = [ES, ES_MX];
+    let language = |locales: &[&str]| Messages::for_locales(&catalogs, locales).language();
+    assert_eq!(language(&["es_MX.UTF-8"]), Some("es_MX"));
+    assert_eq!(language(&["es-MX"]), Some("es_MX"));
+    assert_eq!(language(&["es_AR.UTF-8@euro"]), Some("es"));
+    assert_eq!(language(&["ES"]), Some("es"));
+    assert_eq!(language(&["de_DE", "es"]), Some("es"));
+    assert_eq!(language(&["de_DE"]), None);
+    // English is what the messages are written in, so it needs no catalog
+    // and ends the search
+    assert_eq!(language(&["en_US", "es"]), None);
+
+    let broken = Catalog { language: "de", source: "msgid \"x\"" };
+    assert_eq!(Messages::for_locales(&[broken, ES], &["de", "es"]).language(), Some("es"));
+}
+
+#[test]
+fn the_environment_chooses_the_language() {
+    // The only test that changes the environment, so it can't race another
+    let set = |vars: &[(&str, &str)]| {
+        for name in ["LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"] {
+            env::remove_var(name);
+        }
+        for (name, value) in vars {
+            env::set_var(name, value);
+        }
+        Messages::from_env(&[ES, ES_MX]).language()
+    };
+    assert_eq!(set(&[]), None);
+    assert_eq!(set(&[("LANG", "es_ES.UTF-8")]), Some("es"));
+    assert_eq!(set(&[("LANG", "en_US"), ("LC_MESSAGES", "es_MX")]), Some("es_MX"));
+    assert_eq!(set(&[("LC_MESSAGES", "es_MX"), ("LC_ALL", "en_US")]), None);
+    assert_eq!(set(&[("LANG", "en_US"), ("LC_ALL", "")]), None);
+    assert_eq!(set(&[("LANG", "de_DE"), ("LANGUAGE", "fr:es_MX")]), Some("es_MX"));
+    assert_eq!(set(&[("LANG", "C"), ("LANGUAGE", "es")]), None);
+    assert_eq!(set(&[("LANG", "C.UTF-8"), ("LANGUAGE", "es")]), None);
+}
+
+#[test]
+fn catalogs_are_checked() {
+    let check = |language, source| Catalog { language, source }.check();
+    assert_eq!(ES.check(), Ok(()));
+    assert_eq!(ES_MX.check(), Ok(()));
+
+    let syntax = |line, reason| Err(Error::Syntax { language: "es", line, reason });
+    assert_eq!(check("es", "msgid \"a\"\nmsgstr \"b\"\nmsgid \"c\"\n"), syntax(3, "msgid without msgstr"));
+    assert_eq!(check("es", "msgid \"a\nmsgstr \"b\""), syntax(1, "badly quoted string"));
+    assert_eq!(check("es", "msgid \"a\" \"b\"\nmsgstr \"b\""), syntax(1, "badly quoted string"));
+    assert_eq!(check("es", "msgid \"a\\q\"\nmsgstr \"b\""), syntax(1, "badly quoted string"));
+    assert_eq!(check("es", "\"a\"\nmsgid \"a\"\nmsgstr \"b\""), syntax(1, "string outside an entry"));
+    assert_eq!(check("es", "msgstr \"b\""), syntax(1, "keyword outside an entry"));
+    assert_eq!(check("es", "msgctxt \"menu\"\nmsgid \"a\"\nmsgstr \"b\""), syntax(1, "msgctxt isn't supported"));
+    assert_eq!(check("es", "msgid \"a\"\nmsgctxt \"menu\"\nmsgstr \"b\""), syntax(2, "msgctxt isn't supported"));
+    assert_eq!(check("es", "msgid \"a\"\nmsgstr \"b\"\nmsgstr \"c\""), syntax(3, "keyword out of place"));
+    assert_eq!(check("es", "msgid \"a\"\nmsgstring \"b\""), syntax(2, "unknown keyword"));
+    let out_of_order = "plural forms out of order, or in a message without msgid_plural";
+    assert_eq!(check("es", "msgid \"a\"\nmsgstr[0] \"b\""), syntax(2, out_of_order));
+    let skipped = "msgid \"a\"\nmsgid_plural \"as\"\nmsgstr[0] \"b\"\nmsgstr[2] \"c\"";
+    assert_eq!(check("es", skipped), syntax(4, out_of_order));
+
+    let two_forms = "msgid \"{n} file\"\nmsgid_plural \"{n} files\"\nmsgstr[0] \"a\"\nmsgstr[1] \"b\"";
+    assert_eq!(check("es", two_forms), Ok(()));
+    let plural = |language, found, expected| {
+        Err(Error::PluralForms { language, msgid: "{n} file".to_string(), found, expected })
+    };
+    assert_eq!(check("ru", two_forms), plural("ru", 2, 3));
+    assert_eq!(check("ja", two_forms), plural("ja", 2, 1));
+
+    let duplicate = "msgid \"a\"\nmsgstr \"b\"\nmsgid \"a\"\nmsgstr \"c\"";
+    assert_eq!(check("es", duplicate), Err(Error::Duplicate { language: "es", msgid: "a".to_string() }));
+
+    let placeholder = |name: &str| {
+        Err(Error::Placeholder { language: "es", msgid: "{n} in {dir}".to_string(), name: name.to_string() })
+    };
+    assert_eq!(check("es", "msgid \"{n} in {dir}\"\nmsgstr \"{dir}: {n}\""), Ok(()));
+    assert_eq!(check("es", "msgid \"{n} in {dir}\"\nmsgstr \"{n} en {directory}\""), placeholder("directory"));
+    assert_eq!(check("es", "msgid \"{n} in {dir}\"\nmsgstr \"{{literal}} {n}\""), Ok(()));
+}
+
+#[test]
+fn placeholders_are_filled_in() {
+    assert_eq!(format("{a} and {b}", &[("b", &2), ("a", &"one")]), "one and 2");
+    assert_eq!(format("{a}{a}", &[("a", &'x')]), "xx");
+    assert_eq!(format("{{a}} {a}", &[("a", &1)]), "{a} 1");
+    assert_eq!(format("{missing} {a", &[("a", &1)]), "{missing} {a");
+    assert_eq!(format("{not a name} } {}", &[]), "{not a name} } {}");
+    assert_eq!(format("ünïcode {a}", &[("a", &"ß")]), "ünïcode ß");
+}
@@ -0,0 +1,501 @@
+//! Finding the features a module uses.
+//!
+//! Every section is decoded, and every instruction of every function, so a
+//! feature is found wherever it shows up: a `v128` local is as much SIMD as
+//! an `i8x16.add`. A feature is reported with the first place it was seen.
+//!
+//! | Feature              | Found by                                              |
+//! |----------------------|-------------------------------------------------------|
+//! | `bulk-memory`        | `memory.copy`, `memory.fill` and the other `0xfc`     |
+//! |                      | memory and table instructions, a data count section,  |
+//! |                      | passive data and element segments                     |
+//! | `exception-handling` | a tag section or imported or exported tags, `try`,    |
+//! |                      | `try_table`, `throw` and the rest, `exnref`           |
+//! | `reference-types`    | `externref`, references as locals or results, several |
+//! |                      | tables, `ref.*`, `table.get`/`set`/`grow`/`size`/     |
+//! |                      | `fill`, typed `select`                                |
+//! | `simd`               | `v128` and the `0xfd` instructions                    |
+//! | `threads`            | shared memories and the `0xfe` atomic instructions    |
+//!
+//! Modules using proposals beyond these, such as garbage collection or typed
+//! function references, can't be decoded and are reported as errors rather
+//! than passed as compatible.
+
+use std::collections::BTreeMap;
+
+use ecmaos_manifest::Feature;
+
+use crate::error::{Error, Result};
+
+const HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+/// The features a module uses, each with the first place it was seen.
+pub type Features = BTreeMap<Feature, String>;
+
+/// Decodes a module and returns the features it uses.
+pub fn detect(module: &[u8]) -> Result<Features> {
+    let Some(body) = module.strip_prefix(HEADER) else {
+        return Err(Error::Wasm("not a WASM module (version 1)".to_string()));
+    };
+    let mut detector = Detector { features: Features::new(), functions: 0, tables: 0 };
+    let mut reader = Reader { data: body, offset: 0 };
+    while !reader.done() {
+        let id = reader.byte()?;
+        let size = reader.u32()? as usize;
+        let contents = reader.bytes(size)?;
+        let mut section = Reader { data: contents, offset: 0 };
+        detector.section(id, &mut section).map_err(|e| Error::Wasm(format!("{}: {e}", section_name(id))))?;
+    }
+    Ok(detector.features)
+}
+
+fn section_name(id: u8) -> String {
+    const NAMES: [&str; 14] = [
+        "custom", "type", "import", "function", "table", "memory", "global", "export", "start", "element", "code",
+        "data", "datacount", "tag",
+    ];
+    match NAMES.get(usize::from(id)) {
+        Some(name) => format!("{name} section"),
+        None => format!("section {id}"),
+    }
+}
+
+struct Detector {
+    features: Features,
+    /// Functions imported so far, which come before the module's own in the
+    /// function index space.
+    functions: u32,
+    tables: u32,
+}
+
+impl Detector {
+    fn found(&mut self, feature: Feature, at: &str) {
+        self.features.entry(feature).or_insert_with(|| at.to_string());
+    }
+
+    fn section(&mut self, id: u8, r: &mut Reader) -> Result<()> {
+        match id {
+            0 | 3 | 8 => {}
+            1 => {
+                for i in 0..r.u32()? {
+                    let at = format!("type {i}");
+                    match r.byte()? {
+                        0x60 => {
+                            for _ in 0..r.u32()? {
+                                self.valtype(r, &at)?;
+                            }
+                            for _ in 0..r.u32()? {
+                                self.valtype(r, &at)?;
+                            }
+                        }
+                        form => return Err(unsupported(&at, format!("a type of form {form:#04x}"))),
+                    }
+                }
+            }
+            2 => {
+                for i in 0..r.u32()? {
+                    let at = format!("import {i}");
+                    r.name()?;
+                    r.name()?;
+                    match r.byte()? {
+                        0x00 => {
+                            r.u32()?;
+                            self.functions += 1;
+                        }
+                        0x01 => self.table(r, &at)?,
+                        0x02 => self.limits(r, &at)?,
+                        0x03 => {
+                            self.valtype(r, &at)?;
+                            r.byte()?;
+                        }
+                        0x04 => {
+                            self.found(Feature::ExceptionHandling, &at);
+                            r.byte()?;
+                            r.u32()?;
+                        }
+                        kind => return Err(unsupported(&at, format!("an import of kind {kind:#04x}"))),
+                    }
+                }
+            }
+            4 => {
+                for _ in 0..r.u32()? {
+                    let at = format!("table {}", self.tables);
+                    if r.peek()? == 0x40 {
+                        return Err(unsupported(&at, "a table with an initializer".to_string()));
+                    }
+                    self.table(r, &at)?;
+                }
+            }
+            5 => {
+                for i in 0..r.u32()? {
+                    self.limits(r, &format!("memory {i}"))?;
+                }
+            }
+            6 => {
+                for i in 0..r.u32()? {
+                    let at = format!("global {i}");
+                    self.valtype(r, &at)?;
+                    r.byte()?;
+                    self.expression(r, &at)?;
+                }
+            }
+            7 => {
+                for i in 0..r.u32()? {
+                    r.name()?;
+                    if r.byte()? == 0x04 {
+                        self.found(Feature::ExceptionHandling, &format!("export {i}"));
+                    }
+                    r.u32()?;
+                }
+            }
+            9 => {
+                for i in 0..r.u32()? {
+                    self.element(r, &format!("element segment {i}"))?;
+                }
+            }
+            10 => {
+                for i in 0..r.u32()? {
+                    let at = format!("function {}", self.functions + i);
+                    let size = r.u32()? as usize;
+                    let mut body = Reader { data: r.bytes(size)?, offset: 0 };
+                    for _ in 0..body.u32()? {
+                        body.u32()?;
+                        self.valtype(&mut body, &at)?;
+                    }
+                    while !body.done() {
+                        self.instruction(&mut body, &at)?;
+                    }
+                }
+            }
+            11 => {
+                for i in 0..r.u32()? {
+                    let at = format!("data segment {i}");
+                    match r.u32()? {
+                        0 => self.expression(r, &at)?,
+                        1 => self.found(Feature::BulkMemory, &at),
+                        2 => {
+                            self.found(Feature::BulkMemory, &at);
+                            r.u32()?;
+                            self.expression(r, &at)?;
+                        }
+                        flags => return Err(unsupported(&at, format!("a data segment with flags {flags}"))),
+                    }
+                    let size = r.u32()? as usize;
+                    r.bytes(size)?;
+                }
+            }
+            12 => self.found(Feature::BulkMemory, "datacount section"),
+            13 => self.found(Feature::ExceptionHandling, "tag section"),
+            id => return Err(Error::Wasm(format!("unknown section id {id}"))),
+        }
+        // Sections whose contents don't matter here
+        if matches!(id, 0 | 3 | 8 | 12 | 13) {
+            r.offset = r.data.len();
+        }
+        if !r.done() {
+            return Err(Error::Wasm(format!("{} bytes left over", r.data.len() - r.offset)));
+        }
+        Ok(())
+    }
+
+    fn valtype(&mut self, r: &mut Reader, at: &str) -> Result<()> {
+        match r.byte()? {
+            0x7c..=0x7f => {}
+            0x7b => self.found(Feature::Simd, at),
+            0x70 | 0x6f => self.found(Feature::ReferenceTypes, at),
+            0x69 => self.found(Feature::ExceptionHandling, at),
+            0x63 | 0x64 => return Err(unsupported(at, "a typed reference".to_string())),
+            byte => return Err(Error::Wasm(format!("{at}: unknown value type {byte:#04x}"))),
+        }
+        Ok(())
+    }
+
+    /// A table's element type, which unlike other values may be `funcref`
+    /// without reference types, and limits.
+    fn table(&mut self, r: &mut Reader, at: &str) -> Result<()> {
+        match r.peek()? {
+            0x70 => r.offset += 1,
+            _ => self.valtype(r, at)?,
+        }
+        self.tables += 1;
+        if self.tables > 1 {
+            self.found(Feature::ReferenceTypes, at);
+        }
+        self.limits(r, at)
+    }
+
+    fn limits(&mut self, r: &mut Reader, at: &str) -> Result<()> {
+        let flags = r.byte()?;
+        if flags & 0x02 != 0 {
+            self.found(Feature::Threads, at);
+        }
+        if flags & !0x07 != 0 {
+            return Err(unsupported(at, format!("limits with flags {flags:#04x}")));
+        }
+        r.u64()?;
+        if flags & 0x01 != 0 {
+            r.u64()?;
+        }
+        Ok(())
+    }
+
+    fn element(&mut self, r: &mut Reader, at: &str) -> Result<()> {
+        let flags = r.u32()?;
+        if flags > 7 {
+            return Err(unsupported(at, format!("an element segment with flags {flags}")));
+        }
+        // Bit 0 marks passive segments, or declarative ones with bit 1 also
+        // set; for active segments bit 1 means the table is given. Bit 2
+        // marks elements given as expressions rather than function indices.
+        match flags & 0x03 {
+            0 => {}
+            1 | 2 => self.found(Feature::BulkMemory, at),
+            _ => self.found(Feature::ReferenceTypes, at),
+        }
+        if flags & 0x04 != 0 {
+            self.found(Feature::ReferenceTypes, at);
+        }
+        if flags == 2 || flags == 6 {
+            r.u32()?;
+        }
+        if flags & 0x01 == 0 {
+            self.expression(r, at)?;
+        }
+        if flags != 0 && flags != 4 {
+            // The element kind, or the reference type with expressions
+            match (flags & 0x04, r.peek()?) {
+                (0, _) | (_, 0x70) => r.offset += 1,
+                _ => self.valtype(r, at)?,
+            }
+        }
+        for _ in 0..r.u32()? {
+            if flags & 0x04 != 0 {
+                self.expression(r, at)?;
+            } else {
+                r.u32()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A constant expression, which ends with the first `end`.
+    fn expression(&mut self, r: &mut Reader, at: &str) -> Result<()> {
+        while self.instruction(r, at)? != 0x0b {}
+        Ok(())
+    }
+
+    fn block_type(&mut self, r: &mut Reader, at: &str) -> Result<()> {
+        match r.peek()? {
+            0x40 => r.offset += 1,
+            0x63 | 0x64 | 0x69 | 0x6f | 0x70 | 0x7b..=0x7f => self.valtype(r, at)?,
+            // The index of a function type, for blocks with parameters
+            _ => r.s64()?,
+        }
+        Ok(())
+    }
+
+    /// Decodes one instruction, returning its opcode.
+    fn instruction(&mut self, r: &mut Reader, at: &str) -> Result<u8> {
+        let opcode = r.byte()?;
+        match opcode {
+            0x00 | 0x01 | 0x05 | 0x0b | 0x0f | 0x1a | 0x1b | 0x45..=0xc4 => {}
+            0x02..=0x04 => self.block_type(r, at)?,
+            0x06 => {
+                self.found(Feature::ExceptionHandling, at);
+                self.block_type(r, at)?;
+            }
+            0x07..=0x09 | 0x18 => {
+                self.found(Feature::ExceptionHandling, at);
+                r.u32()?;
+            }
+            0x0a | 0x19 => self.found(Feature::ExceptionHandling, at),
+            0x1f => {
+                self.found(Feature::ExceptionHandling, at);
+                self.block_type(r, at)?;
+                for _ in 0..r.u32()? {
+                    // catch and catch_ref name a tag; catch_all and
+                    // catch_all_ref don't
+                    if r.byte()? < 2 {
+                        r.u32()?;
+                    }
+                    r.u32()?;
+                }
+            }
+            0x0c | 0x0d | 0x10 | 0x12 | 0x20..=0x24 => {
+                r.u32()?;
+            }
+            0x0e => {
+                for _ in 0..r.u32()? + 1 {
+                    r.u32()?;
+                }
+            }
+            0x11 | 0x13 => {
+                r.u32()?;
+                if r.u32()? != 0 {
+                    self.found(Feature::ReferenceTypes, at);
+                }
+            }
+            0x1c => {
+                self.found(Feature::ReferenceTypes, at);
+                for _ in 0..r.u32()? {
+                    self.valtype(r, at)?;
+                }
+            }
+            0x25 | 0x26 => {
+                self.found(Feature::ReferenceTypes, at);
+                r.u32()?;
+            }
+            0x28..=0x3e => r.memarg()?,
+            0x3f | 0x40 => {
+                r.u32()?;
+            }
+            0x41 | 0x42 => r.s64()?,
+            0x43 => {
+                r.bytes(4)?;
+            }
+            0x44 => {
+                r.bytes(8)?;
+            }
+            0xd0 => {
+                self.found(Feature::ReferenceTypes, at);
+                match r.peek()? {
+                    0x69 => {
+                        self.found(Feature::ExceptionHandling, at);
+                        r.offset += 1;
+                    }
+                    0x6f | 0x70 => r.offset += 1,
+                    _ => return Err(unsupported(at, "a typed reference".to_string())),
+                }
+            }
+            0xd1 => self.found(Feature::ReferenceTypes, at),
+            0xd2 => {
+                self.found(Feature::ReferenceTypes, at);
+                r.u32()?;
+            }
+            0xfc => self.fc_instruction(r, at)?,
+            0xfd => {
+                self.found(Feature::Simd, at);
+                match r.u32()? {
+                    0x00..=0x0b | 0x5c | 0x5d => r.memarg()?,
+                    0x0c | 0x0d => {
+                        r.bytes(16)?;
+                    }
+                    0x15..=0x22 => {
+                        r.byte()?;
+                    }
+                    0x54..=0x5b => {
+                        r.memarg()?;
+                        r.byte()?;
+                    }
+                    0x0e..=0x14 | 0x23..=0x53 | 0x5e..=0x113 => {}
+                    opcode => return Err(unknown_instruction(at, format!("0xfd {opcode:#x}"))),
+                }
+            }
+            0xfe => {
+                self.found(Feature::Threads, at);
+                match r.u32()? {
+                    0x03 => {
+                        r.byte()?;
+                    }
+                    0x00..=0x02 | 0x10..=0x4e => r.memarg()?,
+                    opcode => return Err(unknown_instruction(at, format!("0xfe {opcode:#x}"))),
+                }
+            }
+            opcode => return Err(unknown_instruction(at, format!("{opcode:#04x}"))),
+        }
+        Ok(opcode)
+    }
+
+    /// The saturating conversions and the bulk memory and table instructions.
+    fn fc_instruction(&mut self, r: &mut Reader, at: &str) -> Result<()> {
+        let (feature, immediates) = match r.u32()? {
+            // Saturating conversions from floats, which every browser with
+            // any of the features here supports
+            0..=7 => return Ok(()),
+            8 | 10 | 12 | 14 => (Feature::BulkMemory, 2),
+            9 | 11 | 13 => (Feature::BulkMemory, 1),
+            15..=17 => (Feature::ReferenceTypes, 1),
+            opcode => return Err(unknown_instruction(at, format!("0xfc {opcode}"))),
+        };
+        self.found(feature, at);
+        for _ in 0..immediates {
+            r.u32()?;
+        }
+        Ok(())
+    }
+}
+
+fn unsupported(at: &str, what: String) -> Error {
+    Error::Wasm(format!("{at}: uses {what}, from a proposal this tool doesn't check for"))
+}
+
+fn unknown_instruction(at: &str, opcode: String) -> Error {
+    Error::Wasm(format!("{at}: unknown instruction {opcode}"))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn done(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn peek(&self) -> Result<u8> {
+        self.data.get(self.offset).copied().ok_or_else(truncated)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let byte = self.peek()?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+        let end = self.offset.checked_add(length).filter(|&end| end <= self.data.len()).ok_or_else(truncated)?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn name(&mut self) -> Result<()> {
+        let length = self.u32()? as usize;
+        self.bytes(length).map(drop)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        u32::try_from(self.u64()?).map_err(|_| Error::Wasm(format!("number at offset {} is too large", self.offset)))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let mut value = 0;
+        for shift in (0..70).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f).checked_shl(shift).unwrap_or(0);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::Wasm(format!("number at offset {} is too long", self.offset)))
+    }
+
+    /// Skips a signed number; only its length matters here.
+    fn s64(&mut self) -> Result<()> {
+        self.u64().map(drop)
+    }
+
+    /// The alignment, memory and offset of a load or store. A memory index
+    /// follows the alignment only if bit 6 of it is set.
+    fn memarg(&mut self) -> Result<()> {
+        if self.u32()? & 0x40 != 0 {
+            self.u32()?;
+        }
+        self.u64().map(drop)
+    }
+}
+
+fn truncated() -> Error {
+    Error::Wasm("truncated module".to_string())
+}
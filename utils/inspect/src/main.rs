@@ -0,0 +1,169 @@
+//! Lists the WASM features beyond the first version of the standard that
+//! modules use, so a package can declare them and the package manager can
+//! warn before installing it in a browser that lacks them.
+//!
+//! ```text
+//! ecmaos-inspect --manifest package.json dist/*.wasm
+//! ```
+//!
+//! Each module is decoded down to its instructions (see [`features`]) and
+//! the features are listed with the first place each was found. With
+//! `--manifest` their union is written to the `ecmaos.features` field of a
+//! `package.json`, which `install` compares with what the browser supports.
+//! With `--baseline` only the features listed, comma-separated, are allowed,
+//! so a build can be kept to what the browsers it targets support:
+//!
+//! ```text
+//! ecmaos-inspect --baseline bulk-memory,reference-types dist/app.wasm
+//! ```
+//!
+//! The exit status is 0 if every module stays within the baseline, 1 if any
+//! doesn't or couldn't be decoded. Nothing in the tool is specific to a
+//! platform, so it can be built for `wasm32-wasip1` to run inside ecmaOS too.
+
+mod error;
+mod features;
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ecmaos_manifest::Feature;
+use serde_json::{json, Map, Value};
+
+use error::{Error, Result};
+use features::Features;
+
+const USAGE: &str = "usage: ecmaos-inspect [--json] [--baseline FEATURES] [--manifest PACKAGE_JSON] MODULE...";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut json = false;
+    let mut baseline = None;
+    let mut manifest = None;
+    let mut modules = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--baseline" => match args.next() {
+                Some(list) => baseline = Some(parse_features(&list).unwrap_or_else(|e| usage_error(&e))),
+                None => usage_error("--baseline needs a value"),
+            },
+            "--manifest" => match args.next() {
+                Some(path) => manifest = Some(PathBuf::from(path)),
+                None => usage_error("--manifest needs a value"),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ => modules.push(PathBuf::from(arg)),
+        }
+    }
+    if modules.is_empty() {
+        usage_error("no modules given");
+    }
+
+    match run(&modules, json, baseline.as_ref(), manifest.as_deref()) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses a comma-separated list of features; empty for none.
+fn parse_features(list: &str) -> std::result::Result<BTreeSet<Feature>, String> {
+    list.split(',')
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            Feature::ALL.into_iter().find(|feature| feature.name() == name).ok_or_else(|| {
+                let names: Vec<&str> = Feature::ALL.iter().map(|feature| feature.name()).collect();
+                format!("unknown feature {name:?}; expected one of {}", names.join(", "))
+            })
+        })
+        .collect()
+}
+
+/// Returns whether every module stayed within the baseline.
+fn run(modules: &[PathBuf], json: bool, baseline: Option<&BTreeSet<Feature>>, manifest: Option<&Path>) -> Result<bool> {
+    let mut found: Vec<(&Path, Features)> = Vec::new();
+    for path in modules {
+        let features = features::detect(&fs::read(path)?).map_err(|e| Error::Wasm(format!("{}: {e}", path.display())))?;
+        found.push((path, features));
+    }
+
+    if json {
+        let modules: Vec<Value> = found
+            .iter()
+            .map(|(path, features)| {
+                let features: Map<String, Value> =
+                    features.iter().map(|(feature, at)| (feature.name().to_string(), json!(at))).collect();
+                json!({ "path": path.display().to_string(), "features": features })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&json!({ "modules": modules }))?);
+    } else {
+        for (path, features) in &found {
+            if features.is_empty() {
+                println!("{}: WASM 1.0 only", path.display());
+                continue;
+            }
+            println!("{}", path.display());
+            for (feature, at) in features {
+                println!("  {:<20} {}", feature.name(), at);
+            }
+        }
+    }
+
+    let mut within = true;
+    if let Some(baseline) = baseline {
+        for (path, features) in &found {
+            for (feature, at) in features.iter().filter(|(feature, _)| !baseline.contains(feature)) {
+                eprintln!("✗ {} uses {} ({}), which isn't in the baseline", path.display(), feature.name(), at);
+                within = false;
+            }
+        }
+    }
+
+    if let Some(manifest) = manifest {
+        let features: BTreeSet<Feature> = found.iter().flat_map(|(_, features)| features.keys().copied()).collect();
+        record(manifest, &features)?;
+    }
+    Ok(within)
+}
+
+/// Writes the features to the manifest's `ecmaos.features`, keeping the rest
+/// of it, and its order, as it was.
+fn record(path: &Path, features: &BTreeSet<Feature>) -> Result<()> {
+    let mut manifest: Value = serde_json::from_slice(&fs::read(path)?)?;
+    let Some(root) = manifest.as_object_mut() else {
+        return Err(Error::Manifest(format!("{} doesn't hold an object", path.display())));
+    };
+    let ecmaos = root.entry("ecmaos").or_insert_with(|| Value::Object(Map::new()));
+    if let Some(ecmaos) = ecmaos.as_object_mut() {
+        if features.is_empty() {
+            ecmaos.shift_remove("features");
+        } else {
+            ecmaos.insert("features".to_string(), json!(features.iter().map(|feature| feature.name()).collect::<Vec<_>>()));
+        }
+    }
+
+    let issues = ecmaos_manifest::validate(&manifest);
+    if !issues.is_empty() {
+        let error = ecmaos_manifest::Error::Invalid(issues);
+        return Err(Error::Manifest(format!("{}: {}", path.display(), error)));
+    }
+    fs::write(path, serde_json::to_string_pretty(&manifest)? + "\n")?;
+    eprintln!("Recorded {} features in {}", features.len(), path.display());
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use serde_json::{json, Value};
+
+const HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+/// A type section with the one type `[] -> []`, or `[] -> [result]`.
+fn types(result: Option<u8>) -> Vec<u8> {
+    match result {
+        Some(result) => vec![0x01, 0x05, 0x01, 0x60, 0x00, 0x01, result],
+        None => vec![0x01, 0x04, 0x01, 0x60, 0x00, 0x00],
+    }
+}
+
+/// A function section declaring one function of type 0, the sections that go
+/// between it and the code section, and a code section with the function's
+/// body, which has no locals.
+fn function(between: &[u8], instructions: &[u8]) -> Vec<u8> {
+    let mut sections = vec![0x03, 0x02, 0x01, 0x00];
+    sections.extend(between);
+    let size = instructions.len() as u8 + 2;
+    sections.extend([0x0a, size + 2, 0x01, size, 0x00]);
+    sections.extend(instructions);
+    sections.push(0x0b);
+    sections
+}
+
+fn module(sections: &[Vec<u8>]) -> Vec<u8> {
+    let mut module = HEADER.to_vec();
+    for section in sections {
+        module.extend(section);
+    }
+    module
+}
+
+/// Writes the modules to a scratch directory.
+fn scratch(name: &str, modules: &[(&str, Vec<u8>)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ecmaos-inspect-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    for (path, module) in modules {
+        fs::write(root.join(path), module).unwrap();
+    }
+    root
+}
+
+fn run(root: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ecmaos-inspect")).args(args).current_dir(root).output().unwrap()
+}
+
+#[test]
+fn detects_features() {
+    let memory = [0x05, 0x03, 0x01, 0x00, 0x01];
+    let root = scratch(
+        "detect",
+        &[
+            ("mvp.wasm", module(&[types(None), function(&[], &[0x41, 0x7f, 0x1a])])),
+            // memory.fill on memory 0
+            ("bulk.wasm", module(&[types(None), function(&memory, &[0x41, 0, 0x41, 0, 0x41, 0, 0xfc, 0x0b, 0x00])])),
+            // A tag section with one tag of type 0, and throw 0
+            ("eh.wasm", module(&[types(None), function(&[0x0d, 0x03, 0x01, 0x00, 0x00], &[0x08, 0x00])])),
+            // ref.null extern, then drop
+            ("ref.wasm", module(&[types(None), function(&[], &[0xd0, 0x6f, 0x1a])])),
+            // A function returning v128.const 0
+            ("simd.wasm", module(&[types(Some(0x7b)), function(&[], &[&[0xfd, 0x0c][..], &[0; 16]].concat())])),
+            // A shared memory
+            ("threads.wasm", module(&[vec![0x05, 0x04, 0x01, 0x03, 0x01, 0x01]])),
+        ],
+    );
+
+    let output = run(
+        &root,
+        &["--json", "mvp.wasm", "bulk.wasm", "eh.wasm", "ref.wasm", "simd.wasm", "threads.wasm"],
+    );
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let features: Vec<&Value> = report["modules"].as_array().unwrap().iter().map(|module| &module["features"]).collect();
+    assert_eq!(
+        features,
+        [
+            &json!({}),
+            &json!({ "bulk-memory": "function 0" }),
+            &json!({ "exception-handling": "tag section" }),
+            &json!({ "reference-types": "function 0" }),
+            &json!({ "simd": "type 0" }),
+            &json!({ "threads": "memory 0" }),
+        ]
+    );
+}
+
+#[test]
+fn enforces_baseline() {
+    let simd = module(&[types(Some(0x7b)), function(&[], &[&[0xfd, 0x0c][..], &[0; 16]].concat())]);
+    let root = scratch("baseline", &[("simd.wasm", simd)]);
+
+    assert!(run(&root, &["--baseline", "simd", "simd.wasm"]).status.success());
+    let output = run(&root, &["--baseline", "", "simd.wasm"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "✗ simd.wasm uses simd (type 0), which isn't in the baseline\n");
+    assert_eq!(run(&root, &["--baseline", "smid", "simd.wasm"]).status.code(), Some(2));
+}
+
+#[test]
+fn records_features_in_manifest() {
+    let bulk = module(&[types(None), vec![0x0c, 0x01, 0x00]]);
+    let threads = module(&[vec![0x05, 0x04, 0x01, 0x03, 0x01, 0x01]]);
+    let root = scratch("manifest", &[("a.wasm", bulk), ("b.wasm", threads)]);
+    fs::write(root.join("package.json"), r#"{ "version": "1.0.0", "name": "app", "ecmaos": { "kind": "library" } }"#)
+        .unwrap();
+
+    let output = run(&root, &["--manifest", "package.json", "a.wasm", "b.wasm"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        fs::read_to_string(root.join("package.json")).unwrap(),
+        "{\n  \"version\": \"1.0.0\",\n  \"name\": \"app\",\n  \"ecmaos\": {\n    \"kind\": \"library\",\n    \
+         \"features\": [\n      \"bulk-memory\",\n      \"threads\"\n    ]\n  }\n}\n"
+    );
+}
+
+#[test]
+fn rejects_unknown_instructions() {
+    // 0xfb starts the garbage collection instructions
+    let root = scratch("unknown", &[("gc.wasm", module(&[types(None), function(&[], &[0xfb, 0x00])]))]);
+    let output = run(&root, &["gc.wasm"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "error: gc.wasm: code section: function 0: unknown instruction 0xfb\n"
+    );
+}
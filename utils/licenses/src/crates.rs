@@ -0,0 +1,155 @@
+//! The packages in a lockfile and the license metadata they ship with.
+//!
+//! `Cargo.lock` names every package a build can use, but not their
+//! licenses; those are in each package's own `Cargo.toml`. Packages from a
+//! registry are looked up in Cargo's extracted sources, git packages in its
+//! checkouts and path packages below the project directory, so the project
+//! must have been built or fetched (`cargo fetch`) first. Nothing is
+//! downloaded.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use toml::{Table, Value};
+
+use crate::error::{Error, Result};
+
+/// A package in the lockfile and what its metadata says about its license.
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    /// The SPDX license expression, such as `MIT OR Apache-2.0`.
+    pub license: Option<String>,
+    /// The file holding the license text, for packages without an SPDX
+    /// expression, relative to the package.
+    pub license_file: Option<String>,
+    pub repository: Option<String>,
+    /// Where the metadata was read from, if it was found.
+    pub manifest: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<Locked>,
+}
+
+#[derive(Deserialize)]
+struct Locked {
+    name: String,
+    version: String,
+    source: Option<String>,
+}
+
+/// Reads the lockfile of the project in `project` and looks up the license
+/// of every package in it, in lockfile order.
+pub fn scan(project: &Path, lockfile: &Path) -> Result<Vec<Package>> {
+    let text = fs::read_to_string(lockfile)
+        .map_err(|e| Error::Metadata(format!("couldn't read {}: {e}", lockfile.display())))?;
+    let locked: Lockfile =
+        toml::from_str(&text).map_err(|e| Error::Metadata(format!("{}: {e}", lockfile.display())))?;
+
+    let cargo_home = cargo_home();
+    let mut packages = Vec::new();
+    for Locked { name, version, source } in locked.package {
+        let roots = match source.as_deref() {
+            None => vec![project.to_path_buf()],
+            Some(source) if source.starts_with("git+") => vec![cargo_home.join("git").join("checkouts")],
+            Some(_) => subdirectories(&cargo_home.join("registry").join("src")),
+        };
+        let manifest = roots.iter().find_map(|root| {
+            let extracted = root.join(format!("{name}-{version}")).join("Cargo.toml");
+            if extracted.is_file() {
+                Some(extracted)
+            } else if source.as_deref().is_none_or(|source| source.starts_with("git+")) {
+                find_manifest(root, &name, &version)
+            } else {
+                None
+            }
+        });
+
+        let mut package =
+            Package { name, version, license: None, license_file: None, repository: None, manifest: None };
+        if let Some(manifest) = manifest {
+            read_license(&mut package, &manifest)?;
+            package.manifest = Some(manifest);
+        }
+        packages.push(package);
+    }
+    Ok(packages)
+}
+
+/// `$CARGO_HOME`, defaulting to `~/.cargo` as Cargo does.
+fn cargo_home() -> PathBuf {
+    if let Some(home) = env::var_os("CARGO_HOME") {
+        return PathBuf::from(home);
+    }
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).unwrap_or_default();
+    Path::new(&home).join(".cargo")
+}
+
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    let mut dirs: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()).collect();
+    dirs.sort();
+    dirs
+}
+
+/// Searches below `dir` for the manifest of a package, skipping build
+/// output and hidden directories.
+fn find_manifest(dir: &Path, name: &str, version: &str) -> Option<PathBuf> {
+    let manifest = dir.join("Cargo.toml");
+    if let Some(package) = fs::read_to_string(&manifest).ok().and_then(|text| text.parse::<Table>().ok()) {
+        let package = package.get("package").and_then(Value::as_table);
+        let field = |key| package.and_then(|package| package.get(key));
+        let inherited = field("version").is_some_and(Value::is_table);
+        if field("name").and_then(Value::as_str) == Some(name)
+            && (inherited || field("version").and_then(Value::as_str) == Some(version))
+        {
+            return Some(manifest);
+        }
+    }
+    subdirectories(dir)
+        .into_iter()
+        .filter(|sub| sub.file_name().is_some_and(|name| name != "target" && !name.to_string_lossy().starts_with('.')))
+        .find_map(|sub| find_manifest(&sub, name, version))
+}
+
+fn read_license(package: &mut Package, manifest: &Path) -> Result<()> {
+    let parse = |path: &Path| -> Result<Table> {
+        let text = fs::read_to_string(path)?;
+        text.parse().map_err(|e| Error::Metadata(format!("{}: {e}", path.display())))
+    };
+    let table = parse(manifest)?;
+    let Some(fields) = table.get("package").and_then(Value::as_table) else { return Ok(()) };
+
+    // Fields may be inherited from the workspace with `license.workspace =
+    // true`; registry packages have that resolved already
+    let mut workspace: Option<Table> = None;
+    let mut field = |key: &str| -> Result<Option<String>> {
+        match fields.get(key) {
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(Value::Table(table)) if table.get("workspace").and_then(Value::as_bool) == Some(true) => {
+                if workspace.is_none() {
+                    let root = manifest.ancestors().skip(1).map(|dir| dir.join("Cargo.toml")).find(|path| {
+                        parse(path).is_ok_and(|table| table.get("workspace").is_some())
+                    });
+                    workspace = Some(match root {
+                        Some(root) => parse(&root)?,
+                        None => Table::new(),
+                    });
+                }
+                let inherited = workspace.as_ref().and_then(|table| table.get("workspace")?.get("package")?.get(key));
+                Ok(inherited.and_then(Value::as_str).map(str::to_string))
+            }
+            _ => Ok(None),
+        }
+    };
+    package.license = field("license")?;
+    package.license_file = field("license-file")?;
+    package.repository = field("repository")?;
+    Ok(())
+}
@@ -0,0 +1,13 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    Metadata(String),
+    #[error("{0}")]
+    Wasm(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,176 @@
+//! Summarizes the licenses of the packages a Rust project is built from and
+//! embeds the summary in the project's WASM module, where the ecmaOS package
+//! UI can show it.
+//!
+//! ```text
+//! cargo build --release --target wasm32-wasip1
+//! ecmaos-licenses --embed target/wasm32-wasip1/release/app.wasm .
+//! ```
+//!
+//! The packages are read from the project's `Cargo.lock` and their licenses
+//! from their own metadata (see [`crates`]). The summary is a JSON document
+//! in the module's `ecmaos:licenses` custom section:
+//!
+//! ```json
+//! {"packages":[{"name":"serde","version":"1.0.229","license":"MIT OR Apache-2.0","repository":"https://github.com/serde-rs/serde"}]}
+//! ```
+//!
+//! `license` is an SPDX expression, or null with `licenseFile` naming the
+//! file in the package that holds the text. A package whose metadata wasn't
+//! found is listed with neither, and a warning is printed. Running the tool
+//! again replaces the section. Without `--embed` the summary is only
+//! printed, grouped by license, or as the JSON document with `--json`.
+//!
+//! The lockfile lists every package any target of the project could use, so
+//! the summary can include packages that aren't compiled into the module.
+
+mod crates;
+mod error;
+mod wasm;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Map, Value};
+
+use crates::Package;
+use error::{Error, Result};
+
+/// Name of the custom section holding the summary.
+const SECTION: &str = "ecmaos:licenses";
+
+const USAGE: &str = "usage: ecmaos-licenses [--json] [--embed WASM [--out PATH]] [PROJECT]\n       \
+                     ecmaos-licenses --show WASM";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut json = false;
+    let mut embed = None;
+    let mut out = None;
+    let mut show = None;
+    let mut project = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--embed" => match args.next() {
+                Some(path) => embed = Some(PathBuf::from(path)),
+                None => usage_error("--embed needs a value"),
+            },
+            "--out" => match args.next() {
+                Some(path) => out = Some(PathBuf::from(path)),
+                None => usage_error("--out needs a value"),
+            },
+            "--show" => match args.next() {
+                Some(path) => show = Some(PathBuf::from(path)),
+                None => usage_error("--show needs a value"),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ if project.is_none() => project = Some(PathBuf::from(arg)),
+            _ => usage_error(&format!("unexpected argument: {}", arg)),
+        }
+    }
+    if out.is_some() && embed.is_none() {
+        usage_error("--out needs --embed");
+    }
+
+    let result = match show {
+        Some(module) => show_section(&module),
+        None => run(&project.unwrap_or_else(|| PathBuf::from(".")), json, embed.as_deref(), out.as_deref()),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(project: &Path, json: bool, embed: Option<&Path>, out: Option<&Path>) -> Result<()> {
+    let packages = crates::scan(project, &project.join("Cargo.lock"))?;
+    for package in packages.iter().filter(|package| package.manifest.is_none()) {
+        eprintln!(
+            "warning: no metadata found for {} {}; fetch the project's dependencies first",
+            package.name, package.version
+        );
+    }
+
+    let summary = to_json(&packages);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        print_summary(&packages);
+    }
+
+    if let Some(module) = embed {
+        let data = fs::read(module)?;
+        let data = wasm::embed(&data, SECTION, serde_json::to_string(&summary)?.as_bytes())
+            .map_err(|e| Error::Wasm(format!("{}: {}", module.display(), e)))?;
+        let out = out.unwrap_or(module);
+        fs::write(out, data)?;
+        eprintln!("Embedded the licenses of {} packages in {}", packages.len(), out.display());
+    }
+    Ok(())
+}
+
+/// Prints the summary embedded in a module.
+fn show_section(module: &Path) -> Result<()> {
+    let data = fs::read(module)?;
+    let section = wasm::read(&data, SECTION).map_err(|e| Error::Wasm(format!("{}: {}", module.display(), e)))?;
+    let Some(section) = section else {
+        return Err(Error::Wasm(format!("{} has no {} section", module.display(), SECTION)));
+    };
+    let summary: Value = serde_json::from_slice(section)?;
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(())
+}
+
+fn to_json(packages: &[Package]) -> Value {
+    let packages: Vec<Value> = packages
+        .iter()
+        .map(|package| {
+            let mut entry = Map::new();
+            entry.insert("name".to_string(), json!(package.name));
+            entry.insert("version".to_string(), json!(package.version));
+            entry.insert("license".to_string(), json!(package.license));
+            if let Some(file) = &package.license_file {
+                entry.insert("licenseFile".to_string(), json!(file));
+            }
+            if let Some(repository) = &package.repository {
+                entry.insert("repository".to_string(), json!(repository));
+            }
+            Value::Object(entry)
+        })
+        .collect();
+    json!({ "packages": packages })
+}
+
+/// Prints the packages grouped by license.
+fn print_summary(packages: &[Package]) {
+    let mut groups: BTreeMap<&str, Vec<&Package>> = BTreeMap::new();
+    for package in packages {
+        let license = match (&package.license, &package.license_file) {
+            (Some(license), _) => license.as_str(),
+            (None, Some(_)) => "(license file)",
+            (None, None) => "(unknown)",
+        };
+        groups.entry(license).or_default().push(package);
+    }
+    for (license, packages) in groups {
+        println!("{} ({})", license, packages.len());
+        for package in packages {
+            match &package.license_file {
+                Some(file) if package.license.is_none() => println!("  {} {} ({})", package.name, package.version, file),
+                _ => println!("  {} {}", package.name, package.version),
+            }
+        }
+    }
+}
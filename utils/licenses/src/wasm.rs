@@ -0,0 +1,117 @@
+//! Custom sections of WASM modules.
+//!
+//! A module is a header followed by sections, each an id byte, its size as
+//! an unsigned LEB128 number and its contents. Custom sections (id 0) start
+//! with their name and are ignored by runtimes, which makes them the place
+//! for metadata such as licenses.
+
+use crate::error::{Error, Result};
+
+const HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+const CUSTOM: u8 = 0;
+
+/// Returns `module` with its custom sections called `name` replaced by one
+/// holding `contents`, added at the end.
+pub fn embed(module: &[u8], name: &str, contents: &[u8]) -> Result<Vec<u8>> {
+    let mut out = HEADER.to_vec();
+    for section in sections(module)? {
+        if section.custom_name() != Some(name) {
+            out.extend_from_slice(section.raw);
+        }
+    }
+
+    let mut section = Vec::new();
+    write_leb128(&mut section, name.len());
+    section.extend_from_slice(name.as_bytes());
+    section.extend_from_slice(contents);
+    out.push(CUSTOM);
+    write_leb128(&mut out, section.len());
+    out.extend_from_slice(&section);
+    Ok(out)
+}
+
+/// The contents of the first custom section called `name`.
+pub fn read<'a>(module: &'a [u8], name: &str) -> Result<Option<&'a [u8]>> {
+    for section in sections(module)? {
+        if let Some(contents) = section.custom_contents(name) {
+            return Ok(Some(contents));
+        }
+    }
+    Ok(None)
+}
+
+struct Section<'a> {
+    id: u8,
+    /// The whole section, id and size included.
+    raw: &'a [u8],
+    contents: &'a [u8],
+}
+
+impl<'a> Section<'a> {
+    fn custom_name(&self) -> Option<&'a str> {
+        self.custom().map(|(name, _)| name)
+    }
+
+    fn custom_contents(&self, name: &str) -> Option<&'a [u8]> {
+        self.custom().filter(|(custom, _)| *custom == name).map(|(_, contents)| contents)
+    }
+
+    fn custom(&self) -> Option<(&'a str, &'a [u8])> {
+        if self.id != CUSTOM {
+            return None;
+        }
+        let mut offset = 0;
+        let length = read_leb128(self.contents, &mut offset).ok()?;
+        let name = self.contents.get(offset..offset.checked_add(length)?)?;
+        Some((std::str::from_utf8(name).ok()?, &self.contents[offset + length..]))
+    }
+}
+
+fn sections(module: &[u8]) -> Result<Vec<Section<'_>>> {
+    if !module.starts_with(HEADER) {
+        return Err(Error::Wasm("not a WASM module (version 1)".to_string()));
+    }
+    let mut sections = Vec::new();
+    let mut offset = HEADER.len();
+    while offset < module.len() {
+        let start = offset;
+        let id = module[offset];
+        offset += 1;
+        let size = read_leb128(module, &mut offset)?;
+        let end = offset.checked_add(size).filter(|&end| end <= module.len());
+        let Some(end) = end else {
+            return Err(Error::Wasm(format!("section at offset {start} runs past the end of the module")));
+        };
+        sections.push(Section { id, raw: &module[start..end], contents: &module[offset..end] });
+        offset = end;
+    }
+    Ok(sections)
+}
+
+fn read_leb128(data: &[u8], offset: &mut usize) -> Result<usize> {
+    let mut value: u32 = 0;
+    for shift in (0..35).step_by(7) {
+        let Some(&byte) = data.get(*offset) else {
+            return Err(Error::Wasm("truncated module".to_string()));
+        };
+        *offset += 1;
+        value |= u32::from(byte & 0x7f).checked_shl(shift).unwrap_or(0);
+        if byte & 0x80 == 0 {
+            return Ok(value as usize);
+        }
+    }
+    Err(Error::Wasm(format!("section size at offset {} is too long", *offset)))
+}
+
+fn write_leb128(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
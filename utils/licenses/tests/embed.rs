@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use serde_json::{json, Value};
+
+/// The smallest valid module, with one type section after the header.
+const MODULE: &[u8] = b"\0asm\x01\0\0\0\x01\x04\x01\x60\0\0";
+
+/// A project with a path dependency inheriting its license from the
+/// workspace, a registry dependency and one whose sources weren't fetched.
+fn project(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ecmaos-licenses-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let write = |path: &str, contents: &str| {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    };
+    write(
+        "app/Cargo.toml",
+        "[workspace]\nmembers = [\"shared\"]\n\n[workspace.package]\nlicense = \"MIT\"\n\n\
+         [package]\nname = \"app\"\nversion = \"0.1.0\"\nlicense.workspace = true\n",
+    );
+    write("app/shared/Cargo.toml", "[package]\nname = \"shared\"\nversion.workspace = true\nlicense-file = \"COPYING\"\n");
+    write(
+        "app/Cargo.lock",
+        "version = 4\n\n\
+         [[package]]\nname = \"app\"\nversion = \"0.1.0\"\n\n\
+         [[package]]\nname = \"shared\"\nversion = \"0.1.0\"\n\n\
+         [[package]]\nname = \"itoa\"\nversion = \"1.0.0\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\nname = \"missing\"\nversion = \"2.0.0\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    );
+    write(
+        "cargo/registry/src/index.crates.io-0000000000000000/itoa-1.0.0/Cargo.toml",
+        "[package]\nname = \"itoa\"\nversion = \"1.0.0\"\nlicense = \"MIT OR Apache-2.0\"\nrepository = \"https://github.com/dtolnay/itoa\"\n",
+    );
+    fs::write(root.join("app.wasm"), MODULE).unwrap();
+    root
+}
+
+fn run(root: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_ecmaos-licenses"))
+        .args(args)
+        .current_dir(root)
+        .env("CARGO_HOME", root.join("cargo"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    output
+}
+
+/// The contents of every custom section called `name`.
+fn custom_sections(module: &[u8], name: &str) -> Vec<Vec<u8>> {
+    let leb128 = |data: &[u8], offset: &mut usize| {
+        let (mut value, mut shift) = (0, 0);
+        loop {
+            let byte = data[*offset];
+            *offset += 1;
+            value |= usize::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+        }
+    };
+    let mut found = Vec::new();
+    let mut offset = 8;
+    while offset < module.len() {
+        let id = module[offset];
+        offset += 1;
+        let size = leb128(module, &mut offset);
+        let end = offset + size;
+        if id == 0 {
+            let mut start = offset;
+            let length = leb128(module, &mut start);
+            if &module[start..start + length] == name.as_bytes() {
+                found.push(module[start + length..end].to_vec());
+            }
+        }
+        offset = end;
+    }
+    found
+}
+
+#[test]
+fn embeds_summary() {
+    let root = project("embed");
+    let output = run(&root, &["--embed", "app.wasm", "app"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("warning: no metadata found for missing 2.0.0"), "{stderr}");
+
+    // Embedding again replaces the section rather than adding another
+    run(&root, &["--embed", "app.wasm", "app"]);
+    let module = fs::read(root.join("app.wasm")).unwrap();
+    assert_eq!(&module[..MODULE.len()], MODULE);
+    let sections = custom_sections(&module, "ecmaos:licenses");
+    assert_eq!(sections.len(), 1);
+
+    let summary: Value = serde_json::from_slice(&sections[0]).unwrap();
+    assert_eq!(
+        summary,
+        json!({ "packages": [
+            { "name": "app", "version": "0.1.0", "license": "MIT" },
+            { "name": "shared", "version": "0.1.0", "license": null, "licenseFile": "COPYING" },
+            { "name": "itoa", "version": "1.0.0", "license": "MIT OR Apache-2.0", "repository": "https://github.com/dtolnay/itoa" },
+            { "name": "missing", "version": "2.0.0", "license": null },
+        ]})
+    );
+
+    let shown: Value = serde_json::from_slice(&run(&root, &["--show", "app.wasm"]).stdout).unwrap();
+    assert_eq!(shown, summary);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn groups_by_license() {
+    let root = project("summary");
+    let output = run(&root, &["app"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "(license file) (1)\n  shared 0.1.0 (COPYING)\n(unknown) (1)\n  missing 2.0.0\n\
+         MIT (1)\n  app 0.1.0\nMIT OR Apache-2.0 (1)\n  itoa 1.0.0\n"
+    );
+    assert_eq!(fs::read(root.join("app.wasm")).unwrap(), MODULE);
+    fs::remove_dir_all(root).unwrap();
+}
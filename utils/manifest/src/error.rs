@@ -0,0 +1,33 @@
+use std::fmt;
+
+/// One problem found in a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// Where the problem is, such as `ecmaos.permissions[1]`, or empty for
+    /// the manifest as a whole.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("manifest isn't valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// Every problem found, not just the first.
+    #[error("invalid manifest:{}", .0.iter().map(|issue| format!("\n  {issue}")).collect::<String>())]
+    Invalid(Vec<Issue>),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
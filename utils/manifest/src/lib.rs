@@ -0,0 +1,215 @@
+//! The ecmaOS package manifest: a `package.json` with an `ecmaos` section
+//! describing how the package runs and what it may do.
+//!
+//! ```json
+//! {
+//!   "name": "@ecmaos-apps/edit",
+//!   "version": "1.2.0",
+//!   "bin": { "edit": "dist/edit.js" },
+//!   "dependencies": { "@ecmaos/kernel": "^0.6" },
+//!   "ecmaos": {
+//!     "kind": "command",
+//!     "kernel": ">=0.6.0",
+//!     "permissions": ["fs:read:/home/**", "fs:write:/home/**"],
+//!     "features": ["bulk-memory", "simd"]
+//!   }
+//! }
+//! ```
+//!
+//! Every tool that reads or writes manifests goes through [`Manifest::parse`]
+//! so they all agree on what's valid. Fields npm knows about but ecmaOS
+//! doesn't use are kept as they are; inside `ecmaos` nothing unknown is
+//! allowed, since a misspelt permission or kind would otherwise be silently
+//! ignored. Parsing reports every problem at once:
+//!
+//! ```
+//! let error = ecmaos_manifest::Manifest::parse(r#"{
+//!     "name": "Edit",
+//!     "version": "1.2",
+//!     "ecmaos": { "knid": "command", "permissions": ["fs:read:home"] }
+//! }"#).unwrap_err();
+//! assert_eq!(error.to_string(), "invalid manifest:
+//!   name: \"Edit\" may only contain lowercase letters, digits, '-', '.', '_' and '~', and may not start with '.' or '_'
+//!   version: \"1.2\" isn't a version such as 1.2.3
+//!   ecmaos.knid: unknown field \"knid\"; did you mean \"kind\"?
+//!   ecmaos.permissions[0]: \"home\" isn't an absolute path");
+//! ```
+
+mod error;
+pub mod permission;
+mod validate;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use ecmaos_resolver::{Dependencies, Range, Version};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub use error::{Error, Issue, Result};
+pub use permission::Permission;
+pub use validate::validate;
+
+/// The fields allowed in the `ecmaos` section.
+const ECMAOS_FIELDS: &[&str] = &["kind", "kernel", "permissions", "features"];
+
+/// The lifecycle scripts ecmaOS runs; other `ecmaos:` scripts are mistakes.
+const ECMAOS_SCRIPTS: &[&str] = &["ecmaos:preinstall", "ecmaos:postinstall"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub name: String,
+    pub version: Version,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bin: Option<Bin>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, Range>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub optional_dependencies: BTreeMap<String, Range>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub conflicts: BTreeMap<String, Range>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scripts: BTreeMap<String, String>,
+    #[serde(default)]
+    pub ecmaos: Ecmaos,
+    /// Everything else, such as `license` or `repository`, kept so that
+    /// writing a manifest back doesn't lose it.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The commands a package provides: either one, named after the package, or
+/// a map of command names to files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Bin {
+    Single(String),
+    Named(BTreeMap<String, String>),
+}
+
+/// The `ecmaos` section.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ecmaos {
+    #[serde(default)]
+    pub kind: Kind,
+    /// The kernel versions the package works with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel: Option<Range>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<Permission>,
+    /// The WASM features beyond the first version of the standard that the
+    /// package's modules use, which the browser must support to run them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<Feature>,
+}
+
+/// How a package is meant to be used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// Opens its own window.
+    App,
+    /// Runs in the terminal.
+    Command,
+    /// A script for the shell.
+    Script,
+    /// Code for other packages to import.
+    #[default]
+    Library,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 4] = [Kind::App, Kind::Command, Kind::Script, Kind::Library];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::App => "app",
+            Kind::Command => "command",
+            Kind::Script => "script",
+            Kind::Library => "library",
+        }
+    }
+
+    /// Whether packages of this kind must declare a `bin`.
+    pub fn needs_bin(self) -> bool {
+        matches!(self, Kind::App | Kind::Command)
+    }
+}
+
+/// A WASM feature beyond the first version of the standard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Feature {
+    /// `memory.copy`, `memory.fill` and passive data and element segments.
+    BulkMemory,
+    /// Tags and `try`, `throw` and the other exception instructions.
+    ExceptionHandling,
+    /// `externref`, several tables and the instructions on references.
+    ReferenceTypes,
+    /// 128-bit vectors.
+    Simd,
+    /// Shared memories and atomic instructions.
+    Threads,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 5] =
+        [Feature::BulkMemory, Feature::ExceptionHandling, Feature::ReferenceTypes, Feature::Simd, Feature::Threads];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::BulkMemory => "bulk-memory",
+            Feature::ExceptionHandling => "exception-handling",
+            Feature::ReferenceTypes => "reference-types",
+            Feature::Simd => "simd",
+            Feature::Threads => "threads",
+        }
+    }
+}
+
+impl Manifest {
+    /// Parses and validates a manifest.
+    pub fn parse(text: &str) -> Result<Manifest> {
+        Manifest::from_value(serde_json::from_str(text)?)
+    }
+
+    /// Reads, parses and validates a manifest file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Manifest> {
+        Manifest::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Validates an already parsed manifest, such as one version's entry in a
+    /// registry's metadata.
+    pub fn from_value(value: Value) -> Result<Manifest> {
+        let issues = validate(&value);
+        if !issues.is_empty() {
+            return Err(Error::Invalid(issues));
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// The commands the package provides and the files that implement them.
+    pub fn commands(&self) -> BTreeMap<&str, &str> {
+        match &self.bin {
+            Some(Bin::Single(file)) => {
+                let command = self.name.rsplit('/').next().unwrap_or(&self.name);
+                BTreeMap::from([(command, file.as_str())])
+            }
+            Some(Bin::Named(commands)) => commands.iter().map(|(name, file)| (name.as_str(), file.as_str())).collect(),
+            None => BTreeMap::new(),
+        }
+    }
+
+    /// The package's dependencies in the form the resolver takes, where as
+    /// in npm a package listed both as a dependency and an optional one is
+    /// optional.
+    pub fn requirements(&self) -> Dependencies {
+        let mut required = self.dependencies.clone();
+        required.retain(|package, _| !self.optional_dependencies.contains_key(package));
+        Dependencies { required, optional: self.optional_dependencies.clone(), conflicts: self.conflicts.clone() }
+    }
+}
@@ -0,0 +1,132 @@
+//! Permission strings.
+//!
+//! A package lists what it needs beyond running in the terminal as strings
+//! of colon-separated parts:
+//!
+//! | Permission            | Grants                                          |
+//! |-----------------------|-------------------------------------------------|
+//! | `fs:read:<path>`      | reading `<path>`, or everything below it if the |
+//! |                       | path ends in `/**`                              |
+//! | `fs:write:<path>`     | creating, changing and removing files there     |
+//! | `net:fetch:<origin>`  | HTTP requests to an origin, or `*` for any      |
+//! | `process:spawn`       | running other commands                          |
+//! | `clipboard:read`      | reading the clipboard                           |
+//! | `clipboard:write`     | writing the clipboard                           |
+//! | `notifications`       | showing system notifications                    |
+//! | `storage`             | keeping data in the browser's storage           |
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The forms a permission can take, shown when one isn't recognised.
+pub const FORMS: &[&str] = &[
+    "fs:read:<path>",
+    "fs:write:<path>",
+    "net:fetch:<origin>",
+    "process:spawn",
+    "clipboard:read",
+    "clipboard:write",
+    "notifications",
+    "storage",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Permission {
+    FsRead(String),
+    FsWrite(String),
+    NetFetch(String),
+    ProcessSpawn,
+    ClipboardRead,
+    ClipboardWrite,
+    Notifications,
+    Storage,
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let permission = match text.splitn(3, ':').collect::<Vec<_>>()[..] {
+            ["fs", "read", path] => Permission::FsRead(check_path(path)?),
+            ["fs", "write", path] => Permission::FsWrite(check_path(path)?),
+            ["net", "fetch", origin] => Permission::NetFetch(check_origin(origin)?),
+            ["process", "spawn"] => Permission::ProcessSpawn,
+            ["clipboard", "read"] => Permission::ClipboardRead,
+            ["clipboard", "write"] => Permission::ClipboardWrite,
+            ["notifications"] => Permission::Notifications,
+            ["storage"] => Permission::Storage,
+            _ => return Err(format!("unknown permission {text:?}; expected one of {}", FORMS.join(", "))),
+        };
+        Ok(permission)
+    }
+}
+
+/// Accepts an absolute path, optionally ending in `/**`, with no `.` or `..`
+/// segments and no other wildcards.
+fn check_path(path: &str) -> Result<String, String> {
+    let plain = path.strip_suffix("/**").unwrap_or(path);
+    if !path.starts_with('/') {
+        return Err(format!("{path:?} isn't an absolute path"));
+    }
+    if plain.split('/').any(|segment| segment == "." || segment == "..") {
+        return Err(format!("{path:?} may not contain . or .. segments"));
+    }
+    if plain.contains('*') {
+        return Err(format!("{path:?} may only use a wildcard as a trailing /**"));
+    }
+    Ok(path.to_string())
+}
+
+/// Accepts `*` or an origin: `http` or `https`, a host and an optional port,
+/// with nothing after.
+fn check_origin(origin: &str) -> Result<String, String> {
+    if origin == "*" {
+        return Ok(origin.to_string());
+    }
+    let invalid = || format!("{origin:?} isn't an origin such as https://example.com or *");
+    let rest = origin.strip_prefix("https://").or_else(|| origin.strip_prefix("http://")).ok_or_else(invalid)?;
+    let (host, port) = match rest.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (rest, None),
+    };
+    let host_valid = !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty() && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        });
+    let port_valid = port.is_none_or(|port| port.parse::<u16>().is_ok());
+    if host_valid && port_valid {
+        Ok(origin.to_string())
+    } else {
+        Err(invalid())
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Permission::FsRead(path) => write!(f, "fs:read:{path}"),
+            Permission::FsWrite(path) => write!(f, "fs:write:{path}"),
+            Permission::NetFetch(origin) => write!(f, "net:fetch:{origin}"),
+            Permission::ProcessSpawn => f.write_str("process:spawn"),
+            Permission::ClipboardRead => f.write_str("clipboard:read"),
+            Permission::ClipboardWrite => f.write_str("clipboard:write"),
+            Permission::Notifications => f.write_str("notifications"),
+            Permission::Storage => f.write_str("storage"),
+        }
+    }
+}
+
+impl Serialize for Permission {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Permission {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
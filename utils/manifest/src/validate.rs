@@ -0,0 +1,276 @@
+//! Checks a manifest before it's deserialized, so every problem is
+//! reported at once and with the path to it, rather than only the first one
+//! serde runs into.
+
+use std::collections::BTreeSet;
+
+use ecmaos_resolver::{Range, Version};
+use serde_json::{Map, Value};
+
+use crate::error::Issue;
+use crate::permission::Permission;
+use crate::{Feature, Kind, ECMAOS_FIELDS, ECMAOS_SCRIPTS};
+
+/// Longest package name npm accepts.
+const MAX_NAME_LENGTH: usize = 214;
+
+struct Checker {
+    issues: Vec<Issue>,
+}
+
+impl Checker {
+    fn issue(&mut self, path: &str, message: impl Into<String>) {
+        self.issues.push(Issue { path: path.to_string(), message: message.into() });
+    }
+
+    /// The object at `path`, reporting anything else.
+    fn object<'a>(&mut self, value: &'a Value, path: &str) -> Option<&'a Map<String, Value>> {
+        let object = value.as_object();
+        if object.is_none() {
+            self.issue(path, format!("expected an object, found {}", describe(value)));
+        }
+        object
+    }
+
+    fn string<'a>(&mut self, value: &'a Value, path: &str) -> Option<&'a str> {
+        let string = value.as_str();
+        if string.is_none() {
+            self.issue(path, format!("expected a string, found {}", describe(value)));
+        }
+        string
+    }
+}
+
+/// Every problem with a manifest; empty if it's valid.
+pub fn validate(manifest: &Value) -> Vec<Issue> {
+    let mut checker = Checker { issues: Vec::new() };
+    let Some(root) = checker.object(manifest, "") else { return checker.issues };
+
+    match root.get("name") {
+        Some(name) => {
+            if let Some(name) = checker.string(name, "name") {
+                if let Err(message) = check_name(name) {
+                    checker.issue("name", message);
+                }
+            }
+        }
+        None => checker.issue("name", "missing"),
+    }
+
+    match root.get("version") {
+        Some(version) => {
+            if let Some(version) = checker.string(version, "version") {
+                if version.starts_with(['v', '=']) || version.parse::<Version>().is_err() {
+                    checker.issue("version", format!("{version:?} isn't a version such as 1.2.3"));
+                }
+            }
+        }
+        None => checker.issue("version", "missing"),
+    }
+
+    if let Some(description) = root.get("description") {
+        checker.string(description, "description");
+    }
+
+    let bin = root.get("bin");
+    if let Some(bin) = bin {
+        check_bin(&mut checker, bin);
+    }
+
+    for field in ["dependencies", "optionalDependencies", "conflicts"] {
+        let Some(dependencies) = root.get(field) else { continue };
+        let Some(dependencies) = checker.object(dependencies, field) else { continue };
+        for (name, range) in dependencies {
+            let path = format!("{field}.{name}");
+            if let Err(message) = check_name(name) {
+                checker.issue(&path, message);
+            }
+            if let Some(range) = checker.string(range, &path) {
+                if range.parse::<Range>().is_err() {
+                    let message = format!("{range:?} isn't a version range; tags, URLs and paths aren't supported");
+                    checker.issue(&path, message);
+                }
+            }
+        }
+    }
+
+    if let Some(scripts) = root.get("scripts") {
+        if let Some(scripts) = checker.object(scripts, "scripts") {
+            for (name, command) in scripts {
+                let path = format!("scripts.{name}");
+                checker.string(command, &path);
+                if name.starts_with("ecmaos:") && !ECMAOS_SCRIPTS.contains(&name.as_str()) {
+                    checker.issue(&path, unknown("script", name, ECMAOS_SCRIPTS));
+                }
+            }
+        }
+    }
+
+    if let Some(ecmaos) = root.get("ecmaos") {
+        check_ecmaos(&mut checker, ecmaos, bin.is_some());
+    }
+    checker.issues
+}
+
+fn check_bin(checker: &mut Checker, bin: &Value) {
+    let check_path = |checker: &mut Checker, path: &str, file: &str| {
+        if file.is_empty() || file.starts_with('/') || file.split(['/', '\\']).any(|segment| segment == "..") {
+            checker.issue(path, format!("{file:?} must be a path inside the package"));
+        }
+    };
+    match bin {
+        Value::String(file) => check_path(checker, "bin", file),
+        Value::Object(bins) => {
+            for (name, file) in bins {
+                let path = format!("bin.{name}");
+                if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+                    checker.issue(&path, format!("{name:?} isn't a valid command name"));
+                }
+                if let Some(file) = checker.string(file, &path) {
+                    check_path(checker, &path, file);
+                }
+            }
+        }
+        other => checker.issue("bin", format!("expected a string or an object, found {}", describe(other))),
+    }
+}
+
+fn check_ecmaos(checker: &mut Checker, ecmaos: &Value, has_bin: bool) {
+    let Some(ecmaos) = checker.object(ecmaos, "ecmaos") else { return };
+    for field in ecmaos.keys() {
+        if !ECMAOS_FIELDS.contains(&field.as_str()) {
+            checker.issue(&format!("ecmaos.{field}"), unknown("field", field, ECMAOS_FIELDS));
+        }
+    }
+
+    if let Some(kind) = ecmaos.get("kind") {
+        if let Some(name) = checker.string(kind, "ecmaos.kind") {
+            match Kind::ALL.iter().find(|kind| kind.name() == name) {
+                Some(kind) if kind.needs_bin() && !has_bin => {
+                    checker.issue("ecmaos.kind", format!("a package of kind {name:?} needs a bin"));
+                }
+                Some(_) => {}
+                None => {
+                    let names: Vec<&str> = Kind::ALL.iter().map(|kind| kind.name()).collect();
+                    checker.issue("ecmaos.kind", unknown("kind", name, &names));
+                }
+            }
+        }
+    }
+
+    if let Some(kernel) = ecmaos.get("kernel") {
+        if let Some(range) = checker.string(kernel, "ecmaos.kernel") {
+            if range.parse::<Range>().is_err() {
+                checker.issue("ecmaos.kernel", format!("{range:?} isn't a version range"));
+            }
+        }
+    }
+
+    if let Some(permissions) = ecmaos.get("permissions") {
+        check_permissions(checker, permissions);
+    }
+    if let Some(features) = ecmaos.get("features") {
+        check_features(checker, features);
+    }
+}
+
+fn check_permissions(checker: &mut Checker, permissions: &Value) {
+    let Some(permissions) = permissions.as_array() else {
+        checker.issue("ecmaos.permissions", format!("expected an array, found {}", describe(permissions)));
+        return;
+    };
+    let mut seen = BTreeSet::new();
+    for (i, permission) in permissions.iter().enumerate() {
+        let path = format!("ecmaos.permissions[{i}]");
+        let Some(text) = checker.string(permission, &path) else { continue };
+        match text.parse::<Permission>() {
+            Ok(permission) => {
+                if !seen.insert(permission) {
+                    checker.issue(&path, format!("{text:?} is listed twice"));
+                }
+            }
+            Err(message) => checker.issue(&path, message),
+        }
+    }
+}
+
+fn check_features(checker: &mut Checker, features: &Value) {
+    let Some(features) = features.as_array() else {
+        checker.issue("ecmaos.features", format!("expected an array, found {}", describe(features)));
+        return;
+    };
+    let names: Vec<&str> = Feature::ALL.iter().map(|feature| feature.name()).collect();
+    let mut seen = BTreeSet::new();
+    for (i, feature) in features.iter().enumerate() {
+        let path = format!("ecmaos.features[{i}]");
+        let Some(name) = checker.string(feature, &path) else { continue };
+        if !names.contains(&name) {
+            checker.issue(&path, unknown("feature", name, &names));
+        } else if !seen.insert(name) {
+            checker.issue(&path, format!("{name:?} is listed twice"));
+        }
+    }
+}
+
+/// Applies npm's rules for new package names.
+fn check_name(name: &str) -> Result<(), String> {
+    let unscoped = match name.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('/') {
+            Some((scope, rest)) if valid_part(scope) => rest,
+            _ => return Err(format!("{name:?} isn't a valid scoped name such as @scope/name")),
+        },
+        None => name,
+    };
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(format!("{name:?} is longer than {MAX_NAME_LENGTH} characters"));
+    }
+    if !valid_part(unscoped) {
+        let message = "may only contain lowercase letters, digits, '-', '.', '_' and '~', and may not start with '.' or '_'";
+        return Err(format!("{name:?} {message}"));
+    }
+    Ok(())
+}
+
+fn valid_part(part: &str) -> bool {
+    !part.is_empty()
+        && !part.starts_with(['.', '_'])
+        && part.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'-' | b'.' | b'_' | b'~'))
+}
+
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Reports an unrecognised name, suggesting the closest known one if it
+/// looks like a typo.
+fn unknown(what: &str, name: &str, known: &[&str]) -> String {
+    let closest = known.iter().map(|candidate| (distance(name, candidate), candidate)).min();
+    match closest {
+        Some((distance, candidate)) if distance <= 2.max(name.len() / 3) => {
+            format!("unknown {what} {name:?}; did you mean {candidate:?}?")
+        }
+        _ => format!("unknown {what} {name:?}; expected one of {}", known.join(", ")),
+    }
+}
+
+/// The Levenshtein distance between two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,179 @@
+use ecmaos_manifest::{validate, Bin, Error, Feature, Kind, Manifest, Permission};
+use serde_json::{json, Value};
+
+fn valid() -> Value {
+    json!({
+        "name": "@ecmaos-apps/edit",
+        "version": "1.2.0-beta.1",
+        "description": "A text editor",
+        "license": "MIT",
+        "bin": "dist/edit.js",
+        "dependencies": { "@ecmaos/kernel": "^0.6", "lodash": "^4" },
+        "optionalDependencies": { "lodash": "^4.17" },
+        "scripts": { "build": "vite build", "ecmaos:postinstall": "edit --setup" },
+        "ecmaos": {
+            "kind": "command",
+            "kernel": ">=0.6.0 <1",
+            "permissions": ["fs:read:/home/**", "net:fetch:https://example.com:8443", "clipboard:write"],
+            "features": ["bulk-memory", "simd"]
+        }
+    })
+}
+
+/// Applies `change` to a valid manifest and returns the issues found.
+fn issues(change: impl FnOnce(&mut Value)) -> Vec<String> {
+    let mut manifest = valid();
+    change(&mut manifest);
+    validate(&manifest).iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn parses_valid() {
+    let manifest = Manifest::from_value(valid()).unwrap();
+    assert_eq!(manifest.version.to_string(), "1.2.0-beta.1");
+    assert_eq!(manifest.bin, Some(Bin::Single("dist/edit.js".to_string())));
+    assert_eq!(manifest.commands().into_iter().collect::<Vec<_>>(), [("edit", "dist/edit.js")]);
+    assert_eq!(manifest.ecmaos.kind, Kind::Command);
+    assert_eq!(manifest.ecmaos.permissions[0], Permission::FsRead("/home/**".to_string()));
+    assert_eq!(manifest.ecmaos.features, [Feature::BulkMemory, Feature::Simd]);
+    assert_eq!(manifest.other["license"], "MIT");
+
+    let requirements = manifest.requirements();
+    assert!(requirements.required.contains_key("@ecmaos/kernel"));
+    assert!(!requirements.required.contains_key("lodash"));
+    assert!(requirements.optional.contains_key("lodash"));
+}
+
+#[test]
+fn round_trips() {
+    let manifest = Manifest::from_value(valid()).unwrap();
+    assert_eq!(serde_json::to_value(&manifest).unwrap(), valid());
+}
+
+#[test]
+fn defaults() {
+    let manifest = Manifest::parse(r#"{ "name": "util", "version": "0.1.0" }"#).unwrap();
+    assert_eq!(manifest.ecmaos.kind, Kind::Library);
+    assert!(manifest.commands().is_empty());
+    assert!(manifest.ecmaos.permissions.is_empty());
+}
+
+#[test]
+fn reports_every_issue() {
+    let error = Manifest::parse(r#"{ "name": 5, "ecmaos": [] }"#).unwrap_err();
+    let Error::Invalid(issues) = error else { panic!("expected invalid, got {error}") };
+    let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+    assert_eq!(issues, ["name: expected a string, found a number", "version: missing", "ecmaos: expected an object, found an array"]);
+
+    assert!(matches!(Manifest::parse("{"), Err(Error::Json(_))));
+    assert_eq!(validate(&json!([])).len(), 1);
+}
+
+#[test]
+fn names() {
+    for name in ["edit", "@scope/edit", "a.b-c_d~e", "0ad"] {
+        assert_eq!(issues(|m| m["name"] = json!(name)), Vec::<String>::new(), "{name}");
+    }
+    for name in ["", "Edit", ".edit", "_edit", "@scope", "@Scope/edit", "@scope/.edit", "ed it", "a/b"] {
+        assert_eq!(issues(|m| m["name"] = json!(name)).len(), 1, "{name:?} should be rejected");
+    }
+    assert_eq!(issues(|m| m["name"] = json!("a".repeat(215))).len(), 1);
+    assert_eq!(
+        issues(|m| m["dependencies"]["Lodash"] = json!("1")),
+        ["dependencies.Lodash: \"Lodash\" may only contain lowercase letters, digits, '-', '.', '_' and '~', and may not start with '.' or '_'"]
+    );
+}
+
+#[test]
+fn versions_and_ranges() {
+    for version in ["1.2", "v1.2.3", "=1.2.3", "01.2.3", "latest"] {
+        assert_eq!(issues(|m| m["version"] = json!(version)), [format!("version: {version:?} isn't a version such as 1.2.3")]);
+    }
+    assert_eq!(
+        issues(|m| m["dependencies"]["lodash"] = json!("github:lodash/lodash")),
+        ["dependencies.lodash: \"github:lodash/lodash\" isn't a version range; tags, URLs and paths aren't supported"]
+    );
+    assert_eq!(issues(|m| m["ecmaos"]["kernel"] = json!(">=1.2.3.4")), ["ecmaos.kernel: \">=1.2.3.4\" isn't a version range"]);
+    assert_eq!(issues(|m| m["conflicts"] = json!({ "vim": 9 })), ["conflicts.vim: expected a string, found a number"]);
+}
+
+#[test]
+fn bins() {
+    assert_eq!(issues(|m| m["bin"] = json!({ "edit": "dist/edit.js", "ed": "./dist/ed.js" })), Vec::<String>::new());
+    assert_eq!(
+        issues(|m| m["bin"] = json!({ "../edit": "/bin/edit", "ed": "../ed.js" })),
+        [
+            "bin.../edit: \"../edit\" isn't a valid command name",
+            "bin.../edit: \"/bin/edit\" must be a path inside the package",
+            "bin.ed: \"../ed.js\" must be a path inside the package",
+        ]
+    );
+    assert_eq!(issues(|m| m["bin"] = json!(["edit"])), ["bin: expected a string or an object, found an array"]);
+    assert_eq!(
+        issues(|m| drop(m.as_object_mut().unwrap().remove("bin"))),
+        ["ecmaos.kind: a package of kind \"command\" needs a bin"]
+    );
+}
+
+#[test]
+fn ecmaos_section() {
+    assert_eq!(issues(|m| m["ecmaos"]["kind"] = json!("comand")), ["ecmaos.kind: unknown kind \"comand\"; did you mean \"command\"?"]);
+    assert_eq!(
+        issues(|m| m["ecmaos"]["kind"] = json!("daemon")),
+        ["ecmaos.kind: unknown kind \"daemon\"; expected one of app, command, script, library"]
+    );
+    assert_eq!(
+        issues(|m| m["ecmaos"]["permission"] = json!([])),
+        ["ecmaos.permission: unknown field \"permission\"; did you mean \"permissions\"?"]
+    );
+    assert_eq!(
+        issues(|m| m["scripts"]["ecmaos:postinstal"] = json!("true")),
+        ["scripts.ecmaos:postinstal: unknown script \"ecmaos:postinstal\"; did you mean \"ecmaos:postinstall\"?"]
+    );
+}
+
+#[test]
+fn permissions() {
+    let rejected = [
+        ("fs:read:home", "\"home\" isn't an absolute path"),
+        ("fs:write:/home/../etc", "\"/home/../etc\" may not contain . or .. segments"),
+        ("fs:read:/home/*.txt", "\"/home/*.txt\" may only use a wildcard as a trailing /**"),
+        ("net:fetch:example.com", "\"example.com\" isn't an origin such as https://example.com or *"),
+        ("net:fetch:https://example.com/api", "\"https://example.com/api\" isn't an origin such as https://example.com or *"),
+        ("net:fetch:http://localhost:99999", "\"http://localhost:99999\" isn't an origin such as https://example.com or *"),
+    ];
+    for (permission, message) in rejected {
+        assert_eq!(issues(|m| m["ecmaos"]["permissions"] = json!([permission])), [format!("ecmaos.permissions[0]: {message}")]);
+    }
+
+    let unknown = issues(|m| m["ecmaos"]["permissions"] = json!(["storage", "network"]));
+    assert_eq!(unknown.len(), 1);
+    assert!(unknown[0].starts_with("ecmaos.permissions[1]: unknown permission \"network\"; expected one of fs:read:<path>"));
+
+    assert_eq!(
+        issues(|m| m["ecmaos"]["permissions"] = json!(["storage", 1, "storage"])),
+        ["ecmaos.permissions[1]: expected a string, found a number", "ecmaos.permissions[2]: \"storage\" is listed twice"]
+    );
+    assert_eq!(issues(|m| m["ecmaos"]["permissions"] = json!("storage")), ["ecmaos.permissions: expected an array, found a string"]);
+
+    for permission in ["fs:write:/", "net:fetch:*", "process:spawn", "clipboard:read", "notifications"] {
+        let parsed: Permission = permission.parse().unwrap();
+        assert_eq!(parsed.to_string(), permission);
+    }
+}
+
+#[test]
+fn features() {
+    assert_eq!(
+        issues(|m| m["ecmaos"]["features"] = json!(["simd", "thread", "wasm-gc", "simd"])),
+        [
+            "ecmaos.features[1]: unknown feature \"thread\"; did you mean \"threads\"?",
+            "ecmaos.features[2]: unknown feature \"wasm-gc\"; expected one of bulk-memory, exception-handling, reference-types, simd, threads",
+            "ecmaos.features[3]: \"simd\" is listed twice",
+        ]
+    );
+    assert_eq!(issues(|m| m["ecmaos"]["features"] = json!("simd")), ["ecmaos.features: expected an array, found a string"]);
+    for feature in Feature::ALL {
+        assert_eq!(serde_json::to_value(feature).unwrap(), feature.name());
+    }
+}
@@ -0,0 +1,5 @@
+//! Does nothing, unsuccessfully.
+
+fn main() {
+    std::process::exit(1);
+}
@@ -0,0 +1,103 @@
+//! Prints a sequence of numbers.
+//!
+//! ```text
+//! seq [-w] [-s SEPARATOR] [FIRST [INCREMENT]] LAST
+//! ```
+//!
+//! Counts from `FIRST` by `INCREMENT`, both 1 by default, for as long as
+//! it doesn't pass `LAST`. The numbers may have decimals, are shown with as
+//! many as `FIRST` or `INCREMENT` has, and are worked out exactly, so
+//! `seq 0 0.1 1` ends at `1.0`. They're separated by newlines, or by `-s`'s
+//! separator with a newline at the end, and `-w` pads them with zeros to
+//! the same width.
+
+use std::env;
+use std::io;
+
+use ecmaos_micro::{usage_error, write_out, Decimal};
+
+const USAGE: &str = "usage: seq [-w] [-s SEPARATOR] [FIRST [INCREMENT]] LAST";
+
+fn main() {
+    let mut separator = String::from("\n");
+    let mut equal_width = false;
+    let mut numbers = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        // A negative number isn't an option
+        let number = arg.starts_with('-') && arg[1..].starts_with(|c: char| c.is_ascii_digit() || c == '.');
+        match arg.as_str() {
+            "-w" | "--equal-width" => equal_width = true,
+            "-s" | "--separator" => match args.next() {
+                Some(value) => separator = value,
+                None => usage_error(USAGE, &[&arg, " needs a value"]),
+            },
+            "-h" | "--help" => {
+                write_out(&mut io::stdout(), USAGE.as_bytes());
+                write_out(&mut io::stdout(), b"\n");
+                return;
+            }
+            _ if arg.starts_with("-s") => separator = arg[2..].to_string(),
+            _ if arg.starts_with('-') && !number => usage_error(USAGE, &["unknown option: ", &arg]),
+            _ => match Decimal::parse(&arg) {
+                Some(decimal) => numbers.push(decimal),
+                None => usage_error(USAGE, &["invalid number: ", &arg]),
+            },
+        }
+    }
+    let one = Decimal { units: 1, scale: 0 };
+    let (first, step, last) = match numbers[..] {
+        [last] => (one, one, last),
+        [first, last] => (first, one, last),
+        [first, step, last] => (first, step, last),
+        [] => usage_error(USAGE, &["no numbers given"]),
+        _ => usage_error(USAGE, &["too many numbers"]),
+    };
+    if step.units == 0 {
+        usage_error(USAGE, &["the increment can't be zero"]);
+    }
+
+    // Counted at the largest scale of the three, shown at FIRST's or
+    // INCREMENT's, which every number in the sequence fits
+    let scale = first.scale.max(step.scale).max(last.scale);
+    let shown = first.scale.max(step.scale);
+    let (Some(first), Some(step), Some(last)) = (first.rescale(scale), step.rescale(scale), last.rescale(scale)) else {
+        usage_error(USAGE, &["too many digits"]);
+    };
+    let divisor = 10i128.pow(scale - shown);
+    let show = |units: i128| Decimal { units: units / divisor, scale: shown };
+    let width = match equal_width {
+        true => [first.units, last.units]
+            .iter()
+            .map(|&units| {
+                let mut text = Vec::new();
+                show(units).write(&mut text, 0);
+                text.len()
+            })
+            .max()
+            .unwrap_or_default(),
+        false => 0,
+    };
+
+    let mut stdout = io::stdout().lock();
+    let mut out = Vec::new();
+    let mut value = Some(first.units);
+    let mut count = 0usize;
+    let within = |units: i128| if step.units > 0 { units <= last.units } else { units >= last.units };
+    while let Some(units) = value.filter(|&units| within(units)) {
+        if count > 0 {
+            out.extend_from_slice(separator.as_bytes());
+        }
+        show(units).write(&mut out, width);
+        if out.len() >= 8192 {
+            write_out(&mut stdout, &out);
+            out.clear();
+        }
+        count += 1;
+        value = units.checked_add(step.units);
+    }
+    if count > 0 {
+        out.push(b'\n');
+    }
+    write_out(&mut stdout, &out);
+}
@@ -0,0 +1,54 @@
+//! Waits for a while.
+//!
+//! ```text
+//! sleep NUMBER[s|m|h|d]...
+//! ```
+//!
+//! Each number is of seconds, or of minutes, hours or days with a suffix,
+//! and may have decimals (`sleep 1.5`, `sleep 1m 30s`); it waits for all
+//! of them added up, to the nanosecond.
+
+use std::env;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use ecmaos_micro::{usage_error, write_out, Decimal};
+
+const USAGE: &str = "usage: sleep NUMBER[s|m|h|d]...";
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+fn main() {
+    let mut nanos: i128 = 0;
+    let mut given = false;
+    for arg in env::args().skip(1) {
+        if arg == "-h" || arg == "--help" {
+            write_out(&mut io::stdout(), USAGE.as_bytes());
+            write_out(&mut io::stdout(), b"\n");
+            return;
+        }
+        let (number, seconds) = match arg.as_bytes().last() {
+            Some(b's') => (&arg[..arg.len() - 1], 1),
+            Some(b'm') => (&arg[..arg.len() - 1], 60),
+            Some(b'h') => (&arg[..arg.len() - 1], 3600),
+            Some(b'd') => (&arg[..arg.len() - 1], 86_400),
+            _ => (arg.as_str(), 1),
+        };
+        let interval = Decimal::parse(number)
+            .filter(|decimal| decimal.units >= 0)
+            .and_then(|decimal| {
+                let divisor = 10i128.checked_pow(decimal.scale)?;
+                Some(decimal.units.checked_mul(seconds * NANOS_PER_SECOND)? / divisor)
+            })
+            .unwrap_or_else(|| usage_error(USAGE, &["invalid time interval: ", &arg]));
+        nanos = nanos.saturating_add(interval);
+        given = true;
+    }
+    if !given {
+        usage_error(USAGE, &["no time given"]);
+    }
+
+    let seconds = u64::try_from(nanos / NANOS_PER_SECOND).unwrap_or(u64::MAX);
+    thread::sleep(Duration::new(seconds, (nanos % NANOS_PER_SECOND) as u32));
+}
@@ -0,0 +1,28 @@
+//! Prints a line over and over until it can't.
+//!
+//! ```text
+//! yes [STRING...]
+//! ```
+//!
+//! The line is the strings separated by spaces, or `y`. It's written a
+//! buffer of copies at a time, which is also a measure of how fast a pipe
+//! in ecmaOS can carry data.
+
+use std::env;
+use std::io;
+
+use ecmaos_micro::write_out;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut line = match args.is_empty() {
+        true => b"y".to_vec(),
+        false => args.join(" ").into_bytes(),
+    };
+    line.push(b'\n');
+    let buffer = line.repeat((8192 / line.len()).max(1));
+    let mut stdout = io::stdout().lock();
+    loop {
+        write_out(&mut stdout, &buffer);
+    }
+}
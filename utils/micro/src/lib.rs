@@ -0,0 +1,106 @@
+//! What the micro utilities share.
+//!
+//! `seq`, `yes`, `sleep`, `true` and `false` are the building blocks of
+//! shell scripts and the smallest programs ecmaOS runs, which makes them
+//! the baseline for how small a WASM command can be and how quickly the
+//! kernel starts one. So they're built for size: the release profile
+//! optimises for it, with LTO, one codegen unit and aborting panics, and
+//! the code keeps clear of `format!` and of parsing and printing floating
+//! point, which pull in a good deal of `core`. Numbers are held as
+//! [`Decimal`]s instead, and messages are written out a piece at a time.
+
+use std::io::{self, Write};
+use std::process;
+
+/// Says why the command line can't be used, then how to use it, and exits
+/// with status 2.
+pub fn usage_error(usage: &str, parts: &[&str]) -> ! {
+    let mut err = io::stderr().lock();
+    for part in parts.iter().chain(&["\n", usage, "\n"]) {
+        let _ = err.write_all(part.as_bytes());
+    }
+    process::exit(2)
+}
+
+/// Writes `error: ` and `parts` to stderr as a line and exits with status 1.
+pub fn fail(parts: &[&str]) -> ! {
+    let mut err = io::stderr().lock();
+    for part in [&"error: "].into_iter().chain(parts).chain(&["\n"]) {
+        let _ = err.write_all(part.as_bytes());
+    }
+    process::exit(1)
+}
+
+/// Writes `bytes` to stdout, exiting with status 1 if that fails: quietly
+/// if the reader has gone, as when the output is piped into `head`.
+pub fn write_out(out: &mut impl Write, bytes: &[u8]) {
+    if let Err(e) = out.write_all(bytes) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            fail(&["write error"]);
+        }
+        process::exit(1);
+    }
+}
+
+/// A decimal number as a whole number of `10^-scale`ths: 1.25 is 125 at
+/// scale 2.
+#[derive(Clone, Copy)]
+pub struct Decimal {
+    pub units: i128,
+    pub scale: u32,
+}
+
+impl Decimal {
+    /// Parses a number such as `-12.5`, `+3` or `.5`, without an exponent.
+    pub fn parse(text: &str) -> Option<Decimal> {
+        let (negative, digits) = match text.as_bytes().first()? {
+            b'-' => (true, &text[1..]),
+            b'+' => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        let mut units: i128 = 0;
+        for byte in whole.bytes().chain(fraction.bytes()) {
+            if !byte.is_ascii_digit() {
+                return None;
+            }
+            units = units.checked_mul(10)?.checked_add(i128::from(byte - b'0'))?;
+        }
+        let scale = u32::try_from(fraction.len()).ok()?;
+        Some(Decimal { units: if negative { -units } else { units }, scale })
+    }
+
+    /// The same number at a scale at least as large.
+    pub fn rescale(self, scale: u32) -> Option<Decimal> {
+        let units = self.units.checked_mul(10i128.checked_pow(scale.checked_sub(self.scale)?)?)?;
+        Some(Decimal { units, scale })
+    }
+
+    /// Appends the number to `out` with `scale` decimal places, padded
+    /// with zeros after any sign to at least `width` characters.
+    pub fn write(self, out: &mut Vec<u8>, width: usize) {
+        let scale = self.scale as usize;
+        // Digits from the last, with one before the point at least
+        let mut digits = Vec::new();
+        let mut rest = self.units.unsigned_abs();
+        while rest > 0 || digits.len() <= scale {
+            digits.push(b'0' + (rest % 10) as u8);
+            rest /= 10;
+        }
+        let negative = self.units < 0;
+        let len = digits.len() + usize::from(negative) + usize::from(scale > 0);
+        if negative {
+            out.push(b'-');
+        }
+        out.resize(out.len() + width.saturating_sub(len), b'0');
+        for (i, &digit) in digits.iter().rev().enumerate() {
+            if scale > 0 && i == digits.len() - scale {
+                out.push(b'.');
+            }
+            out.push(digit);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::io::Read;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+fn run(program: &str, args: &[&str]) -> Output {
+    Command::new(program).args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+const SEQ: &str = env!("CARGO_BIN_EXE_seq");
+
+#[test]
+fn seq_counts_exactly() {
+    assert_eq!(stdout(&run(SEQ, &["3"])), "1\n2\n3\n");
+    assert_eq!(stdout(&run(SEQ, &["0", "0.1", "0.5"])), "0.0\n0.1\n0.2\n0.3\n0.4\n0.5\n");
+    assert_eq!(stdout(&run(SEQ, &["10", "-2.5", "0"])), "10.0\n7.5\n5.0\n2.5\n0.0\n");
+    assert_eq!(stdout(&run(SEQ, &["-w", "-s", ",", "-3", "2", "10"])), "-3,-1,01,03,05,07,09\n");
+    assert_eq!(stdout(&run(SEQ, &["1", "1.55"])), "1\n");
+    assert_eq!(stdout(&run(SEQ, &["5", "1"])), "");
+    assert_eq!(stdout(&run(SEQ, &["100000"])).lines().count(), 100_000);
+
+    assert_eq!(run(SEQ, &["1", "0", "3"]).status.code(), Some(2));
+    assert_eq!(run(SEQ, &["1e3"]).status.code(), Some(2));
+    assert_eq!(run(SEQ, &[]).status.code(), Some(2));
+}
+
+#[test]
+fn yes_repeats_until_the_reader_goes() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_yes")).arg("a").arg("b").stdout(Stdio::piped()).spawn().unwrap();
+    let mut start = [0; 12];
+    child.stdout.take().unwrap().read_exact(&mut start).unwrap();
+    assert_eq!(&start, b"a b\na b\na b\n");
+    // Dropping the pipe ends it
+    assert_eq!(child.wait().unwrap().code(), Some(1));
+}
+
+#[test]
+fn sleep_adds_up_its_intervals() {
+    let start = Instant::now();
+    assert!(run(env!("CARGO_BIN_EXE_sleep"), &["0.1", ".05s", "0.0001m"]).status.success());
+    assert!(start.elapsed() >= Duration::from_millis(156));
+    assert_eq!(run(env!("CARGO_BIN_EXE_sleep"), &["1x"]).status.code(), Some(2));
+    assert_eq!(run(env!("CARGO_BIN_EXE_sleep"), &["-1"]).status.code(), Some(2));
+}
+
+#[test]
+fn true_and_false() {
+    assert_eq!(run(env!("CARGO_BIN_EXE_true"), &["--anything"]).status.code(), Some(0));
+    assert_eq!(run(env!("CARGO_BIN_EXE_false"), &[]).status.code(), Some(1));
+}
@@ -0,0 +1,23 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Resolve(#[from] ecmaos_resolver::Error),
+    #[error("{0}")]
+    Http(String),
+    #[error("{0}")]
+    Metadata(String),
+    #[error("{0}")]
+    Checksum(String),
+}
+
+impl From<ureq::Error> for Error {
+    fn from(error: ureq::Error) -> Self {
+        Error::Http(error.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,10 @@
+//! The parts of `ecmaos-mirror` that don't depend on its command line: the
+//! [`mirror`] directory and the [`Upstream`] registry it's filled from.
+
+mod error;
+pub mod mirror;
+mod upstream;
+
+pub use error::{Error, Result};
+pub use mirror::Mirror;
+pub use upstream::Upstream;
@@ -0,0 +1,132 @@
+//! Mirrors packages from an npm-compatible registry into a directory that
+//! ecmaOS can install from without a network connection.
+//!
+//! ```text
+//! ecmaos-mirror --out mirror @ecmaos-apps/edit lodash@^4
+//! ```
+//!
+//! Each package is resolved together with its dependencies, and every
+//! version chosen is downloaded, checked against the registry's checksums
+//! and stored with its metadata (see [`ecmaos_mirror::mirror`] for the
+//! layout). The directory also gets the `index.json` listing ZenFS's fetch
+//! backend needs, so serving it with any static file server is enough to
+//! mount it:
+//!
+//! ```text
+//! mount -t fetch http://localhost:8000/ /mnt/mirror
+//! install lodash --registry /mnt/mirror
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::path::{Path, PathBuf};
+
+use ecmaos_resolver::{Dependencies, Range, Resolver, Version};
+use serde_json::Value;
+
+use ecmaos_mirror::{Error, Mirror, Result, Upstream};
+
+const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org";
+
+const USAGE: &str = "usage: ecmaos-mirror [--registry URL] [--out DIR] PACKAGE[@RANGE|@TAG]...";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut registry = DEFAULT_REGISTRY.to_string();
+    let mut out = PathBuf::from("mirror");
+    let mut specs = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--registry" => match args.next() {
+                Some(url) => registry = url,
+                None => usage_error("--registry needs a value"),
+            },
+            "--out" => match args.next() {
+                Some(dir) => out = PathBuf::from(dir),
+                None => usage_error("--out needs a value"),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ => specs.push(arg),
+        }
+    }
+    if specs.is_empty() {
+        usage_error("no packages given");
+    }
+
+    if let Err(e) = run(&registry, &out, &specs) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(registry: &str, out: &Path, specs: &[String]) -> Result<()> {
+    let upstream = Upstream::new(registry);
+    let mirror = Mirror::open(out)?;
+
+    // Each package is resolved on its own, so asking for two versions of
+    // the same package mirrors both
+    let mut wanted: BTreeMap<String, BTreeSet<Version>> = BTreeMap::new();
+    for spec in specs {
+        let (package, range) = split_spec(spec);
+        let range = to_range(&upstream, package, range)?;
+        let root = Dependencies { required: BTreeMap::from([(package.to_string(), range)]), ..Default::default() };
+        let resolution = Resolver::new(&upstream).resolve(&root)?;
+        println!("{}: {} packages", spec, resolution.packages.len());
+        for (package, version) in resolution.packages {
+            wanted.entry(package).or_default().insert(version);
+        }
+    }
+
+    let mut downloaded = 0;
+    for (package, versions) in &wanted {
+        let Some(metadata) = upstream.metadata(package)? else {
+            return Err(Error::Metadata(format!("{} disappeared from the registry", package)));
+        };
+        let count = mirror.store(&upstream, package, &metadata, versions)?;
+        let versions: Vec<String> = versions.iter().map(Version::to_string).collect();
+        println!("  {} {}{}", package, versions.join(", "), if count > 0 { "" } else { " (already mirrored)" });
+        downloaded += count;
+    }
+
+    let entries = mirror.write_index()?;
+    println!(
+        "Mirrored {} packages to {} ({} tarballs downloaded, {} index entries)",
+        wanted.len(),
+        out.display(),
+        downloaded,
+        entries
+    );
+    Ok(())
+}
+
+/// Splits `name@range` the way the kernel's `install` does, keeping the `@`
+/// of a scope with the name.
+fn split_spec(spec: &str) -> (&str, &str) {
+    let start = usize::from(spec.starts_with('@'));
+    match spec[start..].find('@') {
+        Some(at) => (&spec[..start + at], &spec[start + at + 1..]),
+        None => (spec, "latest"),
+    }
+}
+
+/// Parses a range, or looks a dist-tag such as `latest` up in the registry.
+fn to_range(upstream: &Upstream, package: &str, range: &str) -> Result<Range> {
+    if let Ok(range) = range.parse() {
+        return Ok(range);
+    }
+    let metadata = upstream.metadata(package)?;
+    let tagged = metadata.as_ref().and_then(|metadata| metadata["dist-tags"].get(range)).and_then(Value::as_str);
+    match tagged {
+        Some(version) => Ok(format!("={}", version).parse()?),
+        None => Err(Error::Metadata(format!("{} has no version or tag matching {}", package, range))),
+    }
+}
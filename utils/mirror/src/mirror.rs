@@ -0,0 +1,255 @@
+//! The mirror directory.
+//!
+//! ```text
+//! <dir>/index.json                   listing read by `mount -t fetch`
+//! <dir>/<package>/index.json         registry metadata of the mirrored versions
+//! <dir>/<package>/<name>-<version>.tgz
+//! ```
+//!
+//! The metadata is the upstream document with the versions that weren't
+//! mirrored left out and each tarball URL replaced by the tarball's file
+//! name, which `install --registry <dir>` resolves against the package's
+//! directory. Mirroring into an existing directory adds to it: versions
+//! mirrored before are kept, and tarballs already present are only checked.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use base64::Engine;
+use ecmaos_resolver::Version;
+use serde_json::{json, Map, Value};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::error::{Error, Result};
+use crate::upstream::Upstream;
+
+/// Name of the metadata file in each package directory, and of the listing
+/// at the top.
+const INDEX: &str = "index.json";
+
+pub struct Mirror {
+    dir: PathBuf,
+}
+
+impl Mirror {
+    pub fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Mirror { dir: dir.to_path_buf() })
+    }
+
+    /// Adds `versions` of a package, downloading the tarballs that aren't
+    /// there yet. Returns how many were downloaded.
+    pub fn store(
+        &self,
+        upstream: &Upstream,
+        package: &str,
+        metadata: &Value,
+        versions: &BTreeSet<Version>,
+    ) -> Result<usize> {
+        check_name(package)?;
+        let dir = self.dir.join(package);
+        fs::create_dir_all(&dir)?;
+        let existing: Value = match fs::read(dir.join(INDEX)) {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => json!({}),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut mirrored = existing.get("versions").and_then(Value::as_object).cloned().unwrap_or_default();
+        let mut downloaded = 0;
+        let upstream_versions = metadata.get("versions").and_then(Value::as_object).cloned().unwrap_or_default();
+        for (key, manifest) in upstream_versions {
+            if !key.parse().is_ok_and(|version| versions.contains(&version)) {
+                continue;
+            }
+            let mut manifest = manifest;
+            let dist = manifest.get_mut("dist").and_then(Value::as_object_mut);
+            let Some(dist) = dist else {
+                return Err(Error::Metadata(format!("{package}@{key} has no dist information")));
+            };
+            let Some(url) = dist.get("tarball").and_then(Value::as_str).map(str::to_string) else {
+                return Err(Error::Metadata(format!("{package}@{key} has no tarball")));
+            };
+            let file = tarball_name(package, &key, &url);
+            let path = dir.join(&file);
+
+            let present = fs::read(&path).ok().is_some_and(|data| verify(&data, dist, &file).is_ok());
+            if !present {
+                let data = upstream.download(&url)?;
+                verify(&data, dist, &file)?;
+                write(&path, &data)?;
+                downloaded += 1;
+            }
+            dist.insert("tarball".to_string(), Value::String(file));
+            mirrored.insert(key, manifest);
+        }
+
+        let mut document: Map<String, Value> = metadata.as_object().cloned().unwrap_or_default();
+        let tags = dist_tags(&mirrored, [&existing, metadata]);
+        let time = metadata.get("time").and_then(Value::as_object).map(|time| {
+            let kept = time.iter().filter(|(key, _)| mirrored.contains_key(*key) || *key == "created");
+            Value::Object(kept.map(|(key, value)| (key.clone(), value.clone())).collect())
+        });
+        document.insert("versions".to_string(), Value::Object(mirrored));
+        document.insert("dist-tags".to_string(), Value::Object(tags));
+        match time {
+            Some(time) => document.insert("time".to_string(), time),
+            None => document.remove("time"),
+        };
+        write(&dir.join(INDEX), &serde_json::to_vec(&document)?)?;
+        Ok(downloaded)
+    }
+
+    /// Writes the listing `mount -t fetch` reads, in the same form as
+    /// utils/fetch-fs-server.js serves. Returns how many entries it holds.
+    pub fn write_index(&self) -> Result<usize> {
+        let mut entries = BTreeMap::new();
+        list(&self.dir, &self.dir, &mut entries)?;
+        let count = entries.len();
+        let index = json!({ "version": 1, "entries": entries });
+        write(&self.dir.join(INDEX), &serde_json::to_vec(&index)?)?;
+        Ok(count)
+    }
+}
+
+/// Rejects names that would place files outside the mirror.
+pub fn check_name(package: &str) -> Result<()> {
+    let segments: Vec<&str> = package.split('/').collect();
+    let valid = match segments[..] {
+        [name] => !name.starts_with('@'),
+        [scope, _] => scope.starts_with('@') && scope.len() > 1,
+        _ => false,
+    } && segments
+        .iter()
+        .all(|segment| !segment.is_empty() && *segment != "." && *segment != ".." && !segment.contains('\\'));
+    if valid && package != INDEX {
+        Ok(())
+    } else {
+        Err(Error::Metadata(format!("invalid package name: {package}")))
+    }
+}
+
+/// The file a tarball is stored as: the last segment of its URL, as in
+/// `lodash-4.17.21.tgz`, unless that's unusable.
+pub fn tarball_name(package: &str, version: &str, url: &str) -> String {
+    let name = url.rsplit('/').next().unwrap_or_default();
+    if name.is_empty() || name == "." || name == ".." || name == INDEX || name.contains(['\\', '?', '#']) {
+        let base = package.rsplit('/').next().unwrap_or(package);
+        format!("{base}-{version}.tgz")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Checks a tarball against every checksum its metadata lists that can be
+/// checked: the `integrity` hashes and the SHA-1 `shasum`.
+pub fn verify(data: &[u8], dist: &Map<String, Value>, file: &str) -> Result<()> {
+    let mut checked = 0;
+    let mismatch = |algorithm: &str| Error::Checksum(format!("{file}: {algorithm} checksum doesn't match"));
+
+    let integrity = dist.get("integrity").and_then(Value::as_str).unwrap_or_default();
+    for entry in integrity.split_whitespace() {
+        let Some((algorithm, expected)) = entry.split_once('-') else { continue };
+        // Options may follow the hash: sha512-<base64>?<options>
+        let expected = expected.split('?').next().unwrap_or_default();
+        let actual = match algorithm {
+            "sha512" => Sha512::digest(data).to_vec(),
+            "sha256" => Sha256::digest(data).to_vec(),
+            "sha1" => Sha1::digest(data).to_vec(),
+            _ => continue,
+        };
+        if base64::engine::general_purpose::STANDARD.encode(actual) != expected {
+            return Err(mismatch(algorithm));
+        }
+        checked += 1;
+    }
+
+    if let Some(shasum) = dist.get("shasum").and_then(Value::as_str) {
+        let actual: String = Sha1::digest(data).iter().map(|byte| format!("{byte:02x}")).collect();
+        if !actual.eq_ignore_ascii_case(shasum) {
+            return Err(mismatch("sha1"));
+        }
+        checked += 1;
+    }
+
+    if checked == 0 {
+        return Err(Error::Checksum(format!("{file}: no checksum to verify against")));
+    }
+    Ok(())
+}
+
+/// The dist-tags to publish: the upstream and previously mirrored tags that
+/// point at mirrored versions, and a `latest` tag, which the kernel's
+/// `install` needs, on the newest stable version if upstream's isn't there.
+pub fn dist_tags(mirrored: &Map<String, Value>, sources: [&Value; 2]) -> Map<String, Value> {
+    let mut tags = Map::new();
+    for source in sources {
+        for (tag, version) in source.get("dist-tags").and_then(Value::as_object).into_iter().flatten() {
+            if version.as_str().is_some_and(|version| mirrored.contains_key(version)) {
+                tags.insert(tag.clone(), version.clone());
+            }
+        }
+    }
+    if !tags.contains_key("latest") {
+        let mut versions: Vec<(Version, &String)> =
+            mirrored.keys().filter_map(|key| Some((key.parse().ok()?, key))).collect();
+        versions.sort();
+        let newest = versions.iter().rev().find(|(version, _)| !version.is_prerelease()).or(versions.last());
+        if let Some((_, key)) = newest {
+            tags.insert("latest".to_string(), Value::String(key.to_string()));
+        }
+    }
+    tags
+}
+
+/// Adds `path` and everything below it to `entries`, keyed by their path in
+/// the mirror, with the stats ZenFS expects.
+fn list(root: &Path, path: &Path, entries: &mut BTreeMap<String, Value>) -> Result<()> {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let key = format!("/{}", relative.to_string_lossy().replace('\\', "/"));
+    if key == format!("/{INDEX}") {
+        return Ok(());
+    }
+
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    let mode = if metadata.is_dir() { 0o040755 } else { 0o100644 };
+    entries.insert(
+        key,
+        json!({
+            "ino": entries.len() + 1,
+            "mode": mode,
+            "size": metadata.len(),
+            "nlink": 1,
+            "uid": 0,
+            "gid": 0,
+            "atimeMs": modified,
+            "mtimeMs": modified,
+            "ctimeMs": modified,
+            "birthtimeMs": modified,
+        }),
+    );
+
+    if metadata.is_dir() {
+        let mut children: Vec<PathBuf> =
+            fs::read_dir(path)?.map(|entry| entry.map(|e| e.path())).collect::<std::io::Result<_>>()?;
+        children.sort();
+        for child in children {
+            list(root, &child, entries)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a file through a temporary one, so an interrupted run doesn't
+/// leave a truncated file behind.
+fn write(path: &Path, data: &[u8]) -> Result<()> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    fs::write(&partial, data)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
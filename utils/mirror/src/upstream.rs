@@ -0,0 +1,60 @@
+//! The registry packages are mirrored from.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::{BufReader, Read};
+use std::rc::Rc;
+
+use ecmaos_resolver::{Registry, Release};
+use serde_json::Value;
+
+use crate::error::Result;
+
+pub struct Upstream {
+    url: String,
+    agent: ureq::Agent,
+    /// Metadata already fetched, or `None` for packages the registry doesn't
+    /// know.
+    metadata: RefCell<BTreeMap<String, Option<Rc<Value>>>>,
+}
+
+impl Upstream {
+    pub fn new(url: &str) -> Self {
+        Upstream {
+            url: url.trim_end_matches('/').to_string(),
+            agent: ureq::Agent::new(),
+            metadata: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Fetches a package's registry metadata, each package only once.
+    pub fn metadata(&self, package: &str) -> Result<Option<Rc<Value>>> {
+        if let Some(metadata) = self.metadata.borrow().get(package) {
+            return Ok(metadata.clone());
+        }
+        let url = format!("{}/{}", self.url, package.replace('/', "%2f"));
+        let metadata = match self.agent.get(&url).call() {
+            Ok(response) => Some(Rc::new(serde_json::from_reader(BufReader::new(response.into_reader()))?)),
+            Err(ureq::Error::Status(404, _)) => None,
+            Err(e) => return Err(e.into()),
+        };
+        self.metadata.borrow_mut().insert(package.to_string(), metadata.clone());
+        Ok(metadata)
+    }
+
+    pub fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let mut body = Vec::new();
+        self.agent.get(url).call()?.into_reader().read_to_end(&mut body)?;
+        Ok(body)
+    }
+}
+
+impl Registry for Upstream {
+    fn releases(&self, package: &str) -> ecmaos_resolver::Result<Vec<Release>> {
+        match self.metadata(package) {
+            Ok(Some(metadata)) => ecmaos_resolver::releases(package, &metadata),
+            Ok(None) => Ok(Vec::new()),
+            Err(e) => Err(ecmaos_resolver::Error::Registry(e.to_string())),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use ecmaos_mirror::mirror::{check_name, dist_tags, tarball_name, verify};
+use serde_json::{json, Map, Value};
+
+const DATA: &[u8] = b"hello";
+const SHA512: &str = "sha512-m3HSJL1i83hdltRq0+o9czGb+8KJDKra4t/3JRlnPKcjI8PZm6XBHXx6zG4UuMXaDEZjR1wuXDre9G9zvN7AQw==";
+const SHA256: &str = "sha256-LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=";
+const SHASUM: &str = "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d";
+
+fn object(value: Value) -> Map<String, Value> {
+    value.as_object().unwrap().clone()
+}
+
+#[test]
+fn names_stay_inside_the_mirror() {
+    for name in ["lodash", "@ecmaos-apps/edit", "left-pad", "a.b"] {
+        assert!(check_name(name).is_ok(), "{name} refused");
+    }
+    for name in [
+        "",
+        "@scope",
+        "@/name",
+        "@scope/",
+        "scope/name",
+        "@scope/name/more",
+        ".",
+        "..",
+        "@scope/..",
+        "a\\b",
+        "index.json",
+    ] {
+        assert!(check_name(name).is_err(), "{name:?} accepted");
+    }
+}
+
+#[test]
+fn tarballs_are_named_after_their_url() {
+    let url = "https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz";
+    assert_eq!(tarball_name("lodash", "4.17.21", url), "lodash-4.17.21.tgz");
+    let url = "https://registry.npmjs.org/@ecmaos-apps/edit/-/edit-1.0.0.tgz";
+    assert_eq!(tarball_name("@ecmaos-apps/edit", "1.0.0", url), "edit-1.0.0.tgz");
+
+    // Names that can't be used fall back to one built from the package
+    for url in [
+        "https://example.com/",
+        "https://example.com/..",
+        "https://example.com/index.json",
+        "https://example.com/get?id=1",
+        "https://example.com/a\\b.tgz",
+    ] {
+        assert_eq!(tarball_name("@scope/pkg", "2.0.0", url), "pkg-2.0.0.tgz", "{url}");
+    }
+}
+
+#[test]
+fn every_listed_checksum_is_verified() {
+    assert!(verify(DATA, &object(json!({ "integrity": SHA512 })), "t.tgz").is_ok());
+    assert!(verify(DATA, &object(json!({ "shasum": SHASUM.to_uppercase() })), "t.tgz").is_ok());
+    let all = json!({ "integrity": format!("{SHA512}?opt {SHA256} md5-ignored"), "shasum": SHASUM });
+    assert!(verify(DATA, &object(all), "t.tgz").is_ok());
+
+    // One bad checksum fails the tarball even when the others match
+    let bad_sha256 = json!({ "integrity": format!("{SHA512} sha256-AAAA"), "shasum": SHASUM });
+    assert!(verify(DATA, &object(bad_sha256), "t.tgz").is_err());
+    let bad_shasum = json!({ "integrity": SHA512, "shasum": "0000" });
+    assert!(verify(DATA, &object(bad_shasum), "t.tgz").is_err());
+    assert!(verify(b"other", &object(json!({ "integrity": SHA512 })), "t.tgz").is_err());
+
+    // Nothing that can be checked isn't a pass
+    assert!(verify(DATA, &object(json!({})), "t.tgz").is_err());
+    assert!(verify(DATA, &object(json!({ "integrity": "md5-abc" })), "t.tgz").is_err());
+}
+
+#[test]
+fn dist_tags_point_at_mirrored_versions() {
+    let mirrored = object(json!({ "1.0.0": {}, "1.1.0": {}, "2.0.0-beta.1": {} }));
+    let existing = json!({ "dist-tags": { "old": "1.0.0", "gone": "0.9.0" } });
+    let upstream = json!({ "dist-tags": { "latest": "1.1.0", "next": "2.0.0-beta.1", "canary": "3.0.0" } });
+    let tags = dist_tags(&mirrored, [&existing, &upstream]);
+    assert_eq!(Value::Object(tags), json!({ "old": "1.0.0", "latest": "1.1.0", "next": "2.0.0-beta.1" }));
+}
+
+#[test]
+fn latest_falls_back_to_the_newest_stable_version() {
+    let mirrored = object(json!({ "1.0.0": {}, "1.10.0": {}, "1.9.0": {}, "2.0.0-rc.1": {} }));
+    let upstream = json!({ "dist-tags": { "latest": "3.0.0" } });
+    let tags = dist_tags(&mirrored, [&json!({}), &upstream]);
+    assert_eq!(tags["latest"], "1.10.0");
+
+    // A prerelease only when that's all there is
+    let mirrored = object(json!({ "2.0.0-rc.1": {}, "2.0.0-rc.2": {} }));
+    assert_eq!(dist_tags(&mirrored, [&json!({}), &json!({})])["latest"], "2.0.0-rc.2");
+    assert!(dist_tags(&Map::new(), [&json!({}), &upstream]).is_empty());
+}
@@ -0,0 +1,87 @@
+//! Evaluates the profile files of a login session and prints the
+//! environment they result in, for the shell to apply.
+//!
+//! ```text
+//! ecmaos-profile [FILE...]
+//! ```
+//!
+//! Starting from the environment it's run with, the tool evaluates the
+//! files given, or `/etc/profile` and then `~/.profile` when there are none
+//! (skipping either if it doesn't exist), as far as they assign, export and
+//! unset variables (see [`profile`]). What changed is printed as one line of
+//! JSON, with the variables to set and those to remove:
+//!
+//! ```text
+//! {"set":{"EDITOR":"vi","PATH":"/root/bin:/bin:/usr/bin"},"unset":["OLDPWD"]}
+//! ```
+//!
+//! Everything skipped is listed on stderr as a warning. The exit status is 1
+//! if a file given couldn't be read. The tool is built for `wasm32-wasip1`
+//! and installed as `/usr/libexec/profile.wasm`, which the shell runs at
+//! login if it's there.
+
+mod error;
+mod profile;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use error::Result;
+use profile::Profile;
+
+const USAGE: &str = "usage: ecmaos-profile [FILE...]";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut files = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+
+    if let Err(e) = run(&files) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(files: &[PathBuf]) -> Result<()> {
+    let inherited: BTreeMap<String, String> = env::vars().collect();
+    let mut profile = Profile::new(inherited.clone());
+    if files.is_empty() {
+        let mut defaults = vec![PathBuf::from("/etc/profile")];
+        if let Some(home) = inherited.get("HOME") {
+            defaults.push(Path::new(home).join(".profile"));
+        }
+        for path in defaults.iter().filter(|path| path.is_file()) {
+            profile.source(path)?;
+        }
+    } else {
+        for path in files {
+            profile.source(path)?;
+        }
+    }
+
+    for warning in profile.warnings() {
+        eprintln!("warning: {}", warning);
+    }
+    let environment = profile.environment();
+    let set: BTreeMap<&String, &String> =
+        environment.iter().filter(|(name, value)| inherited.get(*name) != Some(*value)).collect();
+    let unset: Vec<&String> = inherited.keys().filter(|name| !environment.contains_key(*name)).collect();
+    println!("{}", serde_json::to_string(&json!({ "set": set, "unset": unset }))?);
+    Ok(())
+}
@@ -0,0 +1,456 @@
+//! Evaluating profile files.
+//!
+//! Profiles are shell scripts, but the ones that set up a session mostly
+//! assign and export variables, so that's the part understood here:
+//!
+//! - `NAME=value`, `export NAME=value ...`, `export NAME` and `unset NAME ...`
+//! - `. FILE` and `source FILE`, which evaluate another file in place
+//! - single and double quotes, backslash escapes, `$NAME`, `${NAME}`,
+//!   `${NAME:-word}` and `${NAME-word}`, and `~` at the start of a word, or
+//!   of a value or after a `:` in one in assignments
+//! - several commands on a line separated by `;`, and lines continued with a
+//!   trailing backslash
+//!
+//! Anything else, such as running a program, command substitution or an
+//! `if`, `case` or loop, is skipped with a warning. A compound command is
+//! skipped to its end, so nothing inside it is applied unconditionally.
+//!
+//! As in a shell, only exported variables make it into the environment;
+//! those inherited from the caller already are.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+/// How deeply `.` may nest before it's taken for a loop.
+const MAX_DEPTH: usize = 16;
+
+/// Words that start and end compound commands.
+const OPENERS: [&str; 6] = ["if", "case", "for", "while", "until", "{"];
+const CLOSERS: [&str; 4] = ["fi", "esac", "done", "}"];
+
+pub struct Profile {
+    vars: BTreeMap<String, String>,
+    exported: BTreeSet<String>,
+    warnings: Vec<String>,
+    depth: usize,
+}
+
+impl Profile {
+    /// Starts from an inherited environment, all of it exported.
+    pub fn new(env: impl IntoIterator<Item = (String, String)>) -> Profile {
+        let vars: BTreeMap<String, String> = env.into_iter().collect();
+        let exported = vars.keys().cloned().collect();
+        Profile { vars, exported, warnings: Vec::new(), depth: 0 }
+    }
+
+    /// Evaluates the file at `path`.
+    pub fn source(&mut self, path: &Path) -> Result<()> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Read { path: path.to_path_buf(), source })?;
+        let file = path.display().to_string();
+        let mut lexer = Lexer { chars: text.chars().collect(), offset: 0, line: 1 };
+        // How many compound commands the one being read is nested in
+        let mut nesting = 0;
+        while let Some(command) = lexer.command() {
+            let at = format!("{}:{}", file, command.line);
+            let first = command.words.first().and_then(|word| word.literal());
+            if nesting > 0 {
+                if command.words.iter().any(|word| word.literal() == Some("{"))
+                    || first.is_some_and(|word| OPENERS.contains(&word))
+                {
+                    nesting += 1;
+                }
+                if first.is_some_and(|word| CLOSERS.contains(&word)) {
+                    nesting -= 1;
+                }
+                continue;
+            }
+            if let Some(opener) = first.filter(|word| OPENERS.contains(word)) {
+                self.warn(&at, format!("skipped the `{opener}` command to its end"));
+                nesting += 1;
+                continue;
+            }
+            if command.words.iter().any(|word| word.literal() == Some("{")) {
+                self.warn(&at, "skipped the function definition".to_string());
+                nesting += 1;
+                continue;
+            }
+            if let Some(operator) = command.operator {
+                self.warn(&at, format!("skipped a command using `{operator}`"));
+                continue;
+            }
+            if let Err(reason) = self.run(&command.words) {
+                self.warn(&at, reason);
+            }
+        }
+        if nesting > 0 {
+            self.warn(&file, "a compound command isn't closed by the end of the file".to_string());
+        }
+        Ok(())
+    }
+
+    /// The warnings about everything skipped so far.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// The exported variables. Variables whose names end in `PATH` hold
+    /// lists of directories, which lose their empty and repeated entries.
+    pub fn environment(&self) -> BTreeMap<String, String> {
+        self.exported
+            .iter()
+            .filter_map(|name| self.vars.get(name).map(|value| (name, value)))
+            .map(|(name, value)| {
+                if !name.ends_with("PATH") {
+                    return (name.clone(), value.clone());
+                }
+                let mut seen = BTreeSet::new();
+                let entries: Vec<&str> =
+                    value.split(':').filter(|entry| !entry.is_empty() && seen.insert(*entry)).collect();
+                (name.clone(), entries.join(":"))
+            })
+            .collect()
+    }
+
+    fn warn(&mut self, at: &str, message: String) {
+        self.warnings.push(format!("{at}: {message}"));
+    }
+
+    fn run(&mut self, words: &[Word]) -> std::result::Result<(), String> {
+        let assignments = words.iter().take_while(|word| word.assignment().is_some()).count();
+        if assignments == words.len() {
+            for word in words {
+                let (name, value) = self.assign(word)?;
+                self.vars.insert(name, value);
+            }
+            return Ok(());
+        }
+        let name = self.expand(&words[assignments])?;
+        if assignments > 0 {
+            return Err(format!("skipped `{name}`, which would only get the variables for itself"));
+        }
+        let args = &words[1..];
+        match name.as_str() {
+            "export" => {
+                for word in args {
+                    if word.assignment().is_some() {
+                        let (name, value) = self.assign(word)?;
+                        self.vars.insert(name.clone(), value);
+                        self.exported.insert(name);
+                    } else {
+                        match self.expand(word)?.as_str() {
+                            "-p" => {}
+                            name if valid_name(name) => {
+                                self.exported.insert(name.to_string());
+                            }
+                            name => return Err(format!("`{name}` isn't a valid variable name")),
+                        }
+                    }
+                }
+            }
+            "unset" => {
+                for word in args {
+                    match self.expand(word)?.as_str() {
+                        "-v" => {}
+                        "-f" => return Err("skipped unsetting functions".to_string()),
+                        name => {
+                            self.vars.remove(name);
+                            self.exported.remove(name);
+                        }
+                    }
+                }
+            }
+            "." | "source" => {
+                let [file] = args else {
+                    return Err(format!("`{name}` needs exactly one file"));
+                };
+                let file = self.expand(file)?;
+                if self.depth == MAX_DEPTH {
+                    return Err(format!("skipped `{name} {file}`, nested {MAX_DEPTH} deep"));
+                }
+                self.depth += 1;
+                let result = self.source(Path::new(&file));
+                self.depth -= 1;
+                result.map_err(|e| e.to_string())?;
+            }
+            _ => return Err(format!("skipped `{name}`: only assignments, export, unset and . are applied")),
+        }
+        Ok(())
+    }
+
+    fn assign(&self, word: &Word) -> std::result::Result<(String, String), String> {
+        let (name, rest) = word.assignment().expect("checked by the caller");
+        let mut value = String::new();
+        for (i, part) in rest.iter().enumerate() {
+            match part {
+                Part::Literal(text) => value.push_str(text),
+                Part::Text { text, quoted: true } => value.push_str(&self.expand_vars(text)?),
+                // A tilde starts the value or follows a colon
+                Part::Text { text, quoted: false } => {
+                    for (j, piece) in split_colons(text).into_iter().enumerate() {
+                        if j > 0 {
+                            value.push(':');
+                        }
+                        let starts = j > 0 || (i == 0 && value.is_empty());
+                        value.push_str(&self.tilde(piece, starts)?);
+                    }
+                }
+            }
+        }
+        Ok((name, value))
+    }
+
+    fn expand(&self, word: &Word) -> std::result::Result<String, String> {
+        let mut value = String::new();
+        for (i, part) in word.parts.iter().enumerate() {
+            match part {
+                Part::Literal(text) => value.push_str(text),
+                Part::Text { text, quoted } => value.push_str(&self.tilde(text, i == 0 && !quoted)?),
+            }
+        }
+        Ok(value)
+    }
+
+    /// Expands `text`, replacing a leading `~` with the home directory if
+    /// it's at the start of a word.
+    fn tilde(&self, text: &str, starts: bool) -> std::result::Result<String, String> {
+        match text.strip_prefix('~') {
+            Some(rest) if starts && (rest.is_empty() || rest.starts_with('/')) => {
+                let home = self.vars.get("HOME").map(String::as_str).unwrap_or_default();
+                Ok(format!("{home}{}", self.expand_vars(rest)?))
+            }
+            _ => self.expand_vars(text),
+        }
+    }
+
+    /// Replaces the parameters in `text`; unset ones are empty.
+    fn expand_vars(&self, text: &str) -> std::result::Result<String, String> {
+        let var = |name: &str| self.vars.get(name).map(String::as_str);
+        let mut out = String::new();
+        let mut rest = text;
+        while let Some(position) = rest.find('$') {
+            out.push_str(&rest[..position]);
+            let after = &rest[position + 1..];
+            if let Some(inner) = after.strip_prefix('{') {
+                let Some(end) = inner.find('}') else {
+                    return Err("a `${` isn't closed".to_string());
+                };
+                let expression = &inner[..end];
+                let length =
+                    expression.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(expression.len());
+                let (name, operator) = expression.split_at(length);
+                if !valid_name(name) {
+                    return Err(format!("skipped a command using `${{{expression}}}`"));
+                }
+                match (operator.strip_prefix(":-"), operator.strip_prefix('-')) {
+                    _ if operator.is_empty() => out.push_str(var(name).unwrap_or_default()),
+                    (Some(word), _) => match var(name).filter(|value| !value.is_empty()) {
+                        Some(value) => out.push_str(value),
+                        None => out.push_str(&self.expand_vars(word)?),
+                    },
+                    (None, Some(word)) => match var(name) {
+                        Some(value) => out.push_str(value),
+                        None => out.push_str(&self.expand_vars(word)?),
+                    },
+                    _ => return Err(format!("skipped a command using `${{{expression}}}`")),
+                }
+                rest = &inner[end + 1..];
+            } else if after.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                let length = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+                out.push_str(var(&after[..length]).unwrap_or_default());
+                rest = &after[length..];
+            } else if after.starts_with('(') {
+                return Err("skipped a command using command substitution".to_string());
+            } else if let Some(c) = after.chars().next().filter(|c| "0123456789?$#@*!-".contains(*c)) {
+                return Err(format!("skipped a command using `${c}`"));
+            } else {
+                out.push('$');
+                rest = after;
+            }
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+/// Splits `text` at the colons outside `${...}`.
+fn split_colons(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let (mut start, mut braces) = (0, 0);
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '$' if chars.peek().is_some_and(|&(_, c)| c == '{') => {
+                chars.next();
+                braces += 1;
+            }
+            '}' if braces > 0 => braces -= 1,
+            ':' if braces == 0 => {
+                pieces.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    pieces.push(&text[start..]);
+    pieces
+}
+
+fn valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A piece of a word: text that's taken as it is, from single quotes or a
+/// backslash escape, or text in which parameters are expanded.
+enum Part {
+    Literal(String),
+    Text { text: String, quoted: bool },
+}
+
+struct Word {
+    parts: Vec<Part>,
+}
+
+impl Word {
+    /// The word, if it's plain text with nothing to expand.
+    fn literal(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [Part::Text { text, quoted: false }] if !text.contains(['$', '~']) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The name and the parts of the value, if the word is an assignment.
+    fn assignment(&self) -> Option<(String, Vec<Part>)> {
+        let Some(Part::Text { text, quoted: false }) = self.parts.first() else { return None };
+        let (name, value) = text.split_once('=')?;
+        if !valid_name(name) {
+            return None;
+        }
+        let mut parts = vec![Part::Text { text: value.to_string(), quoted: false }];
+        parts.extend(self.parts[1..].iter().map(|part| match part {
+            Part::Literal(text) => Part::Literal(text.clone()),
+            Part::Text { text, quoted } => Part::Text { text: text.clone(), quoted: *quoted },
+        }));
+        Some((name.to_string(), parts))
+    }
+}
+
+struct Command {
+    line: usize,
+    words: Vec<Word>,
+    /// The first operator the command uses, such as a pipe or redirection,
+    /// which makes it more than a simple command.
+    operator: Option<&'static str>,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    offset: usize,
+    line: usize,
+}
+
+impl Lexer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.offset).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    /// Reads the next command, which may be empty; `None` at the end.
+    fn command(&mut self) -> Option<Command> {
+        self.peek()?;
+        let mut command = Command { line: self.line, words: Vec::new(), operator: None };
+        let mut parts: Vec<Part> = Vec::new();
+        let mut text = String::new();
+        // Finishes the unquoted text and the word
+        let end_text = |parts: &mut Vec<Part>, text: &mut String| {
+            if !text.is_empty() {
+                parts.push(Part::Text { text: std::mem::take(text), quoted: false });
+            }
+        };
+        let end_word = |words: &mut Vec<Word>, parts: &mut Vec<Part>, text: &mut String| {
+            end_text(parts, text);
+            if !parts.is_empty() {
+                words.push(Word { parts: std::mem::take(parts) });
+            }
+        };
+        while let Some(c) = self.next() {
+            match c {
+                '\n' | ';' => break,
+                ' ' | '\t' | '\r' => end_word(&mut command.words, &mut parts, &mut text),
+                '#' if text.is_empty() && parts.is_empty() => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.next();
+                    }
+                }
+                '\\' => match self.next() {
+                    Some('\n') | None => {}
+                    Some(c) => {
+                        end_text(&mut parts, &mut text);
+                        parts.push(Part::Literal(c.to_string()));
+                    }
+                },
+                '\'' => {
+                    end_text(&mut parts, &mut text);
+                    let mut literal = String::new();
+                    while let Some(c) = self.next().filter(|&c| c != '\'') {
+                        literal.push(c);
+                    }
+                    parts.push(Part::Literal(literal));
+                }
+                '"' => {
+                    end_text(&mut parts, &mut text);
+                    let mut quoted = String::new();
+                    while let Some(c) = self.next().filter(|&c| c != '"') {
+                        match c {
+                            '\\' => match self.next() {
+                                Some('\n') | None => {}
+                                Some(c @ ('$' | '`' | '"' | '\\')) => {
+                                    parts.push(Part::Text { text: std::mem::take(&mut quoted), quoted: true });
+                                    parts.push(Part::Literal(c.to_string()));
+                                }
+                                Some(c) => {
+                                    quoted.push('\\');
+                                    quoted.push(c);
+                                }
+                            },
+                            '`' => {
+                                command.operator.get_or_insert("`");
+                            }
+                            c => quoted.push(c),
+                        }
+                    }
+                    parts.push(Part::Text { text: quoted, quoted: true });
+                }
+                '|' | '&' | '<' | '>' | '(' | ')' | '`' => {
+                    let operator = match c {
+                        '(' if text.ends_with('$') => "$(",
+                        '|' => "|",
+                        '&' => "&",
+                        '<' => "<",
+                        '>' => ">",
+                        '(' => "(",
+                        ')' => ")",
+                        _ => "`",
+                    };
+                    command.operator.get_or_insert(operator);
+                    text.push(c);
+                }
+                c => text.push(c),
+            }
+        }
+        end_word(&mut command.words, &mut parts, &mut text);
+        Some(command)
+    }
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use serde_json::{json, Value};
+
+/// Writes the files to a scratch directory.
+fn scratch(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ecmaos-profile-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    for (path, contents) in files {
+        fs::write(root.join(path), contents).unwrap();
+    }
+    root
+}
+
+/// Runs the tool on the files with only `env` in its environment.
+fn run(root: &PathBuf, env: &[(&str, &str)], files: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ecmaos-profile"))
+        .args(files)
+        .env_clear()
+        .envs(env.iter().copied())
+        .current_dir(root)
+        .output()
+        .unwrap()
+}
+
+fn changes(output: &Output) -> Value {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn applies_assignments_and_exports() {
+    let root = scratch(
+        "assign",
+        &[
+            (
+                "profile",
+                "# comment\n\
+                 PATH=\"/usr/local/bin:$PATH\"; export PATH\n\
+                 export EDITOR=vi PAGER='less -R'\n\
+                 LOCAL=kept-out\n\
+                 export GREETING=\"hello, ${USER}\" DIR=~/bin:~/sbin\n\
+                 export FALLBACK=${MISSING:-default} EMPTY=${MISSING-}\n\
+                 unset OLDPWD\n\
+                 . ./more\n",
+            ),
+            ("more", "export ESCAPED=\"\\$HOME \\\"quoted\\\"\" JOINED=one\\\ntwo\n"),
+        ],
+    );
+    let env = [("HOME", "/home/me"), ("USER", "me"), ("PATH", "/bin"), ("OLDPWD", "/tmp")];
+    assert_eq!(
+        changes(&run(&root, &env, &["profile"])),
+        json!({
+            "set": {
+                "DIR": "/home/me/bin:/home/me/sbin",
+                "EDITOR": "vi",
+                "EMPTY": "",
+                "ESCAPED": "$HOME \"quoted\"",
+                "FALLBACK": "default",
+                "GREETING": "hello, me",
+                "JOINED": "onetwo",
+                "PAGER": "less -R",
+                "PATH": "/usr/local/bin:/bin",
+            },
+            "unset": ["OLDPWD"],
+        })
+    );
+}
+
+#[test]
+fn skips_what_it_cannot_evaluate() {
+    let profile = "if [ -d /opt/bin ]; then\n  export PATH=/opt/bin:$PATH\nfi\n\
+                   umask 022\n\
+                   greet() {\n  export GREETED=1\n}\n\
+                   export NOW=$(date)\n\
+                   ls | head\n\
+                   export AFTER=1\n";
+    let root = scratch("skip", &[("profile", profile)]);
+    let output = run(&root, &[("PATH", "/bin")], &["profile"]);
+    assert_eq!(changes(&output), json!({ "set": { "AFTER": "1" }, "unset": [] }));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "warning: profile:1: skipped the `if` command to its end\n\
+         warning: profile:4: skipped `umask`: only assignments, export, unset and . are applied\n\
+         warning: profile:5: skipped the function definition\n\
+         warning: profile:8: skipped a command using `$(`\n\
+         warning: profile:9: skipped a command using `|`\n"
+    );
+}
+
+#[test]
+fn cleans_up_path_lists() {
+    let root = scratch("paths", &[("profile", "export PATH=/bin::/usr/bin:$PATH MANPATH=:/man:/man\n")]);
+    assert_eq!(
+        changes(&run(&root, &[("PATH", "/usr/bin:/sbin")], &["profile"])),
+        json!({ "set": { "MANPATH": "/man", "PATH": "/bin:/usr/bin:/sbin" }, "unset": [] })
+    );
+}
+
+#[test]
+fn fails_on_missing_files() {
+    let root = scratch("missing", &[]);
+    let output = run(&root, &[], &["nowhere"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: nowhere: "));
+}
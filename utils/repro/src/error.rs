@@ -0,0 +1,25 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    Http(String),
+    #[error("{0}")]
+    Metadata(String),
+    #[error("{0}")]
+    Archive(String),
+    #[error("{0}")]
+    Build(String),
+    #[error("{0}")]
+    Wasm(String),
+}
+
+impl From<ureq::Error> for Error {
+    fn from(error: ureq::Error) -> Self {
+        Error::Http(error.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
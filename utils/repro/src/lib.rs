@@ -0,0 +1,10 @@
+//! The parts of `ecmaos-repro` that don't depend on its command line: the
+//! [`published`] release, the [`source`] it's rebuilt from and the
+//! [`wasm`] comparison of the two.
+
+mod error;
+pub mod published;
+pub mod source;
+pub mod wasm;
+
+pub use error::{Error, Result};
@@ -0,0 +1,141 @@
+//! Checks that a published package can be rebuilt from its source: the
+//! WASM modules in its tarball must match the ones a fresh build produces.
+//!
+//! ```text
+//! ecmaos-repro @ecmaos-apps/hello@1.0.0
+//! ```
+//!
+//! The package's tarball is fetched from the registry and checked against
+//! the registry's checksum (see [`ecmaos_repro::published`]). Its
+//! repository is cloned at the commit npm recorded when publishing, unless
+//! a checkout is given with `--source`, and built with the package's
+//! `build` script or the command given with `--build` (see
+//! [`ecmaos_repro::source`]). Each `.wasm` file in the tarball is then
+//! compared with the file at the same path in the build, ignoring the
+//! sections that record where and when a module was built (see
+//! [`ecmaos_repro::wasm`]), and the differences are reported down to the
+//! byte.
+//!
+//! The exit status is 0 if every module matches, 1 if any doesn't or the
+//! check couldn't be made.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ecmaos_repro::published::Release;
+use ecmaos_repro::source::{self, Checkout};
+use ecmaos_repro::{wasm, Error, Result};
+
+const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org";
+
+const USAGE: &str = "usage: ecmaos-repro [--registry URL|DIR] [--source DIR] [--build COMMAND] PACKAGE@VERSION";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut registry = DEFAULT_REGISTRY.to_string();
+    let mut source = None;
+    let mut build = None;
+    let mut spec = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--registry" => match args.next() {
+                Some(url) => registry = url,
+                None => usage_error("--registry needs a value"),
+            },
+            "--source" => match args.next() {
+                Some(dir) => source = Some(PathBuf::from(dir)),
+                None => usage_error("--source needs a value"),
+            },
+            "--build" => match args.next() {
+                Some(command) => build = Some(command),
+                None => usage_error("--build needs a value"),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ if spec.is_none() => spec = Some(arg),
+            _ => usage_error(&format!("unexpected argument: {}", arg)),
+        }
+    }
+    let Some(spec) = spec else { usage_error("no package given") };
+    let start = usize::from(spec.starts_with('@'));
+    let Some(at) = spec[start..].find('@').map(|at| start + at) else {
+        usage_error("give the exact version to check, as PACKAGE@VERSION")
+    };
+
+    match run(&registry, &spec[..at], &spec[at + 1..], source.as_deref(), build.as_deref()) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Returns whether every module matched.
+fn run(registry: &str, package: &str, version: &str, source: Option<&Path>, build: Option<&str>) -> Result<bool> {
+    let release = Release::fetch(registry, package, version)?;
+    let modules: Vec<&(String, Vec<u8>)> = release.files.iter().filter(|(path, _)| path.ends_with(".wasm")).collect();
+    if modules.is_empty() {
+        return Err(Error::Metadata(format!("{package}@{version} contains no WASM modules")));
+    }
+
+    let checkout;
+    let dir = match source {
+        Some(dir) => dir.to_path_buf(),
+        None => {
+            let Some(source) = release.source() else {
+                return Err(Error::Metadata(format!(
+                    "{package}@{version} doesn't record its repository and commit; give a checkout with --source"
+                )));
+            };
+            eprintln!("Cloning {} at {}", source.url, source.commit);
+            checkout = Checkout::create(&source, package)?;
+            match &source.directory {
+                Some(directory) => checkout.dir.join(directory),
+                None => checkout.dir.clone(),
+            }
+        }
+    };
+    eprintln!("Building {}", dir.display());
+    source::build(&dir, build)?;
+
+    let mut differing = 0;
+    for (path, published) in &modules {
+        let rebuilt = match fs::read(dir.join(path)) {
+            Ok(rebuilt) => rebuilt,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                println!("✗ {path}: not produced by the build");
+                differing += 1;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let differences = wasm::compare(published, &rebuilt).map_err(|e| Error::Wasm(format!("{path}: {e}")))?;
+        if differences.is_empty() {
+            println!("✓ {path}");
+        } else {
+            println!("✗ {path}");
+            for difference in differences {
+                println!("    {difference}");
+            }
+            differing += 1;
+        }
+    }
+
+    if differing == 0 {
+        println!("{package}@{version} is reproducible: all {} modules match", modules.len());
+    } else {
+        println!("{package}@{version} is not reproducible: {differing} of {} modules differ", modules.len());
+    }
+    Ok(differing == 0)
+}
@@ -0,0 +1,150 @@
+//! The published side: a package version's metadata and the files in its
+//! tarball.
+//!
+//! The registry is either an npm-compatible URL or a directory in the layout
+//! `ecmaos-mirror` writes, as with `install --registry`.
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+
+use crate::error::{Error, Result};
+
+const BLOCK: usize = 512;
+
+/// A published version of a package.
+pub struct Release {
+    /// The version's entry in the registry metadata.
+    pub manifest: Value,
+    /// Files in the tarball, relative to its `package/` directory, in
+    /// archive order.
+    pub files: Vec<(String, Vec<u8>)>,
+}
+
+impl Release {
+    /// Fetches a version's metadata and tarball and checks the tarball
+    /// against the registry's checksum.
+    pub fn fetch(registry: &str, package: &str, version: &str) -> Result<Release> {
+        let local = !registry.contains("://");
+        let metadata: Value = if local {
+            serde_json::from_slice(&fs::read(Path::new(registry).join(package).join("index.json"))?)?
+        } else {
+            let url = format!("{}/{}", registry.trim_end_matches('/'), package.replace('/', "%2f"));
+            serde_json::from_reader(ureq::get(&url).call()?.into_reader())?
+        };
+        let Some(manifest) = metadata.get("versions").and_then(|versions| versions.get(version)) else {
+            return Err(Error::Metadata(format!("{package}@{version} isn't in the registry")));
+        };
+        let dist = &manifest["dist"];
+        let Some(tarball) = dist.get("tarball").and_then(Value::as_str) else {
+            return Err(Error::Metadata(format!("{package}@{version} has no tarball")));
+        };
+
+        let data = if local && !tarball.contains("://") {
+            fs::read(Path::new(registry).join(package).join(tarball))?
+        } else {
+            let mut data = Vec::new();
+            ureq::get(tarball).call()?.into_reader().read_to_end(&mut data)?;
+            data
+        };
+        let Some(shasum) = dist.get("shasum").and_then(Value::as_str) else {
+            return Err(Error::Metadata(format!("{package}@{version} has no checksum to verify against")));
+        };
+        let actual: String = Sha1::digest(&data).iter().map(|byte| format!("{byte:02x}")).collect();
+        if !actual.eq_ignore_ascii_case(shasum) {
+            return Err(Error::Archive(format!("{tarball}: sha1 checksum doesn't match")));
+        }
+
+        let mut archive = Vec::new();
+        GzDecoder::new(&data[..]).read_to_end(&mut archive).map_err(|e| Error::Archive(format!("{tarball}: {e}")))?;
+        let files = untar(&archive).map_err(|e| Error::Archive(format!("{tarball}: {e}")))?;
+        Ok(Release { manifest: manifest.clone(), files })
+    }
+
+    /// The source the version was published from: the repository URL, the
+    /// commit npm recorded as `gitHead` and the package's directory in the
+    /// repository, if it isn't at the top.
+    pub fn source(&self) -> Option<Source> {
+        let repository = &self.manifest["repository"];
+        let url = match repository {
+            Value::String(url) => url.as_str(),
+            repository => repository.get("url")?.as_str()?,
+        };
+        Some(Source {
+            url: git_url(url),
+            commit: self.manifest.get("gitHead")?.as_str()?.to_string(),
+            directory: repository.get("directory").and_then(Value::as_str).map(str::to_string),
+        })
+    }
+}
+
+pub struct Source {
+    pub url: String,
+    pub commit: String,
+    pub directory: Option<String>,
+}
+
+/// Turns the forms npm accepts for a repository into a URL git can clone.
+fn git_url(repository: &str) -> String {
+    let repository = repository.strip_prefix("git+").unwrap_or(repository);
+    if let Some(path) = repository.strip_prefix("github:") {
+        return format!("https://github.com/{path}.git");
+    }
+    match repository.split_once(':') {
+        // `owner/repo` shorthand for GitHub
+        None if repository.matches('/').count() == 1 => format!("https://github.com/{repository}.git"),
+        _ => repository.to_string(),
+    }
+}
+
+/// Reads the regular files out of a tar archive, with npm's leading
+/// directory (normally `package/`) removed from their paths.
+fn untar(archive: &[u8]) -> std::result::Result<Vec<(String, Vec<u8>)>, String> {
+    let mut files = Vec::new();
+    let mut offset = 0;
+    let mut long_name = None;
+    while offset + BLOCK <= archive.len() {
+        let header = &archive[offset..offset + BLOCK];
+        if header.iter().all(|&byte| byte == 0) {
+            break;
+        }
+        let field = |start: usize, end: usize| {
+            let field = &header[start..end];
+            let length = field.iter().position(|&byte| byte == 0).unwrap_or(field.len());
+            String::from_utf8_lossy(&field[..length]).into_owned()
+        };
+        let size = usize::from_str_radix(field(124, 136).trim(), 8).map_err(|_| "bad entry size".to_string())?;
+        let start = offset + BLOCK;
+        let Some(contents) = archive.get(start..start + size) else {
+            return Err("truncated archive".to_string());
+        };
+        offset = start + size.div_ceil(BLOCK) * BLOCK;
+
+        let name = match field(345, 500) {
+            prefix if !prefix.is_empty() && &header[257..262] == b"ustar" => format!("{prefix}/{}", field(0, 100)),
+            _ => field(0, 100),
+        };
+        match header[156] {
+            b'0' | 0 => {
+                let name = long_name.take().unwrap_or(name);
+                let name = name.split_once('/').map_or(name.as_str(), |(_, rest)| rest).to_string();
+                files.push((name, contents.to_vec()));
+            }
+            // A pax header, which can carry a path too long for the header
+            b'x' => long_name = pax_path(contents),
+            _ => long_name = None,
+        }
+    }
+    Ok(files)
+}
+
+/// The `path` record of a pax extended header. Records are
+/// `<length> <key>=<value>\n`.
+fn pax_path(header: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(header);
+    text.lines().find_map(|record| record.split_once(' ')?.1.strip_prefix("path=").map(str::to_string))
+}
@@ -0,0 +1,73 @@
+//! The rebuilt side: checking out the source a package was published from
+//! and building it.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::published::Source;
+
+/// A clone of a package's repository, removed when dropped.
+pub struct Checkout {
+    pub dir: PathBuf,
+}
+
+impl Checkout {
+    /// Clones the repository into a scratch directory and checks out the
+    /// published commit.
+    pub fn create(source: &Source, name: &str) -> Result<Checkout> {
+        let dir = std::env::temp_dir().join(format!("ecmaos-repro-{}-{}", name.replace('/', "-"), std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let checkout = Checkout { dir };
+        let dir = checkout.dir.to_string_lossy();
+        run(Command::new("git").args(["clone", "--quiet", "--no-checkout", &source.url, &dir]), "git clone")?;
+        run(Command::new("git").args(["-C", &dir, "checkout", "--quiet", &source.commit]), "git checkout")?;
+        Ok(checkout)
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Builds the package in `dir` with `command`, or with its `build` script if
+/// no command is given. The build's output goes to stderr.
+///
+/// `SOURCE_DATE_EPOCH` is set to the time of the checked out commit, which
+/// tools that stamp their output with a date use instead of the current
+/// time.
+pub fn build(dir: &Path, command: Option<&str>) -> Result<()> {
+    let command = match command {
+        Some(command) => command.to_string(),
+        None => {
+            let manifest: Value = serde_json::from_slice(&fs::read(dir.join("package.json"))?)?;
+            if manifest["scripts"].get("build").is_none() {
+                return Err(Error::Build("the package has no build script; give the command with --build".into()));
+            }
+            "npm run build".to_string()
+        }
+    };
+
+    let mut build = Command::new("sh");
+    build.args(["-c", &command]).current_dir(dir).stdout(Stdio::from(io::stderr()));
+    let epoch = Command::new("git").args(["log", "-1", "--format=%ct"]).current_dir(dir).output();
+    if let Some(epoch) = epoch.ok().filter(|output| output.status.success()) {
+        build.env("SOURCE_DATE_EPOCH", String::from_utf8_lossy(&epoch.stdout).trim());
+    }
+    run(&mut build, &command)
+}
+
+fn run(command: &mut Command, what: &str) -> Result<()> {
+    let status = command.status().map_err(|e| Error::Build(format!("couldn't run {what}: {e}")))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::Build(format!("{what} failed ({status})")))
+    }
+}
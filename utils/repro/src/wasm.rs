@@ -0,0 +1,172 @@
+//! Comparing WASM modules.
+//!
+//! Two builds of the same source rarely match byte for byte as they come
+//! out of the compiler: debug information records the directory the build
+//! ran in, and some toolchains stamp the module with a build id or the
+//! versions of the tools used. Those custom sections are dropped from both
+//! modules before comparing; everything a runtime executes is compared as
+//! is. Builds whose code embeds paths, such as Rust panic locations, must
+//! map them to fixed ones (`--remap-path-prefix`) to be reproducible.
+
+use std::fmt::Write as _;
+
+use crate::error::{Error, Result};
+
+const HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+/// Custom sections that depend on where and when a module was built.
+const VOLATILE: &[&str] = &["producers", "build_id", "sourceMappingURL", "external_debug_info"];
+
+/// Differing ranges listed per section before the rest are only counted.
+const MAX_RANGES: usize = 5;
+
+/// Bytes shown around a difference.
+const CONTEXT: usize = 8;
+
+struct Section<'a> {
+    id: u8,
+    /// For custom sections, the name.
+    name: Option<String>,
+    contents: &'a [u8],
+}
+
+impl Section<'_> {
+    fn label(&self) -> String {
+        const NAMES: [&str; 14] = [
+            "custom",
+            "type",
+            "import",
+            "function",
+            "table",
+            "memory",
+            "global",
+            "export",
+            "start",
+            "element",
+            "code",
+            "data",
+            "datacount",
+            "tag",
+        ];
+        match (&self.name, NAMES.get(usize::from(self.id))) {
+            (Some(name), _) => format!("custom section {name:?}"),
+            (None, Some(name)) => format!("{name} section"),
+            (None, None) => format!("section {}", self.id),
+        }
+    }
+
+    fn volatile(&self) -> bool {
+        self.name.as_deref().is_some_and(|name| name.starts_with(".debug_") || VOLATILE.contains(&name))
+    }
+}
+
+/// Compares two modules once the volatile sections are dropped, returning
+/// a description of each difference; empty if they match.
+pub fn compare(published: &[u8], rebuilt: &[u8]) -> Result<Vec<String>> {
+    let published: Vec<Section> = sections(published)?.into_iter().filter(|section| !section.volatile()).collect();
+    let rebuilt: Vec<Section> = sections(rebuilt)?.into_iter().filter(|section| !section.volatile()).collect();
+
+    let labels = |sections: &[Section]| sections.iter().map(Section::label).collect::<Vec<_>>();
+    if labels(&published) != labels(&rebuilt) {
+        return Ok(vec![format!(
+            "different sections:\n      published: {}\n      rebuilt:   {}",
+            labels(&published).join(", "),
+            labels(&rebuilt).join(", ")
+        )]);
+    }
+
+    let mut differences = Vec::new();
+    for (published, rebuilt) in published.iter().zip(&rebuilt) {
+        if published.contents != rebuilt.contents {
+            differences.push(describe(&published.label(), published.contents, rebuilt.contents));
+        }
+    }
+    Ok(differences)
+}
+
+/// Describes how the contents of a section differ: the sizes if they do,
+/// and the first few ranges of differing bytes with some context.
+fn describe(label: &str, published: &[u8], rebuilt: &[u8]) -> String {
+    let mut out = format!("{label} differs");
+    if published.len() != rebuilt.len() {
+        let _ = write!(out, " ({} bytes published, {} rebuilt)", published.len(), rebuilt.len());
+    }
+
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    let shared = published.len().min(rebuilt.len());
+    while offset < shared {
+        if published[offset] == rebuilt[offset] {
+            offset += 1;
+            continue;
+        }
+        let start = offset;
+        while offset < shared && published[offset] != rebuilt[offset] {
+            offset += 1;
+        }
+        ranges.push(start..offset);
+    }
+    if published.len() != rebuilt.len() && ranges.last().is_none_or(|range| range.end < shared) {
+        ranges.push(shared..shared);
+    }
+
+    for range in ranges.iter().take(MAX_RANGES) {
+        let window = range.start.saturating_sub(CONTEXT)..range.end + CONTEXT;
+        let hex = |data: &[u8]| {
+            let window = window.start.min(data.len())..window.end.min(data.len());
+            data[window].iter().map(|byte| format!("{byte:02x}")).collect::<Vec<_>>().join(" ")
+        };
+        let _ = write!(out, "\n      at section offset {:#x}", range.start);
+        if range.end > range.start + 1 {
+            let _ = write!(out, "..{:#x}", range.end);
+        }
+        let _ = write!(out, ":\n        published: {}\n        rebuilt:   {}", hex(published), hex(rebuilt));
+    }
+    if ranges.len() > MAX_RANGES {
+        let _ = write!(out, "\n      and {} more differing ranges", ranges.len() - MAX_RANGES);
+    }
+    out
+}
+
+fn sections(module: &[u8]) -> Result<Vec<Section<'_>>> {
+    if !module.starts_with(HEADER) {
+        return Err(Error::Wasm("not a WASM module (version 1)".to_string()));
+    }
+    let mut sections = Vec::new();
+    let mut offset = HEADER.len();
+    while offset < module.len() {
+        let id = module[offset];
+        offset += 1;
+        let size = read_leb128(module, &mut offset)?;
+        let Some(contents) = offset.checked_add(size).and_then(|end| module.get(offset..end)) else {
+            return Err(Error::Wasm(format!("section at offset {offset} runs past the end of the module")));
+        };
+        offset += size;
+        let name = if id == 0 {
+            let mut start = 0;
+            let length = read_leb128(contents, &mut start)?;
+            let name =
+                contents.get(start..start + length).ok_or_else(|| Error::Wasm("truncated section name".into()))?;
+            Some(String::from_utf8_lossy(name).into_owned())
+        } else {
+            None
+        };
+        sections.push(Section { id, name, contents });
+    }
+    Ok(sections)
+}
+
+fn read_leb128(data: &[u8], offset: &mut usize) -> Result<usize> {
+    let mut value: u32 = 0;
+    for shift in (0..35).step_by(7) {
+        let Some(&byte) = data.get(*offset) else {
+            return Err(Error::Wasm("truncated module".to_string()));
+        };
+        *offset += 1;
+        value |= u32::from(byte & 0x7f).checked_shl(shift).unwrap_or(0);
+        if byte & 0x80 == 0 {
+            return Ok(value as usize);
+        }
+    }
+    Err(Error::Wasm(format!("number at offset {} is too long", *offset)))
+}
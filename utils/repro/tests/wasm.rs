@@ -0,0 +1,118 @@
+use ecmaos_repro::wasm::compare;
+
+const TYPE: u8 = 1;
+const CODE: u8 = 10;
+
+/// A module made of `sections`, each an id and its contents.
+fn module(sections: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    for (id, contents) in sections {
+        assert!(contents.len() < 0x80, "sizes are written as a single byte");
+        module.push(*id);
+        module.push(contents.len() as u8);
+        module.extend_from_slice(contents);
+    }
+    module
+}
+
+/// A custom section with `name` and `data`.
+fn custom(name: &str, data: &[u8]) -> (u8, Vec<u8>) {
+    let mut contents = vec![name.len() as u8];
+    contents.extend_from_slice(name.as_bytes());
+    contents.extend_from_slice(data);
+    (0, contents)
+}
+
+#[test]
+fn identical_modules_match() {
+    let sections = [(TYPE, vec![1, 0x60, 0, 0]), (CODE, vec![1, 2, 0, 0x0b]), custom("name", b"main")];
+    assert!(compare(&module(&sections), &module(&sections)).unwrap().is_empty());
+}
+
+#[test]
+fn build_specific_sections_are_ignored() {
+    let code = (CODE, vec![1, 2, 0, 0x0b]);
+    let published = module(&[
+        code.clone(),
+        custom("producers", b"rustc 1.80"),
+        custom(".debug_info", b"/home/alice/src"),
+        custom("build_id", b"\x01\x02"),
+    ]);
+    let rebuilt = module(&[
+        code,
+        custom("producers", b"rustc 1.81"),
+        custom(".debug_info", b"/tmp/ecmaos-repro-hello-42"),
+        custom(".debug_line", b"only here"),
+        custom("sourceMappingURL", b"hello.wasm.map"),
+    ]);
+    assert!(compare(&published, &rebuilt).unwrap().is_empty());
+}
+
+#[test]
+fn other_custom_sections_are_compared() {
+    let published = module(&[custom("name", b"main")]);
+    let rebuilt = module(&[custom("name", b"mane")]);
+    let differences = compare(&published, &rebuilt).unwrap();
+    assert_eq!(differences.len(), 1);
+    assert!(differences[0].starts_with("custom section \"name\" differs\n"), "{}", differences[0]);
+    assert!(differences[0].contains("at section offset 0x7..0x9:"), "{}", differences[0]);
+}
+
+#[test]
+fn differing_bytes_are_located() {
+    let published = module(&[(TYPE, vec![1, 0x60, 0, 0]), (CODE, vec![1, 4, 0, 0x41, 1, 0x0b])]);
+    let rebuilt = module(&[(TYPE, vec![1, 0x60, 0, 0]), (CODE, vec![1, 4, 0, 0x41, 2, 0x0b, 0x0b, 0x0b])]);
+    let differences = compare(&published, &rebuilt).unwrap();
+    assert_eq!(
+        differences,
+        [concat!(
+            "code section differs (6 bytes published, 8 rebuilt)\n",
+            "      at section offset 0x4:\n",
+            "        published: 01 04 00 41 01 0b\n",
+            "        rebuilt:   01 04 00 41 02 0b 0b 0b\n",
+            "      at section offset 0x6:\n",
+            "        published: 01 04 00 41 01 0b\n",
+            "        rebuilt:   01 04 00 41 02 0b 0b 0b",
+        )]
+    );
+}
+
+#[test]
+fn many_differences_are_counted() {
+    let published = module(&[(CODE, [0u8, 1].repeat(10))]);
+    let rebuilt = module(&[(CODE, [0u8, 2].repeat(10))]);
+    let differences = compare(&published, &rebuilt).unwrap();
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].matches("at section offset").count(), 5);
+    assert!(differences[0].ends_with("and 5 more differing ranges"), "{}", differences[0]);
+}
+
+#[test]
+fn different_sections_are_reported_as_such() {
+    let published = module(&[(TYPE, vec![0]), (CODE, vec![0])]);
+    let rebuilt = module(&[(TYPE, vec![0]), custom("name", b""), (CODE, vec![0])]);
+    let differences = compare(&published, &rebuilt).unwrap();
+    assert_eq!(
+        differences,
+        [concat!(
+            "different sections:\n",
+            "      published: type section, code section\n",
+            "      rebuilt:   type section, custom section \"name\", code section",
+        )]
+    );
+}
+
+#[test]
+fn malformed_modules_are_errors() {
+    let valid = module(&[(CODE, vec![0])]);
+    assert!(compare(b"\0asm\x02\0\0\0", &valid).is_err());
+    assert!(compare(&valid, b"not wasm").is_err());
+
+    // A section longer than what's left of the module
+    let mut truncated = module(&[(CODE, vec![0, 0, 0])]);
+    truncated.pop();
+    assert!(compare(&truncated, &valid).is_err());
+
+    // A custom section whose name runs past its end
+    assert!(compare(&module(&[(0, vec![5, b'a'])]), &valid).is_err());
+}
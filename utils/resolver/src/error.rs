@@ -0,0 +1,40 @@
+use std::fmt;
+
+use crate::solver::Requirement;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum Error {
+    #[error("invalid version: {0}")]
+    Version(String),
+    #[error("invalid version range: {0}")]
+    Range(String),
+    #[error("invalid package metadata: {0}")]
+    Metadata(String),
+    #[error("registry error: {0}")]
+    Registry(String),
+    #[error("{0}")]
+    Conflict(Conflict),
+    #[error("gave up after trying {0} combinations of versions")]
+    TooComplex(usize),
+}
+
+/// Why no version of a package could be chosen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub package: String,
+    /// Every requirement on the package at the point it failed.
+    pub requirements: Vec<Requirement>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no version of {} satisfies", self.package)?;
+        for (i, requirement) in self.requirements.iter().enumerate() {
+            f.write_str(if i == 0 { " " } else { ", " })?;
+            write!(f, "{requirement}")?;
+        }
+        Ok(())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,91 @@
+//! Package version resolution for ecmaOS.
+//!
+//! Given the dependencies of a root package and a [`Registry`] to look
+//! releases up in, [`Resolver`] chooses one version of every package needed
+//! so that all of the constraints hold at once:
+//!
+//! - required dependencies, as npm [`Range`]s (`^1.2.0`, `~2.1`, `>=3 <4`,
+//!   `1.x || 2.x`, ...)
+//! - optional dependencies, which are installed when some version fits and
+//!   otherwise reported as skipped
+//! - conflicts, read from a `conflicts` field alongside `dependencies`, which
+//!   rule out versions of another package
+//!
+//! Unlike installing the newest match of every range one package at a time,
+//! the resolver backtracks: when the newest version of a package leads to a
+//! dead end further down, older ones are tried before giving up. It still
+//! prefers newer versions, or a version passed to [`Resolver::prefer`], and
+//! its choices depend only on its inputs, never on the order the registry
+//! lists things in.
+//!
+//! ```
+//! use ecmaos_resolver::{Dependencies, MemoryRegistry, Resolver};
+//! use serde_json::json;
+//!
+//! let mut registry = MemoryRegistry::new();
+//! registry.insert_metadata("left-pad", &json!({ "versions": { "1.1.0": {}, "1.3.0": {} } }))?;
+//! let root = Dependencies::from_manifest(&json!({ "dependencies": { "left-pad": "^1.1.0" } }))?;
+//! let resolution = Resolver::new(&registry).resolve(&root)?;
+//! assert_eq!(resolution.packages["left-pad"].to_string(), "1.3.0");
+//! # Ok::<(), ecmaos_resolver::Error>(())
+//! ```
+
+mod error;
+mod range;
+mod registry;
+mod solver;
+mod version;
+
+pub use error::{Conflict, Error, Result};
+pub use range::Range;
+pub use registry::{releases, Dependencies, MemoryRegistry, Registry, Release};
+pub use solver::{Kind, Requirement, Resolution, Resolver};
+pub use version::{Identifier, Version};
+
+#[cfg(feature = "serde")]
+mod text {
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::{Range, Version};
+
+    fn serialize<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+
+    impl Serialize for Version {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Version {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize(deserializer)
+        }
+    }
+
+    impl Serialize for Range {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Range {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize(deserializer)
+        }
+    }
+}
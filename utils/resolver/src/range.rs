@@ -0,0 +1,243 @@
+//! Version ranges in npm's syntax.
+//!
+//! A range is a `||`-separated list of comparator sets, and a version
+//! satisfies it when it satisfies every comparator of at least one set.
+//! The shorthand forms are expanded into plain comparators when parsed:
+//!
+//! | Range           | Expands to                 |
+//! |-----------------|----------------------------|
+//! | `1.2.x`, `1.2`  | `>=1.2.0 <1.3.0-0`         |
+//! | `~1.2.3`        | `>=1.2.3 <1.3.0-0`         |
+//! | `^1.2.3`        | `>=1.2.3 <2.0.0-0`         |
+//! | `^0.2.3`        | `>=0.2.3 <0.3.0-0`         |
+//! | `1.2 - 2.3.4`   | `>=1.2.0 <=2.3.4`          |
+//! | `*`, `""`       | any version                |
+//!
+//! As in npm, a pre-release only satisfies a set that mentions a
+//! pre-release of the same `major.minor.patch`, so `^1.2.0` doesn't pick up
+//! `1.5.0-beta` but `>=1.5.0-alpha <2` does.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::version::{parse_number, parse_pre, split, Version};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparator {
+    op: Op,
+    version: Version,
+}
+
+impl Comparator {
+    fn new(op: Op, version: Version) -> Self {
+        Comparator { op, version }
+    }
+
+    /// A comparator nothing satisfies, for ranges such as `>*`.
+    fn none() -> Self {
+        Comparator::new(Op::Lt, Version::lowest(0, 0, 0))
+    }
+
+    fn matches(&self, version: &Version) -> bool {
+        match self.op {
+            Op::Eq => *version == self.version,
+            Op::Lt => *version < self.version,
+            Op::Le => *version <= self.version,
+            Op::Gt => *version > self.version,
+            Op::Ge => *version >= self.version,
+        }
+    }
+}
+
+/// A version with trailing parts left out or written as `x`/`*`.
+struct Partial {
+    major: Option<u64>,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    version: Version,
+}
+
+impl Partial {
+    fn parse(text: &str) -> Result<Self> {
+        let invalid = || Error::Range(text.to_string());
+        let trimmed = text.trim_start_matches('=').trim_start_matches('v');
+        let (release, pre, build) = split(trimmed);
+        let mut parts = [None; 3];
+        let mut wildcard = false;
+        for (i, part) in release.split('.').enumerate() {
+            if i == 3 {
+                return Err(invalid());
+            }
+            if matches!(part, "x" | "X" | "*") {
+                wildcard = true;
+            } else if !wildcard {
+                parts[i] = Some(parse_number(part, text).map_err(|_| invalid())?);
+            }
+        }
+        let [major, minor, patch] = parts;
+        let pre = match (pre, patch) {
+            (Some(pre), Some(_)) => parse_pre(pre, text).map_err(|_| invalid())?,
+            (Some(_), None) => return Err(invalid()),
+            (None, _) => Vec::new(),
+        };
+        let version = Version {
+            pre,
+            build: build.to_string(),
+            ..Version::new(major.unwrap_or(0), minor.unwrap_or(0), patch.unwrap_or(0))
+        };
+        Ok(Partial { major, minor, patch, version })
+    }
+
+    /// The lowest version the partial covers.
+    fn floor(&self) -> Comparator {
+        Comparator::new(Op::Ge, self.version.clone())
+    }
+
+    /// The exclusive bound just above every version the partial covers, or
+    /// `None` when it covers everything.
+    fn ceiling(&self) -> Option<Comparator> {
+        let bound = match (self.major, self.minor, self.patch) {
+            (None, _, _) => return None,
+            (Some(major), None, _) => Version::lowest(major + 1, 0, 0),
+            (Some(major), Some(minor), None) => Version::lowest(major, minor + 1, 0),
+            (Some(_), Some(_), Some(_)) => return Some(Comparator::new(Op::Le, self.version.clone())),
+        };
+        Some(Comparator::new(Op::Lt, bound))
+    }
+}
+
+/// A parsed npm version range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    text: String,
+    sets: Vec<Vec<Comparator>>,
+}
+
+impl Range {
+    /// The range every version satisfies.
+    pub fn any() -> Self {
+        Range { text: "*".to_string(), sets: vec![Vec::new()] }
+    }
+
+    pub fn satisfies(&self, version: &Version) -> bool {
+        self.sets.iter().any(|set| {
+            set.iter().all(|comparator| comparator.matches(version))
+                && (!version.is_prerelease()
+                    || set.iter().any(|c| c.version.is_prerelease() && c.version.same_release(version)))
+        })
+    }
+}
+
+fn parse_set(text: &str) -> Result<Vec<Comparator>> {
+    if let Some((from, to)) = text.split_once(" - ") {
+        let from = Partial::parse(from.trim())?;
+        let to = Partial::parse(to.trim())?;
+        let mut set = Vec::new();
+        if from.major.is_some() {
+            set.push(from.floor());
+        }
+        set.extend(to.ceiling());
+        return Ok(set);
+    }
+
+    // Operators may be separated from their version: `>= 1.2.3`
+    let mut tokens = Vec::new();
+    let mut pending = String::new();
+    for token in text.split_whitespace() {
+        pending.push_str(token);
+        if !token.bytes().all(|b| matches!(b, b'<' | b'>' | b'=' | b'~' | b'^')) {
+            tokens.push(std::mem::take(&mut pending));
+        }
+    }
+    if !pending.is_empty() {
+        return Err(Error::Range(text.to_string()));
+    }
+
+    let mut set = Vec::new();
+    for token in tokens {
+        set.extend(parse_comparator(&token)?);
+    }
+    Ok(set)
+}
+
+fn parse_comparator(token: &str) -> Result<Vec<Comparator>> {
+    const PREFIXES: [&str; 8] = ["<=", ">=", "<", ">", "=", "~>", "~", "^"];
+    let prefix = PREFIXES.iter().find(|prefix| token.starts_with(*prefix)).copied().unwrap_or("");
+    let partial = Partial::parse(&token[prefix.len()..])?;
+    let Partial { major, minor, patch, .. } = partial;
+    let full = patch.is_some();
+
+    let set = match prefix {
+        "" | "=" if full => vec![Comparator::new(Op::Eq, partial.version)],
+        "" | "=" => partial.ceiling().map(|ceiling| vec![partial.floor(), ceiling]).unwrap_or_default(),
+        "~>" | "~" => {
+            let Some(major) = major else { return Ok(Vec::new()) };
+            let ceiling = match minor {
+                Some(minor) => Version::lowest(major, minor + 1, 0),
+                None => Version::lowest(major + 1, 0, 0),
+            };
+            vec![partial.floor(), Comparator::new(Op::Lt, ceiling)]
+        }
+        "^" => {
+            let Some(major) = major else { return Ok(Vec::new()) };
+            // The left-most non-zero part may not change
+            let ceiling = match (major, minor, patch) {
+                (0, Some(0), Some(patch)) => Version::lowest(0, 0, patch + 1),
+                (0, Some(minor), _) => Version::lowest(0, minor + 1, 0),
+                _ => Version::lowest(major + 1, 0, 0),
+            };
+            vec![partial.floor(), Comparator::new(Op::Lt, ceiling)]
+        }
+        ">" => match (major, minor, patch) {
+            (None, _, _) => vec![Comparator::none()],
+            (Some(major), None, _) => vec![Comparator::new(Op::Ge, Version::new(major + 1, 0, 0))],
+            (Some(major), Some(minor), None) => vec![Comparator::new(Op::Ge, Version::new(major, minor + 1, 0))],
+            _ => vec![Comparator::new(Op::Gt, partial.version)],
+        },
+        ">=" => match major {
+            None => Vec::new(),
+            Some(_) => vec![partial.floor()],
+        },
+        "<" => match major {
+            None => vec![Comparator::none()],
+            Some(_) if full => vec![Comparator::new(Op::Lt, partial.version)],
+            Some(major) => {
+                vec![Comparator::new(Op::Lt, Version::lowest(major, minor.unwrap_or(0), 0))]
+            }
+        },
+        "<=" => partial.ceiling().into_iter().collect(),
+        _ => unreachable!("every prefix is handled"),
+    };
+    Ok(set)
+}
+
+impl FromStr for Range {
+    type Err = Error;
+
+    /// Parses a range. Specifiers that don't name registry versions, such as
+    /// tags, git URLs or `file:` paths, are rejected.
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(Range::any());
+        }
+        let sets = text.split("||").map(|set| parse_set(set.trim())).collect::<Result<_>>()?;
+        Ok(Range { text: text.to_string(), sets })
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
@@ -0,0 +1,109 @@
+//! Where the resolver learns which versions of a package exist.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::range::Range;
+use crate::version::Version;
+
+/// The dependencies declared by a package, or by the root being installed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dependencies {
+    /// Packages that must be installed in a matching version.
+    pub required: BTreeMap<String, Range>,
+    /// Packages installed when a matching version can be found, and left
+    /// out otherwise.
+    pub optional: BTreeMap<String, Range>,
+    /// Packages that must not be installed in a matching version.
+    pub conflicts: BTreeMap<String, Range>,
+}
+
+impl Dependencies {
+    /// Reads the `dependencies`, `optionalDependencies` and `conflicts`
+    /// fields of a `package.json` or of a version in registry metadata. As in
+    /// npm, a package listed both as a dependency and an optional one is
+    /// optional.
+    pub fn from_manifest(manifest: &Value) -> Result<Self> {
+        let field = |name: &str| -> Result<BTreeMap<String, Range>> {
+            let Some(value) = manifest.get(name) else { return Ok(BTreeMap::new()) };
+            let Some(entries) = value.as_object() else {
+                return Err(Error::Metadata(format!("{name} is not an object")));
+            };
+            entries
+                .iter()
+                .map(|(package, range)| {
+                    let range = range
+                        .as_str()
+                        .ok_or_else(|| Error::Metadata(format!("{name}.{package} is not a string")))?;
+                    Ok((package.clone(), range.parse()?))
+                })
+                .collect()
+        };
+
+        let optional = field("optionalDependencies")?;
+        let mut required = field("dependencies")?;
+        required.retain(|package, _| !optional.contains_key(package));
+        Ok(Dependencies { required, optional, conflicts: field("conflicts")? })
+    }
+}
+
+/// A published version of a package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: Version,
+    pub dependencies: Dependencies,
+}
+
+/// A source of package metadata, such as the npm registry or a local mirror.
+pub trait Registry {
+    /// Lists the published versions of a package in any order, or none if
+    /// the registry doesn't know it.
+    fn releases(&self, package: &str) -> Result<Vec<Release>>;
+}
+
+/// A registry held in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryRegistry {
+    packages: BTreeMap<String, Vec<Release>>,
+}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, package: &str, release: Release) {
+        self.packages.entry(package.to_string()).or_default().push(release);
+    }
+
+    /// Adds every version from a package's registry metadata, as read by
+    /// [`releases`].
+    pub fn insert_metadata(&mut self, package: &str, metadata: &Value) -> Result<()> {
+        for release in releases(package, metadata)? {
+            self.insert(package, release);
+        }
+        Ok(())
+    }
+}
+
+/// Reads the versions in a package's registry metadata, the document served
+/// at `https://registry.npmjs.org/<package>`. Versions the resolver can't
+/// use, such as ones depending on git URLs or tags, are left out rather than
+/// failing the whole package.
+pub fn releases(package: &str, metadata: &Value) -> Result<Vec<Release>> {
+    let Some(versions) = metadata.get("versions").and_then(Value::as_object) else {
+        return Err(Error::Metadata(format!("{package} has no versions")));
+    };
+    let release = |version: &str, manifest: &Value| -> Result<Release> {
+        Ok(Release { version: version.parse()?, dependencies: Dependencies::from_manifest(manifest)? })
+    };
+    Ok(versions.iter().filter_map(|(version, manifest)| release(version, manifest).ok()).collect())
+}
+
+impl Registry for MemoryRegistry {
+    fn releases(&self, package: &str) -> Result<Vec<Release>> {
+        Ok(self.packages.get(package).cloned().unwrap_or_default())
+    }
+}
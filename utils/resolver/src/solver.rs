@@ -0,0 +1,259 @@
+//! The search for a set of versions that satisfies every requirement.
+//!
+//! Each package gets a single version. The search repeatedly picks the
+//! undecided package with the fewest remaining candidates, tries its
+//! candidates from the preferred version down, adds the chosen release's own
+//! requirements and backtracks when a package is left with no candidate.
+//! Packages are visited in name order whenever the heuristic ties, so the
+//! same inputs always give the same answer.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::error::{Conflict, Error, Result};
+use crate::range::Range;
+use crate::registry::{Dependencies, Registry, Release};
+use crate::version::Version;
+
+/// Combinations of versions tried before giving up.
+const DEFAULT_LIMIT: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Required,
+    Optional,
+    Conflict,
+}
+
+/// A constraint one package places on another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    /// The package and version placing it, or `None` for the root.
+    pub dependent: Option<(String, Version)>,
+    pub kind: Kind,
+    pub range: Range,
+}
+
+impl Requirement {
+    fn allows(&self, version: &Version) -> bool {
+        match self.kind {
+            Kind::Required | Kind::Optional => self.range.satisfies(version),
+            Kind::Conflict => !self.range.satisfies(version),
+        }
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dependent = match &self.dependent {
+            Some((package, version)) => format!("{package}@{version}"),
+            None => "the root".to_string(),
+        };
+        match self.kind {
+            Kind::Required => write!(f, "{} (required by {dependent})", self.range),
+            Kind::Optional => write!(f, "{} (optional for {dependent})", self.range),
+            Kind::Conflict => write!(f, "not {} (conflicts with {dependent})", self.range),
+        }
+    }
+}
+
+/// The versions chosen for an install.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Resolution {
+    pub packages: BTreeMap<String, Version>,
+    /// Optional dependencies left out because no version of them fits.
+    pub skipped: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct State {
+    /// `None` for optional dependencies that were left out.
+    chosen: BTreeMap<String, Option<Version>>,
+    requirements: BTreeMap<String, Vec<Requirement>>,
+}
+
+enum Failure {
+    /// The branch can't be completed. The conflict is `proven` when its
+    /// requirements rule out every version, rather than just the one chosen
+    /// on this branch, which makes it the better explanation to report.
+    Conflict { conflict: Conflict, proven: bool },
+    Fatal(Error),
+}
+
+impl From<Error> for Failure {
+    fn from(error: Error) -> Self {
+        Failure::Fatal(error)
+    }
+}
+
+type Search<T> = std::result::Result<T, Failure>;
+
+/// Chooses package versions from a registry.
+pub struct Resolver<'a, R: Registry + ?Sized> {
+    registry: &'a R,
+    preferred: BTreeMap<String, Version>,
+    limit: usize,
+    steps: usize,
+    /// Releases already fetched, newest first.
+    releases: BTreeMap<String, Rc<Vec<Release>>>,
+}
+
+impl<'a, R: Registry + ?Sized> Resolver<'a, R> {
+    pub fn new(registry: &'a R) -> Self {
+        Resolver { registry, preferred: BTreeMap::new(), limit: DEFAULT_LIMIT, steps: 0, releases: BTreeMap::new() }
+    }
+
+    /// Tries a version before any other whenever it fits, such as the one
+    /// already installed or recorded in a lockfile.
+    pub fn prefer(mut self, package: &str, version: Version) -> Self {
+        self.preferred.insert(package.to_string(), version);
+        self
+    }
+
+    /// Sets how many combinations of versions are tried before giving up
+    /// with [`Error::TooComplex`].
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Chooses a version for every package the root needs, directly or not.
+    pub fn resolve(&mut self, root: &Dependencies) -> Result<Resolution> {
+        self.steps = 0;
+        let mut state = State::default();
+        let outcome = self.add(&mut state, None, root).and_then(|()| self.search(state));
+        match outcome {
+            Ok(state) => {
+                let mut resolution = Resolution::default();
+                for (package, version) in state.chosen {
+                    match version {
+                        Some(version) => {
+                            resolution.packages.insert(package, version);
+                        }
+                        None => {
+                            resolution.skipped.insert(package);
+                        }
+                    }
+                }
+                Ok(resolution)
+            }
+            Err(Failure::Conflict { conflict, .. }) => Err(Error::Conflict(conflict)),
+            Err(Failure::Fatal(error)) => Err(error),
+        }
+    }
+
+    fn releases(&mut self, package: &str) -> Result<Rc<Vec<Release>>> {
+        if let Some(releases) = self.releases.get(package) {
+            return Ok(releases.clone());
+        }
+        let mut releases = self.registry.releases(package)?;
+        releases.sort_by(|a, b| b.version.cmp(&a.version));
+        releases.dedup_by(|a, b| a.version == b.version);
+        let releases = Rc::new(releases);
+        self.releases.insert(package.to_string(), releases.clone());
+        Ok(releases)
+    }
+
+    /// The choices left for a package, in the order to try them: the
+    /// preferred version, the rest newest first, then leaving it out if
+    /// nothing requires it.
+    fn candidates(&mut self, package: &str, requirements: &[Requirement]) -> Result<Vec<Option<Release>>> {
+        let releases = self.releases(package)?;
+        let mut candidates: Vec<Option<Release>> = releases
+            .iter()
+            .filter(|release| requirements.iter().all(|requirement| requirement.allows(&release.version)))
+            .cloned()
+            .map(Some)
+            .collect();
+        if let Some(preferred) = self.preferred.get(package) {
+            if let Some(i) = candidates.iter().position(|c| c.as_ref().is_some_and(|r| r.version == *preferred)) {
+                let candidate = candidates.remove(i);
+                candidates.insert(0, candidate);
+            }
+        }
+        if requirements.iter().all(|requirement| requirement.kind != Kind::Required) {
+            candidates.push(None);
+        }
+        Ok(candidates)
+    }
+
+    /// Records a package's requirements, failing if one rules out a version
+    /// already chosen.
+    fn add(&mut self, state: &mut State, dependent: Option<(String, Version)>, dependencies: &Dependencies) -> Search<()> {
+        let kinds = [
+            (Kind::Required, &dependencies.required),
+            (Kind::Optional, &dependencies.optional),
+            (Kind::Conflict, &dependencies.conflicts),
+        ];
+        for (kind, entries) in kinds {
+            for (package, range) in entries {
+                let requirement = Requirement { dependent: dependent.clone(), kind, range: range.clone() };
+                let violated = match state.chosen.get(package) {
+                    Some(Some(version)) => !requirement.allows(version),
+                    Some(None) => kind == Kind::Required,
+                    None => false,
+                };
+                let requirements = state.requirements.entry(package.clone()).or_default();
+                requirements.push(requirement);
+                if violated {
+                    let requirements = requirements.clone();
+                    let proven = self.candidates(package, &requirements)?.is_empty();
+                    return Err(Failure::Conflict { conflict: Conflict { package: package.clone(), requirements }, proven });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn search(&mut self, state: State) -> Search<State> {
+        self.steps += 1;
+        if self.steps > self.limit {
+            return Err(Failure::Fatal(Error::TooComplex(self.limit)));
+        }
+
+        let mut next: Option<(&String, Vec<Option<Release>>)> = None;
+        for (package, requirements) in &state.requirements {
+            if state.chosen.contains_key(package) || requirements.iter().all(|r| r.kind == Kind::Conflict) {
+                continue;
+            }
+            let candidates = self.candidates(package, requirements)?;
+            if next.as_ref().is_none_or(|(_, best)| candidates.len() < best.len()) {
+                next = Some((package, candidates));
+            }
+        }
+        let Some((package, candidates)) = next else { return Ok(state) };
+
+        let package = package.clone();
+        if candidates.is_empty() {
+            let conflict = Conflict { package: package.clone(), requirements: state.requirements[&package].clone() };
+            return Err(Failure::Conflict { conflict, proven: true });
+        }
+
+        // Report the first proven conflict found, or failing that the first
+        // conflict at all
+        let mut failure: Option<(Conflict, bool)> = None;
+        for candidate in candidates {
+            let mut branch = state.clone();
+            branch.chosen.insert(package.clone(), candidate.as_ref().map(|release| release.version.clone()));
+            let outcome = match &candidate {
+                Some(release) => {
+                    let dependent = Some((package.clone(), release.version.clone()));
+                    self.add(&mut branch, dependent, &release.dependencies)
+                }
+                None => Ok(()),
+            };
+            match outcome.and_then(|()| self.search(branch)) {
+                Ok(state) => return Ok(state),
+                Err(Failure::Conflict { conflict, proven }) => {
+                    if failure.as_ref().is_none_or(|(_, found)| proven && !found) {
+                        failure = Some((conflict, proven));
+                    }
+                }
+                Err(fatal) => return Err(fatal),
+            }
+        }
+        let (conflict, proven) = failure.expect("a failed candidate leaves a conflict");
+        Err(Failure::Conflict { conflict, proven })
+    }
+}
@@ -0,0 +1,152 @@
+//! Semantic versions, ordered the way npm orders them.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// One dot-separated part of a pre-release tag. Numeric parts sort before
+/// alphanumeric ones and compare by value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Identifier {
+    Numeric(u64),
+    Alphanumeric(String),
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Numeric(n) => write!(f, "{n}"),
+            Identifier::Alphanumeric(s) => f.write_str(s),
+        }
+    }
+}
+
+/// A `major.minor.patch[-pre][+build]` version. Build metadata is kept for
+/// display but ignored when comparing.
+#[derive(Debug, Clone, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+    pub build: String,
+}
+
+impl Version {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Version { major, minor, patch, pre: Vec::new(), build: String::new() }
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// The lowest pre-release of this version (`1.2.3-0`), used as an
+    /// exclusive upper bound so that `<2.0.0-0` also rules out `2.0.0-beta`.
+    pub(crate) fn lowest(major: u64, minor: u64, patch: u64) -> Self {
+        Version { pre: vec![Identifier::Numeric(0)], ..Version::new(major, minor, patch) }
+    }
+
+    pub(crate) fn same_release(&self, other: &Version) -> bool {
+        (self.major, self.minor, self.patch) == (other.major, other.minor, other.patch)
+    }
+}
+
+pub(crate) fn parse_number(part: &str, text: &str) -> Result<u64> {
+    if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) || (part.len() > 1 && part.starts_with('0')) {
+        return Err(Error::Version(text.to_string()));
+    }
+    part.parse().map_err(|_| Error::Version(text.to_string()))
+}
+
+pub(crate) fn parse_pre(pre: &str, text: &str) -> Result<Vec<Identifier>> {
+    pre.split('.')
+        .map(|part| {
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+                Err(Error::Version(text.to_string()))
+            } else if part.bytes().all(|b| b.is_ascii_digit()) {
+                parse_number(part, text).map(Identifier::Numeric)
+            } else {
+                Ok(Identifier::Alphanumeric(part.to_string()))
+            }
+        })
+        .collect()
+}
+
+/// Splits `1.2.3-pre+build` into the release, pre-release and build parts.
+pub(crate) fn split(text: &str) -> (&str, Option<&str>, &str) {
+    let (rest, build) = text.split_once('+').unwrap_or((text, ""));
+    match rest.split_once('-') {
+        Some((release, pre)) => (release, Some(pre), build),
+        None => (rest, None, build),
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    /// Parses a version, tolerating the leading `v` or `=` npm accepts.
+    fn from_str(text: &str) -> Result<Self> {
+        let trimmed = text.trim().trim_start_matches('=').trim_start_matches('v');
+        let (release, pre, build) = split(trimmed);
+        let parts: Vec<&str> = release.split('.').collect();
+        let [major, minor, patch] = parts[..] else {
+            return Err(Error::Version(text.to_string()));
+        };
+        Ok(Version {
+            major: parse_number(major, text)?,
+            minor: parse_number(minor, text)?,
+            patch: parse_number(patch, text)?,
+            pre: pre.map(|pre| parse_pre(pre, text)).transpose()?.unwrap_or_default(),
+            build: build.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        for (i, part) in self.pre.iter().enumerate() {
+            f.write_str(if i == 0 { "-" } else { "." })?;
+            write!(f, "{part}")?;
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build)?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl std::hash::Hash for Version {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (self.major, self.minor, self.patch, &self.pre).hash(state);
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch)).then_with(|| {
+            // A pre-release sorts before the release it leads up to
+            match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            }
+        })
+    }
+}
@@ -0,0 +1,98 @@
+//! Resolves every case in `tests/golden/*.json` and compares the outcome
+//! with the `.txt` file next to it. Run with `UPDATE_GOLDEN=1` to rewrite the
+//! expected outcomes after an intended change, then review the diff.
+//!
+//! A case holds the root's dependencies in `package.json` form, the
+//! registry metadata of every package keyed by name, and optionally the
+//! versions to prefer:
+//!
+//! ```json
+//! {
+//!   "root": { "dependencies": { "a": "^1.0.0" } },
+//!   "prefer": { "a": "1.0.0" },
+//!   "registry": { "a": { "versions": { "1.0.0": {}, "1.1.0": {} } } }
+//! }
+//! ```
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use ecmaos_resolver::{Dependencies, MemoryRegistry, Resolver};
+use serde_json::{Map, Value};
+
+fn registry(packages: &Map<String, Value>, reversed: bool) -> MemoryRegistry {
+    let mut registry = MemoryRegistry::new();
+    let mut packages: Vec<_> = packages.iter().collect();
+    if reversed {
+        packages.reverse();
+    }
+    for (package, metadata) in packages {
+        let mut metadata = metadata.clone();
+        if reversed {
+            let versions = metadata["versions"].as_object().expect("versions is an object");
+            let versions: Map<String, Value> = versions.iter().rev().map(|(k, v)| (k.clone(), v.clone())).collect();
+            metadata["versions"] = Value::Object(versions);
+        }
+        registry.insert_metadata(package, &metadata).unwrap_or_else(|e| panic!("{package}: {e}"));
+    }
+    registry
+}
+
+fn resolve(case: &Value, reversed: bool) -> String {
+    let registry = registry(case["registry"].as_object().expect("registry is an object"), reversed);
+    let root = Dependencies::from_manifest(&case["root"]).expect("root is a valid manifest");
+    let mut resolver = Resolver::new(&registry);
+    for (package, version) in case.get("prefer").and_then(Value::as_object).into_iter().flatten() {
+        let version = version.as_str().expect("preferred versions are strings").parse().expect("valid version");
+        resolver = resolver.prefer(package, version);
+    }
+
+    let mut out = String::new();
+    match resolver.resolve(&root) {
+        Ok(resolution) => {
+            for (package, version) in &resolution.packages {
+                writeln!(out, "{package}@{version}").unwrap();
+            }
+            for package in &resolution.skipped {
+                writeln!(out, "skipped {package}").unwrap();
+            }
+        }
+        Err(e) => writeln!(out, "error: {e}").unwrap(),
+    }
+    out
+}
+
+#[test]
+fn golden() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut cases: Vec<_> = fs::read_dir(&dir)
+        .expect("golden directory exists")
+        .map(|entry| entry.expect("readable entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty(), "no cases in {}", dir.display());
+
+    let mut mismatched = Vec::new();
+    for path in cases {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let case: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("{name}: {e}"));
+        let actual = resolve(&case, false);
+        assert_eq!(actual, resolve(&case, true), "{name}: outcome depends on registry order");
+
+        let expected_path = path.with_extension("txt");
+        if update {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        if actual != expected {
+            eprintln!("--- {name}: expected\n{expected}--- {name}: actual\n{actual}");
+            mismatched.push(name);
+        }
+    }
+    assert!(mismatched.is_empty(), "outcomes differ for {mismatched:?}; rerun with UPDATE_GOLDEN=1 if intended");
+}
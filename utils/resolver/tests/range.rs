@@ -0,0 +1,85 @@
+use ecmaos_resolver::{Range, Version};
+
+/// Ranges with versions that satisfy them and versions that don't, checked
+/// against the behaviour of npm's `semver` package.
+const CASES: &[(&str, &[&str], &[&str])] = &[
+    ("", &["0.0.0", "9.9.9"], &["1.0.0-beta"]),
+    ("*", &["1.2.3"], &["1.2.3-beta"]),
+    ("1.2.3", &["1.2.3", "v1.2.3", "1.2.3+build"], &["1.2.4"]),
+    ("=1.2.3", &["1.2.3"], &["1.2.2"]),
+    ("1.2.x", &["1.2.0", "1.2.99"], &["1.3.0", "1.1.9"]),
+    ("1.2", &["1.2.7"], &["1.3.0"]),
+    ("1", &["1.0.0", "1.99.0"], &["2.0.0", "2.0.0-0"]),
+    ("~1.2.3", &["1.2.3", "1.2.9"], &["1.3.0", "1.2.2"]),
+    ("~1.2", &["1.2.0", "1.2.9"], &["1.3.0"]),
+    ("~1", &["1.9.0"], &["2.0.0"]),
+    ("~1.2.3-beta.2", &["1.2.3-beta.2", "1.2.3-beta.10", "1.2.3"], &["1.2.3-beta.1", "1.2.4-beta"]),
+    ("^1.2.3", &["1.2.3", "1.9.9"], &["2.0.0", "1.2.2", "1.5.0-beta"]),
+    ("^0.2.3", &["0.2.3", "0.2.9"], &["0.3.0"]),
+    ("^0.0.3", &["0.0.3"], &["0.0.4"]),
+    ("^0.0", &["0.0.9"], &["0.1.0"]),
+    ("^1.x", &["1.0.0", "1.9.0"], &["2.0.0"]),
+    ("^0.x", &["0.9.0"], &["1.0.0"]),
+    ("^1.2.3-beta.1", &["1.2.3-beta.1", "1.2.3-rc", "1.8.0"], &["1.2.4-beta"]),
+    (">1.2.3", &["1.2.4"], &["1.2.3"]),
+    (">1.2", &["1.3.0"], &["1.2.9"]),
+    (">1", &["2.0.0"], &["1.9.9"]),
+    (">=1.2", &["1.2.0"], &["1.1.9"]),
+    ("<1.2.3", &["1.2.2"], &["1.2.3", "1.2.3-beta"]),
+    ("<1.2", &["1.1.9"], &["1.2.0", "1.2.0-beta"]),
+    ("<=1.2", &["1.2.9"], &["1.3.0"]),
+    ("<=1.2.3", &["1.2.3"], &["1.2.4"]),
+    (">*", &[], &["0.0.0", "1.0.0"]),
+    (">= 1.2.3 < 2", &["1.2.3", "1.9.0"], &["2.0.0", "1.2.2"]),
+    (">=1.0.0 <2.0.0 || >=3.0.0", &["1.5.0", "3.0.0"], &["2.5.0"]),
+    ("1.x || 3.x", &["1.0.0", "3.1.0"], &["2.0.0"]),
+    ("1.2 - 2.3.4", &["1.2.0", "2.3.4"], &["2.3.5", "1.1.9"]),
+    ("1.2.3 - 2", &["2.9.9"], &["3.0.0"]),
+    ("1.2.3 - 2.3", &["2.3.9"], &["2.4.0"]),
+    (">=1.5.0-alpha <2", &["1.5.0-alpha", "1.5.0-beta", "1.6.0"], &["1.6.0-beta"]),
+];
+
+#[test]
+fn satisfies() {
+    for (range, matching, other) in CASES {
+        let parsed: Range = range.parse().unwrap_or_else(|e| panic!("{range:?}: {e}"));
+        for version in *matching {
+            let version: Version = version.parse().unwrap();
+            assert!(parsed.satisfies(&version), "{range:?} should allow {version}");
+        }
+        for version in *other {
+            let version: Version = version.parse().unwrap();
+            assert!(!parsed.satisfies(&version), "{range:?} should not allow {version}");
+        }
+    }
+}
+
+#[test]
+fn rejects_non_registry_specifiers() {
+    for range in ["latest", "file:../a", "git+https://example.com/a.git", "1.2.3.4", ">=", "^1.x-beta"] {
+        assert!(range.parse::<Range>().is_err(), "{range:?} should not parse");
+    }
+}
+
+#[test]
+fn orders_versions() {
+    let ordered = [
+        "1.0.0-0",
+        "1.0.0-alpha",
+        "1.0.0-alpha.1",
+        "1.0.0-alpha.beta",
+        "1.0.0-beta",
+        "1.0.0-beta.2",
+        "1.0.0-beta.11",
+        "1.0.0-rc.1",
+        "1.0.0",
+        "1.0.1",
+        "1.10.0",
+        "2.0.0",
+    ];
+    let versions: Vec<Version> = ordered.iter().map(|v| v.parse().unwrap()).collect();
+    for pair in versions.windows(2) {
+        assert!(pair[0] < pair[1], "{} should sort before {}", pair[0], pair[1]);
+    }
+    assert_eq!("1.0.0+a".parse::<Version>().unwrap(), "1.0.0+b".parse::<Version>().unwrap());
+}
@@ -0,0 +1,157 @@
+//! `--format` strings: text with `%` sequences standing for fields of a
+//! [`Stat`], as stat(1) takes them.
+//!
+//! | | |
+//! |---|---|
+//! | `%n` `%N` | name; quoted, with where a link points |
+//! | `%s` `%F` | size in bytes; file type |
+//! | `%a` `%A` `%f` | permissions in octal; symbolically; raw mode in hex |
+//! | `%u` `%g` | owner's user and group ID |
+//! | `%d` `%D` `%i` `%h` | device in decimal and hex, inode, hard links |
+//! | `%b` `%B` `%o` | blocks allocated, the size of those blocks, I/O block size |
+//! | `%x` `%y` `%z` `%w` | last access, modification, status change and birth |
+//! | `%X` `%Y` `%Z` `%W` | those as seconds since the epoch |
+//! | `%%` | a `%` |
+//!
+//! A width may come between the `%` and the letter, with `-` to align left
+//! and `0` to pad with zeros (`%-10s`, `%04a`). A field the filesystem or
+//! platform doesn't provide is shown as `-`, and an unknown sequence as `?`.
+//! Times are in UTC, since WASI has no time zones.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ecmaos_date::civil_from_days;
+
+use crate::metadata::Stat;
+
+/// The layout used without `--format`.
+pub const DEFAULT: &str = "  File: %N\n  Size: %-10s\tBlocks: %-10b IO Block: %-6o %F\n\
+                           Device: %Dh/%dd\tInode: %-11i Links: %h\n\
+                           Access: (%04a/%A)  Uid: %u   Gid: %g\n\
+                           Access: %x\nModify: %y\nChange: %z\n Birth: %w";
+
+/// `format` with the fields of `stat` filled in.
+pub fn render(format: &str, stat: &Stat) -> String {
+    let mut out = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let (mut left, mut zero, mut width) = (false, false, 0);
+        while let Some(&flag @ ('-' | '0')) = chars.peek() {
+            left |= flag == '-';
+            zero |= flag == '0';
+            chars.next();
+        }
+        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+            width = width * 10 + digit as usize;
+            chars.next();
+        }
+        let Some(field) = chars.next() else {
+            out.push('%');
+            break;
+        };
+        let value = match field {
+            '%' => "%".to_string(),
+            field => value(field, stat).unwrap_or_else(|| "-".to_string()),
+        };
+        match (left, zero) {
+            (true, _) => out += &format!("{value:<width$}"),
+            (false, true) => out += &format!("{value:0>width$}"),
+            (false, false) => out += &format!("{value:>width$}"),
+        }
+    }
+    out
+}
+
+/// The text of the field `%field`, if the file has it.
+fn value(field: char, stat: &Stat) -> Option<String> {
+    let unix = stat.unix.as_ref();
+    let value = match field {
+        'n' => stat.name.clone(),
+        'N' => match &stat.target {
+            Some(target) => format!("'{}' -> '{}'", stat.name, target.display()),
+            None => format!("'{}'", stat.name),
+        },
+        's' => stat.size.to_string(),
+        'F' => stat.kind.to_string(),
+        'a' => format!("{:o}", unix?.mode & 0o7777),
+        'A' => symbolic(unix?.mode),
+        'f' => format!("{:x}", unix?.mode),
+        'u' => unix?.uid.to_string(),
+        'g' => unix?.gid.to_string(),
+        'd' => unix?.device.to_string(),
+        'D' => format!("{:x}", unix?.device),
+        'i' => unix?.inode.to_string(),
+        'h' => unix?.links.to_string(),
+        'b' => unix?.blocks.to_string(),
+        'B' => unix.map(|_| "512")?.to_string(),
+        'o' => unix?.block_size.to_string(),
+        'x' => date(stat.accessed?),
+        'y' => date(stat.modified?),
+        'z' => date(stat.changed?),
+        'w' => date(stat.born?),
+        'X' => seconds(stat.accessed?),
+        'Y' => seconds(stat.modified?),
+        'Z' => seconds(stat.changed?),
+        'W' => seconds(stat.born?),
+        _ => "?".to_string(),
+    };
+    Some(value)
+}
+
+/// A mode as `ls -l` shows it: `-rw-r--r--`.
+fn symbolic(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o060000 => 'b',
+        0o020000 => 'c',
+        0o010000 => 'p',
+        0o140000 => 's',
+        _ => '-',
+    };
+    let mut out = String::from(kind);
+    // Owner, group and others, each with the set-ID or sticky bit that
+    // shows in its execute column
+    for (shift, special, letter) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = mode >> shift;
+        out.push(if bits & 4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 2 != 0 { 'w' } else { '-' });
+        out.push(match (bits & 1 != 0, mode & special != 0) {
+            (true, true) => letter,
+            (false, true) => letter.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+/// Seconds since the epoch, negative before it.
+fn seconds(time: SystemTime) -> String {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs().to_string(),
+        Err(e) => format!("-{}", e.duration().as_secs()),
+    }
+}
+
+/// `time` in UTC as `2025-01-01 12:00:00.000000000 +0000`.
+fn date(time: SystemTime) -> String {
+    let Ok(since) = time.duration_since(UNIX_EPOCH) else {
+        return seconds(time);
+    };
+    let seconds = since.as_secs();
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}.{:09} +0000",
+        rest / 3600,
+        rest / 60 % 60,
+        rest % 60,
+        since.subsec_nanos()
+    )
+}
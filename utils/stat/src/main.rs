@@ -0,0 +1,69 @@
+//! Shows everything the filesystem says about files.
+//!
+//! ```text
+//! stat [-L] [-c FORMAT] FILE...
+//! ```
+//!
+//! Without a format each file gets stat(1)'s layout (see
+//! [`format::DEFAULT`]): name, size, type, device, inode, links, mode,
+//! owner and the four times. `-c` (`--format`) prints the given format
+//! instead, one line per file (see [`format`] for what it can show). `-L`
+//! describes what links point to rather than the links. Fields the platform
+//! can't get at show as `-` (see [`metadata`]), so running it over the same
+//! files on different filesystem backends shows what each keeps. The exit
+//! status is 1 if any file couldn't be looked up.
+
+mod format;
+mod metadata;
+
+use std::env;
+
+use metadata::Stat;
+
+const USAGE: &str = "usage: stat [-L] [-c FORMAT] FILE...";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut follow = false;
+    let mut format = None;
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-L" | "--dereference" => follow = true,
+            "-c" | "--format" => match args.next() {
+                Some(value) => format = Some(value),
+                None => usage_error(&format!("{} needs a value", arg)),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with("--format=") => format = Some(arg["--format=".len()..].to_string()),
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ => files.push(arg),
+        }
+    }
+    if files.is_empty() {
+        usage_error("no files given");
+    }
+
+    let format = format.as_deref().unwrap_or(format::DEFAULT);
+    let mut failed = false;
+    for name in &files {
+        match Stat::of(name, follow) {
+            Ok(stat) => println!("{}", format::render(format, &stat)),
+            Err(e) => {
+                eprintln!("error: {}: {}", name, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,126 @@
+//! What the filesystem says about a file.
+//!
+//! The size, type and times come from `std::fs::Metadata` and are there on
+//! every platform the kernel's filesystems might be reached from, though a
+//! backend may not keep every time (birth most often). The device, inode,
+//! links, mode, owner and block counts only come through on Unix hosts:
+//! WASI has them, but Rust's `std::os::wasi::fs` is still unstable, so on
+//! `wasm32-wasip1` they're left out, as is the change time.
+
+use std::fs::{self, FileType, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub struct Stat {
+    pub name: String,
+    /// Where a symbolic link points.
+    pub target: Option<PathBuf>,
+    pub size: u64,
+    pub kind: &'static str,
+    pub accessed: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub changed: Option<SystemTime>,
+    pub born: Option<SystemTime>,
+    pub unix: Option<Unix>,
+}
+
+/// The fields only Unix hosts expose.
+pub struct Unix {
+    pub device: u64,
+    pub inode: u64,
+    pub links: u64,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /// The size of block I/O is best done in.
+    pub block_size: u64,
+    /// 512-byte blocks allocated.
+    pub blocks: u64,
+}
+
+impl Stat {
+    /// Looks up `name`, or where it points if it's a link and `follow` is
+    /// set.
+    pub fn of(name: &str, follow: bool) -> io::Result<Stat> {
+        let path = Path::new(name);
+        let metadata = if follow { fs::metadata(path)? } else { fs::symlink_metadata(path)? };
+        let target = match metadata.file_type().is_symlink() {
+            true => Some(fs::read_link(path)?),
+            false => None,
+        };
+        Ok(Stat {
+            name: name.to_string(),
+            target,
+            size: metadata.len(),
+            kind: kind(metadata.file_type(), metadata.len()),
+            accessed: metadata.accessed().ok(),
+            modified: metadata.modified().ok(),
+            changed: changed(&metadata),
+            born: metadata.created().ok(),
+            unix: unix(&metadata),
+        })
+    }
+}
+
+fn kind(file_type: FileType, size: u64) -> &'static str {
+    if file_type.is_symlink() {
+        return "symbolic link";
+    }
+    if file_type.is_dir() {
+        return "directory";
+    }
+    if file_type.is_file() {
+        return if size == 0 { "regular empty file" } else { "regular file" };
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_block_device() {
+            return "block special file";
+        }
+        if file_type.is_char_device() {
+            return "character special file";
+        }
+        if file_type.is_fifo() {
+            return "fifo";
+        }
+        if file_type.is_socket() {
+            return "socket";
+        }
+    }
+    "unknown file type"
+}
+
+#[cfg(unix)]
+fn changed(metadata: &Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+    use std::time::{Duration, UNIX_EPOCH};
+    let (seconds, nanos) = (u64::try_from(metadata.ctime()).ok()?, u32::try_from(metadata.ctime_nsec()).ok()?);
+    UNIX_EPOCH.checked_add(Duration::new(seconds, nanos))
+}
+
+#[cfg(not(unix))]
+fn changed(_: &Metadata) -> Option<SystemTime> {
+    None
+}
+
+#[cfg(unix)]
+fn unix(metadata: &Metadata) -> Option<Unix> {
+    use std::os::unix::fs::MetadataExt;
+    Some(Unix {
+        device: metadata.dev(),
+        inode: metadata.ino(),
+        links: metadata.nlink(),
+        mode: metadata.mode(),
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        block_size: metadata.blksize(),
+        blocks: metadata.blocks(),
+    })
+}
+
+#[cfg(not(unix))]
+fn unix(_: &Metadata) -> Option<Unix> {
+    None
+}
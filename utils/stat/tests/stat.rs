@@ -0,0 +1,68 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// A scratch directory with a 5-byte `file`, a `link` to it and a `dir`.
+fn scratch(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ecmaos-stat-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("dir")).unwrap();
+    fs::write(root.join("file"), "hello").unwrap();
+    fs::set_permissions(root.join("file"), fs::Permissions::from_mode(0o640)).unwrap();
+    fs::set_permissions(root.join("dir"), fs::Permissions::from_mode(0o1755)).unwrap();
+    std::os::unix::fs::symlink("file", root.join("link")).unwrap();
+    root
+}
+
+fn run(root: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_stat")).args(args).current_dir(root).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn prints_the_default_layout() {
+    let root = scratch("default");
+    let out = stdout(&run(&root, &["file", "link"]));
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 16, "{}", out);
+    assert_eq!(lines[0], "  File: 'file'");
+    assert!(lines[1].starts_with("  Size: 5         \tBlocks: ") && lines[1].ends_with(" regular file"), "{}", out);
+    assert!(lines[3].starts_with("Access: (0640/-rw-r-----)  Uid: "), "{}", out);
+    assert!(lines[5].starts_with("Modify: ") && lines[5].ends_with(" +0000"), "{}", out);
+    assert_eq!(lines[8], "  File: 'link' -> 'file'");
+    assert!(lines[9].ends_with(" symbolic link"), "{}", out);
+}
+
+#[test]
+fn fills_in_format_strings() {
+    let root = scratch("format");
+    let format = "%n|%s|%-4s|%04s|%a|%A|%f|%F|%N|%B|%q|%%";
+    assert_eq!(
+        stdout(&run(&root, &["-c", format, "file"])),
+        "file|5|5   |0005|640|-rw-r-----|81a0|regular file|'file'|512|?|%\n"
+    );
+    assert_eq!(stdout(&run(&root, &["-c", "%a %A %F", "dir"])), "1755 drwxr-xr-t directory\n");
+    assert_eq!(stdout(&run(&root, &["--format=%F %N", "link"])), "symbolic link 'link' -> 'file'\n");
+    assert_eq!(stdout(&run(&root, &["-L", "--format", "%F %N", "link"])), "regular file 'link'\n");
+
+    // Times as seconds and as dates
+    let times = stdout(&run(&root, &["-c", "%Y %y", "file"]));
+    let (seconds, date) = times.trim_end().split_once(' ').unwrap();
+    assert!(seconds.parse::<u64>().unwrap() > 1_700_000_000);
+    assert!(date.starts_with("20") && date.len() == "2025-01-01 12:00:00.000000000 +0000".len(), "{}", date);
+}
+
+#[test]
+fn reports_files_it_cannot_find() {
+    let root = scratch("missing");
+    let output = run(&root, &["-c", "%n", "missing", "file"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "file\n");
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: missing: "));
+    assert_eq!(run(&root, &[]).status.code(), Some(2));
+}
@@ -0,0 +1,83 @@
+//! Lists what's in the trash, or puts files back where they came from.
+//!
+//! ```text
+//! restore [--to PATH] [NAME|PATH...]
+//! ```
+//!
+//! With no arguments every entry is listed, oldest first, with when it was
+//! trashed, its name in the trash and where it came from. Otherwise each
+//! argument is the path a file was trashed from, restoring the one trashed
+//! most recently, or else the name of an entry. A file is never
+//! restored over an existing one; `--to` puts a single entry somewhere else
+//! instead. The exit status is 1 if anything couldn't be restored.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use ecmaos_trash::info::format_date;
+use ecmaos_trash::{Result, Trash};
+
+const USAGE: &str = "usage: restore [--to PATH] [NAME|PATH...]";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut to = None;
+    let mut names = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => match args.next() {
+                Some(path) => to = Some(PathBuf::from(path)),
+                None => usage_error("--to needs a value"),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ => names.push(arg),
+        }
+    }
+    if to.is_some() && names.len() != 1 {
+        usage_error("--to needs exactly one entry to restore");
+    }
+
+    let result = Trash::home().and_then(|trash| {
+        if names.is_empty() {
+            list(&trash).map(|()| true)
+        } else {
+            Ok(restore(&trash, &names, to.as_deref()))
+        }
+    });
+    match result {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn list(trash: &Trash) -> Result<()> {
+    for entry in trash.entries()? {
+        println!("{}\t{}\t{}", format_date(entry.info.deleted), entry.name, entry.info.path.display());
+    }
+    Ok(())
+}
+
+/// Restores each entry named, returning whether they all were.
+fn restore(trash: &Trash, names: &[String], to: Option<&Path>) -> bool {
+    let mut restored = true;
+    for name in names {
+        if let Err(e) = trash.find(name).and_then(|entry| trash.restore(&entry, to)) {
+            eprintln!("error: {}", e);
+            restored = false;
+        }
+    }
+    restored
+}
@@ -0,0 +1,67 @@
+//! Deletes what's in the trash for good.
+//!
+//! ```text
+//! trash-empty [-v] [DAYS]
+//! ```
+//!
+//! With `DAYS`, only entries trashed more than that many days ago are
+//! deleted. Without, the trash is emptied, including anything a trash or
+//! restore that was interrupted left behind. `-v` lists what's deleted.
+
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ecmaos_trash::{Result, Trash};
+
+const USAGE: &str = "usage: trash-empty [-v] [DAYS]";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut verbose = false;
+    let mut days = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-v" | "--verbose" => verbose = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ if days.is_none() => match arg.parse::<u64>() {
+                Ok(n) => days = Some(n),
+                Err(_) => usage_error("DAYS needs to be a number"),
+            },
+            _ => usage_error(&format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    if let Err(e) = Trash::home().and_then(|trash| empty(&trash, days, verbose)) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn empty(trash: &Trash, days: Option<u64>, verbose: bool) -> Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+    let cutoff = days.map(|days| now.saturating_sub(days.saturating_mul(86_400)));
+    for entry in trash.entries()? {
+        if cutoff.is_none_or(|cutoff| entry.info.deleted < cutoff) {
+            trash.remove(&entry)?;
+            if verbose {
+                println!("{}", entry.info.path.display());
+            }
+        }
+    }
+    if days.is_none() {
+        for orphan in trash.remove_orphans()? {
+            if verbose {
+                println!("{}", orphan.display());
+            }
+        }
+    }
+    Ok(())
+}
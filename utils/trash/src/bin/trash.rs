@@ -0,0 +1,65 @@
+//! Moves files and directories to the trash instead of deleting them.
+//!
+//! ```text
+//! trash [-f] [-v] FILE...
+//! ```
+//!
+//! `-f` ignores files that don't exist and `-v` shows the name each file
+//! was given in the trash. The exit status is 1 if any file couldn't be
+//! trashed; the others still are.
+
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+use ecmaos_trash::{Error, Trash};
+
+const USAGE: &str = "usage: trash [-f] [-v] FILE...";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut force = false;
+    let mut verbose = false;
+    let mut files = Vec::new();
+    let mut options = true;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--" if options => options = false,
+            "-f" | "--force" if options => force = true,
+            "-v" | "--verbose" if options => verbose = true,
+            "-h" | "--help" if options => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if options && arg.starts_with('-') && arg != "-" => usage_error(&format!("unknown option: {}", arg)),
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if files.is_empty() {
+        usage_error("no files given");
+    }
+
+    let trash = Trash::home().unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    });
+    let mut failed = false;
+    for file in &files {
+        match trash.put(file) {
+            Ok(entry) if verbose => println!("{} -> {}", entry.info.path.display(), entry.name),
+            Ok(_) => {}
+            Err(Error::Io { source, .. }) if force && source.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                eprintln!("error: {}", e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{}: {source}", path.display())]
+    Io { path: PathBuf, source: std::io::Error },
+    #[error("{}: {reason}", path.display())]
+    Refused { path: PathBuf, reason: &'static str },
+    #[error("{}: already exists", .0.display())]
+    Exists(PathBuf),
+    #[error("{0}: not in the trash")]
+    NotInTrash(String),
+    #[error("neither XDG_DATA_HOME nor HOME is set, so there's no trash to use")]
+    NoHome,
+}
+
+impl Error {
+    /// Wraps an I/O error with the path it happened on.
+    pub(crate) fn io(path: impl Into<PathBuf>) -> impl FnOnce(std::io::Error) -> Error {
+        let path = path.into();
+        move |source| Error::Io { path, source }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
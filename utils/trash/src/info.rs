@@ -0,0 +1,111 @@
+//! The `.trashinfo` files recording where each trashed file came from.
+//!
+//! ```text
+//! [Trash Info]
+//! Path=/home/user/My%20Notes.txt
+//! DeletionDate=2025-01-01T12:00:00
+//! ```
+//!
+//! The path is absolute, with every byte outside the URL-safe characters
+//! and `/` percent-encoded. The specification asks for the date in local
+//! time, but WASI has no time zones, so it's written in UTC; dates written
+//! by other tools are read as they are.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use ecmaos_date::{civil_from_days, days_from_civil};
+
+/// What's recorded about a trashed file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrashInfo {
+    /// Where the file was before it was trashed.
+    pub path: PathBuf,
+    /// When it was trashed, in seconds since the Unix epoch.
+    pub deleted: u64,
+}
+
+impl TrashInfo {
+    /// Reads an info file's contents, or `None` if they aren't one.
+    pub fn parse(text: &str) -> Option<TrashInfo> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'));
+        if lines.next()? != "[Trash Info]" {
+            return None;
+        }
+        let (mut path, mut deleted) = (None, None);
+        for line in lines {
+            // Another group may follow, which isn't ours to read
+            if line.starts_with('[') {
+                break;
+            }
+            match line.split_once('=') {
+                Some(("Path", value)) => path = Some(decode(value)?),
+                Some(("DeletionDate", value)) => deleted = Some(parse_date(value)?),
+                _ => {}
+            }
+        }
+        let path = PathBuf::from(path?);
+        path.is_absolute().then_some(TrashInfo { path, deleted: deleted? })
+    }
+}
+
+impl fmt::Display for TrashInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "[Trash Info]")?;
+        writeln!(f, "Path={}", encode(&self.path))?;
+        writeln!(f, "DeletionDate={}", format_date(self.deleted))
+    }
+}
+
+fn encode(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path.to_string_lossy().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+fn decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::new();
+    let mut rest = text.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(after.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &after[2..];
+        } else {
+            bytes.push(byte);
+            rest = after;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// `seconds` since the epoch as `YYYY-MM-DDTHH:MM:SS`.
+pub fn format_date(seconds: u64) -> String {
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}", rest / 3600, rest % 3600 / 60, rest % 60)
+}
+
+/// The seconds since the epoch of a `YYYY-MM-DDTHH:MM:SS` date.
+fn parse_date(text: &str) -> Option<u64> {
+    let (date, time) = text.split_once('T')?;
+    let [year, month, day] = fields(date, '-')?;
+    let [hours, minutes, seconds] = fields(time, ':')?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    u64::try_from(days * 86_400 + hours * 3600 + minutes * 60 + seconds).ok()
+}
+
+/// The three numbers `text` holds, separated by `separator`.
+fn fields(text: &str, separator: char) -> Option<[i64; 3]> {
+    let fields: Vec<i64> = text.split(separator).map(|field| field.parse().ok()).collect::<Option<_>>()?;
+    fields.try_into().ok()
+}
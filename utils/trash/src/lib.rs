@@ -0,0 +1,26 @@
+//! A trash for ecmaOS, laid out as the freedesktop.org specification
+//! describes, so files can be set aside instead of deleted and put back.
+//!
+//! The trash is `$XDG_DATA_HOME/Trash`, or `~/.local/share/Trash`. A
+//! trashed file or directory is moved into its `files` directory, and an
+//! [`info`] file with the same name in `info` records where it was and when
+//! it was trashed. The info file is created first, exclusively, to claim the
+//! name; if another file of the same name is in the trash already, a number
+//! is added to it (`notes.txt`, `notes.2.txt`). Moving between filesystems
+//! falls back to copying and deleting.
+//!
+//! The crate builds three commands, for `wasm32-wasip1` or the host:
+//!
+//! ```text
+//! trash [-f] [-v] FILE...
+//! restore [--to PATH] [NAME|PATH...]
+//! trash-empty [-v] [DAYS]
+//! ```
+
+mod error;
+pub mod info;
+mod trash;
+
+pub use error::{Error, Result};
+pub use info::TrashInfo;
+pub use trash::{Entry, Trash};
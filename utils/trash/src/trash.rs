@@ -0,0 +1,287 @@
+//! Moving files into the trash and back out.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+use crate::info::TrashInfo;
+
+/// A trashed file: its name in the trash and where it came from.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub info: TrashInfo,
+}
+
+/// A trash directory, with its `files` and `info` subdirectories.
+pub struct Trash {
+    root: PathBuf,
+}
+
+impl Trash {
+    pub fn at(root: impl Into<PathBuf>) -> Trash {
+        Trash { root: root.into() }
+    }
+
+    /// The user's trash: `$XDG_DATA_HOME/Trash`, or `~/.local/share/Trash`.
+    pub fn home() -> Result<Trash> {
+        let var = |name| env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+        match (var("XDG_DATA_HOME"), var("HOME")) {
+            (Some(data), _) => Ok(Trash::at(data.join("Trash"))),
+            (None, Some(home)) => Ok(Trash::at(home.join(".local/share/Trash"))),
+            (None, None) => Err(Error::NoHome),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn file(&self, name: &str) -> PathBuf {
+        self.root.join("files").join(name)
+    }
+
+    fn info_file(&self, name: &str) -> PathBuf {
+        self.root.join("info").join(format!("{name}.trashinfo"))
+    }
+
+    /// Moves a file or directory into the trash, under its own name if that
+    /// isn't taken and with a number added to it if it is.
+    pub fn put(&self, path: &Path) -> Result<Entry> {
+        let path = absolute(path)?;
+        fs::symlink_metadata(&path).map_err(Error::io(&path))?;
+        if self.root.starts_with(&path) {
+            return Err(Error::Refused { path, reason: "can't move the trash into itself" });
+        }
+        if path.starts_with(&self.root) {
+            return Err(Error::Refused { path, reason: "is in the trash already" });
+        }
+        let Some(base) = path.file_name().and_then(|name| name.to_str()).map(str::to_string) else {
+            return Err(Error::Refused { path, reason: "has no name that can be recorded" });
+        };
+        for directory in ["files", "info"] {
+            let directory = self.root.join(directory);
+            fs::create_dir_all(&directory).map_err(Error::io(directory))?;
+        }
+
+        let deleted = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+        let info = TrashInfo { path, deleted };
+        let name = self.reserve(&base, &info)?;
+        let file = self.file(&name);
+        if let Err(e) = move_path(&info.path, &file) {
+            let _ = fs::remove_file(self.info_file(&name));
+            return Err(e);
+        }
+        Ok(Entry { name, info })
+    }
+
+    /// Picks a name for a file and claims it by creating its info file,
+    /// which fails if another has it, so concurrent puts can't collide.
+    fn reserve(&self, base: &str, info: &TrashInfo) -> Result<String> {
+        for number in 1.. {
+            let name = numbered(base, number);
+            // A file left behind without its info file still holds the name
+            if fs::symlink_metadata(self.file(&name)).is_ok() {
+                continue;
+            }
+            let path = self.info_file(&name);
+            match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => {
+                    if let Err(e) = io::Write::write_all(&mut &file, info.to_string().as_bytes()) {
+                        let _ = fs::remove_file(&path);
+                        return Err(Error::Io { path, source: e });
+                    }
+                    return Ok(name);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(Error::Io { path, source: e }),
+            }
+        }
+        unreachable!("ran out of numbers for {base}")
+    }
+
+    /// Everything in the trash that has a readable info file, oldest first.
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        for name in self.names("info")? {
+            let Some(name) = name.strip_suffix(".trashinfo") else { continue };
+            let path = self.info_file(name);
+            let (text, written) = match fs::read_to_string(&path).and_then(|text| Ok((text, written(&path)?))) {
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(Error::Io { path, source: e }),
+            };
+            if let Some(info) = TrashInfo::parse(&text) {
+                entries.push((written, Entry { name: name.to_string(), info }));
+            }
+        }
+        // Dates only go down to the second, so files trashed within the same
+        // one are told apart by when their info files were written
+        entries.sort_by(|(a_written, a), (b_written, b)| {
+            (a.info.deleted, a_written, &a.name).cmp(&(b.info.deleted, b_written, &b.name))
+        });
+        Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// Deletes the files without an info file, and info files without a
+    /// file, which a trash or restore interrupted halfway leaves behind.
+    pub fn remove_orphans(&self) -> Result<Vec<PathBuf>> {
+        let files = self.names("files")?;
+        let infos = self.names("info")?;
+        let has_info = |name: &String| infos.contains(&format!("{name}.trashinfo"));
+        let has_file = |info: &String| info.strip_suffix(".trashinfo").is_some_and(|name| files.contains(&name.into()));
+        let mut orphans: Vec<PathBuf> =
+            files.iter().filter(|name| !has_info(name)).map(|name| self.file(name)).collect();
+        orphans.extend(infos.iter().filter(|info| !has_file(info)).map(|info| self.root.join("info").join(info)));
+        for orphan in &orphans {
+            remove_path(orphan)?;
+        }
+        Ok(orphans)
+    }
+
+    /// The names in one of the trash's subdirectories, none if it's missing.
+    fn names(&self, directory: &str) -> Result<Vec<String>> {
+        let directory = self.root.join(directory);
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::Io { path: directory, source: e }),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(Error::io(&directory))?;
+            names.extend(entry.file_name().into_string());
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// The most recently trashed entry that was at `path`, or failing that
+    /// the one named `path` in the trash.
+    pub fn find(&self, path: &str) -> Result<Entry> {
+        let entries = self.entries()?;
+        let original = absolute(Path::new(path))?;
+        let found = match entries.iter().rev().find(|entry| entry.info.path == original) {
+            Some(entry) => Some(entry),
+            None => entries.iter().find(|entry| entry.name == path),
+        };
+        found.cloned().ok_or(Error::NotInTrash(path.to_string()))
+    }
+
+    /// Moves an entry back to where it came from, or to `to`, creating the
+    /// directories leading to it. An existing file is never replaced.
+    pub fn restore(&self, entry: &Entry, to: Option<&Path>) -> Result<PathBuf> {
+        let destination = match to {
+            Some(to) => absolute(to)?,
+            None => entry.info.path.clone(),
+        };
+        if fs::symlink_metadata(&destination).is_ok() {
+            return Err(Error::Exists(destination));
+        }
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).map_err(Error::io(parent))?;
+        }
+        move_path(&self.file(&entry.name), &destination)?;
+        let info = self.info_file(&entry.name);
+        fs::remove_file(&info).map_err(Error::io(info))?;
+        Ok(destination)
+    }
+
+    /// Deletes an entry for good.
+    pub fn remove(&self, entry: &Entry) -> Result<()> {
+        remove_path(&self.file(&entry.name))?;
+        remove_path(&self.info_file(&entry.name))
+    }
+}
+
+/// When a file was last written, or the epoch where that isn't recorded.
+fn written(path: &Path) -> io::Result<SystemTime> {
+    Ok(fs::metadata(path)?.modified().unwrap_or(UNIX_EPOCH))
+}
+
+/// `name` for the first file with it, and `name` with `.number` before its
+/// extension for the others: `notes.txt`, `notes.2.txt`, `notes.3.txt`.
+fn numbered(name: &str, number: usize) -> String {
+    if number == 1 {
+        return name.to_string();
+    }
+    // A leading dot marks a hidden file rather than an extension
+    match name.char_indices().skip(1).find(|&(_, c)| c == '.') {
+        Some((dot, _)) => format!("{}.{number}{}", &name[..dot], &name[dot..]),
+        None => format!("{name}.{number}"),
+    }
+}
+
+/// `path` made absolute against the working directory, with `.` and `..`
+/// resolved without following links, as the info file records it.
+fn absolute(path: &Path) -> Result<PathBuf> {
+    let path =
+        if path.is_absolute() { path.to_path_buf() } else { env::current_dir().map_err(Error::io(path))?.join(path) };
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    Ok(normal)
+}
+
+/// Renames `from` to `to`, copying and deleting it when they're on
+/// different filesystems.
+fn move_path(from: &Path, to: &Path) -> Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_path(from, to)?;
+            remove_path(from)
+        }
+        Err(e) => Err(Error::Io { path: from.to_path_buf(), source: e }),
+    }
+}
+
+fn copy_path(from: &Path, to: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(from).map_err(Error::io(from))?;
+    if metadata.is_dir() {
+        fs::create_dir(to).map_err(Error::io(to))?;
+        for entry in fs::read_dir(from).map_err(Error::io(from))? {
+            let entry = entry.map_err(Error::io(from))?;
+            copy_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else if metadata.is_symlink() {
+        let target = fs::read_link(from).map_err(Error::io(from))?;
+        symlink(&target, to).map_err(Error::io(to))
+    } else {
+        fs::copy(from, to).map(drop).map_err(Error::io(from))
+    }
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(target_os = "wasi")]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::wasi::fs::symlink_path(target, link)
+}
+
+/// Deletes a file, link or directory tree; one that's already gone is fine.
+fn remove_path(path: &Path) -> Result<()> {
+    let result = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(Error::Io { path: path.to_path_buf(), source: e }),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A scratch home directory with the files given.
+fn home(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ecmaos-trash-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    for (path, contents) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    root
+}
+
+/// Runs one of the commands in `home`, with it as `HOME`.
+fn run(home: &Path, command: &str, args: &[&str]) -> Output {
+    let program = match command {
+        "trash" => env!("CARGO_BIN_EXE_trash"),
+        "restore" => env!("CARGO_BIN_EXE_restore"),
+        _ => env!("CARGO_BIN_EXE_trash-empty"),
+    };
+    Command::new(program).args(args).env_clear().env("HOME", home).current_dir(home).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn trash_dir(home: &Path) -> PathBuf {
+    home.join(".local/share/Trash")
+}
+
+#[test]
+fn trashes_and_restores() {
+    let home = home("restore", &[("notes.txt", "notes"), ("project/src/main.rs", "fn main() {}")]);
+    stdout(&run(&home, "trash", &["notes.txt", "./project/../project"]));
+    assert!(!home.join("notes.txt").exists() && !home.join("project").exists());
+    let trash = trash_dir(&home);
+    assert_eq!(fs::read_to_string(trash.join("files/notes.txt")).unwrap(), "notes");
+    assert!(trash.join("files/project/src/main.rs").is_file());
+    let info = fs::read_to_string(trash.join("info/project.trashinfo")).unwrap();
+    assert!(info.starts_with(&format!("[Trash Info]\nPath={}/project\nDeletionDate=", home.display())), "{}", info);
+
+    let listing = stdout(&run(&home, "restore", &[]));
+    let lines: Vec<Vec<&str>> = listing.lines().map(|line| line.split('\t').collect()).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines.iter().map(|fields| fields[1]).collect::<Vec<_>>(), ["notes.txt", "project"]);
+    assert_eq!(lines[0][2], home.join("notes.txt").to_str().unwrap());
+
+    // By relative and absolute path
+    stdout(&run(&home, "restore", &["notes.txt"]));
+    stdout(&run(&home, "restore", &[home.join("project").to_str().unwrap()]));
+    assert_eq!(fs::read_to_string(home.join("notes.txt")).unwrap(), "notes");
+    assert!(home.join("project/src/main.rs").is_file());
+    assert_eq!(fs::read_dir(trash.join("files")).unwrap().count(), 0);
+    assert_eq!(fs::read_dir(trash.join("info")).unwrap().count(), 0);
+}
+
+#[test]
+fn numbers_names_that_collide() {
+    let home = home("collide", &[]);
+    for (version, name) in ["first", "second", "third"].iter().zip(["notes.txt", ".profile", "backup.tar.gz"]) {
+        for file in [name, &format!("sub/{name}")] {
+            fs::create_dir_all(home.join("sub")).unwrap();
+            fs::write(home.join(file), version).unwrap();
+            stdout(&run(&home, "trash", &[file]));
+        }
+    }
+    let mut names: Vec<String> = fs::read_dir(trash_dir(&home).join("files"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, [".profile", ".profile.2", "backup.2.tar.gz", "backup.tar.gz", "notes.2.txt", "notes.txt"]);
+
+    // The latest file trashed from a path is the one restored, and never
+    // over one that's there
+    fs::write(home.join("notes.txt"), "latest").unwrap();
+    stdout(&run(&home, "trash", &["notes.txt"]));
+    fs::write(home.join("notes.txt"), "new").unwrap();
+    let output = run(&home, "restore", &["notes.txt"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).ends_with("notes.txt: already exists\n"));
+    stdout(&run(&home, "restore", &["--to", "old/notes.txt", "notes.txt"]));
+    assert_eq!(fs::read_to_string(home.join("old/notes.txt")).unwrap(), "latest");
+    assert_eq!(fs::read_to_string(home.join("notes.txt")).unwrap(), "new");
+
+    // By name in the trash, for one that has another's path
+    stdout(&run(&home, "restore", &["--to", "sub/restored.txt", "notes.2.txt"]));
+    assert_eq!(fs::read_to_string(home.join("sub/restored.txt")).unwrap(), "first");
+}
+
+#[test]
+fn records_paths_percent_encoded() {
+    let home = home("encode", &[("My Notes 100%.txt", "notes")]);
+    stdout(&run(&home, "trash", &["My Notes 100%.txt"]));
+    let info = fs::read_to_string(trash_dir(&home).join("info/My Notes 100%.txt.trashinfo")).unwrap();
+    assert!(info.contains("/My%20Notes%20100%25.txt\n"), "{}", info);
+    assert!(stdout(&run(&home, "restore", &[])).ends_with("/My Notes 100%.txt\n"));
+}
+
+#[test]
+fn empties_by_age() {
+    let home = home("empty", &[("new.txt", "new")]);
+    let trash = trash_dir(&home);
+    stdout(&run(&home, "trash", &["new.txt"]));
+    fs::write(trash.join("files/old.txt"), "old").unwrap();
+    let old = "[Trash Info]\nPath=/home/user/old.txt\nDeletionDate=2020-02-29T23:59:59\n";
+    fs::write(trash.join("info/old.txt.trashinfo"), old).unwrap();
+    fs::write(trash.join("files/orphan.txt"), "orphan").unwrap();
+
+    assert_eq!(stdout(&run(&home, "trash-empty", &["-v", "30"])), "/home/user/old.txt\n");
+    assert!(trash.join("files/new.txt").exists() && trash.join("files/orphan.txt").exists());
+    let emptied = stdout(&run(&home, "trash-empty", &["-v"]));
+    assert_eq!(emptied.lines().count(), 2);
+    assert_eq!(fs::read_dir(trash.join("files")).unwrap().count(), 0);
+    assert_eq!(fs::read_dir(trash.join("info")).unwrap().count(), 0);
+}
+
+#[test]
+fn refuses_what_it_cannot_trash() {
+    let home = home("refuse", &[("kept.txt", "kept")]);
+    stdout(&run(&home, "trash", &["kept.txt"]));
+    let output = run(&home, "trash", &[".local", "missing.txt"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("/.local: can't move the trash into itself\n"), "{}", stderr);
+    assert!(stderr.contains("/missing.txt: "), "{}", stderr);
+    assert!(home.join(".local/share/Trash/files/kept.txt").exists());
+    stdout(&run(&home, "trash", &["-f", "missing.txt"]));
+    assert_eq!(run(&home, "restore", &["missing.txt"]).status.code(), Some(1));
+}
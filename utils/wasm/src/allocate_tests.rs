@@ -0,0 +1,183 @@
+//! Tests of WASI's `fd_allocate` and `fd_advise`.
+//!
+//! Nothing in std calls either, so these call them directly. A runtime may
+//! not implement them, and that's fine as long as it says so with ENOSYS
+//! (or ENOTSUP, which wasmtime gives for `fd_allocate`) and leaves the file
+//! alone; what these catch is a call that claims to work and doesn't, or
+//! fails some other way. Each test reports which of the two it found.
+//!
+//! There's neither to call on other targets, so they skip these.
+
+#[cfg(not(target_os = "wasi"))]
+use crate::harness::context::TestCtx;
+
+pub(crate) const ALLOCATE_CONTENT: &str = "0123456789";
+
+#[cfg(target_os = "wasi")]
+mod wasi {
+    use std::fs::{self, File, OpenOptions};
+    use std::io;
+    use std::os::fd::AsRawFd;
+
+    use super::ALLOCATE_CONTENT;
+    use crate::harness::assert::{expect_eq, expect_eq_bytes, expect_ok};
+    use crate::harness::context::TestCtx;
+
+    /// `errno::badf`
+    const EBADF: i32 = 8;
+    /// `errno::inval`
+    const EINVAL: i32 = 28;
+    /// `errno::nosys` and `errno::notsup`: the call isn't implemented.
+    const UNSUPPORTED: [i32; 2] = [52, 58];
+    /// `errno::notcapable`
+    const ENOTCAPABLE: i32 = 76;
+
+    /// A descriptor nothing has open.
+    const UNOPENED: i32 = i32::MAX;
+
+    /// The advice `fd_advise` takes, from `advice::normal` on.
+    const ADVICE: [(u8, &str); 6] =
+        [(0, "normal"), (1, "sequential"), (2, "random"), (3, "willneed"), (4, "dontneed"), (5, "noreuse")];
+
+    #[link(wasm_import_module = "wasi_snapshot_preview1")]
+    extern "C" {
+        #[link_name = "fd_allocate"]
+        fn wasi_fd_allocate(fd: i32, offset: i64, len: i64) -> i32;
+        #[link_name = "fd_advise"]
+        fn wasi_fd_advise(fd: i32, offset: i64, len: i64, advice: i32) -> i32;
+    }
+
+    fn fd_allocate(fd: i32, offset: u64, len: u64) -> io::Result<()> {
+        // SAFETY: the call only takes numbers
+        match unsafe { wasi_fd_allocate(fd, offset as i64, len as i64) } {
+            0 => Ok(()),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    fn fd_advise(fd: i32, offset: u64, len: u64, advice: u8) -> io::Result<()> {
+        // SAFETY: the call only takes numbers
+        match unsafe { wasi_fd_advise(fd, offset as i64, len as i64, advice as i32) } {
+            0 => Ok(()),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    fn unsupported(error: &io::Error) -> bool {
+        error.raw_os_error().is_some_and(|errno| UNSUPPORTED.contains(&errno))
+    }
+
+    /// Checks that the file at `path` holds `expected`.
+    fn expect_content(path: &str, expected: &[u8], what: &str) {
+        if let Some(content) = expect_ok(fs::read(path), "Read back") {
+            expect_eq_bytes(&content, expected, what);
+        }
+    }
+
+    pub(crate) fn test_fd_allocate(ctx: &TestCtx) {
+        let path = &ctx.path("allocate.txt");
+        let Some(file) = expect_ok(OpenOptions::new().read(true).write(true).open(path), "Open to write") else {
+            return;
+        };
+        let fd = file.as_raw_fd();
+
+        step!("Allocating space the file already has");
+        match fd_allocate(fd, 0, 4) {
+            Ok(()) => pass!("Allocate: supported"),
+            Err(e) if unsupported(&e) => {
+                pass!(e => "Allocate: not supported ({})", e);
+                expect_content(path, ALLOCATE_CONTENT.as_bytes(), "File left alone");
+                return;
+            }
+            Err(e) => {
+                fail!(e => "Allocate: {}", e);
+                return;
+            }
+        }
+        expect_content(path, ALLOCATE_CONTENT.as_bytes(), "File unchanged");
+
+        step!("Allocating past the end");
+        if expect_ok(fd_allocate(fd, 8, 100), "Allocate 100 bytes from offset 8").is_some() {
+            let mut expected = ALLOCATE_CONTENT.as_bytes().to_vec();
+            expected.resize(108, 0);
+            if let Some(metadata) = expect_ok(file.metadata(), "Metadata") {
+                expect_eq(metadata.len(), 108, "File grown to the end of the allocation");
+            }
+            expect_content(path, &expected, "Content kept and the rest zero-filled");
+        }
+
+        step!("Allocating where it can't");
+        if let Some(read_only) = expect_ok(File::open(path), "Open read-only") {
+            match fd_allocate(read_only.as_raw_fd(), 0, 200) {
+                Ok(()) => fail!("Allocate through a read-only descriptor: succeeded"),
+                Err(e) if matches!(e.raw_os_error(), Some(EBADF | ENOTCAPABLE)) => {
+                    pass!(e => "Allocate through a read-only descriptor refused: {}", e)
+                }
+                Err(e) => {
+                    fail!(e => "Allocate through a read-only descriptor: expected EBADF or ENOTCAPABLE, got {}", e)
+                }
+            }
+        }
+        match fd_allocate(UNOPENED, 0, 10) {
+            Ok(()) => fail!("Allocate through a descriptor that isn't open: succeeded"),
+            Err(e) => {
+                expect_eq(e.raw_os_error(), Some(EBADF), "Allocate through a descriptor that isn't open refused");
+            }
+        }
+    }
+
+    pub(crate) fn test_fd_advise(ctx: &TestCtx) {
+        let path = &ctx.path("allocate.txt");
+        let Some(file) = expect_ok(File::open(path), "Open") else {
+            return;
+        };
+        let fd = file.as_raw_fd();
+
+        step!("Giving each kind of advice");
+        let mut supported = 0;
+        for (advice, name) in ADVICE {
+            match fd_advise(fd, 0, ALLOCATE_CONTENT.len() as u64, advice) {
+                Ok(()) => {
+                    supported += 1;
+                    pass!("Advise {}: supported", name);
+                }
+                Err(e) if unsupported(&e) => pass!(e => "Advise {}: not supported ({})", name, e),
+                Err(e) => fail!(e => "Advise {}: {}", name, e),
+            }
+        }
+        detail!("{} of {} kinds of advice taken", supported, ADVICE.len());
+        // Advice is only a hint, so it changes nothing anyone can see
+        expect_content(path, ALLOCATE_CONTENT.as_bytes(), "File unchanged");
+
+        step!("Giving advice that can't be taken");
+        if supported > 0 {
+            match fd_advise(fd, 0, 10, ADVICE.len() as u8) {
+                Ok(()) => fail!("Advice that doesn't exist: taken"),
+                Err(e) => {
+                    expect_eq(e.raw_os_error(), Some(EINVAL), "Advice that doesn't exist refused");
+                }
+            }
+            expect_ok(fd_advise(fd, 5, 1000, 0), "Advise past the end of the file");
+        }
+        match fd_advise(UNOPENED, 0, 10, 0) {
+            Ok(()) => fail!("Advise a descriptor that isn't open: taken"),
+            Err(e) if supported == 0 && unsupported(&e) => pass!(e => "Advise a descriptor that isn't open: {}", e),
+            Err(e) => {
+                expect_eq(e.raw_os_error(), Some(EBADF), "Advise a descriptor that isn't open refused");
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "wasi")]
+pub(crate) use wasi::{test_fd_advise, test_fd_allocate};
+
+#[cfg(not(target_os = "wasi"))]
+pub(crate) fn test_fd_allocate(_ctx: &TestCtx) {
+    skip!("fd_allocate is only reachable on WASI targets");
+}
+
+#[cfg(not(target_os = "wasi"))]
+pub(crate) fn test_fd_advise(_ctx: &TestCtx) {
+    skip!("fd_advise is only reachable on WASI targets");
+}
@@ -0,0 +1,328 @@
+//! Runs the WASM interface test suite.
+
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use ecmaos_wasm_tests::harness::capabilities::{Capabilities, Capability};
+use ecmaos_wasm_tests::harness::context::{Session, DEFAULT_ROOT};
+use ecmaos_wasm_tests::harness::random::{self, Rng};
+use ecmaos_wasm_tests::harness::registry::{Shard, TestCase};
+use ecmaos_wasm_tests::harness::{
+    self, baseline, log, parallel, persist, progress, registry, skiplist, snapshot, Format, Verbosity,
+};
+use ecmaos_wasm_tests::{error, warn, TESTS};
+
+const USAGE: &str = "usage: test.wasm [-q|-v|-vv] [--list] [--interactive] [--self-test] [--format text|json|tap] \
+                     [--ascii] [--iterations N] [--retries N] [--shuffle] [--seed N] [--jobs N|auto] \
+                     [--update-snapshots] [--no-persist] [--root DIR] [--log-file FILE] [--heartbeat SECONDS] \
+                     [--baseline FILE [--regression-threshold PERCENT]] [--tags TAG,...] [--skip-tags TAG,...] \
+                     [--skip-file FILE] [--filter PATTERN...] [--shard INDEX/COUNT]\n\
+                     \x20      test.wasm diff OLD NEW [--ascii] [--delta FILE]\n\
+                     --root is the directory to work in, /tmp by default; it has to exist.\n\
+                     WASM_TEST_FILTER (space-separated patterns), WASM_TEST_FORMAT, WASM_TEST_SEED, WASM_TEST_ROOT \
+                     and WASM_TEST_SKIP_FILE stand in for flags not given";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut list = false;
+    let mut interactive = false;
+    let mut self_test = false;
+    let mut patterns = Vec::new();
+    let mut tags = Vec::new();
+    let mut skip_tags = Vec::new();
+    let mut filtering = false;
+    let mut iterations = 1;
+    let mut shuffle = false;
+    let mut jobs = 1;
+    let mut format = None;
+    let mut seed = None;
+    let mut baseline_path = None;
+    let mut log_path = None;
+    let mut root = None;
+    let mut shard = None;
+    let mut skip_path = None;
+    let mut threshold = 50.0;
+    let mut args = env::args().skip(1).peekable();
+    if args.next_if_eq("diff").is_some() {
+        diff(args);
+    }
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-q" | "--quiet" => harness::set_verbosity(Verbosity::Quiet),
+            "-v" | "--verbose" => harness::set_verbosity(Verbosity::Verbose),
+            "-vv" => harness::set_verbosity(Verbosity::Debug),
+            "--list" => list = true,
+            "--interactive" => interactive = true,
+            "--self-test" => self_test = true,
+            "--update-snapshots" => snapshot::set_update(true),
+            "--no-persist" => persist::set_enabled(false),
+            "--ascii" => harness::set_ascii(true),
+            "--filter" => filtering = true,
+            "--shuffle" => shuffle = true,
+            "--seed" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => seed = Some(n),
+                None => usage_error("--seed needs a number"),
+            },
+            "--iterations" => match args.next().and_then(|n| n.parse().ok()).filter(|&n: &usize| n > 0) {
+                Some(n) => iterations = n,
+                None => usage_error("--iterations needs a positive number"),
+            },
+            "--baseline" => match args.next() {
+                Some(path) => baseline_path = Some(path),
+                None => usage_error("--baseline needs a value"),
+            },
+            "--root" => match args.next() {
+                Some(dir) => root = Some(dir),
+                None => usage_error("--root needs a directory"),
+            },
+            "--log-file" => match args.next() {
+                Some(path) => log_path = Some(path),
+                None => usage_error("--log-file needs a value"),
+            },
+            "--retries" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => harness::set_retries(n),
+                None => usage_error("--retries needs a number"),
+            },
+            "--heartbeat" => match args.next().and_then(|n| n.parse().ok()).filter(|&n: &f64| n >= 0.0) {
+                Some(seconds) => progress::set_interval(Duration::from_secs_f64(seconds)),
+                None => usage_error("--heartbeat needs a number of seconds"),
+            },
+            "--regression-threshold" => match args.next().and_then(|n| n.parse().ok()).filter(|&n: &f64| n >= 0.0) {
+                Some(percent) => threshold = percent,
+                None => usage_error("--regression-threshold needs a percentage"),
+            },
+            "--jobs" => match args.next().as_deref() {
+                Some("auto") => jobs = thread::available_parallelism().map_or(1, usize::from),
+                Some(n) => match n.parse().ok().filter(|&n: &usize| n > 0) {
+                    Some(n) => jobs = n,
+                    None => usage_error("--jobs needs a positive number or auto"),
+                },
+                None => usage_error("--jobs needs a positive number or auto"),
+            },
+            "--tag" | "--tags" => match args.next() {
+                Some(list) => tags.extend(list.split(',').filter(|tag| !tag.is_empty()).map(str::to_string)),
+                None => usage_error(&format!("{} needs a value", arg)),
+            },
+            "--shard" => match args.next().as_deref().and_then(Shard::parse) {
+                Some(part) => shard = Some(part),
+                None => usage_error("--shard needs INDEX/COUNT, with INDEX from 1 to COUNT"),
+            },
+            "--skip-file" => match args.next() {
+                Some(path) => skip_path = Some(path),
+                None => usage_error("--skip-file needs a value"),
+            },
+            "--skip-tags" => match args.next() {
+                Some(list) => skip_tags.extend(list.split(',').filter(|tag| !tag.is_empty()).map(str::to_string)),
+                None => usage_error("--skip-tags needs a value"),
+            },
+            "--format" => {
+                let name = args.next().unwrap_or_default();
+                match Format::parse(&name) {
+                    Some(f) => format = Some(f),
+                    None => usage_error(&format!("unknown format: {}", name)),
+                }
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ if filtering => patterns.push(arg),
+            _ => {}
+        }
+    }
+
+    // Exporting variables is easier in the ecmaOS shell than passing long
+    // argument lists, so these stand in for flags that weren't given
+    let var = |name| env::var(name).ok().filter(|value: &String| !value.is_empty());
+    if !filtering {
+        if let Some(list) = var("WASM_TEST_FILTER") {
+            patterns.extend(list.split_whitespace().map(str::to_string));
+        }
+    }
+    if format.is_none() {
+        if let Some(name) = var("WASM_TEST_FORMAT") {
+            format = Some(Format::parse(&name).unwrap_or_else(|| usage_error(&format!("unknown WASM_TEST_FORMAT: {}", name))));
+        }
+    }
+    if seed.is_none() {
+        if let Some(n) = var("WASM_TEST_SEED") {
+            seed = Some(n.parse().unwrap_or_else(|_| usage_error("WASM_TEST_SEED needs to be a number")));
+        }
+    }
+    let skip_path = skip_path.or_else(|| var("WASM_TEST_SKIP_FILE"));
+    let root = root.or_else(|| var("WASM_TEST_ROOT")).unwrap_or_else(|| DEFAULT_ROOT.to_string());
+    if let Some(format) = format {
+        harness::set_format(format);
+    }
+    if let Some(seed) = seed {
+        random::set_seed(seed);
+    }
+
+    if let Some(path) = &log_path {
+        if let Err(e) = log::set_file(Path::new(path)) {
+            error!("couldn't open the log file {}: {}", path, e);
+            std::process::exit(2);
+        }
+    }
+    // The harness checks itself without a scratch directory, and saves no
+    // report
+    if self_test {
+        persist::set_enabled(false);
+        harness::selftest::run();
+        if harness::end().failed > 0 {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(path) = &baseline_path {
+        if let Err(e) = baseline::load(path, threshold) {
+            error!("couldn't read the baseline {}: {}", path, e);
+            std::process::exit(2);
+        }
+    }
+
+    if let Some(path) = &skip_path {
+        match skiplist::load(path, TESTS.iter().map(|test| test.name)) {
+            Ok(unmatched) => {
+                for pattern in unmatched {
+                    warn!("{} matches no test", pattern);
+                }
+            }
+            Err(e) => {
+                error!("couldn't read the skip list {}: {}", path, e);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let mut selected = registry::select(TESTS, &patterns, &tags, &skip_tags);
+    if let Some(shard) = shard {
+        selected = shard.select(selected);
+        persist::set_shard(shard);
+    }
+
+    // One test per line: name, tags and required capabilities, tab-separated
+    if list {
+        for test in selected {
+            let requires: Vec<_> = test.requires.iter().map(|capability| capability.name()).collect();
+            println!("{}\t{}\t{}", test.name, test.tags.join(","), requires.join(","));
+        }
+        return;
+    }
+
+    let session = match Session::create(Path::new(&root)) {
+        Ok(session) => session,
+        Err(e) => {
+            error!("couldn't create the scratch directory in {}: {}", root, e);
+            std::process::exit(2);
+        }
+    };
+
+    let capabilities = Capabilities::probe(&session);
+    if jobs > 1 {
+        match capabilities.missing(Capability::Threads) {
+            Some(reason) => warn!("--jobs needs threads, which the runtime lacks ({}); running one test at a time", reason),
+            None => parallel::set_jobs(jobs),
+        }
+    }
+    if interactive {
+        harness::begin(None, &session, &capabilities);
+        run_interactive(&selected, &session, &capabilities);
+    } else {
+        harness::begin(Some(selected.len() * iterations), &session, &capabilities);
+        run(&mut selected, iterations, shuffle, &session, &capabilities);
+    }
+
+    let summary = harness::end();
+    persist::save();
+    // exit() skips destructors, so the scratch directory is removed first
+    drop(session);
+    if summary.failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Compares two JSON reports, exiting with 1 if a test newly fails.
+fn diff(mut args: impl Iterator<Item = String>) -> ! {
+    let mut paths = Vec::new();
+    let mut delta = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ascii" => harness::set_ascii(true),
+            "--delta" => match args.next() {
+                Some(path) => delta = Some(path),
+                None => usage_error("--delta needs a value"),
+            },
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ => paths.push(arg),
+        }
+    }
+    let [old, new] = &paths[..] else {
+        usage_error("diff needs two reports");
+    };
+    match baseline::diff(old, new, delta.as_deref()) {
+        Ok(failing) => std::process::exit(failing as i32),
+        Err(e) => {
+            error!("couldn't compare the reports: {}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn run(selected: &mut [&TestCase], iterations: usize, shuffle: bool, session: &Session, capabilities: &Capabilities) {
+    let mut rng = Rng::new(random::seed());
+    let mut number = 0;
+    for iteration in 1..=iterations {
+        if shuffle {
+            rng.shuffle(selected);
+        }
+        harness::iteration(iteration, iterations, shuffle.then_some(&selected[..]));
+        parallel::run_all(selected, session, capabilities, |result| {
+            number += 1;
+            harness::report(number, &result);
+        });
+    }
+}
+
+/// Runs tests as their names are read from stdin, one per line, until it
+/// ends. A line that doesn't name a test runs every test whose name contains
+/// it, as `--filter` would.
+fn run_interactive(selected: &[&TestCase], session: &Session, capabilities: &Capabilities) {
+    let prompt = || {
+        if harness::format() == Format::Text {
+            print!("> ");
+            let _ = io::stdout().flush();
+        }
+    };
+    let mut number = 0;
+    prompt();
+    for line in io::stdin().lines() {
+        let Ok(line) = line else { break };
+        let name = line.trim();
+        if !name.is_empty() {
+            let tests: Vec<&TestCase> = match selected.iter().find(|test| test.name == name) {
+                Some(test) => vec![test],
+                None => selected.iter().copied().filter(|test| test.name.contains(name)).collect(),
+            };
+            if tests.is_empty() {
+                warn!("no test matches {}", name);
+            }
+            for test in tests {
+                number += 1;
+                let result = harness::run(test, session, capabilities);
+                harness::report(number, &result);
+                harness::outcome(&result);
+            }
+        }
+        prompt();
+    }
+}
@@ -0,0 +1,150 @@
+//! Tests of changing a descriptor's flags after it was opened, through
+//! WASI's `fd_fdstat_set_flags`.
+//!
+//! std opens files with their flags and never changes them, so nothing else
+//! calls `fd_fdstat_set_flags`. These turn append and nonblock on and off
+//! on open descriptors, check that `fd_fdstat_get` reports the change, and
+//! that writes made after it land where the new flags say.
+//!
+//! There's no `fd_fdstat_set_flags` to call on other targets, so they skip
+//! these.
+
+#[cfg(not(target_os = "wasi"))]
+use crate::harness::context::TestCtx;
+
+pub(crate) const FLAGS_CONTENT: &str = "0123456789";
+
+#[cfg(target_os = "wasi")]
+mod wasi {
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{self, Seek, SeekFrom, Write};
+    use std::os::fd::AsRawFd;
+
+    use crate::harness::assert::{expect_eq, expect_eq_bytes, expect_ok};
+    use crate::harness::context::TestCtx;
+
+    /// `fdflags::append`
+    const APPEND: u16 = 1 << 0;
+    /// `fdflags::nonblock`
+    const NONBLOCK: u16 = 1 << 2;
+
+    /// `errno::badf`
+    const EBADF: i32 = 8;
+
+    /// A descriptor nothing has open. One that was closed could be reused
+    /// by a test running alongside before its flags were set.
+    const UNOPENED: i32 = i32::MAX;
+
+    #[link(wasm_import_module = "wasi_snapshot_preview1")]
+    extern "C" {
+        #[link_name = "fd_fdstat_get"]
+        fn wasi_fd_fdstat_get(fd: i32, stat: i32) -> i32;
+        #[link_name = "fd_fdstat_set_flags"]
+        fn wasi_fd_fdstat_set_flags(fd: i32, flags: i32) -> i32;
+    }
+
+    /// The flags `fd_fdstat_get` reports for `fd`.
+    fn flags(fd: i32) -> io::Result<u16> {
+        // The fdstat: the file type, the flags at offset 2, then the rights
+        let mut stat = [0u64; 3];
+        // SAFETY: stat outlives the call and is the 24 bytes the runtime
+        // writes, aligned for the rights in it
+        let errno = unsafe { wasi_fd_fdstat_get(fd, stat.as_mut_ptr() as i32) };
+        match errno {
+            0 => Ok(u16::from_le_bytes(stat[0].to_le_bytes()[2..4].try_into().unwrap())),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    fn set_flags(fd: i32, flags: u16) -> io::Result<()> {
+        // SAFETY: the call only takes numbers
+        match unsafe { wasi_fd_fdstat_set_flags(fd, flags as i32) } {
+            0 => Ok(()),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    /// Sets the flags of `file` and checks that they read back as set.
+    fn expect_flags(file: &File, wanted: u16, what: &str) -> Option<()> {
+        expect_ok(set_flags(file.as_raw_fd(), wanted), what)?;
+        let reported = expect_ok(flags(file.as_raw_fd()), "Get the flags")?;
+        expect_eq(reported & (APPEND | NONBLOCK), wanted, "Flags reported");
+        Some(())
+    }
+
+    /// Seeks `file` to the start, writes `data` and checks that the file
+    /// then holds `expected`.
+    fn expect_write(file: &mut File, path: &str, data: &str, expected: &str, what: &str) {
+        let written = file.seek(SeekFrom::Start(0)).and_then(|_| file.write_all(data.as_bytes()));
+        if expect_ok(written, &format!("Write {:?} after seeking to the start", data)).is_none() {
+            return;
+        }
+        if let Some(content) = expect_ok(fs::read(path), "Read back") {
+            expect_eq_bytes(&content, expected.as_bytes(), what);
+        }
+    }
+
+    pub(crate) fn test_fd_flags(ctx: &TestCtx) {
+        let path = &ctx.path("flags.txt");
+
+        step!("Opening without flags");
+        let Some(mut file) = expect_ok(OpenOptions::new().write(true).open(path), "Open to write") else {
+            return;
+        };
+        if let Some(flags) = expect_ok(flags(file.as_raw_fd()), "Get the flags") {
+            expect_eq(flags & (APPEND | NONBLOCK), 0, "No flags reported");
+        }
+        expect_write(&mut file, path, "a", "a123456789", "Write landed at the start");
+
+        step!("Turning append on");
+        if expect_flags(&file, APPEND, "Set append").is_some() {
+            expect_write(&mut file, path, "b", "a123456789b", "Write landed at the end");
+            if let Some(position) = expect_ok(file.stream_position(), "Position after the write") {
+                expect_eq(position, 11, "Position at the end");
+            }
+        }
+
+        step!("Turning append off again");
+        if expect_flags(&file, 0, "Clear append").is_some() {
+            expect_write(&mut file, path, "c", "c123456789b", "Write landed at the start");
+        }
+
+        step!("Turning nonblock on");
+        // Regular files never block, so writes carry on as before
+        if expect_flags(&file, NONBLOCK, "Set nonblock").is_some() {
+            expect_write(&mut file, path, "d", "d123456789b", "Write landed at the start");
+        }
+        if expect_flags(&file, NONBLOCK | APPEND, "Add append to nonblock").is_some() {
+            expect_write(&mut file, path, "e", "d123456789be", "Write landed at the end");
+        }
+        drop(file);
+
+        step!("Clearing append on a file opened to append");
+        let Some(mut file) = expect_ok(OpenOptions::new().append(true).open(path), "Open to append") else {
+            return;
+        };
+        if let Some(flags) = expect_ok(flags(file.as_raw_fd()), "Get the flags") {
+            expect_eq(flags & APPEND, APPEND, "Append reported");
+        }
+        expect_write(&mut file, path, "f", "d123456789bef", "Write landed at the end");
+        if expect_flags(&file, 0, "Clear append").is_some() {
+            expect_write(&mut file, path, "g", "g123456789bef", "Write landed at the start");
+        }
+
+        step!("Setting the flags of a descriptor that isn't open");
+        match set_flags(UNOPENED, APPEND) {
+            Ok(()) => fail!("Set the flags: succeeded"),
+            Err(e) => {
+                expect_eq(e.raw_os_error(), Some(EBADF), "Refused with EBADF");
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "wasi")]
+pub(crate) use wasi::test_fd_flags;
+
+#[cfg(not(target_os = "wasi"))]
+pub(crate) fn test_fd_flags(_ctx: &TestCtx) {
+    skip!("fd_fdstat_set_flags is only reachable on WASI targets");
+}
@@ -0,0 +1,515 @@
+//! Tests of the filesystem: files, directories, metadata, file descriptors
+//! and links.
+
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::harness::assert::{expect_eq, expect_eq_bytes, expect_err_kind, expect_ok, expect_true};
+use crate::harness::context::TestCtx;
+use crate::harness::progress;
+use crate::harness::snapshot;
+
+pub(crate) fn test_file_operations(ctx: &TestCtx) {
+    let test_file = &ctx.path("test_file.txt");
+    let test_content = "Hello from WASM test!\nThis is a test file.\n";
+    
+    step!("Writing to: {}", test_file);
+    if expect_ok(fs::write(test_file, test_content), "Write file").is_none() {
+        return;
+    }
+    
+    step!("Reading from: {}", test_file);
+    if let Some(content) = expect_ok(fs::read(test_file), "Read file") {
+        expect_eq_bytes(&content, test_content.as_bytes(), "Content read back");
+    }
+    
+    step!("Getting file metadata");
+    if let Some(metadata) = expect_ok(fs::metadata(test_file), "Get metadata") {
+        expect_eq(metadata.len(), test_content.len() as u64, "Size");
+        expect_true(metadata.is_file(), "Is a file");
+        expect_true(!metadata.is_dir(), "Is not a directory");
+    }
+    
+    step!("Cleaning up test file");
+    expect_ok(fs::remove_file(test_file), "Remove file");
+}
+
+pub(crate) fn test_directory_operations(ctx: &TestCtx) {
+    let test_dir = &ctx.path("test_dir");
+    
+    step!("Creating directory: {}", test_dir);
+    if expect_ok(fs::create_dir(test_dir), "Create directory").is_none() {
+        return;
+    }
+    
+    let test_file = format!("{}/test.txt", test_dir);
+    step!("Creating file in directory: {}", test_file);
+    expect_ok(fs::write(&test_file, "test content"), "Create file in directory");
+    
+    step!("Reading directory: {}", test_dir);
+    if let Some(entries) = expect_ok(fs::read_dir(test_dir), "Read directory") {
+        let mut names = Vec::new();
+        for entry in entries {
+            if let Some(entry) = expect_ok(entry, "Read entry") {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                detail!("Entry {}: {}", names.len() + 1, name);
+                names.push(name);
+            }
+        }
+        names.sort();
+        snapshot::record("listing", names.join("\n"));
+        expect_eq(names, vec!["test.txt".to_string()], "Directory entries");
+    }
+    
+    step!("Removing directory: {}", test_dir);
+    expect_ok(fs::remove_dir_all(test_dir), "Remove directory");
+    expect_true(!Path::new(test_dir).exists(), "Directory no longer exists");
+}
+
+pub(crate) fn test_path_operations(ctx: &TestCtx) {
+    let test_path = ctx.path("path_test");
+    
+    step!("Testing path operations on: {}", test_path);
+    
+    step!("Creating directory");
+    if expect_ok(fs::create_dir_all(&test_path), "Create directory").is_none() {
+        return;
+    }
+    
+    let nested_file = format!("{}/nested/file.txt", test_path);
+    step!("Creating nested file: {}", nested_file);
+    let parent = Path::new(&nested_file).parent();
+    expect_eq(parent, Some(Path::new(&format!("{}/nested", test_path))), "Parent of nested file");
+    if let Some(parent) = parent {
+        if expect_ok(fs::create_dir_all(parent), "Create parent directory").is_some() {
+            expect_ok(fs::write(&nested_file, "nested content"), "Create nested file");
+            expect_true(Path::new(&nested_file).exists(), "Nested file exists");
+        }
+    }
+}
+
+pub(crate) const STAT_CONTENT: &str = "stat test content";
+
+pub(crate) fn test_stat_operations(ctx: &TestCtx) {
+    let test_file = &ctx.path("stat_test.txt");
+    
+    step!("Testing stat on: {}", test_file);
+    if let Some(metadata) = expect_ok(fs::metadata(test_file), "Stat file") {
+        expect_eq(metadata.len(), STAT_CONTENT.len() as u64, "File size");
+        expect_true(metadata.is_file(), "Is a file");
+        expect_true(!metadata.is_dir(), "Is not a directory");
+        expect_true(!metadata.file_type().is_symlink(), "Is not a symlink");
+        snapshot::record(
+            "metadata",
+            format!(
+                "len {}\nis_file {}\nis_dir {}\nis_symlink {}\nreadonly {}",
+                metadata.len(),
+                metadata.is_file(),
+                metadata.is_dir(),
+                metadata.file_type().is_symlink(),
+                metadata.permissions().readonly()
+            ),
+        );
+        
+        if let Ok(modified) = metadata.modified() {
+            detail!("Modified: {:?}", modified);
+        }
+        if let Ok(accessed) = metadata.accessed() {
+            detail!("Accessed: {:?}", accessed);
+        }
+    }
+}
+
+pub(crate) const SEEK_CONTENT: &str = "0123456789ABCDEF\n";
+
+pub(crate) fn test_seek_operations(ctx: &TestCtx) {
+    let test_file = &ctx.path("seek_test.txt");
+    
+    if let Some(mut file) = expect_ok(fs::File::open(test_file), "Open file") {
+        let mut buffer = [0u8; 5];
+        
+        step!("Testing read from start");
+        if expect_ok(file.read_exact(&mut buffer), "Read").is_some() {
+            expect_eq_bytes(&buffer, b"01234", "Bytes at start");
+        }
+        
+        step!("Testing seek and read");
+        if let Some(position) = expect_ok(file.seek(io::SeekFrom::Start(5)), "Seek") {
+            expect_eq(position, 5, "Position after seek");
+            if expect_ok(file.read_exact(&mut buffer), "Read after seek").is_some() {
+                expect_eq_bytes(&buffer, b"56789", "Bytes after seek");
+            }
+        }
+    }
+}
+
+pub(crate) const RENAME_CONTENT: &str = "Original content";
+
+pub(crate) fn test_file_rename(ctx: &TestCtx) {
+    let test_file = &ctx.path("rename_source.txt");
+    let renamed_file = &ctx.path("rename_target.txt");
+    
+    step!("Renaming file");
+    if expect_ok(fs::rename(test_file, renamed_file), "Rename file").is_some() {
+        expect_true(!Path::new(test_file).exists(), "Source no longer exists");
+        if let Some(content) = expect_ok(fs::read(renamed_file), "Read renamed file") {
+            expect_eq_bytes(&content, RENAME_CONTENT.as_bytes(), "Renamed file content");
+        }
+    }
+}
+
+pub(crate) const TRUNCATE_CONTENT: &str = "This is a longer file content that will be truncated";
+
+pub(crate) fn test_file_truncate(ctx: &TestCtx) {
+    let test_file = &ctx.path("truncate_test.txt");
+    
+    if let Some(meta) = expect_ok(fs::metadata(test_file), "Get initial metadata") {
+        expect_eq(meta.len(), TRUNCATE_CONTENT.len() as u64, "Initial size");
+    }
+    
+    step!("Truncating file to 10 bytes");
+    if let Some(file) = expect_ok(fs::OpenOptions::new().write(true).open(test_file), "Open file for truncation") {
+        if expect_ok(file.set_len(10), "Truncate file").is_some() {
+            if let Some(content) = expect_ok(fs::read(test_file), "Read truncated file") {
+                expect_eq_bytes(&content, &TRUNCATE_CONTENT.as_bytes()[..10], "Truncated content");
+            }
+        }
+    }
+}
+
+pub(crate) fn test_multiple_file_descriptors(ctx: &TestCtx) {
+    use std::io::Write;
+    
+    let file1 = &ctx.path("fd1.txt");
+    let file2 = &ctx.path("fd2.txt");
+    let file3 = &ctx.path("fd3.txt");
+    
+    step!("Opening multiple files simultaneously");
+    
+    let mut handles = Vec::new();
+    let file_paths = [file1, file2, file3];
+    
+    for (i, path) in file_paths.iter().enumerate() {
+        if let Some(file) = expect_ok(fs::File::create(path), &format!("Open file {}: {}", i + 1, path)) {
+            handles.push((i + 1, *path, file));
+        }
+    }
+    
+    step!("Writing to multiple files");
+    for (i, _path, ref mut file) in handles.iter_mut() {
+        let content = format!("Content for file {}\n", i);
+        expect_ok(file.write_all(content.as_bytes()), &format!("Write to file {}", i));
+    }
+    
+    step!("Closing all files");
+    handles.clear();
+    
+    step!("Verifying all files were written");
+    for (i, path) in file_paths.iter().enumerate() {
+        if let Some(content) = expect_ok(fs::read(path), &format!("Read {}", path)) {
+            let expected = format!("Content for file {}\n", i + 1);
+            expect_eq_bytes(&content, expected.as_bytes(), &format!("Content of {}", path));
+        }
+    }
+}
+
+pub(crate) fn test_large_file_operations(ctx: &TestCtx) {
+    use std::io::Write;
+    
+    let test_file = &ctx.path("large_file.txt");
+    let large_size = 1024 * 100; // 100KB
+    let chunk = b"0123456789ABCDEF";
+    let expected: Vec<u8> = chunk.iter().copied().cycle().take(large_size).collect();
+    
+    step!("Creating large file ({} bytes)", large_size);
+    let Some(mut file) = expect_ok(fs::File::create(test_file), "Create large file") else {
+        return;
+    };
+    for (i, piece) in expected.chunks(chunk.len()).enumerate() {
+        if let Err(e) = file.write_all(piece) {
+            fail!(e => "Failed to write chunk {}: {}", i, e);
+            return;
+        }
+        // Thousands of small writes can take a while on a slow backend
+        progress::heartbeat();
+    }
+    drop(file);
+    pass!("Large file created");
+    
+    if let Some(meta) = expect_ok(fs::metadata(test_file), "Get file metadata") {
+        expect_eq(meta.len(), large_size as u64, "File size");
+    }
+    
+    step!("Reading large file");
+    if let Some(data) = expect_ok(fs::read(test_file), "Read large file") {
+        expect_eq_bytes(&data, &expected, "Large file contents");
+    }
+}
+
+pub(crate) fn test_error_conditions(ctx: &TestCtx) {
+    use io::ErrorKind::{NotADirectory, NotFound};
+
+    // What each error looks like, compared as a snapshot
+    let mut errors = Vec::new();
+    let mut describe = |what: &str, result: &io::Result<_>| {
+        let outcome = match result {
+            Ok(_) => "ok".to_string(),
+            Err(e) => format!("{:?}: {}", e.kind(), e),
+        };
+        errors.push(format!("{}: {}", what, outcome));
+    };
+    
+    step!("Testing non-existent file read");
+    let missing_file = &ctx.path("nonexistent_file.txt");
+    let missing_dir = &ctx.path("nonexistent_dir");
+    let result = fs::read_to_string(missing_file).map(drop);
+    describe("read missing file", &result);
+    expect_err_kind(result, NotFound, "Read non-existent file");
+    
+    step!("Testing non-existent directory read");
+    let result = fs::read_dir(missing_dir).map(drop);
+    describe("read missing directory", &result);
+    expect_err_kind(result, NotFound, "Read non-existent directory");
+    
+    step!("Testing file in non-existent directory");
+    let result = fs::write(format!("{}/file.txt", missing_dir), "test");
+    describe("write in missing directory", &result);
+    expect_err_kind(result, NotFound, "Write to non-existent directory");
+    
+    step!("Testing removing non-existent file");
+    let result = fs::remove_file(missing_file);
+    describe("remove missing file", &result);
+    expect_err_kind(result, NotFound, "Remove non-existent file");
+    
+    let test_file = &ctx.path("error_test.txt");
+    step!("Testing removing file as directory");
+    let result = fs::remove_dir(test_file);
+    describe("remove file as directory", &result);
+    expect_err_kind(result, NotADirectory, "Remove file as directory");
+
+    snapshot::record("errors", errors.join("\n"));
+}
+
+pub(crate) fn test_file_permissions(ctx: &TestCtx) {
+    let test_file = &ctx.path("perms_test.txt");
+    
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        
+        step!("Getting current permissions");
+        if let Some(meta) = expect_ok(fs::metadata(test_file), "Get file metadata") {
+            detail!("Current mode: {:o}", meta.permissions().mode());
+            
+            step!("Setting new permissions");
+            let new_perms = fs::Permissions::from_mode(0o600);
+            if expect_ok(fs::set_permissions(test_file, new_perms), "Set permissions").is_some() {
+                if let Some(new_meta) = expect_ok(fs::metadata(test_file), "Get new metadata") {
+                    expect_eq(new_meta.permissions().mode() & 0o777, 0o600, "New mode");
+                }
+            }
+        }
+    }
+    
+    #[cfg(not(unix))]
+    {
+        // On WASI, we can still test permissions, just without mode() access
+        step!("Testing file permissions (WASI)");
+        
+        if let Some(meta) = expect_ok(fs::metadata(test_file), "Get file metadata") {
+            let perms = meta.permissions();
+            detail!("Permissions: {:?}", perms);
+            
+            // Set the same permissions: we can't read the numeric mode back on
+            // WASI, but we can test that the chmod syscall works
+            if expect_ok(fs::set_permissions(test_file, perms.clone()), "Set permissions").is_some() {
+                if let Some(new_meta) = expect_ok(fs::metadata(test_file), "Get new metadata") {
+                    expect_eq(new_meta.permissions().readonly(), perms.readonly(), "Read-only flag");
+                }
+                expect_ok(fs::read_to_string(test_file), "Read file after permission change");
+            }
+        }
+    }
+}
+
+/// How far `create_old_file` backdates its file.
+pub(crate) const FILE_AGE: Duration = Duration::from_secs(3600);
+
+/// Creates the timestamp test's file with a modified time in the past, where
+/// the runtime supports setting it.
+pub(crate) fn create_old_file(ctx: &TestCtx) -> io::Result<()> {
+    let file = fs::File::create(ctx.path("timestamp_test.txt"))?;
+    (&file).write_all(b"timestamp test")?;
+    match file.set_modified(SystemTime::now() - FILE_AGE) {
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => Ok(()),
+        result => result,
+    }
+}
+
+pub(crate) fn test_file_timestamps(ctx: &TestCtx) {
+    let test_file = &ctx.path("timestamp_test.txt");
+    
+    let mut modified = None;
+    if let Some(meta) = expect_ok(fs::metadata(test_file), "Get file metadata") {
+        modified = meta.modified().ok();
+        if let Some(modified) = modified {
+            detail!("Modified time: {:?}", modified);
+        }
+        if let Ok(accessed) = meta.accessed() {
+            detail!("Accessed time: {:?}", accessed);
+        }
+        if let Ok(created) = meta.created() {
+            detail!("Created time: {:?}", created);
+        }
+    }
+    
+    step!("Modifying file to update timestamps");
+    if expect_ok(fs::write(test_file, "updated content"), "Update file").is_some() {
+        if let Some(new_meta) = expect_ok(fs::metadata(test_file), "Get updated metadata") {
+            if let (Some(modified), Ok(new_modified)) = (modified, new_meta.modified()) {
+                detail!("New modified time: {:?}", new_modified);
+                if modified + FILE_AGE / 2 <= SystemTime::now() {
+                    expect_true(new_modified > modified, "Modified time moved forward");
+                } else {
+                    // Not backdated, so both writes may share a timestamp
+                    expect_true(new_modified >= modified, "Modified time didn't go backwards");
+                }
+            }
+        }
+    }
+}
+
+pub(crate) const FD_OPS_CONTENT: &str = "File descriptor operations test\nLine 2\nLine 3";
+
+pub(crate) fn test_file_descriptor_operations(ctx: &TestCtx) {
+    use std::io::SeekFrom;
+    
+    let test_file = &ctx.path("fd_ops.txt");
+    let content = FD_OPS_CONTENT;
+    
+    if let Some(mut file) = expect_ok(fs::File::open(test_file), "Open file") {
+        step!("Testing file position");
+        if let Some(position) = expect_ok(file.stream_position(), "Get position") {
+            expect_eq(position, 0, "Initial position");
+        }
+        
+        step!("Seeking to end");
+        if let Some(position) = expect_ok(file.seek(SeekFrom::End(0)), "Seek to end") {
+            expect_eq(position, content.len() as u64, "Position at end");
+            
+            step!("Seeking back to start");
+            if let Some(position) = expect_ok(file.seek(SeekFrom::Start(0)), "Seek to start") {
+                expect_eq(position, 0, "Position at start");
+                
+                let mut buffer = String::new();
+                if expect_ok(file.read_to_string(&mut buffer), "Read from start").is_some() {
+                    expect_eq(buffer.as_str(), content, "Content read from start");
+                }
+            }
+        }
+        
+        step!("Testing relative seek");
+        if expect_ok(file.seek(SeekFrom::Start(0)), "Seek to start").is_some() {
+            if let Some(position) = expect_ok(file.seek(SeekFrom::Current(10)), "Relative seek") {
+                expect_eq(position, 10, "Position after relative seek");
+                
+                let mut buffer = [0u8; 5];
+                if expect_ok(file.read_exact(&mut buffer), "Read after relative seek").is_some() {
+                    expect_eq_bytes(&buffer, &content.as_bytes()[10..15], "Bytes after relative seek");
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn test_concurrent_operations(ctx: &TestCtx) {
+    use std::io::Write;
+    
+    let base_dir = &ctx.path("concurrent");
+    
+    step!("Creating test directory");
+    if expect_ok(fs::create_dir_all(base_dir), "Create directory").is_none() {
+        return;
+    }
+    
+    step!("Creating multiple files concurrently");
+    let mut handles = Vec::new();
+    
+    for i in 0..5 {
+        let file_path = format!("{}/file_{}.txt", base_dir, i);
+        if let Some(mut file) = expect_ok(fs::File::create(&file_path), &format!("Create file {}", i)) {
+            let content = format!("Content for file {}\n", i);
+            if expect_ok(file.write_all(content.as_bytes()), &format!("Write to file {}", i)).is_some() {
+                handles.push((i, file_path, content));
+            }
+        }
+    }
+    
+    step!("Reading all files");
+    for (i, path, expected) in handles.iter() {
+        if let Some(content) = expect_ok(fs::read(path), &format!("Read file {}", i)) {
+            expect_eq_bytes(&content, expected.as_bytes(), &format!("Content of file {}", i));
+        }
+    }
+    
+    step!("Removing all files");
+    for (i, path, _) in handles.iter() {
+        expect_ok(fs::remove_file(path), &format!("Remove file {}", i));
+    }
+}
+
+pub(crate) const LINK_CONTENT: &str = "linked content";
+
+pub(crate) fn test_symlinks(ctx: &TestCtx) {
+    let target = &ctx.path("symlink_target.txt");
+    let link = &ctx.path("symlink.txt");
+    
+    step!("Creating symlink: {}", link);
+    #[allow(deprecated)]
+    let created = fs::soft_link(target, link);
+    if expect_ok(created, "Create symlink").is_none() {
+        return;
+    }
+    if let Some(read) = expect_ok(fs::read_link(link), "Read link") {
+        expect_eq(read.as_path(), Path::new(target), "Link target");
+    }
+    if let Some(meta) = expect_ok(fs::symlink_metadata(link), "Get link metadata") {
+        expect_true(meta.file_type().is_symlink(), "Is a symlink");
+    }
+    if let Some(content) = expect_ok(fs::read(link), "Read through symlink") {
+        expect_eq_bytes(&content, LINK_CONTENT.as_bytes(), "Content through symlink");
+    }
+    
+    step!("Removing the target");
+    if expect_ok(fs::remove_file(target), "Remove target").is_some() {
+        expect_err_kind(fs::read_to_string(link), io::ErrorKind::NotFound, "Read dangling symlink");
+        expect_true(fs::symlink_metadata(link).is_ok(), "Dangling symlink remains");
+    }
+}
+
+pub(crate) fn test_hard_links(ctx: &TestCtx) {
+    let source = &ctx.path("link_source.txt");
+    let link = &ctx.path("link_target.txt");
+    
+    step!("Creating hard link: {}", link);
+    if expect_ok(fs::hard_link(source, link), "Create hard link").is_none() {
+        return;
+    }
+    if let Some(content) = expect_ok(fs::read(link), "Read through link") {
+        expect_eq_bytes(&content, LINK_CONTENT.as_bytes(), "Content through link");
+    }
+    
+    step!("Writing through the link");
+    if expect_ok(fs::write(link, "changed"), "Write through link").is_some() {
+        if let Some(content) = expect_ok(fs::read(source), "Read source") {
+            expect_eq_bytes(&content, b"changed", "Source sees the change");
+        }
+    }
+    
+    step!("Removing the source");
+    if expect_ok(fs::remove_file(source), "Remove source").is_some() {
+        expect_true(Path::new(link).exists(), "Link outlives the source");
+    }
+}
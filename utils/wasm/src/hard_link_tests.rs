@@ -0,0 +1,183 @@
+//! Tests of hard links beyond the basics checked by the `hard_links` test:
+//! the link count in a file's metadata, what a change through one name
+//! does to the others, links between directories, and the links that
+//! can't be made.
+//!
+//! ZenFS's backends keep a file's names in different ways, so these pin
+//! down what POSIX expects of them all: every name is the same file, its
+//! link count is the number of names it has, and it lasts until the last
+//! of them is removed.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::harness::assert::{expect_eq, expect_eq_bytes, expect_err_kind, expect_ok, expect_true};
+use crate::harness::context::TestCtx;
+
+pub(crate) const SOURCE_CONTENT: &str = "hard link source";
+
+/// Checks that the file at `path` holds `expected`.
+fn expect_content(path: &str, expected: &[u8], what: &str) {
+    if let Some(content) = expect_ok(fs::read(path), &format!("Read {}", path)) {
+        expect_eq_bytes(&content, expected, what);
+    }
+}
+
+// WASI's filestat has a link count, but std only shows it to WASM programs
+// behind an unstable feature
+#[cfg(unix)]
+pub(crate) fn test_hard_link_count(ctx: &TestCtx) {
+    use std::os::unix::fs::MetadataExt;
+
+    let source = &ctx.path("source.txt");
+    let (first, second) = (&ctx.path("first.txt"), &ctx.path("sub/second.txt"));
+    let expect_count = |path: &str, count: u64| {
+        if let Some(metadata) = expect_ok(fs::metadata(path), &format!("Metadata of {}", path)) {
+            expect_eq(metadata.nlink(), count, &format!("Link count of {}", path));
+        }
+    };
+
+    step!("Counting the names of a new file");
+    expect_count(source, 1);
+
+    step!("Adding names");
+    if expect_ok(fs::hard_link(source, first), "Link in the same directory").is_none()
+        || expect_ok(fs::hard_link(first, second), "Link from a link, in another directory").is_none()
+    {
+        return;
+    }
+    for path in [source, first, second] {
+        expect_count(path, 3);
+    }
+    if let (Some(original), Some(linked)) = (
+        expect_ok(fs::metadata(source), "Metadata of the source"),
+        expect_ok(fs::metadata(second), "Metadata of the link"),
+    ) {
+        expect_eq((linked.dev(), linked.ino()), (original.dev(), original.ino()), "Same file");
+    }
+
+    step!("Removing names");
+    if expect_ok(fs::remove_file(source), "Remove the source").is_some() {
+        expect_count(first, 2);
+        expect_count(second, 2);
+    }
+    if expect_ok(fs::remove_file(first), "Remove the first link").is_some() {
+        expect_count(second, 1);
+    }
+
+    step!("Comparing with a copy");
+    let copy = &ctx.path("copy.txt");
+    if expect_ok(fs::copy(second, copy), "Copy the file").is_some() {
+        expect_count(copy, 1);
+        expect_count(second, 1);
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn test_hard_link_count(_ctx: &TestCtx) {
+    skip!("Link counts aren't visible on this target");
+}
+
+pub(crate) fn test_hard_link_names(ctx: &TestCtx) {
+    let source = &ctx.path("source.txt");
+    let link = &ctx.path("link.txt");
+
+    step!("Linking a second name");
+    if expect_ok(fs::hard_link(source, link), "Create hard link").is_none() {
+        return;
+    }
+    expect_content(link, SOURCE_CONTENT.as_bytes(), "Content through the link");
+
+    step!("Changing the file through each name");
+    if expect_ok(fs::write(link, "rewritten"), "Replace the content through the link").is_some() {
+        expect_content(source, b"rewritten", "Source sees the new content");
+    }
+    let appended = fs::OpenOptions::new().append(true).open(source).and_then(|mut file| file.write_all(b" twice"));
+    if expect_ok(appended, "Append through the source").is_some() {
+        expect_content(link, b"rewritten twice", "Link sees the appended content");
+    }
+    let truncated = fs::OpenOptions::new().write(true).open(link).and_then(|file| file.set_len(4));
+    if expect_ok(truncated, "Truncate through the link").is_some() {
+        if let Some(metadata) = expect_ok(fs::metadata(source), "Metadata of the source") {
+            expect_eq(metadata.len(), 4, "Source is truncated too");
+        }
+    }
+
+    step!("Removing the source");
+    if expect_ok(fs::remove_file(source), "Remove the source").is_none() {
+        return;
+    }
+    expect_err_kind(fs::metadata(source), io::ErrorKind::NotFound, "Source name gone");
+    expect_content(link, b"rewr", "Link keeps the content");
+
+    step!("Reusing the source's name");
+    if expect_ok(fs::write(source, "new file"), "Create a new file with the source's name").is_some() {
+        expect_content(link, b"rewr", "Link isn't the new file");
+        expect_content(source, b"new file", "New file has its own content");
+    }
+}
+
+pub(crate) fn test_hard_link_directories(ctx: &TestCtx) {
+    let source = &ctx.path("from/file.txt");
+    let link = &ctx.path("to/file.txt");
+
+    step!("Linking into another directory");
+    if expect_ok(fs::hard_link(source, link), "Link across directories").is_none() {
+        return;
+    }
+    expect_content(link, SOURCE_CONTENT.as_bytes(), "Content through the link");
+    if let Some(entries) = expect_ok(fs::read_dir(ctx.path("to")), "List the link's directory") {
+        let names: Vec<String> =
+            entries.filter_map(Result::ok).map(|entry| entry.file_name().to_string_lossy().into_owned()).collect();
+        expect_eq(names, vec!["file.txt".to_string()], "Entries of the link's directory");
+    }
+
+    step!("Renaming one name onto the other");
+    // Both name the same file, so POSIX has rename do nothing
+    if expect_ok(fs::rename(source, link), "Rename onto another name of the same file").is_some() {
+        expect_true(Path::new(source).is_file(), "Source name left alone");
+        expect_true(Path::new(link).is_file(), "Link name left alone");
+    }
+
+    step!("Moving a name to a third directory");
+    let moved = &ctx.path("third/moved.txt");
+    if expect_ok(fs::rename(link, moved), "Move the link").is_some() {
+        expect_content(moved, SOURCE_CONTENT.as_bytes(), "Content under the new name");
+        if expect_ok(fs::write(moved, "moved"), "Write through the moved name").is_some() {
+            expect_content(source, b"moved", "Source sees the change");
+        }
+    }
+
+    step!("Removing the source's directory");
+    if expect_ok(fs::remove_file(source), "Remove the source").is_some()
+        && expect_ok(fs::remove_dir(ctx.path("from")), "Remove its directory").is_some()
+    {
+        expect_content(moved, b"moved", "Link outlives the directory");
+    }
+}
+
+pub(crate) fn test_hard_link_errors(ctx: &TestCtx) {
+    let source = &ctx.path("source.txt");
+
+    step!("Linking what can't be linked");
+    let missing = &ctx.path("missing.txt");
+    expect_err_kind(fs::hard_link(missing, ctx.path("from_missing.txt")), io::ErrorKind::NotFound, "Link to nothing");
+    let dir_link = &ctx.path("dir_link");
+    expect_err_kind(fs::hard_link(ctx.path("dir"), dir_link), io::ErrorKind::PermissionDenied, "Link a directory");
+    expect_true(!Path::new(dir_link).exists(), "No directory link made");
+
+    step!("Linking over existing names");
+    let existing = &ctx.path("existing.txt");
+    if expect_ok(fs::write(existing, "existing"), "Create a file").is_some() {
+        expect_err_kind(fs::hard_link(source, existing), io::ErrorKind::AlreadyExists, "Link over a file");
+        expect_content(existing, b"existing", "File left as it was");
+    }
+    expect_err_kind(fs::hard_link(source, ctx.path("dir")), io::ErrorKind::AlreadyExists, "Link over a directory");
+    expect_err_kind(fs::hard_link(source, source), io::ErrorKind::AlreadyExists, "Link a file to its own name");
+
+    step!("Linking into a missing directory");
+    let orphan = ctx.path("missing_dir/link.txt");
+    expect_err_kind(fs::hard_link(source, orphan), io::ErrorKind::NotFound, "Link into a missing directory");
+    expect_content(source, SOURCE_CONTENT.as_bytes(), "Source left as it was");
+}
@@ -0,0 +1,1151 @@
+//! Result recording and reporting for the WASM interface test suite.
+//!
+//! Tests describe what they do through the `step!`, `detail!`, `pass!` and
+//! `fail!` macros, or the checks in [`assert`], instead of printing
+//! directly, and each message is recorded against the running test. [`run`]
+//! runs a test and works out its [`Status`] from what it recorded,
+//! [`report`] writes the result in the chosen [`Format`] and [`Verbosity`],
+//! and [`end`] writes the summary. The suite exits with status 1 if any
+//! test failed unexpectedly, so scripts can gate on it.
+//!
+//! The rest of the harness is in its submodules:
+//!
+//! - [`registry`]: the tests, their tags and fixtures, and sharding
+//! - [`context`]: the scratch directories tests work in
+//! - [`capabilities`] and [`skiplist`]: tests skipped without running
+//! - [`snapshot`]: outputs compared with golden files
+//! - [`parallel`]: running tests concurrently with `--jobs`
+//! - [`progress`]: test numbers and the heartbeat of long tests
+//! - [`random`]: the seed and `--seed`
+//! - [`baseline`]: comparing a run with an earlier one
+//! - [`persist`]: the report saved under `/var/log/wasm-tests`
+//! - [`log`]: diagnostics, which never go to stdout
+//! - [`selftest`]: `--self-test`, checks of the harness itself
+
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// First, so its macros can be used by the modules after it
+#[macro_use]
+pub mod log;
+
+pub mod assert;
+pub mod baseline;
+pub mod capabilities;
+pub mod context;
+pub mod parallel;
+pub mod persist;
+pub mod progress;
+pub mod random;
+pub mod registry;
+pub mod selftest;
+pub mod skiplist;
+pub mod snapshot;
+pub mod table;
+
+use capabilities::{Capabilities, Capability};
+use context::Session;
+use registry::TestCase;
+use table::{Align, Table};
+
+/// How results are written.
+///
+/// In JSON mode a line listing the capabilities the runtime lacks comes
+/// first, then nothing is printed while a test runs; once it finishes a
+/// single line holding its result object is written, with its time in
+/// milliseconds as `duration`. TAP mode writes Test Anything Protocol lines
+/// (`ok 1 - name`), with the failures of a test as diagnostics below it, its
+/// time in a YAML block (`duration_ms`), and XFAIL and XPASS marked
+/// `# TODO`. Output a test writes itself (the stdio test does, on purpose)
+/// passes through unchanged in either mode, so consumers should skip lines
+/// they don't recognise.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Tap,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "tap" => Some(Format::Tap),
+            _ => None,
+        }
+    }
+}
+
+/// How much text mode prints. By default that's only the failures of
+/// tests that failed, once they finish; with `-v` or `-vv` it's every
+/// message as it happens, and each test's time below its messages.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Verbosity {
+    /// The summary only (`-q`).
+    Quiet,
+    /// Failures and the summary.
+    Normal,
+    /// Every message as it's reported, except details (`-v`).
+    Verbose,
+    /// Every message, details included (`-vv`).
+    Debug,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Step,
+    Detail,
+    Pass,
+    Fail,
+    Skip,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Step => "step",
+            Kind::Detail => "detail",
+            Kind::Pass => "pass",
+            Kind::Fail => "fail",
+            Kind::Skip => "skip",
+        }
+    }
+}
+
+pub struct Message {
+    pub kind: Kind,
+    pub text: String,
+    /// Source file and line the message was reported from.
+    pub location: Option<(&'static str, u32)>,
+    /// Values of a failed comparison, formatted with `{:?}`.
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+    /// The OS error the message was reported with.
+    pub error: Option<OsError>,
+}
+
+impl Message {
+    pub fn new(kind: Kind, text: String) -> Message {
+        Message { kind, text, location: None, expected: None, actual: None, error: None }
+    }
+}
+
+/// What an `io::Error` says about its cause, as opposed to its text.
+///
+/// Messages carry it when reported with the error that caused them
+/// (`fail!(e => "...")` or a failed [`assert::expect_ok`]), and the first
+/// errno a test reports is its result's `errno`, so the kernel can tell
+/// which syscall misbehaved. With the kind [`assert::expect_err_kind`]
+/// expected, the errnos the kernel returns can be tabulated against the
+/// ones POSIX calls for.
+#[derive(Clone, Copy)]
+pub struct OsError {
+    pub kind: io::ErrorKind,
+    /// The raw errno, if the error came from the OS.
+    pub errno: Option<i32>,
+}
+
+impl OsError {
+    fn of(error: &io::Error) -> OsError {
+        OsError { kind: error.kind(), errno: error.raw_os_error() }
+    }
+}
+
+/// How a test went. It fails if it reported any failure, and is skipped if
+/// it reported a `skip!` (because the target doesn't support what it tests)
+/// without failing, or without running if it needs a capability the runtime
+/// lacks or is on the skip list. A test registered with an `xfail` reason
+/// covers a known gap in the kernel: if it fails it's XFAIL rather than
+/// failed, and if it passes XPASS, a sign the annotation can go.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Status {
+    Passed,
+    Failed,
+    Skipped,
+    /// Failed as expected.
+    XFailed,
+    /// Passed although expected to fail.
+    XPassed,
+    /// Failed, then passed when retried; doesn't fail the run.
+    Flaky,
+}
+
+impl Status {
+    fn name(self) -> &'static str {
+        match self {
+            Status::Passed => "passed",
+            Status::Failed => "failed",
+            Status::Skipped => "skipped",
+            Status::XFailed => "xfail",
+            Status::XPassed => "xpass",
+            Status::Flaky => "flaky",
+        }
+    }
+}
+
+pub struct TestResult {
+    pub name: &'static str,
+    pub title: &'static str,
+    /// The test's first tag, which names the group it's in.
+    pub category: &'static str,
+    pub status: Status,
+    pub duration: Duration,
+    pub messages: Vec<Message>,
+    pub errno: Option<i32>,
+    /// Why the test was expected to fail.
+    pub xfail: Option<&'static str>,
+    /// How many times the test ran: more than once if it failed and was
+    /// retried (see [`set_retries`]).
+    pub attempts: usize,
+}
+
+/// Totals of the results reported so far.
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub xfailed: usize,
+    pub xpassed: usize,
+    /// Tests that passed on a retry, with the attempt they passed on, in run
+    /// order.
+    pub flakes: Vec<(&'static str, usize)>,
+    /// Names of the failed tests, in run order.
+    pub failures: Vec<&'static str>,
+    /// Names of the tests that passed although expected to fail.
+    pub xpasses: Vec<&'static str>,
+    /// How often each test that ran passed and failed, counting XPASS as
+    /// passing and XFAIL as failing, in the order they first ran.
+    pub outcomes: Vec<Outcomes>,
+    /// The last result of each test reported, in the order they first ran.
+    pub results: Vec<Reported>,
+}
+
+pub struct Reported {
+    pub name: &'static str,
+    pub category: &'static str,
+    pub status: Status,
+    pub duration: Duration,
+}
+
+pub struct Outcomes {
+    pub name: &'static str,
+    pub passed: usize,
+    pub failed: usize,
+    /// Time spent in the test over all those runs.
+    pub duration: Duration,
+}
+
+impl Outcomes {
+    pub fn runs(&self) -> usize {
+        self.passed + self.failed
+    }
+
+    /// The time the test took per run.
+    pub fn mean(&self) -> Duration {
+        self.duration / self.runs().max(1) as u32
+    }
+}
+
+impl Summary {
+    const fn new() -> Summary {
+        Summary {
+            passed: 0,
+            failed: 0,
+            skipped: 0,
+            xfailed: 0,
+            xpassed: 0,
+            flakes: Vec::new(),
+            failures: Vec::new(),
+            xpasses: Vec::new(),
+            outcomes: Vec::new(),
+            results: Vec::new(),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.passed + self.failed + self.skipped + self.xfailed + self.xpassed + self.flakes.len()
+    }
+
+    /// Tests that passed in some iterations and failed in others.
+    pub fn flaky(&self) -> impl Iterator<Item = &Outcomes> {
+        self.outcomes.iter().filter(|outcomes| outcomes.passed > 0 && outcomes.failed > 0)
+    }
+
+    /// The `count` tests that took longest on average, slowest first.
+    pub fn slowest(&self, count: usize) -> Vec<&Outcomes> {
+        let mut slowest: Vec<&Outcomes> = self.outcomes.iter().collect();
+        slowest.sort_by_key(|outcomes| Reverse(outcomes.mean()));
+        slowest.truncate(count);
+        slowest
+    }
+
+    fn record(&mut self, name: &'static str, passed: bool, duration: Duration) {
+        let index = match self.outcomes.iter().position(|outcomes| outcomes.name == name) {
+            Some(index) => index,
+            None => {
+                self.outcomes.push(Outcomes { name, passed: 0, failed: 0, duration: Duration::ZERO });
+                self.outcomes.len() - 1
+            }
+        };
+        let outcomes = &mut self.outcomes[index];
+        outcomes.duration += duration;
+        if passed {
+            outcomes.passed += 1;
+        } else {
+            outcomes.failed += 1;
+        }
+    }
+}
+
+static FORMAT: OnceLock<Format> = OnceLock::new();
+static VERBOSITY: OnceLock<Verbosity> = OnceLock::new();
+static SUMMARY: Mutex<Summary> = Mutex::new(Summary::new());
+static PLAN_AT_END: AtomicBool = AtomicBool::new(false);
+static RETRIES: AtomicUsize = AtomicUsize::new(0);
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// How many of the slowest tests the summary lists.
+const SLOWEST: usize = 10;
+
+/// The test running on this thread, for the panic hook to report.
+#[derive(Clone, Copy)]
+struct Running {
+    number: usize,
+    name: &'static str,
+    title: &'static str,
+    category: &'static str,
+    xfail: Option<&'static str>,
+    started: Instant,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<(Vec<Message>, Option<i32>)>> = const { RefCell::new(None) };
+    static RUNNING: Cell<Option<Running>> = const { Cell::new(None) };
+    /// Whether messages are being kept apart from the test's (see
+    /// [`recorded`]), and so aren't printed as they come.
+    static CAPTURING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets how many more times a failing test is run before it's reported as
+/// failed; must be called before any test runs.
+pub fn set_retries(retries: usize) {
+    RETRIES.store(retries, Ordering::Relaxed);
+}
+
+/// Draws the summary table and marks passes and failures in text mode with
+/// ASCII characters only.
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+fn ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// Selects the output format; must be called before any test runs.
+pub fn set_format(format: Format) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn format() -> Format {
+    *FORMAT.get().unwrap_or(&Format::Text)
+}
+
+/// Selects how much text mode prints; must be called before any test runs.
+pub fn set_verbosity(verbosity: Verbosity) {
+    let _ = VERBOSITY.set(verbosity);
+}
+
+fn verbosity() -> Verbosity {
+    *VERBOSITY.get().unwrap_or(&Verbosity::Normal)
+}
+
+/// Whether text mode prints output that needs at least `verbosity`.
+fn shows(verbosity: Verbosity) -> bool {
+    format() == Format::Text && self::verbosity() >= verbosity
+}
+
+/// Whether messages needing `verbosity` are printed as they're reported,
+/// which they aren't while tests run concurrently.
+fn shows_live(verbosity: Verbosity) -> bool {
+    shows(verbosity) && parallel::jobs() == 1
+}
+
+/// The verbosity a message needs to be printed.
+fn verbosity_of(message: &Message) -> Verbosity {
+    if message.kind == Kind::Detail {
+        Verbosity::Debug
+    } else {
+        Verbosity::Verbose
+    }
+}
+
+/// Records a message against the running test. Used through the macros.
+pub fn record(kind: Kind, text: String, error: Option<&io::Error>) {
+    record_message(Message::new(kind, text), error);
+}
+
+/// Records a message with its location and compared values, if any.
+pub fn record_message(mut message: Message, error: Option<&io::Error>) {
+    progress::heartbeat();
+    message.error = error.map(OsError::of);
+    if shows_live(verbosity_of(&message)) && !CAPTURING.get() {
+        print_message(&message);
+    }
+
+    CURRENT.with(|current| {
+        if let Some((messages, errno)) = current.borrow_mut().as_mut() {
+            if errno.is_none() {
+                *errno = error.and_then(io::Error::raw_os_error);
+            }
+            messages.push(message);
+        }
+    });
+}
+
+/// Prints a message in text mode.
+fn print_message(message: &Message) {
+    let text = &message.text;
+    match message.kind {
+        Kind::Step => println!("  {}", text),
+        Kind::Detail => println!("    {}", text),
+        Kind::Pass => println!("  {} {}", if ascii() { "+" } else { "✓" }, text),
+        Kind::Fail => {
+            let mut lines = text.lines();
+            let first = lines.next().unwrap_or_default();
+            let mark = if ascii() { "x" } else { "✗" };
+            match message.location {
+                Some((file, line)) => eprintln!("  {} {} ({}:{})", mark, first, file, line),
+                None => eprintln!("  {} {}", mark, first),
+            }
+            for line in lines.map(str::to_string).chain(comparison(message)) {
+                eprintln!("      {}", line);
+            }
+        }
+        Kind::Skip => println!("  - skipped: {}", text),
+    }
+}
+
+/// The `expected:`/`actual:` lines shown below a failed comparison, and the
+/// `error:` line of a failure caused by an OS error.
+fn comparison(message: &Message) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(expected) = &message.expected {
+        lines.push(format!("expected: {}", expected));
+    }
+    if let Some(actual) = &message.actual {
+        lines.push(format!("actual:   {}", actual));
+    }
+    match message.error {
+        Some(OsError { kind, errno: Some(errno) }) => lines.push(format!("error:    {:?} (errno {})", kind, errno)),
+        Some(OsError { kind, errno: None }) => lines.push(format!("error:    {:?}", kind)),
+        None => {}
+    }
+    lines
+}
+
+/// Runs a single test in its own scratch directory and collects what it
+/// reported. A test on the skip list (see [`skiplist`]) or requiring a
+/// capability the runtime lacks (see [`capabilities`]) is skipped.
+///
+/// A test that panics fails with the panic's message. Where panics unwind
+/// its teardown still runs and the suite carries on after it. On `wasm32`
+/// targets they abort instead, so a panic hook reports the test as failed,
+/// writes the summary and saves the report before the run ends with it.
+///
+/// A test that fails unexpectedly is run again, in a new scratch directory,
+/// up to the number of retries set; if it passes on one of them it's
+/// reported as flaky. The result holds the messages of every attempt, with
+/// a step marking where each retry began, and the time they took together.
+pub fn run(test: &TestCase, session: &Session, capabilities: &Capabilities) -> TestResult {
+    run_numbered(test, progress::reserve(1), session, capabilities)
+}
+
+/// [`run`] for a test whose number was claimed with [`progress::reserve`].
+fn run_numbered(test: &TestCase, number: usize, session: &Session, capabilities: &Capabilities) -> TestResult {
+    progress::start(test.name, number);
+    RUNNING.set(Some(Running {
+        number,
+        name: test.name,
+        title: test.title,
+        category: test.tags.first().copied().unwrap_or_default(),
+        xfail: test.xfail,
+        started: Instant::now(),
+    }));
+    if shows_live(Verbosity::Verbose) {
+        println!("\n{} {}", progress::label(number), test.title);
+    }
+
+    let attempts = RETRIES.load(Ordering::Relaxed) + 1;
+    let mut result = attempt(test, session, capabilities);
+    while result.status == Status::Failed && result.attempts < attempts {
+        let retry = Message::new(Kind::Step, format!("Retrying (attempt {} of {})", result.attempts + 1, attempts));
+        if shows_live(verbosity_of(&retry)) {
+            print_message(&retry);
+        }
+        info!("{}: failed; retrying", test.name);
+        let mut next = attempt(test, session, capabilities);
+        if next.status == Status::Passed {
+            next.status = Status::Flaky;
+        }
+        next.attempts += result.attempts;
+        next.duration += result.duration;
+        next.errno = result.errno.or(next.errno);
+        result.messages.push(retry);
+        result.messages.append(&mut next.messages);
+        next.messages = result.messages;
+        result = next;
+    }
+    RUNNING.set(None);
+    progress::finish();
+    result
+}
+
+/// Runs a test once.
+fn attempt(test: &TestCase, session: &Session, capabilities: &Capabilities) -> TestResult {
+    CURRENT.with(|current| *current.borrow_mut() = Some((Vec::new(), None)));
+    snapshot::start();
+    let start = Instant::now();
+    let skip = skiplist::listed(test.name).or_else(|| {
+        let (capability, reason) = capabilities.first_missing(test.requires)?;
+        Some(format!("Runtime lacks {} ({})", capability.name(), reason))
+    });
+    let context = match skip {
+        Some(text) => {
+            record_message(Message::new(Kind::Skip, text), None);
+            None
+        }
+        None => Some(session.context(test.name)),
+    };
+    if let Some(Ok(ctx)) = &context {
+        debug!("{}: running in {}", test.name, ctx.dir().display());
+    }
+    match context {
+        None => {}
+        Some(Ok(ctx)) => {
+            match test.set_up(&ctx) {
+                Ok(()) => match panic::catch_unwind(AssertUnwindSafe(|| (test.run)(&ctx))) {
+                    Ok(()) => snapshot::finish(test.name),
+                    Err(payload) => {
+                        let text = format!("Panicked: {}", panic_message(&*payload));
+                        record_message(Message::new(Kind::Fail, text), None);
+                    }
+                },
+                Err(e) => {
+                    let text = format!("Failed to set up the test's fixtures: {}", e);
+                    record_message(Message::new(Kind::Fail, text), Some(&e));
+                }
+            }
+            if let Some(teardown) = test.teardown {
+                teardown(&ctx);
+            }
+        }
+        Some(Err(e)) => {
+            let text = format!("Failed to create the test's directory: {}", e);
+            record_message(Message::new(Kind::Fail, text), Some(&e));
+        }
+    }
+    let duration = start.elapsed();
+    debug!("{}: finished in {:.3} ms", test.name, duration.as_secs_f64() * 1000.0);
+    let (messages, errno) = CURRENT.with(|current| current.borrow_mut().take()).unwrap_or_default();
+
+    TestResult {
+        name: test.name,
+        title: test.title,
+        category: test.tags.first().copied().unwrap_or_default(),
+        status: status(&messages, test.xfail.is_some()),
+        duration,
+        messages,
+        errno,
+        xfail: test.xfail,
+        attempts: 1,
+    }
+}
+
+/// The status of a test that reported `messages`, and was or wasn't
+/// expected to fail.
+fn status(messages: &[Message], xfail: bool) -> Status {
+    let reported = |kind| messages.iter().any(|message: &Message| message.kind == kind);
+    match (reported(Kind::Fail), reported(Kind::Skip), xfail) {
+        (true, _, false) => Status::Failed,
+        (true, _, true) => Status::XFailed,
+        (false, true, _) => Status::Skipped,
+        (false, false, false) => Status::Passed,
+        (false, false, true) => Status::XPassed,
+    }
+}
+
+/// Runs `f` and returns what it recorded, kept apart from the messages of
+/// the test running on this thread.
+fn recorded(f: impl FnOnce()) -> (Vec<Message>, Option<i32>) {
+    let outer = CURRENT.with(|current| current.replace(Some((Vec::new(), None))));
+    let capturing = CAPTURING.replace(true);
+    let outcome = panic::catch_unwind(AssertUnwindSafe(f));
+    CAPTURING.set(capturing);
+    let inner = CURRENT.with(|current| current.replace(outer)).unwrap_or_default();
+    if let Err(payload) = outcome {
+        panic::resume_unwind(payload);
+    }
+    inner
+}
+
+/// Makes a panic in a test fail the test rather than lose the run's results,
+/// where panics abort and can't be caught: the test is reported as failed,
+/// then the summary is written and the report saved as if the run had
+/// ended there. Tests that haven't been reported yet, such as those still
+/// running on other threads, are left out.
+fn set_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let Some(running) = RUNNING.take() else { return };
+        let result = abandon(&running, panic_message(info.payload()));
+        report(running.number, &result);
+        if format() == Format::Tap {
+            println!("Bail out! {} panicked and the runtime can't carry on", running.name);
+        }
+        end();
+        persist::save();
+        let _ = io::Write::flush(&mut io::stdout());
+    }));
+}
+
+/// The result of the running test, which panicked with `message` and can't
+/// carry on: what it reported before, and the panic as a failure.
+fn abandon(running: &Running, message: &str) -> TestResult {
+    let failure = Message::new(Kind::Fail, format!("Panicked: {}", message));
+    if shows_live(verbosity_of(&failure)) && !CAPTURING.get() {
+        print_message(&failure);
+    }
+    // The panic may have come while the messages were borrowed
+    let recorded = CURRENT.with(|current| current.try_borrow_mut().ok().and_then(|mut current| current.take()));
+    let (mut messages, errno) = recorded.unwrap_or_default();
+    messages.push(failure);
+    TestResult {
+        name: running.name,
+        title: running.title,
+        category: running.category,
+        status: status(&messages, running.xfail.is_some()),
+        duration: running.started.elapsed(),
+        messages,
+        errno,
+        xfail: running.xfail,
+        attempts: 1,
+    }
+}
+
+/// The message a panic was raised with, if it was given one.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => payload.downcast_ref::<String>().map_or("(no message)", String::as_str),
+    }
+}
+
+/// Starts the output for a run of `count` tests, showing the seed and the
+/// scratch directory, and listing the capabilities the runtime lacks. With
+/// no count, as when the tests to run are read as the run goes, TAP's plan
+/// comes at the end.
+pub fn begin(count: Option<usize>, session: &Session, capabilities: &Capabilities) {
+    let missing: Vec<(Capability, &str)> = Capability::ALL
+        .iter()
+        .filter_map(|&capability| capabilities.missing(capability).map(|reason| (capability, reason)))
+        .collect();
+    progress::set_total(count);
+    if cfg!(panic = "abort") {
+        set_panic_hook();
+    }
+    json_line(|| {
+        let mut out = format!("{{\"seed\":{},\"scratch\":", random::seed());
+        push_json_str(&mut out, &session.dir().to_string_lossy());
+        out.push_str(",\"capabilities\":{\"missing\":{");
+        for (i, (capability, reason)) in missing.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "\"{}\":", capability.name());
+            push_json_str(&mut out, reason);
+        }
+        out.push_str("}}}");
+        out
+    });
+    match format() {
+        Format::Text if !shows(Verbosity::Normal) => {}
+        Format::Text => {
+            println!("=== WASM Interface Test Suite ===");
+            println!("  seed: {}", random::seed());
+            println!("  scratch: {}", session.dir().display());
+            for (capability, reason) in &missing {
+                println!("  no {}: {}", capability.name(), reason);
+            }
+        }
+        Format::Json => {}
+        Format::Tap => {
+            println!("TAP version 13");
+            match count {
+                Some(count) => println!("1..{}", count),
+                None => PLAN_AT_END.store(true, Ordering::Relaxed),
+            }
+            println!("# seed: {}", random::seed());
+            println!("# scratch: {}", session.dir().display());
+            for (capability, reason) in &missing {
+                println!("# no {}: {}", capability.name(), reason);
+            }
+        }
+    }
+}
+
+/// Announces the `number`th of `count` iterations of the suite, listing
+/// the order the tests run in if it was shuffled. Nothing is written for a
+/// single iteration in the usual order.
+///
+/// Running the suite several times over (`--iterations`), in a new random
+/// order each time with `--shuffle`, flushes out bugs that only show up
+/// intermittently or after particular other tests. A test that passed in
+/// some iterations and failed in others is listed as flaky in the summary.
+pub fn iteration(number: usize, count: usize, order: Option<&[&TestCase]>) {
+    if count == 1 && order.is_none() {
+        return;
+    }
+    let names: Vec<&str> = order.unwrap_or_default().iter().map(|test| test.name).collect();
+    json_line(|| {
+        let mut out = format!("{{\"iteration\":{},\"of\":{}", number, count);
+        if order.is_some() {
+            out.push_str(",\"order\":[");
+            for (i, name) in names.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                push_json_str(&mut out, name);
+            }
+            out.push(']');
+        }
+        out.push('}');
+        out
+    });
+    match format() {
+        Format::Text if !shows(Verbosity::Normal) => {}
+        Format::Text => {
+            println!("\n=== Iteration {} of {} ===", number, count);
+            if order.is_some() {
+                println!("  order: {}", names.join(", "));
+            }
+        }
+        Format::Json => {}
+        Format::Tap => {
+            println!("# iteration {} of {}", number, count);
+            if order.is_some() {
+                println!("# order: {}", names.join(", "));
+            }
+        }
+    }
+}
+
+/// Writes the result of the `number`th test (counting from 1) once it has
+/// finished.
+pub fn report(number: usize, result: &TestResult) {
+    {
+        let mut summary = SUMMARY.lock().unwrap();
+        match result.status {
+            Status::Passed => summary.passed += 1,
+            Status::Failed => {
+                summary.failed += 1;
+                summary.failures.push(result.name);
+            }
+            Status::Skipped => summary.skipped += 1,
+            Status::XFailed => summary.xfailed += 1,
+            Status::XPassed => {
+                summary.xpassed += 1;
+                summary.xpasses.push(result.name);
+            }
+            Status::Flaky => summary.flakes.push((result.name, result.attempts)),
+        }
+        match result.status {
+            Status::Passed | Status::XPassed | Status::Flaky => summary.record(result.name, true, result.duration),
+            Status::Failed | Status::XFailed => summary.record(result.name, false, result.duration),
+            Status::Skipped => {}
+        }
+        let reported = Reported {
+            name: result.name,
+            category: result.category,
+            status: result.status,
+            duration: result.duration,
+        };
+        match summary.results.iter_mut().find(|earlier| earlier.name == result.name) {
+            Some(earlier) => *earlier = reported,
+            None => summary.results.push(reported),
+        }
+    }
+
+    json_line(|| to_json(result));
+    let reason = result.xfail.unwrap_or_default();
+    match format() {
+        Format::Text if shows(Verbosity::Verbose) => {
+            // Messages that couldn't be printed as they came are printed now
+            if !shows_live(Verbosity::Verbose) {
+                println!("\n{} {}", progress::label(number), result.title);
+                for message in result.messages.iter().filter(|message| shows(verbosity_of(message))) {
+                    print_message(message);
+                }
+            }
+            match result.status {
+                Status::XFailed => println!("  XFAIL: {}", reason),
+                Status::XPassed => println!("  XPASS: passed although expected to fail ({})", reason),
+                Status::Flaky => println!("  FLAKY: passed on attempt {}", result.attempts),
+                _ => {}
+            }
+            println!("  time: {:.3} ms", ms(result.duration));
+        }
+        // The messages weren't printed as they came, so a failed test's
+        // failures are printed now
+        Format::Text if shows(Verbosity::Normal) => match result.status {
+            Status::Failed | Status::Flaky => {
+                println!("\n{} {}", progress::label(number), result.title);
+                for message in result.messages.iter().filter(|message| message.kind == Kind::Fail) {
+                    print_message(message);
+                }
+                if result.status == Status::Flaky {
+                    println!("  FLAKY: passed on attempt {}", result.attempts);
+                }
+            }
+            Status::XPassed => {
+                println!("\n{} {}", progress::label(number), result.title);
+                println!("  XPASS: passed although expected to fail ({})", reason);
+            }
+            _ => {}
+        },
+        Format::Text | Format::Json => {}
+        Format::Tap => {
+            match result.status {
+                Status::Passed => println!("ok {} - {}", number, result.name),
+                Status::Failed => println!("not ok {} - {}", number, result.name),
+                Status::XFailed => println!("not ok {} - {} # TODO {}", number, result.name, reason),
+                Status::XPassed => println!("ok {} - {} # TODO {}", number, result.name, reason),
+                Status::Flaky => {
+                    println!("ok {} - {} (flaky, passed on attempt {})", number, result.name, result.attempts)
+                }
+                Status::Skipped => {
+                    let reason = result.messages.iter().find(|message| message.kind == Kind::Skip);
+                    let reason = reason.map(|message| message.text.as_str()).unwrap_or_default();
+                    println!("ok {} - {} # SKIP {}", number, result.name, reason);
+                }
+            }
+            println!("  ---\n  duration_ms: {:.3}\n  ...", ms(result.duration));
+            for message in result.messages.iter().filter(|message| message.kind == Kind::Fail) {
+                for line in message.text.lines() {
+                    println!("# {}", line);
+                }
+                if let Some((file, line)) = message.location {
+                    println!("#   at {}:{}", file, line);
+                }
+                for line in comparison(message) {
+                    println!("#   {}", line);
+                }
+            }
+        }
+    }
+}
+
+/// Writes a line with how a test went, whatever the verbosity, for runs that
+/// report each test as soon as it's asked for (`--interactive`). Other
+/// formats have a line per test anyway.
+pub fn outcome(result: &TestResult) {
+    if format() == Format::Text {
+        println!("{}: {} ({:.3} ms)", result.name, result.status.name(), ms(result.duration));
+    }
+}
+
+/// Finishes the output once every test has been reported and returns the
+/// totals.
+///
+/// Text mode's summary opens with a table of every test that ran: its
+/// status and time in its last run, and its category (its first tag). It
+/// ends with the ten slowest tests by their mean time over the iterations,
+/// which is where slow syscalls through the kernel's bridge show up.
+pub fn end() -> Summary {
+    let summary = std::mem::replace(&mut *SUMMARY.lock().unwrap(), Summary::new());
+    json_line(|| {
+        let mut out = format!(
+            "{{\"summary\":{{\"passed\":{},\"failed\":{},\"skipped\":{},\"xfail\":{},\"xpass\":{},\
+             \"flakes\":{},\"total\":{}",
+            summary.passed,
+            summary.failed,
+            summary.skipped,
+            summary.xfailed,
+            summary.xpassed,
+            summary.flakes.len(),
+            summary.total()
+        );
+        out.push_str(",\"flaky\":[");
+        for (i, outcomes) in summary.flaky().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            push_json_str(&mut out, outcomes.name);
+            let _ = write!(out, ",\"passed\":{},\"failed\":{}}}", outcomes.passed, outcomes.failed);
+        }
+        out.push_str("],\"slowest\":[");
+        for (i, outcomes) in summary.slowest(SLOWEST).iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            push_json_str(&mut out, outcomes.name);
+            let _ = write!(out, ",\"duration\":{:.3},\"runs\":{}}}", ms(outcomes.mean()), outcomes.runs());
+        }
+        out.push_str("]}}");
+        out
+    });
+    match format() {
+        Format::Text => {
+            println!("\n=== Summary ===");
+            if !summary.results.is_empty() {
+                let mut table = Table::new(&[
+                    ("Test", Align::Left),
+                    ("Status", Align::Left),
+                    ("Time (ms)", Align::Right),
+                    ("Category", Align::Left),
+                ]);
+                for result in &summary.results {
+                    table.row(vec![
+                        result.name.to_string(),
+                        result.status.name().to_string(),
+                        format!("{:.3}", ms(result.duration)),
+                        result.category.to_string(),
+                    ]);
+                }
+                for line in table.render(ascii()) {
+                    println!("{}", line);
+                }
+                println!();
+            }
+            println!("  passed   {:>4}", summary.passed);
+            println!("  failed   {:>4}", summary.failed);
+            println!("  skipped  {:>4}", summary.skipped);
+            println!("  xfail    {:>4}", summary.xfailed);
+            println!("  xpass    {:>4}", summary.xpassed);
+            println!("  flaky    {:>4}", summary.flakes.len());
+            println!("  total    {:>4}", summary.total());
+            if !summary.failures.is_empty() {
+                println!("\nFailed tests:");
+                for name in &summary.failures {
+                    println!("  {}", name);
+                }
+                println!("\nReplay with --seed {}", random::seed());
+            }
+            if !summary.xpasses.is_empty() {
+                println!("\nPassed although expected to fail:");
+                for name in &summary.xpasses {
+                    println!("  {}", name);
+                }
+            }
+            if !summary.flakes.is_empty() {
+                println!("\nPassed when retried:");
+                for (name, attempts) in &summary.flakes {
+                    println!("  {} (attempt {})", name, attempts);
+                }
+            }
+            if summary.flaky().next().is_some() {
+                println!("\nFlaky tests:");
+                for outcomes in summary.flaky() {
+                    println!("  {} (passed {} of {})", outcomes.name, outcomes.passed, outcomes.passed + outcomes.failed);
+                }
+            }
+            let slowest = summary.slowest(SLOWEST);
+            if !slowest.is_empty() {
+                println!("\nSlowest tests:");
+                for outcomes in slowest {
+                    print!("  {:>10.3} ms  {}", ms(outcomes.mean()), outcomes.name);
+                    if outcomes.runs() > 1 {
+                        print!(" (mean of {})", outcomes.runs());
+                    }
+                    println!();
+                }
+            }
+        }
+        Format::Json => {}
+        Format::Tap => {
+            if PLAN_AT_END.load(Ordering::Relaxed) {
+                println!("1..{}", summary.total());
+            }
+            println!(
+                "# passed {}, failed {}, skipped {}, xfail {}, xpass {}, flaky {}, total {}",
+                summary.passed,
+                summary.failed,
+                summary.skipped,
+                summary.xfailed,
+                summary.xpassed,
+                summary.flakes.len(),
+                summary.total()
+            );
+            for outcomes in summary.flaky() {
+                println!("# flaky: {} passed {} of {}", outcomes.name, outcomes.passed, outcomes.passed + outcomes.failed);
+            }
+            for outcomes in summary.slowest(SLOWEST) {
+                println!("# slowest: {} {:.3} ms", outcomes.name, ms(outcomes.mean()));
+            }
+        }
+    }
+    baseline::print(&summary);
+    summary
+}
+
+/// A duration in milliseconds, as every report gives them.
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Writes a line of the JSON report: to stdout in JSON mode, and to the
+/// saved results (see [`persist`]) whatever the mode.
+fn json_line(line: impl FnOnce() -> String) {
+    let stdout = format() == Format::Json;
+    if stdout || persist::enabled() {
+        let line = line();
+        persist::push(&line);
+        if stdout {
+            println!("{}", line);
+        }
+    }
+}
+
+fn to_json(result: &TestResult) -> String {
+    let mut out = String::new();
+    out.push_str("{\"name\":");
+    push_json_str(&mut out, result.name);
+    let _ = write!(out, ",\"status\":\"{}\"", result.status.name());
+    if let Some(reason) = result.xfail {
+        out.push_str(",\"xfail\":");
+        push_json_str(&mut out, reason);
+    }
+    let _ = write!(out, ",\"duration\":{:.3}", ms(result.duration));
+    if result.attempts > 1 {
+        let _ = write!(out, ",\"attempts\":{}", result.attempts);
+    }
+    out.push_str(",\"messages\":[");
+    for (i, message) in result.messages.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "{{\"kind\":\"{}\",\"text\":", message.kind.name());
+        push_json_str(&mut out, &message.text);
+        if let Some((file, line)) = message.location {
+            let _ = write!(out, ",\"file\":");
+            push_json_str(&mut out, file);
+            let _ = write!(out, ",\"line\":{}", line);
+        }
+        if let Some(expected) = &message.expected {
+            out.push_str(",\"expected\":");
+            push_json_str(&mut out, expected);
+        }
+        if let Some(actual) = &message.actual {
+            out.push_str(",\"actual\":");
+            push_json_str(&mut out, actual);
+        }
+        if let Some(error) = message.error {
+            let _ = write!(out, ",\"error\":{{\"kind\":\"{:?}\",\"errno\":", error.kind);
+            match error.errno {
+                Some(errno) => {
+                    let _ = write!(out, "{}}}", errno);
+                }
+                None => out.push_str("null}"),
+            }
+        }
+        out.push('}');
+    }
+    out.push_str("],\"errno\":");
+    match result.errno {
+        Some(errno) => {
+            let _ = write!(out, "{}", errno);
+        }
+        None => out.push_str("null"),
+    }
+    out.push('}');
+    out
+}
+
+fn push_json_str(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Describes what the test is about to do.
+#[macro_export]
+macro_rules! step {
+    ($($arg:tt)+) => {
+        $crate::harness::record($crate::harness::Kind::Step, format!($($arg)+), None)
+    };
+}
+
+/// Reports a value observed along the way.
+#[macro_export]
+macro_rules! detail {
+    ($($arg:tt)+) => {
+        $crate::harness::record($crate::harness::Kind::Detail, format!($($arg)+), None)
+    };
+}
+
+/// Reports a successful check. `pass!(e => ...)` records the errno of an
+/// expected error.
+#[macro_export]
+macro_rules! pass {
+    ($err:ident => $($arg:tt)+) => {
+        $crate::harness::record($crate::harness::Kind::Pass, format!($($arg)+), Some(&$err))
+    };
+    ($($arg:tt)+) => {
+        $crate::harness::record($crate::harness::Kind::Pass, format!($($arg)+), None)
+    };
+}
+
+/// Marks the test as skipped, e.g. when the target doesn't support what it
+/// tests. A test that also reports a failure still fails.
+#[macro_export]
+macro_rules! skip {
+    ($($arg:tt)+) => {
+        $crate::harness::record($crate::harness::Kind::Skip, format!($($arg)+), None)
+    };
+}
+
+/// Reports a failed check, failing the test. `fail!(e => ...)` records the
+/// errno of the error that caused it.
+#[macro_export]
+macro_rules! fail {
+    ($err:ident => $($arg:tt)+) => {
+        $crate::harness::record_message(
+            $crate::harness::Message {
+                location: Some((file!(), line!())),
+                ..$crate::harness::Message::new($crate::harness::Kind::Fail, format!($($arg)+))
+            },
+            Some(&$err),
+        )
+    };
+    ($($arg:tt)+) => {
+        $crate::harness::record_message(
+            $crate::harness::Message {
+                location: Some((file!(), line!())),
+                ..$crate::harness::Message::new($crate::harness::Kind::Fail, format!($($arg)+))
+            },
+            None,
+        )
+    };
+}
@@ -0,0 +1,180 @@
+//! Checks that record structured results against the running test.
+//!
+//! Every check records a pass or a failure and hands back what the test
+//! needs to carry on, so a test reads as a sequence of checks rather than
+//! nested `match`es:
+//!
+//! ```ignore
+//! let Some(file) = expect_ok(fs::File::open(path), "Open file") else { return };
+//! expect_eq_bytes(&data, b"hello", "File contents");
+//! ```
+//!
+//! Failures carry the file and line of the check, and comparisons also the
+//! expected and actual values, which the JSON and TAP output report
+//! separately from the message. A check of an error's kind records the kind
+//! it expected whether or not it passes, next to the error itself.
+
+use std::fmt::Debug;
+use std::io;
+use std::panic::Location;
+
+use super::{record_message, Kind, Message};
+
+/// Longest byte string shown in full in a failed comparison.
+const MAX_SHOWN_BYTES: usize = 64;
+
+#[track_caller]
+fn failure(text: String, expected: Option<String>, actual: Option<String>) -> Message {
+    let location = Location::caller();
+    Message {
+        location: Some((location.file(), location.line())),
+        expected,
+        actual,
+        ..Message::new(Kind::Fail, text)
+    }
+}
+
+/// Checks that an operation succeeded and returns its value.
+#[track_caller]
+pub fn expect_ok<T>(result: io::Result<T>, what: &str) -> Option<T> {
+    match result {
+        Ok(value) => {
+            record_message(Message::new(Kind::Pass, what.to_string()), None);
+            Some(value)
+        }
+        Err(e) => {
+            record_message(failure(format!("{}: {}", what, e), None, None), Some(&e));
+            None
+        }
+    }
+}
+
+/// Checks that an operation failed with the given kind of error.
+#[track_caller]
+pub fn expect_err_kind<T>(result: io::Result<T>, kind: io::ErrorKind, what: &str) -> bool {
+    match result {
+        Ok(_) => {
+            let message = failure(
+                format!("{}: unexpectedly succeeded", what),
+                Some(format!("{:?}", kind)),
+                Some("Ok".to_string()),
+            );
+            record_message(message, None);
+            false
+        }
+        Err(e) if e.kind() == kind => {
+            let message = Message {
+                expected: Some(format!("{:?}", kind)),
+                ..Message::new(Kind::Pass, format!("{}: {:?}", what, kind))
+            };
+            record_message(message, Some(&e));
+            true
+        }
+        Err(e) => {
+            let message = failure(
+                format!("{}: wrong error: {}", what, e),
+                Some(format!("{:?}", kind)),
+                Some(format!("{:?}", e.kind())),
+            );
+            record_message(message, Some(&e));
+            false
+        }
+    }
+}
+
+/// Checks that two values are equal.
+#[track_caller]
+pub fn expect_eq<T: Debug + PartialEq>(actual: T, expected: T, what: &str) -> bool {
+    if actual == expected {
+        record_message(Message::new(Kind::Pass, format!("{}: {:?}", what, actual)), None);
+        return true;
+    }
+    let message =
+        failure(format!("{}: values differ", what), Some(format!("{:?}", expected)), Some(format!("{:?}", actual)));
+    record_message(message, None);
+    false
+}
+
+/// Checks that two byte strings are equal. A failure gives both lengths and
+/// the first offset they differ at, with the bytes around it in hex, which
+/// shows a write that was cut short or landed in the wrong place for what it
+/// is. Long contents are abbreviated in the expected and actual values.
+#[track_caller]
+pub fn expect_eq_bytes(actual: &[u8], expected: &[u8], what: &str) -> bool {
+    if actual == expected {
+        record_message(Message::new(Kind::Pass, format!("{} ({} bytes)", what, actual.len())), None);
+        return true;
+    }
+    let offset = actual.iter().zip(expected).position(|(a, b)| a != b).unwrap_or(actual.len().min(expected.len()));
+    let mut text = match actual.len() == expected.len() {
+        true => format!("{}: contents differ at byte {} of {}", what, offset, actual.len()),
+        false => format!(
+            "{}: contents differ at byte {}; expected {} bytes, got {}",
+            what,
+            offset,
+            expected.len(),
+            actual.len()
+        ),
+    };
+    for line in hex_context(expected, actual, offset) {
+        text.push('\n');
+        text.push_str(&line);
+    }
+    let message = failure(text, Some(show_bytes(expected)), Some(show_bytes(actual)));
+    record_message(message, None);
+    false
+}
+
+/// Checks a condition that has no value worth comparing.
+#[track_caller]
+pub fn expect_true(condition: bool, what: &str) -> bool {
+    if condition {
+        record_message(Message::new(Kind::Pass, what.to_string()), None);
+    } else {
+        record_message(failure(format!("{}: condition was false", what), None, None), None);
+    }
+    condition
+}
+
+/// Bytes shown on each line of a hex context.
+const HEX_ROW: usize = 16;
+
+/// The rows of `expected` and `actual` around `offset`, in hex and as text,
+/// one above the other and with a caret under the byte at `offset`: the row
+/// it's in, and the rows before and after it.
+fn hex_context(expected: &[u8], actual: &[u8], offset: usize) -> Vec<String> {
+    let row = |bytes: &[u8], start: usize| {
+        let mut hex = String::new();
+        let mut text = String::new();
+        for at in start..start + HEX_ROW {
+            match bytes.get(at) {
+                Some(&byte) => {
+                    hex.push_str(&format!("{:02x} ", byte));
+                    text.push(if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' });
+                }
+                None => hex.push_str("   "),
+            }
+        }
+        format!("{} {}", hex, text).trim_end().to_string()
+    };
+    let first = (offset / HEX_ROW).saturating_sub(1) * HEX_ROW;
+    let end = expected.len().max(actual.len());
+    let mut lines = Vec::new();
+    for start in (first..=offset / HEX_ROW * HEX_ROW + HEX_ROW).step_by(HEX_ROW).filter(|&start| start < end) {
+        lines.push(format!("{:08x}  expected  {}", start, row(expected, start)));
+        lines.push(format!("{:8}  actual    {}", "", row(actual, start)));
+        if (start..start + HEX_ROW).contains(&offset) {
+            lines.push(format!("{:20}{}^^", "", " ".repeat((offset - start) * 3)));
+        }
+    }
+    lines
+}
+
+fn show_bytes(bytes: &[u8]) -> String {
+    let shown = &bytes[..bytes.len().min(MAX_SHOWN_BYTES)];
+    let mut text = format!("{:?}", String::from_utf8_lossy(shown));
+    if bytes.len() > shown.len() {
+        text.push_str(&format!("... ({} bytes)", bytes.len()));
+    }
+    text
+}
@@ -0,0 +1,476 @@
+//! Comparing a run with an earlier one.
+//!
+//! With `--baseline results.json`, where the file holds the output of an
+//! earlier `--format json` run, the summary is followed by what changed
+//! since: tests that fail now but didn't then, tests that failed then and
+//! pass now, and tests whose mean duration grew by more than the threshold
+//! (`--regression-threshold`, 50% by default) and by at least a millisecond,
+//! below which timings are mostly noise. A test counts as failing if it
+//! failed unexpectedly in any iteration; XFAIL doesn't count.
+//!
+//! Only the result lines of the report are read. Everything else, such as
+//! the summary or output a test wrote itself, is skipped.
+//!
+//! `test.wasm diff OLD NEW` compares two such reports without running
+//! anything. It lists the tests whose standing changed: newly failing,
+//! newly passing, and newly unsupported, meaning skipped in every
+//! iteration, usually for want of a capability. `--delta FILE` writes the
+//! same lists as JSON, for release notes to be made from.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::{format, json_line, push_json_str, shows, Format, Summary, Verbosity};
+
+/// Smallest growth in a test's mean duration reported as a regression.
+const MIN_REGRESSION: Duration = Duration::from_millis(1);
+
+struct Baseline {
+    path: String,
+    /// Growth in duration, as a fraction, beyond which a test is reported.
+    threshold: f64,
+    tests: Vec<Previous>,
+}
+
+/// How a test did in the baseline run, over all its iterations.
+struct Previous {
+    name: String,
+    failed: bool,
+    runs: u32,
+    duration: Duration,
+}
+
+static BASELINE: OnceLock<Baseline> = OnceLock::new();
+
+/// Loads the report at `path` to compare the run with; `threshold` is the
+/// percentage a test's duration may grow by before it's reported.
+pub fn load(path: &str, threshold: f64) -> io::Result<()> {
+    let tests = read(path)?;
+    let _ = BASELINE.set(Baseline { path: path.to_string(), threshold: threshold / 100.0, tests });
+    Ok(())
+}
+
+/// Reads how each test did in the report at `path`.
+fn read(path: &str) -> io::Result<Vec<Previous>> {
+    let text = fs::read_to_string(path)?;
+    let mut tests: Vec<Previous> = Vec::new();
+    for value in text.lines().filter_map(|line| Parser::new(line).parse()) {
+        let (Some(name), Some(status)) = (value.get("name").and_then(Json::as_str), value.get("status")) else {
+            continue;
+        };
+        let index = match tests.iter().position(|test| test.name == name) {
+            Some(index) => index,
+            None => {
+                tests.push(Previous { name: name.to_string(), failed: false, runs: 0, duration: Duration::ZERO });
+                tests.len() - 1
+            }
+        };
+        let test = &mut tests[index];
+        match status.as_str() {
+            Some("skipped") => continue,
+            Some("failed") => test.failed = true,
+            _ => {}
+        }
+        if let Some(duration) = value.get("duration").and_then(Json::as_f64) {
+            test.runs += 1;
+            test.duration += Duration::from_secs_f64(duration.max(0.0) / 1000.0);
+        }
+    }
+    if tests.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "no test results in it; was it written with --format json?"));
+    }
+    Ok(tests)
+}
+
+/// What changed since the baseline run.
+struct Delta<'a> {
+    /// Tests failing now but not then, and whether they were in the
+    /// baseline at all.
+    failing: Vec<(&'a str, bool)>,
+    passing: Vec<&'a str>,
+    /// Tests that got slower, with their mean durations then and now.
+    slower: Vec<(&'a str, Duration, Duration)>,
+}
+
+fn compare<'a>(baseline: &'a Baseline, summary: &'a Summary) -> Delta<'a> {
+    let previous = |name: &str| baseline.tests.iter().find(|test| test.name == name);
+    let mut delta = Delta { failing: Vec::new(), passing: Vec::new(), slower: Vec::new() };
+    for outcomes in &summary.outcomes {
+        let failing = summary.failures.contains(&outcomes.name);
+        let previous = previous(outcomes.name);
+        let failed_before = previous.is_some_and(|previous| previous.failed);
+        if failing && !failed_before {
+            delta.failing.push((outcomes.name, previous.is_some()));
+        } else if !failing && failed_before && outcomes.passed > 0 && outcomes.failed == 0 {
+            delta.passing.push(outcomes.name);
+        }
+
+        let Some(previous) = previous.filter(|previous| previous.runs > 0) else { continue };
+        let runs = (outcomes.passed + outcomes.failed) as u32;
+        if runs == 0 {
+            continue;
+        }
+        let (before, after) = (previous.duration / previous.runs, outcomes.duration / runs);
+        if after > before.mul_f64(1.0 + baseline.threshold) && after - before >= MIN_REGRESSION {
+            delta.slower.push((outcomes.name, before, after));
+        }
+    }
+    delta
+}
+
+/// Writes what changed since the baseline run, if there is one.
+pub(super) fn print(summary: &Summary) {
+    let Some(baseline) = BASELINE.get() else { return };
+    let delta = compare(baseline, summary);
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let growth = |before: Duration, after: Duration| (ms(after) / ms(before).max(0.001) - 1.0) * 100.0;
+    let suffix = |known: bool| if known { "" } else { " (not in the baseline)" };
+    json_line(|| {
+        let mut out = String::from("{\"baseline\":{\"path\":");
+        push_json_str(&mut out, &baseline.path);
+        out.push_str(",\"newlyFailing\":[");
+        for (i, (name, _)) in delta.failing.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            push_json_str(&mut out, name);
+        }
+        out.push_str("],\"newlyPassing\":[");
+        for (i, name) in delta.passing.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            push_json_str(&mut out, name);
+        }
+        out.push_str("],\"slower\":[");
+        for (i, (name, before, after)) in delta.slower.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            push_json_str(&mut out, name);
+            let _ = write!(out, ",\"before\":{:.3},\"after\":{:.3}}}", ms(*before), ms(*after));
+        }
+        out.push_str("]}}");
+        out
+    });
+    match format() {
+        Format::Text if !shows(Verbosity::Normal) => {}
+        Format::Text => {
+            println!("\n=== Compared with {} ===", baseline.path);
+            if delta.failing.is_empty() && delta.passing.is_empty() && delta.slower.is_empty() {
+                println!("  no changes");
+            }
+            if !delta.failing.is_empty() {
+                println!("  Newly failing:");
+                for (name, known) in &delta.failing {
+                    println!("    {}{}", name, suffix(*known));
+                }
+            }
+            if !delta.passing.is_empty() {
+                println!("  Newly passing:");
+                for name in &delta.passing {
+                    println!("    {}", name);
+                }
+            }
+            if !delta.slower.is_empty() {
+                println!("  Slower by more than {}%:", baseline.threshold * 100.0);
+                for (name, before, after) in &delta.slower {
+                    let (growth, before, after) = (growth(*before, *after), ms(*before), ms(*after));
+                    let arrow = if super::ascii() { "->" } else { "→" };
+                    println!("    {}: {:.3} ms {} {:.3} ms (+{:.0}%)", name, before, arrow, after, growth);
+                }
+            }
+        }
+        Format::Json => {}
+        Format::Tap => {
+            println!("# compared with {}", baseline.path);
+            for (name, known) in &delta.failing {
+                println!("# newly failing: {}{}", name, suffix(*known));
+            }
+            for name in &delta.passing {
+                println!("# newly passing: {}", name);
+            }
+            for (name, before, after) in &delta.slower {
+                println!("# slower: {} {:.3} ms -> {:.3} ms (+{:.0}%)", name, ms(*before), ms(*after), growth(*before, *after));
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Standing {
+    Passing,
+    Failing,
+    /// Skipped in every iteration.
+    Unsupported,
+}
+
+impl Standing {
+    fn of(test: &Previous) -> Standing {
+        match (test.failed, test.runs) {
+            (true, _) => Standing::Failing,
+            (false, 0) => Standing::Unsupported,
+            (false, _) => Standing::Passing,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Standing::Passing => "passing",
+            Standing::Failing => "failing",
+            Standing::Unsupported => "unsupported",
+        }
+    }
+}
+
+/// A test whose standing changed, and what it was before; `None` if it
+/// wasn't in the old report.
+type Change<'a> = (&'a str, Option<Standing>);
+
+/// The groups `diff` sorts changes into, with their headings, the keys of
+/// the delta file and the colours of the headings.
+const GROUPS: [(Standing, &str, &str, &str); 3] = [
+    (Standing::Failing, "Newly failing", "newlyFailing", "\x1b[31m"),
+    (Standing::Passing, "Newly passing", "newlyPassing", "\x1b[32m"),
+    (Standing::Unsupported, "Newly unsupported", "newlyUnsupported", "\x1b[33m"),
+];
+
+const RESET: &str = "\x1b[0m";
+
+/// Compares the reports at `old` and `new`, printing the tests whose
+/// standing changed and writing them to `delta` as JSON if it's given.
+/// Returns whether any test fails in the new report that didn't in the old.
+pub fn diff(old: &str, new: &str, delta: Option<&str>) -> io::Result<bool> {
+    let read = |path: &str| read(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)));
+    let (before, after) = (read(old)?, read(new)?);
+    let standing = |tests: &[Previous], name: &str| tests.iter().find(|test| test.name == name).map(Standing::of);
+    let changes = |group: Standing| -> Vec<Change> {
+        after
+            .iter()
+            .filter(|test| Standing::of(test) == group)
+            .map(|test| (test.name.as_str(), standing(&before, &test.name)))
+            .filter(|(_, was)| *was != Some(group))
+            .collect()
+    };
+    let groups: Vec<Vec<Change>> = GROUPS.iter().map(|(group, ..)| changes(*group)).collect();
+    let removed: Vec<&str> = before
+        .iter()
+        .map(|test| test.name.as_str())
+        .filter(|name| standing(&after, name).is_none())
+        .collect();
+
+    let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let paint = |text: &str, code: &str| match color {
+        true => format!("{}{}{}", code, text, RESET),
+        false => text.to_string(),
+    };
+    let arrow = if super::ascii() { "->" } else { "→" };
+    println!("=== {} {} {} ===", old, arrow, new);
+    if groups.iter().all(Vec::is_empty) && removed.is_empty() {
+        println!("  no changes");
+    }
+    for ((_, heading, _, code), changes) in GROUPS.iter().zip(&groups) {
+        if changes.is_empty() {
+            continue;
+        }
+        println!("  {} ({}):", paint(heading, code), changes.len());
+        for (name, was) in changes {
+            match was {
+                Some(was) => println!("    {} (was {})", name, was.name()),
+                None => println!("    {} (not in {})", name, old),
+            }
+        }
+    }
+    if !removed.is_empty() {
+        println!("  Not in {} ({}):", new, removed.len());
+        for name in &removed {
+            println!("    {}", name);
+        }
+    }
+
+    if let Some(path) = delta {
+        let mut out = String::from("{\"old\":");
+        push_json_str(&mut out, old);
+        out.push_str(",\"new\":");
+        push_json_str(&mut out, new);
+        for ((_, _, key, _), changes) in GROUPS.iter().zip(&groups) {
+            let _ = write!(out, ",\"{}\":[", key);
+            for (i, (name, was)) in changes.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str("{\"name\":");
+                push_json_str(&mut out, name);
+                match was {
+                    Some(was) => {
+                        let _ = write!(out, ",\"was\":\"{}\"}}", was.name());
+                    }
+                    None => out.push_str(",\"was\":null}"),
+                }
+            }
+            out.push(']');
+        }
+        out.push_str(",\"removed\":[");
+        for (i, name) in removed.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            push_json_str(&mut out, name);
+        }
+        out.push_str("]}\n");
+        fs::write(path, out).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+    }
+    Ok(!groups[0].is_empty())
+}
+
+/// Just enough of JSON to read reports back.
+enum Json {
+    Null,
+    Bool,
+    Number(f64),
+    String(String),
+    Array,
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Parser<'a> {
+        Parser { text, offset: 0 }
+    }
+
+    /// Parses the whole text as one value; `None` if it isn't one.
+    fn parse(mut self) -> Option<Json> {
+        let value = self.value()?;
+        self.whitespace();
+        (self.offset == self.text.len()).then_some(value)
+    }
+
+    fn whitespace(&mut self) {
+        let rest = &self.text[self.offset..];
+        self.offset += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.whitespace();
+        let found = self.text[self.offset..].starts_with(token);
+        if found {
+            self.offset += token.len();
+        }
+        found
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.whitespace();
+        match self.text[self.offset..].chars().next()? {
+            '{' => {
+                self.offset += 1;
+                let mut members = Vec::new();
+                if !self.eat("}") {
+                    loop {
+                        self.whitespace();
+                        let name = self.string()?;
+                        if !self.eat(":") {
+                            return None;
+                        }
+                        members.push((name, self.value()?));
+                        if self.eat("}") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            return None;
+                        }
+                    }
+                }
+                Some(Json::Object(members))
+            }
+            '[' => {
+                self.offset += 1;
+                if !self.eat("]") {
+                    loop {
+                        self.value()?;
+                        if self.eat("]") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            return None;
+                        }
+                    }
+                }
+                Some(Json::Array)
+            }
+            '"' => self.string().map(Json::String),
+            _ if self.eat("null") => Some(Json::Null),
+            _ if self.eat("true") || self.eat("false") => Some(Json::Bool),
+            _ => {
+                let rest = &self.text[self.offset..];
+                let length = rest.find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E')).unwrap_or(rest.len());
+                let number = rest[..length].parse().ok()?;
+                self.offset += length;
+                Some(Json::Number(number))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let mut chars = self.text[self.offset..].char_indices();
+        if chars.next()?.1 != '"' {
+            return None;
+        }
+        let mut value = String::new();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.offset += i + 1;
+                    return Some(value);
+                }
+                '\\' => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    't' => value.push('\t'),
+                    'b' => value.push('\u{8}'),
+                    'f' => value.push('\u{c}'),
+                    'u' => {
+                        let hex: String = (0..4).filter_map(|_| chars.next().map(|(_, c)| c)).collect();
+                        value.push(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32).unwrap_or('\u{fffd}'));
+                    }
+                    c => value.push(c),
+                },
+                c => value.push(c),
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,161 @@
+//! What the runtime running the suite can do.
+//!
+//! Runtimes legitimately differ: one has no sockets, another ignores chmod.
+//! Rather than have each test discover that by failing, every capability is
+//! probed once before the first test runs, in a scratch directory of its
+//! own, and a test requiring one the runtime lacks is skipped with the
+//! reason the probe gave. The results are shown at the start of the run.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::net::TcpListener;
+use std::path::Path;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::context::Session;
+
+/// What a test needs the runtime to provide.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Capability {
+    Stdio,
+    Args,
+    Environment,
+    Filesystem,
+    Clock,
+    /// Permission changes are stored and reported back.
+    Permissions,
+    WorkingDirectory,
+    Symlinks,
+    Threads,
+    Sockets,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 10] = [
+        Capability::Stdio,
+        Capability::Args,
+        Capability::Environment,
+        Capability::Filesystem,
+        Capability::Clock,
+        Capability::Permissions,
+        Capability::WorkingDirectory,
+        Capability::Symlinks,
+        Capability::Threads,
+        Capability::Sockets,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::Stdio => "stdio",
+            Capability::Args => "args",
+            Capability::Environment => "environment",
+            Capability::Filesystem => "filesystem",
+            Capability::Clock => "clock",
+            Capability::Permissions => "permissions",
+            Capability::WorkingDirectory => "working-directory",
+            Capability::Symlinks => "symlinks",
+            Capability::Threads => "threads",
+            Capability::Sockets => "sockets",
+        }
+    }
+}
+
+/// The outcome of probing every capability.
+pub struct Capabilities {
+    /// Capabilities the runtime lacks, with the reason the probe gave.
+    missing: Vec<(Capability, String)>,
+}
+
+impl Capabilities {
+    /// Probes every capability. Nothing is treated as missing if the
+    /// probe's scratch directory can't be created; the filesystem tests
+    /// will report that.
+    pub fn probe(session: &Session) -> Capabilities {
+        let mut missing = Vec::new();
+        let Ok(ctx) = session.context(".capabilities") else {
+            return Capabilities { missing };
+        };
+        for capability in Capability::ALL {
+            if let Err(reason) = probe(capability, ctx.dir()) {
+                missing.push((capability, reason));
+            }
+        }
+        Capabilities { missing }
+    }
+
+    /// Why the runtime lacks a capability, or `None` if it has it.
+    pub fn missing(&self, capability: Capability) -> Option<&str> {
+        self.missing.iter().find(|(missing, _)| *missing == capability).map(|(_, reason)| reason.as_str())
+    }
+
+    /// The first capability in `requires` the runtime lacks, and why.
+    pub fn first_missing(&self, requires: &[Capability]) -> Option<(Capability, &str)> {
+        requires.iter().find_map(|&capability| self.missing(capability).map(|reason| (capability, reason)))
+    }
+}
+
+fn probe(capability: Capability, dir: &Path) -> Result<(), String> {
+    let describe = |what: &str, e: io::Error| format!("{}: {}", what, e);
+    match capability {
+        Capability::Stdio | Capability::Environment => Ok(()),
+        Capability::Args => match env::args().next() {
+            Some(_) => Ok(()),
+            None => Err("no arguments passed, not even the program name".to_string()),
+        },
+        Capability::Filesystem => {
+            let file = dir.join("probe.txt");
+            fs::write(&file, "probe").map_err(|e| describe("write", e))?;
+            match fs::read_to_string(&file) {
+                Ok(content) if content == "probe" => Ok(()),
+                Ok(_) => Err("a file read back different contents".to_string()),
+                Err(e) => Err(describe("read", e)),
+            }
+        }
+        Capability::Clock => match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(elapsed) if elapsed.as_secs() > 0 => Ok(()),
+            _ => Err("the clock reads the Unix epoch or earlier".to_string()),
+        },
+        Capability::Permissions => {
+            let file = dir.join("permissions.txt");
+            fs::write(&file, "probe").map_err(|e| describe("write", e))?;
+            let original = fs::metadata(&file).map_err(|e| describe("stat", e))?.permissions();
+            let mut readonly = original.clone();
+            readonly.set_readonly(true);
+            fs::set_permissions(&file, readonly).map_err(|e| describe("chmod", e))?;
+            let honored = fs::metadata(&file).map_err(|e| describe("stat", e))?.permissions().readonly();
+            let _ = fs::set_permissions(&file, original);
+            if honored {
+                Ok(())
+            } else {
+                Err("chmod succeeds but isn't honored".to_string())
+            }
+        }
+        Capability::WorkingDirectory => {
+            let cwd = env::current_dir().map_err(|e| describe("getcwd", e))?;
+            env::set_current_dir(&cwd).map_err(|e| describe("chdir", e))
+        }
+        Capability::Symlinks => {
+            let target = dir.join("target.txt");
+            let link = dir.join("link.txt");
+            fs::write(&target, "probe").map_err(|e| describe("write", e))?;
+            // The only way to create a symlink that std offers on every target
+            #[allow(deprecated)]
+            fs::soft_link(&target, &link).map_err(|e| describe("symlink", e))?;
+            match fs::read_link(&link) {
+                Ok(read) if read == target => Ok(()),
+                Ok(read) => Err(format!("readlink returned {:?}", read)),
+                Err(e) => Err(describe("readlink", e)),
+            }
+        }
+        Capability::Threads => {
+            let handle = thread::Builder::new().spawn(|| 42).map_err(|e| describe("spawn", e))?;
+            match handle.join() {
+                Ok(42) => Ok(()),
+                _ => Err("the spawned thread didn't finish".to_string()),
+            }
+        }
+        Capability::Sockets => TcpListener::bind("127.0.0.1:0").map(drop).map_err(|e| describe("bind", e)),
+    }
+}
@@ -0,0 +1,97 @@
+//! Per-run and per-test scratch directories.
+//!
+//! Each run of the suite works in its own `wasm-test-<random>/` directory,
+//! in `/tmp` unless another root is given (`--root` or `WASM_TEST_ROOT`),
+//! and each test in a subdirectory of it named after the test, so
+//! concurrent runs don't clobber each other's files. Tests get their paths
+//! from [`TestCtx::path`] rather than hard-coding them. A test's directory
+//! is removed when it finishes and the run's directory when the run ends,
+//! whether or not the tests passed. Tests that need random data get it
+//! from [`TestCtx::rng`], so it can be replayed with the run's seed.
+//!
+//! Pointing the root at a directory on another filesystem backend, such as
+//! an OPFS or IndexedDB mount, runs the same tests against that backend.
+//! The root has to exist already: were it created, a backend that failed
+//! to mount would go unnoticed and the tests would run on whatever is
+//! underneath.
+
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::random::Rng;
+
+/// Directory every run's scratch directory is created in unless another is
+/// given.
+pub const DEFAULT_ROOT: &str = "/tmp";
+
+/// The scratch directory of a run of the suite.
+pub struct Session {
+    root: PathBuf,
+}
+
+impl Session {
+    /// Creates the run's scratch directory inside `base`, which must be an
+    /// existing directory.
+    pub fn create(base: &Path) -> io::Result<Session> {
+        if !fs::metadata(base)?.is_dir() {
+            return Err(io::ErrorKind::NotADirectory.into());
+        }
+        // RandomState is seeded from the runtime's random source
+        let id = RandomState::new().build_hasher().finish();
+        let root = base.join(format!("wasm-test-{:016x}", id));
+        fs::create_dir_all(&root)?;
+        Ok(Session { root })
+    }
+
+    /// The run's scratch directory.
+    pub fn dir(&self) -> &Path {
+        &self.root
+    }
+
+    /// Creates the scratch directory of the test called `name`.
+    pub fn context(&self, name: &str) -> io::Result<TestCtx> {
+        let dir = self.root.join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        Ok(TestCtx { name: name.to_string(), dir })
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// What a running test gets from the harness.
+pub struct TestCtx {
+    name: String,
+    dir: PathBuf,
+}
+
+impl TestCtx {
+    /// The test's scratch directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of `name` inside the test's scratch directory.
+    pub fn path(&self, name: &str) -> String {
+        self.dir.join(name).to_string_lossy().into_owned()
+    }
+
+    /// The test's random number generator, derived from the run's seed. Each
+    /// call starts the same sequence again.
+    pub fn rng(&self) -> Rng {
+        Rng::for_test(&self.name)
+    }
+}
+
+impl Drop for TestCtx {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
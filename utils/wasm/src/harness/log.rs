@@ -0,0 +1,104 @@
+//! Diagnostics about the run itself, as opposed to its results.
+//!
+//! Whatever the harness or a runner has to say that isn't a result (a report
+//! it couldn't save, a flag the runtime can't honour, where a test is
+//! running) is logged at a [`Level`] with the `debug!`, `info!`, `warn!` and
+//! `error!` macros. Messages at or above a threshold go to stderr, so stdout
+//! is left to the reporters and whatever parses them: warnings and errors by
+//! default, errors only with `-q`, info with `-v` and everything with `-vv`.
+//! With `--log-file` every message, debug included, is also appended to a
+//! file with the time since the run started, so the detail can be kept in
+//! the VFS without being shown.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use super::{verbosity, Verbosity};
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static FILE: Mutex<Option<File>> = Mutex::new(None);
+
+/// Appends every message from now on to the file at `path`, creating it if
+/// needed.
+pub fn set_file(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    STARTED.get_or_init(Instant::now);
+    *FILE.lock().unwrap() = Some(file);
+    Ok(())
+}
+
+/// The lowest level printed to stderr at the verbosity chosen.
+fn threshold() -> Level {
+    match verbosity() {
+        Verbosity::Quiet => Level::Error,
+        Verbosity::Normal => Level::Warn,
+        Verbosity::Verbose => Level::Info,
+        Verbosity::Debug => Level::Debug,
+    }
+}
+
+/// Logs a message; used through the macros.
+pub fn write(level: Level, message: String) {
+    let elapsed = STARTED.get_or_init(Instant::now).elapsed();
+    if level >= threshold() {
+        eprintln!("{}: {}", level.name().to_lowercase(), message);
+    }
+    if let Some(file) = FILE.lock().unwrap().as_mut() {
+        // A log line that can't be written isn't worth stopping the run for
+        let _ = writeln!(file, "{:10.3} {:<5} {}", elapsed.as_secs_f64(), level.name(), message);
+    }
+}
+
+/// Logs detail only worth seeing while chasing a problem.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::harness::log::write($crate::harness::log::Level::Debug, format!($($arg)+))
+    };
+}
+
+/// Logs what the run is doing.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::harness::log::write($crate::harness::log::Level::Info, format!($($arg)+))
+    };
+}
+
+/// Logs something that went wrong without stopping the run.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::harness::log::write($crate::harness::log::Level::Warn, format!($($arg)+))
+    };
+}
+
+/// Logs something that stops the run, or part of it.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::harness::log::write($crate::harness::log::Level::Error, format!($($arg)+))
+    };
+}
@@ -0,0 +1,112 @@
+//! Running tests concurrently.
+//!
+//! With `--jobs N` and a runtime that can spawn threads (see
+//! [`Capability::Threads`](super::capabilities::Capability::Threads)), the
+//! tests of an iteration are shared out among N worker threads, which both
+//! shortens the run and puts the kernel's handling of concurrent syscalls
+//! to the test. Each test already works in a scratch directory of its own,
+//! and everything it records is kept per thread, so tests don't see each
+//! other's files or messages.
+//!
+//! Results are still reported in run order, each as soon as every test
+//! before it has finished, so the output reads the same as a serial run's.
+//! Messages aren't printed as they happen, since those of different tests
+//! would interleave; `-v` prints them with the test's result instead.
+//! Tests tagged `destructive` change state other tests can see, such as the
+//! working directory, so they run on their own once the tests before them
+//! have finished.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use super::capabilities::Capabilities;
+use super::context::Session;
+use super::progress;
+use super::registry::TestCase;
+use super::TestResult;
+
+static JOBS: AtomicUsize = AtomicUsize::new(1);
+
+/// Sets how many tests may run at once; must be called before any test
+/// runs.
+pub fn set_jobs(jobs: usize) {
+    JOBS.store(jobs.max(1), Ordering::Relaxed);
+}
+
+/// How many tests may run at once.
+pub fn jobs() -> usize {
+    JOBS.load(Ordering::Relaxed)
+}
+
+/// Runs `tests` and passes each result to `report`, in order.
+pub fn run_all(tests: &[&TestCase], session: &Session, capabilities: &Capabilities, mut report: impl FnMut(TestResult)) {
+    let mut rest = tests;
+    while !rest.is_empty() {
+        // The tests up to the next exclusive one, or that one on its own
+        let exclusive = |test: &&TestCase| test.tags.contains(&"destructive");
+        let length = match rest.iter().position(exclusive) {
+            Some(0) => 1,
+            Some(position) => position,
+            None => rest.len(),
+        };
+        let (batch, next) = rest.split_at(length);
+        run_batch(batch, session, capabilities, &mut report);
+        rest = next;
+    }
+}
+
+fn run_batch(tests: &[&TestCase], session: &Session, capabilities: &Capabilities, report: &mut impl FnMut(TestResult)) {
+    let jobs = jobs().min(tests.len());
+    if jobs <= 1 {
+        for test in tests {
+            report(super::run(test, session, capabilities));
+        }
+        return;
+    }
+
+    let first = progress::reserve(tests.len());
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let spawned = thread::scope(|scope| {
+        let mut workers = 0;
+        for _ in 0..jobs {
+            let (sender, next) = (sender.clone(), &next);
+            let worker = move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(test) = tests.get(index) else { break };
+                let result = super::run_numbered(test, first + index, session, capabilities);
+                if sender.send((index, result)).is_err() {
+                    break;
+                }
+            };
+            if thread::Builder::new().spawn_scoped(scope, worker).is_ok() {
+                workers += 1;
+            }
+        }
+        drop(sender);
+        info!("running {} tests on {} of {} threads", tests.len(), workers, jobs);
+
+        // Results arrive in the order the tests finish; each is held until
+        // the ones before it are in
+        let mut pending: Vec<Option<TestResult>> = tests.iter().map(|_| None).collect();
+        let mut reported = 0;
+        for (index, result) in receiver {
+            pending[index] = Some(result);
+            while let Some(result) = pending.get_mut(reported).and_then(Option::take) {
+                report(result);
+                reported += 1;
+            }
+        }
+        workers > 0
+    });
+
+    // Threads were probed for, but spawning can still fail, e.g. once the
+    // runtime's thread limit is reached
+    if !spawned {
+        warn!("couldn't start any threads; running {} tests one at a time", tests.len());
+        for (index, test) in tests.iter().enumerate() {
+            report(super::run_numbered(test, first + index, session, capabilities));
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Saving results inside the OS.
+//!
+//! The JSON report of every run, the lines `--format json` prints whatever
+//! format was chosen, is also written to
+//! `/var/log/wasm-tests/<timestamp>.json`, so past runs can be looked at with
+//! the shell's tools from inside ecmaOS, or handed to `--baseline`. The
+//! timestamp is when the run started, in UTC (`20250101T120000.000Z`), so
+//! the files sort by date. A shard of the suite (see
+//! [`Shard`](super::registry::Shard)) adds which it is
+//! (`20250101T120000.000Z-shard-2-of-4.json`), since the shards of a run
+//! start together. `--no-persist` turns this off. A report that
+//! can't be written is logged as an error without failing the run.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ecmaos_date::civil_from_days;
+
+use super::registry::Shard;
+use super::{format, shows, Format, Verbosity};
+
+/// Directory the reports are written to.
+pub const LOG_DIR: &str = "/var/log/wasm-tests";
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static STARTED: OnceLock<SystemTime> = OnceLock::new();
+static REPORT: Mutex<String> = Mutex::new(String::new());
+static SHARD: OnceLock<Shard> = OnceLock::new();
+
+/// Sets whether the report is saved; it is unless turned off.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub(super) fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Notes that the run is a shard of the suite, to name the report after.
+pub fn set_shard(shard: Shard) {
+    let _ = SHARD.set(shard);
+}
+
+/// Adds a line to the report.
+pub(super) fn push(line: &str) {
+    if !enabled() {
+        return;
+    }
+    STARTED.get_or_init(SystemTime::now);
+    let mut report = REPORT.lock().unwrap();
+    report.push_str(line);
+    report.push('\n');
+}
+
+/// Writes the report, if there's one, creating the directory as needed.
+pub fn save() {
+    let (Some(started), true) = (STARTED.get(), enabled()) else { return };
+    let mut name = timestamp(*started);
+    if let Some(shard) = SHARD.get() {
+        name += &format!("-shard-{}-of-{}", shard.index, shard.count);
+    }
+    let path = Path::new(LOG_DIR).join(format!("{}.json", name));
+    let report = std::mem::take(&mut *REPORT.lock().unwrap());
+    match write(&path, &report) {
+        Ok(()) => match format() {
+            Format::Text if shows(Verbosity::Normal) => println!("\nResults saved to {}", path.display()),
+            Format::Tap => println!("# results saved to {}", path.display()),
+            _ => {}
+        },
+        Err(e) => error!("couldn't save the results to {}: {}", path.display(), e),
+    }
+}
+
+fn write(path: &Path, report: &str) -> io::Result<()> {
+    fs::create_dir_all(LOG_DIR)?;
+    fs::write(path, report)
+}
+
+/// `time` in UTC as `YYYYMMDDTHHMMSS.mmmZ`.
+fn timestamp(time: SystemTime) -> String {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since.as_secs();
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest / 60 % 60,
+        rest % 60,
+        since.subsec_millis()
+    )
+}
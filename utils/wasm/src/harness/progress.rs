@@ -0,0 +1,93 @@
+//! Progress through a run, and signs of life from long tests.
+//!
+//! Each test's header in text mode starts with its place in the run,
+//! counting every iteration (`[7/25] Large file operations`), or just its
+//! number when how many tests will run isn't known, as with
+//! `--interactive`. A test still running once the heartbeat interval has
+//! passed says so on stderr, and again after every further interval, so a
+//! slow filesystem backend doesn't pass for a hung process. The interval is
+//! 5 seconds unless `--heartbeat` sets another; 0 turns it off.
+//!
+//! Not every runtime has a thread to spare for a timer, so the heartbeat
+//! is checked whenever the test reports something, and tests that spend a
+//! long time in a loop call [`heartbeat`] as they go. Times come from the
+//! monotonic clock, so changes to the wall clock can't cause or hide one.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use super::{verbosity, Verbosity};
+
+/// The number of tests in the run, or 0 if that isn't known.
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+static STARTED: AtomicUsize = AtomicUsize::new(0);
+static INTERVAL_MS: AtomicU64 = AtomicU64::new(5000);
+
+/// The test running on this thread.
+#[derive(Clone, Copy)]
+struct Running {
+    number: usize,
+    name: &'static str,
+    started: Instant,
+    last_beat: Instant,
+}
+
+thread_local! {
+    static RUNNING: Cell<Option<Running>> = const { Cell::new(None) };
+}
+
+/// Sets how long a test runs before the heartbeat says it still is; zero
+/// turns the heartbeat off.
+pub fn set_interval(interval: Duration) {
+    INTERVAL_MS.store(interval.as_millis().try_into().unwrap_or(u64::MAX), Ordering::Relaxed);
+}
+
+pub(super) fn set_total(total: Option<usize>) {
+    TOTAL.store(total.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Claims numbers for the next `count` tests and returns the first. Tests
+/// run concurrently claim theirs together before any starts, so each one's
+/// number is its place in the report rather than in the order they start.
+pub(super) fn reserve(count: usize) -> usize {
+    STARTED.fetch_add(count, Ordering::Relaxed) + 1
+}
+
+/// Notes that the test numbered `number` started on this thread.
+pub(super) fn start(name: &'static str, number: usize) {
+    let now = Instant::now();
+    RUNNING.with(|running| running.set(Some(Running { number, name, started: now, last_beat: now })));
+}
+
+pub(super) fn finish() {
+    RUNNING.with(|running| running.set(None));
+}
+
+/// The prefix of the test numbered `number`: `[7/25]`, or `[7]`.
+pub(super) fn label(number: usize) -> String {
+    match TOTAL.load(Ordering::Relaxed) {
+        0 => format!("[{}]", number),
+        total => format!("[{}/{}]", number, total),
+    }
+}
+
+/// Says the running test is still running, if it has been for another
+/// interval since it was last said. Cheap enough to call in a tight loop.
+pub fn heartbeat() {
+    let interval = INTERVAL_MS.load(Ordering::Relaxed);
+    if interval == 0 || verbosity() == Verbosity::Quiet {
+        return;
+    }
+    RUNNING.with(|running| {
+        let Some(mut current) = running.get() else { return };
+        let now = Instant::now();
+        if now.duration_since(current.last_beat) < Duration::from_millis(interval) {
+            return;
+        }
+        let elapsed = now.duration_since(current.started).as_secs_f64();
+        eprintln!("{} {}: still running after {:.0} s", label(current.number), current.name, elapsed);
+        current.last_beat = now;
+        running.set(Some(current));
+    });
+}
@@ -0,0 +1,85 @@
+//! Reproducible randomness.
+//!
+//! Everything random the suite does, such as the order `--shuffle` runs the
+//! tests in and the data tests generate, comes from generators derived from
+//! a single seed: the one given with `--seed`, or one drawn from the
+//! runtime's random source. The seed is shown at the start of the run, so
+//! passing it back replays a failing run exactly. Each test gets its own
+//! generator, derived from the seed and its name, so what it generates
+//! doesn't depend on which tests ran before it.
+//!
+//! The only exception is the name of the run's scratch directory, which
+//! must differ between concurrent runs even if they share a seed.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::OnceLock;
+
+static SEED: OnceLock<u64> = OnceLock::new();
+
+/// Selects the seed; must be called before anything random happens.
+pub fn set_seed(seed: u64) {
+    let _ = SEED.set(seed);
+}
+
+/// The seed of the run, drawn from the runtime's random source the first
+/// time it's needed if none was set.
+pub fn seed() -> u64 {
+    *SEED.get_or_init(|| RandomState::new().build_hasher().finish())
+}
+
+/// A SplitMix64 generator: small and fast, with good enough output for
+/// shuffling and test data. Not for anything security related.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// The generator of the test called `name`.
+    pub fn for_test(name: &str) -> Rng {
+        // FNV-1a, unlike std's hashers, is the same on every Rust version
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in name.bytes() {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        Rng::new(seed() ^ hash)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`. `bound` must not be 0.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        // Rejects the top of the range that would make low numbers likelier
+        let limit = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < limit {
+                return value % bound;
+            }
+        }
+    }
+
+    pub fn fill(&mut self, buffer: &mut [u8]) {
+        for chunk in buffer.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    /// Puts `items` in a random order.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+}
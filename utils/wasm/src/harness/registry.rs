@@ -0,0 +1,141 @@
+//! The registry of tests the suite can run.
+//!
+//! Every test is declared once, as a [`TestCase`] in the `TESTS` table at
+//! the root of the crate, with the tags it can be selected by, the
+//! capabilities it needs from the runtime, whether it's expected to fail,
+//! and its fixtures: the files and directories it expects in its scratch
+//! directory, and optional setup and teardown hooks. `--filter`, `--tags`,
+//! `--skip-tags` and `--list` all work from that table, so adding a test
+//! means writing the function and registering it; `main` doesn't change.
+//!
+//! Tags name what a test exercises, such as `fs`, `time`, `env`, `stdio` or
+//! `process`, plus two that say how it behaves: `perf` for tests that take
+//! noticeably longer than the rest, and `destructive` for ones that change
+//! state outside their scratch directory, such as the process's working
+//! directory. A quick, safe smoke run is `--skip-tags perf,destructive`.
+//!
+//! The selected tests can be split into shards (`--shard 2/4`) so several
+//! processes can run the suite between them, which is quicker and puts the
+//! kernel's running of concurrent processes to the test. Tests are dealt
+//! out in turn, the first to shard 1, the second to shard 2 and so on, so
+//! slow tests registered together end up in different shards. The split
+//! depends only on the selection, so every shard has to be given the same
+//! filters.
+
+use std::fmt;
+use std::io;
+
+use super::capabilities::Capability;
+use super::context::TestCtx;
+
+pub struct TestCase {
+    /// Name used with `--filter` and shown by `--list`.
+    pub name: &'static str,
+    /// Title shown in text output.
+    pub title: &'static str,
+    pub tags: &'static [&'static str],
+    pub requires: &'static [Capability],
+    /// Directories created in the test's scratch directory before it runs.
+    pub dirs: &'static [&'static str],
+    /// Files created after `dirs`, as (name, contents).
+    pub files: &'static [(&'static str, &'static str)],
+    /// Runs after the fixtures are created; the test fails without running
+    /// if it returns an error.
+    pub setup: Option<fn(&TestCtx) -> io::Result<()>>,
+    pub run: fn(&TestCtx),
+    /// Runs after the test, even if it failed. The scratch directory is
+    /// removed afterwards either way.
+    pub teardown: Option<fn(&TestCtx)>,
+    /// Why the test is expected to fail, for known gaps in the kernel. Its
+    /// failures are reported as XFAIL and don't fail the run.
+    pub xfail: Option<&'static str>,
+}
+
+impl TestCase {
+    pub const fn new(name: &'static str, title: &'static str, run: fn(&TestCtx)) -> TestCase {
+        TestCase { name, title, tags: &[], requires: &[], dirs: &[], files: &[], setup: None, run, teardown: None, xfail: None }
+    }
+
+    pub const fn tags(self, tags: &'static [&'static str]) -> TestCase {
+        TestCase { tags, ..self }
+    }
+
+    pub const fn requires(self, requires: &'static [Capability]) -> TestCase {
+        TestCase { requires, ..self }
+    }
+
+    pub const fn dirs(self, dirs: &'static [&'static str]) -> TestCase {
+        TestCase { dirs, ..self }
+    }
+
+    pub const fn files(self, files: &'static [(&'static str, &'static str)]) -> TestCase {
+        TestCase { files, ..self }
+    }
+
+    pub const fn setup(self, setup: fn(&TestCtx) -> io::Result<()>) -> TestCase {
+        TestCase { setup: Some(setup), ..self }
+    }
+
+    pub const fn teardown(self, teardown: fn(&TestCtx)) -> TestCase {
+        TestCase { teardown: Some(teardown), ..self }
+    }
+
+    pub const fn xfail(self, reason: &'static str) -> TestCase {
+        TestCase { xfail: Some(reason), ..self }
+    }
+
+    /// Creates the test's fixtures in its scratch directory.
+    pub fn set_up(&self, ctx: &TestCtx) -> io::Result<()> {
+        for dir in self.dirs {
+            std::fs::create_dir_all(ctx.path(dir))?;
+        }
+        for (name, contents) in self.files {
+            std::fs::write(ctx.path(name), contents)?;
+        }
+        match self.setup {
+            Some(setup) => setup(ctx),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns the tests whose name contains any of `patterns` and that have any
+/// of `tags`, but none of `skip_tags`; an empty list doesn't restrict the
+/// selection.
+pub fn select<'a>(tests: &'a [TestCase], patterns: &[String], tags: &[String], skip_tags: &[String]) -> Vec<&'a TestCase> {
+    let tagged = |test: &TestCase, tags: &[String]| tags.iter().any(|tag| test.tags.contains(&tag.as_str()));
+    tests
+        .iter()
+        .filter(|test| patterns.is_empty() || patterns.iter().any(|pattern| test.name.contains(pattern.as_str())))
+        .filter(|test| tags.is_empty() || tagged(test, tags))
+        .filter(|test| !tagged(test, skip_tags))
+        .collect()
+}
+
+/// One of the parts the selected tests are split into: the `index`th of
+/// `count`, counting from 1.
+#[derive(Clone, Copy)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Shard {
+    /// Parses `INDEX/COUNT`, as in `2/4`.
+    pub fn parse(text: &str) -> Option<Shard> {
+        let (index, count) = text.split_once('/')?;
+        let (index, count) = (index.trim().parse().ok()?, count.trim().parse().ok()?);
+        (1..=count).contains(&index).then_some(Shard { index, count })
+    }
+
+    /// The tests of `tests` that fall in this shard, in the same order.
+    pub fn select(self, tests: Vec<&TestCase>) -> Vec<&TestCase> {
+        tests.into_iter().skip(self.index - 1).step_by(self.count).collect()
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
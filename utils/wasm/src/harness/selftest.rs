@@ -0,0 +1,249 @@
+//! Tests of the harness itself.
+//!
+//! `--self-test` runs these instead of the suite. They check that the
+//! assertions record what they should, that a test's status follows from
+//! what it reported, that a test whose panic can't be caught still fails
+//! with what it reported, that results come out as the JSON the tools reading
+//! reports expect, that random numbers are reproducible from the seed, that
+//! times are added up and ranked as the summary needs, and that tests are
+//! selected and sharded as asked. They only work on values in memory,
+//! never touching the filesystem, so a change to the harness can be
+//! checked apart from the kernel it's meant to test. They're reported as
+//! the suite's tests are, in any format.
+
+use std::io;
+use std::panic;
+use std::time::{Duration, Instant};
+
+use ecmaos_glob::Glob;
+
+use super::assert::{expect_eq, expect_eq_bytes, expect_err_kind, expect_ok, expect_true};
+use super::context::TestCtx;
+use super::random::{self, Rng};
+use super::registry::{self, Shard, TestCase};
+use super::{
+    abandon, format, json_line, ms, panic_message, progress, record_message, recorded, report, shows, shows_live,
+    status, to_json, Format, Kind, Message, OsError, Running, Status, Summary, TestResult, Verbosity, CURRENT,
+};
+
+/// Every self-test in run order: name, title and what it checks.
+const CASES: &[(&str, &str, fn())] = &[
+    ("assertions", "Assertions record passes and failures", assertions),
+    ("statuses", "Statuses follow from what a test reported", statuses),
+    ("panics", "A panic that can't be caught fails its test", panics),
+    ("json", "Results are written as JSON", json),
+    ("random", "Random numbers are reproducible", random_numbers),
+    ("timing", "Times are added up, averaged and ranked", timing),
+    ("selection", "Tests are matched, selected and sharded", selection),
+];
+
+/// Runs the self-tests, reporting each as it finishes; [`end`](super::end)
+/// gives the totals.
+pub fn run() {
+    progress::set_total(Some(CASES.len()));
+    json_line(|| format!("{{\"seed\":{},\"self_test\":true}}", random::seed()));
+    match format() {
+        Format::Text if !shows(Verbosity::Normal) => {}
+        Format::Text => {
+            println!("=== WASM Test Harness Self-Test ===");
+            println!("  seed: {}", random::seed());
+        }
+        Format::Json => {}
+        Format::Tap => {
+            println!("TAP version 13");
+            println!("1..{}", CASES.len());
+            println!("# seed: {}", random::seed());
+        }
+    }
+
+    for &(name, title, check) in CASES {
+        let number = progress::reserve(1);
+        progress::start(name, number);
+        if shows_live(Verbosity::Verbose) {
+            println!("\n{} {}", progress::label(number), title);
+        }
+        CURRENT.with(|current| *current.borrow_mut() = Some((Vec::new(), None)));
+        let start = Instant::now();
+        if let Err(payload) = panic::catch_unwind(check) {
+            let text = format!("Panicked: {}", panic_message(&*payload));
+            record_message(Message::new(Kind::Fail, text), None);
+        }
+        let duration = start.elapsed();
+        let (messages, errno) = CURRENT.with(|current| current.borrow_mut().take()).unwrap_or_default();
+        progress::finish();
+
+        let status = status(&messages, false);
+        let result = TestResult {
+            name,
+            title,
+            category: "harness",
+            status,
+            duration,
+            messages,
+            errno,
+            xfail: None,
+            attempts: 1,
+        };
+        report(number, &result);
+    }
+}
+
+fn assertions() {
+    let (messages, errno) = recorded(|| {
+        expect_true(true, "true");
+        expect_eq(2 + 2, 4, "sum");
+        expect_eq("a", "b", "letters");
+        expect_eq_bytes(b"abcd", b"abXd", "bytes");
+        expect_ok(Err::<(), _>(io::Error::from_raw_os_error(2)), "open");
+        expect_err_kind(Ok(()), io::ErrorKind::NotFound, "remove");
+    });
+    let kinds: Vec<&str> = messages.iter().map(|message| message.kind.name()).collect();
+    expect_eq(kinds, vec!["pass", "pass", "fail", "fail", "fail", "fail"], "Kinds recorded");
+    if let [_, _, letters, bytes, open, remove] = &messages[..] {
+        expect_eq(letters.location.map(|(file, _)| file), Some(file!()), "Location of a failure");
+        let compared = (letters.expected.as_deref(), letters.actual.as_deref());
+        expect_eq(compared, (Some("\"b\""), Some("\"a\"")), "Values compared");
+        expect_true(bytes.text.starts_with("bytes: contents differ at byte 2 of 4\n"), "Offset of differing bytes");
+        expect_eq(open.error.and_then(|error| error.errno), Some(2), "errno of an OS error");
+        expect_eq(remove.expected.as_deref(), Some("NotFound"), "Kind of error expected");
+    }
+    expect_eq(errno, Some(2), "First errno");
+}
+
+fn statuses() {
+    let of = |kinds: &[Kind], xfail| {
+        let messages: Vec<Message> = kinds.iter().map(|&kind| Message::new(kind, String::new())).collect();
+        status(&messages, xfail).name()
+    };
+    expect_eq(of(&[Kind::Step, Kind::Pass], false), "passed", "Only passes");
+    expect_eq(of(&[Kind::Pass, Kind::Fail], false), "failed", "A failure");
+    expect_eq(of(&[Kind::Skip, Kind::Fail], false), "failed", "A failure after a skip");
+    expect_eq(of(&[Kind::Skip], false), "skipped", "A skip");
+    expect_eq(of(&[Kind::Fail], true), "xfail", "An expected failure");
+    expect_eq(of(&[], true), "xpass", "A pass when expected to fail");
+}
+
+fn panics() {
+    let running = |xfail| Running { number: 1, name: "panics", title: "", category: "", xfail, started: Instant::now() };
+    let mut abandoned = None;
+    let (left, _) = recorded(|| {
+        expect_true(true, "Before the panic");
+        abandoned = Some(abandon(&running(None), "boom"));
+    });
+    expect_true(left.is_empty(), "Messages taken into the result");
+    if let Some(result) = abandoned {
+        expect_eq(result.status.name(), "failed", "Status");
+        let kinds: Vec<&str> = result.messages.iter().map(|message| message.kind.name()).collect();
+        expect_eq(kinds, vec!["pass", "fail"], "Kinds recorded");
+        let text = result.messages.last().map(|message| message.text.as_str());
+        expect_eq(text, Some("Panicked: boom"), "The panic's message");
+    }
+
+    let mut abandoned = None;
+    recorded(|| abandoned = Some(abandon(&running(Some("known gap")), "boom")));
+    expect_eq(abandoned.map(|result| result.status.name()), Some("xfail"), "Status when expected to fail");
+}
+
+fn json() {
+    let result = TestResult {
+        name: "quote\"d",
+        title: "",
+        category: "",
+        status: Status::Failed,
+        duration: Duration::from_micros(1500),
+        messages: vec![
+            Message::new(Kind::Step, "tab\tnewline\n\u{1}".to_string()),
+            Message {
+                location: Some(("src/x.rs", 7)),
+                expected: Some("1".to_string()),
+                actual: Some("2".to_string()),
+                error: Some(OsError { kind: io::ErrorKind::NotFound, errno: Some(2) }),
+                ..Message::new(Kind::Fail, "back\\slash".to_string())
+            },
+        ],
+        errno: Some(2),
+        xfail: None,
+        attempts: 2,
+    };
+    expect_eq(
+        to_json(&result).as_str(),
+        concat!(
+            r#"{"name":"quote\"d","status":"failed","duration":1.500,"attempts":2,"messages":["#,
+            r#"{"kind":"step","text":"tab\tnewline\n\u0001"},"#,
+            r#"{"kind":"fail","text":"back\\slash","file":"src/x.rs","line":7,"expected":"1","actual":"2","#,
+            r#""error":{"kind":"NotFound","errno":2}}],"errno":2}"#
+        ),
+        "A result as JSON",
+    );
+}
+
+fn random_numbers() {
+    // SplitMix64's reference implementation gives these for the seed 0
+    let mut rng = Rng::new(0);
+    let values = [rng.next_u64(), rng.next_u64(), rng.next_u64()];
+    expect_eq(values, [0xe220_a839_7b1d_cdaf, 0x6e78_9e6a_a1b9_65f4, 0x06c4_5d18_8009_454f], "Numbers from the seed 0");
+
+    let draw = |name| Rng::for_test(name).next_u64();
+    expect_eq(draw("a"), draw("a"), "The same numbers for the same test");
+    expect_true(draw("a") != draw("b"), "Other numbers for another test");
+
+    let mut rng = Rng::new(random::seed());
+    expect_true((0..1000).all(|_| rng.below(7) < 7), "Numbers below a bound");
+    let shuffled = || {
+        let mut items: Vec<u32> = (0..50).collect();
+        Rng::new(random::seed()).shuffle(&mut items);
+        items
+    };
+    let order = shuffled();
+    expect_eq(&order, &shuffled(), "The same order from the same seed");
+    let mut sorted = order.clone();
+    sorted.sort();
+    expect_eq(sorted, (0..50).collect(), "Every item kept by a shuffle");
+}
+
+fn timing() {
+    let mut summary = Summary::new();
+    summary.record("fast", true, Duration::from_millis(1));
+    summary.record("slow", true, Duration::from_millis(30));
+    summary.record("slow", false, Duration::from_millis(10));
+    summary.record("medium", false, Duration::from_millis(5));
+
+    let slowest: Vec<_> = summary.slowest(2).iter().map(|outcomes| (outcomes.name, outcomes.mean())).collect();
+    let expected = vec![("slow", Duration::from_millis(20)), ("medium", Duration::from_millis(5))];
+    expect_eq(slowest, expected, "Slowest by mean time");
+    let flaky: Vec<&str> = summary.flaky().map(|outcomes| outcomes.name).collect();
+    expect_eq(flaky, vec!["slow"], "Passed in some runs and failed in others");
+    expect_eq(format!("{:.3}", ms(Duration::from_micros(2500))), "2.500".to_string(), "Milliseconds");
+}
+
+fn selection() {
+    for (pattern, name, matches) in [
+        ("file_*", "file_modes", true),
+        ("file_mode?", "file_modes", true),
+        ("*links", "hard_links", true),
+        ("file_?", "file_modes", false),
+        ("*_ops", "file_operations", false),
+        ("{hard,sym}links", "symlinks", true),
+        ("[!h]*_links", "hard_links", false),
+    ] {
+        let glob = Glob::new(pattern).unwrap();
+        expect_eq(glob.matches(name), matches, &format!("{} against {}", pattern, name));
+    }
+
+    fn noop(_: &TestCtx) {}
+    const TESTS: &[TestCase] = &[
+        TestCase::new("one", "", noop).tags(&["fs"]),
+        TestCase::new("two", "", noop).tags(&["fs", "perf"]),
+        TestCase::new("three", "", noop),
+    ];
+    let names = |tests: Vec<&TestCase>| tests.iter().map(|test| test.name).collect::<Vec<_>>();
+    let select = |patterns: &[&str], tags: &[&str], skip_tags: &[&str]| {
+        let strings = |list: &[&str]| list.iter().map(|item| item.to_string()).collect::<Vec<_>>();
+        names(registry::select(TESTS, &strings(patterns), &strings(tags), &strings(skip_tags)))
+    };
+    expect_eq(select(&["t"], &[], &[]), vec!["two", "three"], "Selected by name");
+    expect_eq(select(&[], &["fs"], &["perf"]), vec!["one"], "Selected by tag");
+    let shard = |index| names(Shard { index, count: 2 }.select(TESTS.iter().collect()));
+    expect_eq((shard(1), shard(2)), (vec!["one", "three"], vec!["two"]), "Split into shards");
+    expect_true(Shard::parse("3/2").is_none(), "A shard past the count");
+}
@@ -0,0 +1,84 @@
+//! Skip lists: tests a deployment knows it can't pass.
+//!
+//! With `--skip-file /etc/wasm-tests.skip`, tests named in that file are
+//! skipped, so an ecmaOS deployment lacking a feature can keep a list in
+//! its filesystem rather than rebuild the suite or spell out `--filter` on
+//! every run. Each line holds a test name or a glob pattern as the shell
+//! takes them (`file_mode?`, `*_links`, `{hard,sym}links`), optionally
+//! followed by a `#` and why; blank lines and lines holding only a comment
+//! are ignored:
+//!
+//! ```text
+//! # The OPFS backend has no links yet
+//! symlinks
+//! hard_links
+//! file_mode?      # modes aren't kept
+//! ```
+//!
+//! A listed test is still reported, as skipped, with the line that listed
+//! it and the comment, so the run shows what went untested. Patterns that
+//! match no test are warned about, since they're likely typos or left over
+//! from a test that was renamed.
+
+use std::fs;
+use std::io;
+use std::sync::OnceLock;
+
+use ecmaos_glob::Glob;
+
+struct SkipList {
+    path: String,
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    pattern: String,
+    glob: Glob,
+    line: usize,
+    reason: Option<String>,
+}
+
+static SKIP_LIST: OnceLock<SkipList> = OnceLock::new();
+
+/// Reads the skip list at `path`, and returns the patterns in it that none
+/// of `names` match.
+pub fn load<'a>(path: &str, names: impl IntoIterator<Item = &'a str>) -> io::Result<Vec<String>> {
+    let text = fs::read_to_string(path)?;
+    let mut entries = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let (pattern, reason) = match line.split_once('#') {
+            Some((pattern, reason)) => (pattern.trim(), Some(reason.trim()).filter(|reason| !reason.is_empty())),
+            None => (line.trim(), None),
+        };
+        if pattern.is_empty() {
+            continue;
+        }
+        if pattern.contains(char::is_whitespace) {
+            let message = format!("line {}: one test name or pattern per line", number + 1);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        let glob = Glob::new(pattern)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", number + 1, e)))?;
+        let reason = reason.map(str::to_string);
+        entries.push(Entry { pattern: pattern.to_string(), glob, line: number + 1, reason });
+    }
+
+    let names: Vec<&str> = names.into_iter().collect();
+    let unmatched = entries
+        .iter()
+        .filter(|entry| !names.iter().any(|name| entry.glob.matches(name)))
+        .map(|entry| entry.pattern.clone())
+        .collect();
+    let _ = SKIP_LIST.set(SkipList { path: path.to_string(), entries });
+    Ok(unmatched)
+}
+
+/// Why the test called `name` is skipped, if the skip list has it.
+pub(super) fn listed(name: &str) -> Option<String> {
+    let list = SKIP_LIST.get()?;
+    let entry = list.entries.iter().find(|entry| entry.glob.matches(name))?;
+    Some(match &entry.reason {
+        Some(reason) => format!("Listed in {}:{} ({})", list.path, entry.line, reason),
+        None => format!("Listed in {}:{}", list.path, entry.line),
+    })
+}
@@ -0,0 +1,169 @@
+//! Golden-file snapshots.
+//!
+//! A test records outputs whose exact form matters, such as directory
+//! listings, stat fields or error messages, with [`record`] under a name of
+//! its choosing. Once the test finishes they're compared with the ones in
+//! `/tmp/snapshots/<test>.golden`, and each snapshot that changed, appeared
+//! or disappeared fails the test with a line diff. With `--update-snapshots`
+//! the file is rewritten from what was recorded instead. A test with no
+//! golden file yet only notes that there was nothing to compare against.
+//!
+//! Golden files hold each snapshot under a `### <name>` line, with every
+//! line of its value indented by two spaces so no value can be mistaken for
+//! a header:
+//!
+//! ```text
+//! ### listing
+//!   a.txt
+//!   b.txt
+//! ```
+
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::{record_message, Kind, Message};
+
+/// Directory the golden files are kept in.
+const SNAPSHOT_DIR: &str = "/tmp/snapshots";
+
+static UPDATE: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static RECORDED: RefCell<Vec<(String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Makes tests rewrite their golden files rather than compare against them.
+pub fn set_update(update: bool) {
+    UPDATE.store(update, Ordering::Relaxed);
+}
+
+/// Records `value` as the snapshot called `name` of the running test.
+pub fn record(name: &str, value: impl AsRef<str>) {
+    let value = value.as_ref().trim_end_matches('\n').to_string();
+    RECORDED.with(|recorded| recorded.borrow_mut().push((name.to_string(), value)));
+}
+
+/// Forgets snapshots left over from an earlier test.
+pub(super) fn start() {
+    RECORDED.with(|recorded| recorded.borrow_mut().clear());
+}
+
+/// Compares the snapshots the test called `test` recorded with its golden
+/// file, or rewrites the file when updating.
+pub(super) fn finish(test: &str) {
+    let recorded = RECORDED.with(|recorded| recorded.take());
+    if recorded.is_empty() {
+        return;
+    }
+    let path = Path::new(SNAPSHOT_DIR).join(format!("{}.golden", test));
+
+    if UPDATE.load(Ordering::Relaxed) {
+        match write(&path, &recorded) {
+            Ok(()) => {
+                let text = format!("Updated {} snapshots in {}", recorded.len(), path.display());
+                record_message(Message::new(Kind::Pass, text), None);
+            }
+            Err(e) => {
+                let text = format!("Failed to write {}: {}", path.display(), e);
+                record_message(Message::new(Kind::Fail, text), Some(&e));
+            }
+        }
+        return;
+    }
+
+    let golden = match fs::read_to_string(&path) {
+        Ok(text) => parse(&text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let text = format!("No snapshots in {} to compare with; record them with --update-snapshots", path.display());
+            record_message(Message::new(Kind::Step, text), None);
+            return;
+        }
+        Err(e) => {
+            let text = format!("Failed to read {}: {}", path.display(), e);
+            record_message(Message::new(Kind::Fail, text), Some(&e));
+            return;
+        }
+    };
+
+    for (name, value) in &recorded {
+        let message = match golden.iter().find(|(golden_name, _)| golden_name == name) {
+            Some((_, expected)) if expected == value => Message::new(Kind::Pass, format!("Snapshot {} matches", name)),
+            Some((_, expected)) => {
+                Message::new(Kind::Fail, format!("Snapshot {} changed:\n{}", name, diff(expected, value)))
+            }
+            None => Message::new(Kind::Fail, format!("Snapshot {} is new:\n{}", name, diff("", value))),
+        };
+        record_message(message, None);
+    }
+    for (name, _) in golden.iter().filter(|(name, _)| !recorded.iter().any(|(recorded, _)| recorded == name)) {
+        record_message(Message::new(Kind::Fail, format!("Snapshot {} is no longer recorded", name)), None);
+    }
+}
+
+fn write(path: &Path, recorded: &[(String, String)]) -> io::Result<()> {
+    let mut text = String::new();
+    for (name, value) in recorded {
+        let _ = writeln!(text, "### {}", name);
+        for line in value.lines() {
+            let _ = writeln!(text, "  {}", line);
+        }
+    }
+    fs::create_dir_all(SNAPSHOT_DIR)?;
+    fs::write(path, text)
+}
+
+fn parse(text: &str) -> Vec<(String, String)> {
+    let mut snapshots: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in text.lines() {
+        if let Some(name) = line.strip_prefix("### ") {
+            snapshots.push((name.to_string(), Vec::new()));
+            continue;
+        }
+        // Editors may strip the indentation of empty lines
+        let line = line.strip_prefix("  ").or(line.is_empty().then_some(""));
+        if let (Some((_, lines)), Some(line)) = (snapshots.last_mut(), line) {
+            lines.push(line);
+        }
+    }
+    snapshots.into_iter().map(|(name, lines)| (name, lines.join("\n"))).collect()
+}
+
+/// A line diff of two values: unchanged lines are prefixed with two spaces,
+/// removed ones with `-` and added ones with `+`.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = if expected.is_empty() { Vec::new() } else { expected.lines().collect() };
+    let actual: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequence lengths of every pair of suffixes
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            lines.push(format!("  {}", expected[i]));
+            i += 1;
+            j += 1;
+        } else if j < actual.len() && (i == expected.len() || common[i][j + 1] >= common[i + 1][j]) {
+            lines.push(format!("+ {}", actual[j]));
+            j += 1;
+        } else {
+            lines.push(format!("- {}", expected[i]));
+            i += 1;
+        }
+    }
+    lines.join("\n")
+}
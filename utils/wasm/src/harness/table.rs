@@ -0,0 +1,70 @@
+//! Tables for text output, drawn with box-drawing characters or, for
+//! terminals that can't show those, in plain ASCII (see
+//! [`set_ascii`](super::set_ascii)).
+
+#[derive(Clone, Copy)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+pub struct Table {
+    columns: Vec<(&'static str, Align)>,
+    rows: Vec<Vec<String>>,
+}
+
+/// The corners and joins of a table's top, its rule below the headers and
+/// its bottom, each as (left, middle, right), then the horizontal and
+/// vertical lines.
+type Lines = ([[char; 3]; 3], char, char);
+
+const BOX: Lines = ([['┌', '┬', '┐'], ['├', '┼', '┤'], ['└', '┴', '┘']], '─', '│');
+const ASCII: Lines = ([['+'; 3]; 3], '-', '|');
+
+impl Table {
+    /// A table with the given column headers and how each column's cells
+    /// are aligned.
+    pub fn new(columns: &[(&'static str, Align)]) -> Table {
+        Table { columns: columns.to_vec(), rows: Vec::new() }
+    }
+
+    /// Adds a row, with a cell for each column.
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    /// The lines of the table, headers first.
+    pub fn render(&self, ascii: bool) -> Vec<String> {
+        let (corners, horizontal, vertical) = if ascii { ASCII } else { BOX };
+        let widths: Vec<usize> = (0..self.columns.len())
+            .map(|i| {
+                let cells = self.rows.iter().map(|row| row[i].chars().count());
+                cells.chain([self.columns[i].0.chars().count()]).max().unwrap_or_default()
+            })
+            .collect();
+        let rule = |[left, middle, right]: [char; 3]| {
+            let parts: Vec<String> = widths.iter().map(|&width| horizontal.to_string().repeat(width + 2)).collect();
+            format!("{}{}{}", left, parts.join(&middle.to_string()), right)
+        };
+        let line = |cells: Vec<&str>| {
+            let parts: Vec<String> = cells
+                .iter()
+                .zip(&self.columns)
+                .zip(&widths)
+                .map(|((cell, (_, align)), &width)| match align {
+                    Align::Left => format!(" {:<width$} ", cell),
+                    Align::Right => format!(" {:>width$} ", cell),
+                })
+                .collect();
+            format!("{}{}{}", vertical, parts.join(&vertical.to_string()), vertical)
+        };
+
+        let mut lines = vec![rule(corners[0]), line(self.columns.iter().map(|(header, _)| *header).collect())];
+        lines.push(rule(corners[1]));
+        for row in &self.rows {
+            lines.push(line(row.iter().map(String::as_str).collect()));
+        }
+        lines.push(rule(corners[2]));
+        lines
+    }
+}
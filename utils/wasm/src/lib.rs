@@ -0,0 +1,214 @@
+//! The WASM interface test suite: a harness for checking how a runtime
+//! implements WASI, and the tests that check it.
+//!
+//! The tests are grouped by what they exercise, each group in a module of
+//! its own, and registered in run order in [`TESTS`]. The suite's runner is
+//! `src/bin/test.rs`, built as `testrs.wasm`. Other programs, such as
+//! benchmarks, fuzzers or conformance runners, can use the same harness,
+//! and run all of the tests or a selection of them, by adding a binary to
+//! `src/bin` that depends on this crate. The harness's macros (`step!`,
+//! `fail!` and the rest) are exported at the root of the crate.
+
+#[macro_use]
+pub mod harness;
+
+mod allocate_tests;
+mod fdflags_tests;
+mod fs_tests;
+mod hard_link_tests;
+mod open_tests;
+mod permission_tests;
+mod position_tests;
+mod pread_tests;
+mod process_tests;
+mod random_tests;
+mod readdir_tests;
+mod rights_tests;
+mod stdio_tests;
+mod symlink_tests;
+mod sync_tests;
+mod time_tests;
+
+use harness::capabilities::Capability::*;
+use harness::registry::TestCase;
+
+/// Every test in run order.
+pub const TESTS: &[TestCase] = &[
+    TestCase::new("stdout_stderr", "stdout/stderr I/O", stdio_tests::test_stdout_stderr)
+        .tags(&["stdio"])
+        .requires(&[Stdio]),
+    TestCase::new("command_line_args", "Command-line arguments", process_tests::test_command_line_args)
+        .tags(&["process"])
+        .requires(&[Args]),
+    TestCase::new("environment_variables", "Environment variables", process_tests::test_environment_variables)
+        .tags(&["process", "env"])
+        .requires(&[Environment]),
+    TestCase::new("file_operations", "File operations", fs_tests::test_file_operations)
+        .tags(&["fs"])
+        .requires(&[Filesystem]),
+    TestCase::new("directory_operations", "Directory operations", fs_tests::test_directory_operations)
+        .tags(&["fs", "dir"])
+        .requires(&[Filesystem]),
+    TestCase::new("path_operations", "Path operations", fs_tests::test_path_operations)
+        .tags(&["fs", "dir"])
+        .requires(&[Filesystem]),
+    TestCase::new("stat_operations", "Stat operations", fs_tests::test_stat_operations)
+        .tags(&["fs", "metadata"])
+        .requires(&[Filesystem])
+        .files(&[("stat_test.txt", fs_tests::STAT_CONTENT)]),
+    TestCase::new("time_operations", "Time operations", time_tests::test_time_operations)
+        .tags(&["time"])
+        .requires(&[Clock]),
+    TestCase::new("random_operations", "Random operations", random_tests::test_random_operations).tags(&["random"]),
+    TestCase::new("seek_operations", "Seek operations", fs_tests::test_seek_operations)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem])
+        .files(&[("seek_test.txt", fs_tests::SEEK_CONTENT)]),
+    TestCase::new("file_rename", "File rename operations", fs_tests::test_file_rename)
+        .tags(&["fs"])
+        .requires(&[Filesystem])
+        .files(&[("rename_source.txt", fs_tests::RENAME_CONTENT)]),
+    TestCase::new("file_truncate", "File truncate operations", fs_tests::test_file_truncate)
+        .tags(&["fs"])
+        .requires(&[Filesystem])
+        .files(&[("truncate_test.txt", fs_tests::TRUNCATE_CONTENT)]),
+    TestCase::new("multiple_file_descriptors", "Multiple file descriptors", fs_tests::test_multiple_file_descriptors)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem]),
+    TestCase::new("large_file_operations", "Large file operations", fs_tests::test_large_file_operations)
+        .tags(&["fs", "perf"])
+        .requires(&[Filesystem]),
+    TestCase::new("error_conditions", "Error conditions", fs_tests::test_error_conditions)
+        .tags(&["fs", "errors"])
+        .requires(&[Filesystem])
+        .files(&[("error_test.txt", "test")]),
+    TestCase::new("file_permissions", "File permissions", fs_tests::test_file_permissions)
+        .tags(&["fs", "metadata"])
+        .requires(&[Filesystem, Permissions])
+        .files(&[("perms_test.txt", "permissions test")]),
+    TestCase::new("file_modes", "File modes", permission_tests::test_file_modes)
+        .tags(&["fs", "metadata", "permissions"])
+        .requires(&[Filesystem, Permissions])
+        .files(&[("mode_test.txt", "mode test")])
+        .dirs(&["mode_dir"]),
+    TestCase::new("creation_umask", "umask on file creation", permission_tests::test_creation_umask)
+        .tags(&["fs", "metadata", "permissions"])
+        .requires(&[Filesystem, Permissions]),
+    TestCase::new("file_ownership", "File ownership", permission_tests::test_file_ownership)
+        .tags(&["fs", "metadata", "permissions"])
+        .requires(&[Filesystem, Permissions])
+        .files(&[("owner_test.txt", "ownership test")]),
+    TestCase::new("working_directory", "Working directory operations", process_tests::test_working_directory)
+        .tags(&["process", "dir", "destructive"])
+        .requires(&[Filesystem, WorkingDirectory])
+        .dirs(&["cwd_test"])
+        .teardown(process_tests::leave_test_directory),
+    TestCase::new("file_timestamps", "File timestamps", fs_tests::test_file_timestamps)
+        .tags(&["fs", "metadata", "time"])
+        .requires(&[Filesystem, Clock])
+        .setup(fs_tests::create_old_file),
+    TestCase::new("file_descriptor_operations", "File descriptor operations", fs_tests::test_file_descriptor_operations)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem])
+        .files(&[("fd_ops.txt", fs_tests::FD_OPS_CONTENT)]),
+    TestCase::new("open_flags", "Open flags", open_tests::test_open_flags)
+        .tags(&["fs", "fd", "errors"])
+        .requires(&[Filesystem])
+        .dirs(&["dir"]),
+    TestCase::new("append_mode", "Append mode", open_tests::test_append_mode)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem])
+        .files(&[("append.txt", open_tests::ORIGINAL)]),
+    TestCase::new("fd_flags", "Changing descriptor flags", fdflags_tests::test_fd_flags)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem])
+        .files(&[("flags.txt", fdflags_tests::FLAGS_CONTENT)])
+        .xfail("fd_fdstat_set_flags is a stub"),
+    TestCase::new("fd_allocate", "Allocating space with fd_allocate", allocate_tests::test_fd_allocate)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem])
+        .files(&[("allocate.txt", allocate_tests::ALLOCATE_CONTENT)])
+        .xfail("fd_allocate is a stub"),
+    TestCase::new("fd_advise", "Advice with fd_advise", allocate_tests::test_fd_advise)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem])
+        .files(&[("allocate.txt", allocate_tests::ALLOCATE_CONTENT)])
+        .xfail("fd_advise is a stub"),
+    TestCase::new("fd_sync", "Syncing to storage", sync_tests::test_fd_sync).tags(&["fs", "fd"]).requires(&[Filesystem]),
+    TestCase::new("fd_tell", "Tracking the position with fd_tell", position_tests::test_fd_tell)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem])
+        .files(&[("position.txt", position_tests::POSITION_CONTENT)]),
+    TestCase::new("pread_pwrite", "Reading and writing at offsets", pread_tests::test_pread_pwrite)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem])
+        .files(&[("pread.txt", pread_tests::PREAD_CONTENT)]),
+    TestCase::new("create_new_exclusive", "Exclusive creation", open_tests::test_create_new_exclusive)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem]),
+    TestCase::new("create_new_race", "Exclusive creation across threads", open_tests::test_create_new_race)
+        .tags(&["fs", "fd", "threads"])
+        .requires(&[Filesystem, Threads]),
+    TestCase::new("fd_access_modes", "Descriptor access modes", rights_tests::test_fd_access_modes)
+        .tags(&["fs", "fd", "permissions"])
+        .requires(&[Filesystem])
+        .files(&[("rights.txt", rights_tests::RIGHTS_CONTENT)]),
+    TestCase::new("fd_rights", "Descriptor rights", rights_tests::test_fd_rights)
+        .tags(&["fs", "fd", "permissions"])
+        .requires(&[Filesystem])
+        .files(&[("rights.txt", rights_tests::RIGHTS_CONTENT)]),
+    TestCase::new("concurrent_operations", "Concurrent file operations", fs_tests::test_concurrent_operations)
+        .tags(&["fs", "fd"])
+        .requires(&[Filesystem]),
+    TestCase::new("readdir_pagination", "Paged fd_readdir listings", readdir_tests::test_readdir_pagination)
+        .tags(&["fs", "dir"])
+        .requires(&[Filesystem])
+        .dirs(&["many"]),
+    TestCase::new("symlinks", "Symbolic links", fs_tests::test_symlinks)
+        .tags(&["fs", "links"])
+        .requires(&[Filesystem, Symlinks])
+        .files(&[("symlink_target.txt", fs_tests::LINK_CONTENT)]),
+    TestCase::new("hard_links", "Hard links", fs_tests::test_hard_links)
+        .tags(&["fs", "links"])
+        .requires(&[Filesystem])
+        .files(&[("link_source.txt", fs_tests::LINK_CONTENT)])
+        .xfail("path_link not implemented"),
+    TestCase::new("hard_link_count", "Hard link counts", hard_link_tests::test_hard_link_count)
+        .tags(&["fs", "links", "metadata"])
+        .requires(&[Filesystem])
+        .dirs(&["sub"])
+        .files(&[("source.txt", hard_link_tests::SOURCE_CONTENT)])
+        .xfail("path_link not implemented"),
+    TestCase::new("hard_link_names", "Hard link names", hard_link_tests::test_hard_link_names)
+        .tags(&["fs", "links"])
+        .requires(&[Filesystem])
+        .files(&[("source.txt", hard_link_tests::SOURCE_CONTENT)])
+        .xfail("path_link not implemented"),
+    TestCase::new("hard_link_directories", "Hard links across directories", hard_link_tests::test_hard_link_directories)
+        .tags(&["fs", "links"])
+        .requires(&[Filesystem])
+        .dirs(&["from", "to", "third"])
+        .files(&[("from/file.txt", hard_link_tests::SOURCE_CONTENT)])
+        .xfail("path_link not implemented"),
+    TestCase::new("hard_link_errors", "Hard link errors", hard_link_tests::test_hard_link_errors)
+        .tags(&["fs", "links"])
+        .requires(&[Filesystem])
+        .dirs(&["dir"])
+        .files(&[("source.txt", hard_link_tests::SOURCE_CONTENT)])
+        .xfail("path_link not implemented"),
+    TestCase::new("symlink_operations", "Symbolic link operations", symlink_tests::test_symlink_operations)
+        .tags(&["fs", "links"])
+        .requires(&[Filesystem, Symlinks])
+        .dirs(&["dir"])
+        .files(&[("target.txt", symlink_tests::TARGET_CONTENT), ("dir/inner.txt", symlink_tests::TARGET_CONTENT)]),
+    TestCase::new("symlink_metadata", "Symbolic link metadata", symlink_tests::test_symlink_metadata)
+        .tags(&["fs", "links", "metadata"])
+        .requires(&[Filesystem, Symlinks])
+        .files(&[("target.txt", symlink_tests::TARGET_CONTENT)]),
+    TestCase::new("dangling_symlinks", "Dangling symbolic links", symlink_tests::test_dangling_symlinks)
+        .tags(&["fs", "links"])
+        .requires(&[Filesystem, Symlinks]),
+    TestCase::new("symlink_loops", "Symbolic link loops", symlink_tests::test_symlink_loops)
+        .tags(&["fs", "links"])
+        .requires(&[Filesystem, Symlinks]),
+];
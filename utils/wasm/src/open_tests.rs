@@ -0,0 +1,370 @@
+//! Tests of opening files with each combination of `OpenOptions` flags:
+//! whether the file has to exist or mustn't, what truncating and appending
+//! do to it, which ways it can be used once open, and the errors for names
+//! that aren't files.
+//!
+//! Only the combinations std passes on to the system are tried. The rest,
+//! such as truncating without writing, are turned down by std itself on
+//! some targets and not on others, so they say nothing about the kernel.
+//! What every combination did is kept as a snapshot, so a change in any
+//! of them shows up as a diff.
+//!
+//! Appending gets a test of its own, since it's the kernel's fd layer
+//! rather than the program that decides where an appended write lands: at
+//! the end of the file as it is at the time of the write, wherever the
+//! handle was sought to and whatever other handles have written since.
+//!
+//! So does exclusive creation, which is only any use to programs that lock
+//! or claim names with it if the check that the file doesn't exist and the
+//! creation happen as one step, even when several threads race to create
+//! the same file.
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Barrier;
+use std::thread;
+
+use crate::harness::assert::{expect_eq, expect_eq_bytes, expect_err_kind, expect_ok, expect_true};
+use crate::harness::context::TestCtx;
+use crate::harness::snapshot;
+
+pub(crate) const ORIGINAL: &str = "original content";
+
+/// What's written at the start of each file once it's open.
+const WRITTEN: &str = "new";
+
+/// How many times `test_create_new_exclusive` creates the same file.
+const ATTEMPTS: usize = 20;
+
+/// How many files `test_create_new_race` has threads race to create, and
+/// how many threads race for each.
+const ROUNDS: usize = 20;
+const RACERS: usize = 4;
+
+/// A combination of `OpenOptions` flags.
+#[derive(Clone, Copy)]
+struct Flags {
+    read: bool,
+    write: bool,
+    append: bool,
+    create: bool,
+    truncate: bool,
+    create_new: bool,
+}
+
+impl Flags {
+    /// Every combination that std accepts on all targets: one with some way
+    /// to use the file, that only creates or truncates files it can write,
+    /// and that doesn't both append and truncate.
+    fn all() -> Vec<Flags> {
+        let access = [
+            (true, false, false),
+            (false, true, false),
+            (true, true, false),
+            (false, false, true),
+            (true, false, true),
+        ];
+        let creation = [
+            (false, false, false),
+            (true, false, false),
+            (false, true, false),
+            (true, true, false),
+            (false, false, true),
+        ];
+        let mut all = Vec::new();
+        for (read, write, append) in access {
+            for (create, truncate, create_new) in creation {
+                let flags = Flags { read, write, append, create, truncate, create_new };
+                let creates = create || truncate || create_new;
+                if (flags.writes() || !creates) && !(append && truncate) {
+                    all.push(flags);
+                }
+            }
+        }
+        all
+    }
+
+    fn writes(&self) -> bool {
+        self.write || self.append
+    }
+
+    fn options(&self) -> OpenOptions {
+        let mut options = OpenOptions::new();
+        options
+            .read(self.read)
+            .write(self.write)
+            .append(self.append)
+            .create(self.create)
+            .truncate(self.truncate)
+            .create_new(self.create_new);
+        options
+    }
+
+    /// The error opening a file with these flags should fail with.
+    fn error(&self, existing: bool) -> Option<io::ErrorKind> {
+        match existing {
+            true if self.create_new => Some(io::ErrorKind::AlreadyExists),
+            false if !self.create && !self.create_new => Some(io::ErrorKind::NotFound),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = [
+            (self.read, "read"),
+            (self.write, "write"),
+            (self.append, "append"),
+            (self.create, "create"),
+            (self.truncate, "truncate"),
+            (self.create_new, "create_new"),
+        ];
+        let set: Vec<&str> = names.iter().filter(|(set, _)| *set).map(|(_, name)| *name).collect();
+        write!(f, "{}", set.join("|"))
+    }
+}
+
+fn outcome<T>(result: &io::Result<T>) -> String {
+    match result {
+        Ok(_) => "ok".to_string(),
+        Err(e) => format!("{:?}", e.kind()),
+    }
+}
+
+/// Opens `path` with `flags`, uses the file every way, and checks what
+/// each step did. Returns what happened, for the snapshot.
+fn try_flags(path: &str, flags: Flags, existing: bool) -> String {
+    let opened = flags.options().open(path);
+    let what = format!("open: {}", outcome(&opened));
+    if let Some(kind) = flags.error(existing) {
+        expect_err_kind(opened, kind, "Open");
+        match existing {
+            true => expect_true(fs::read(path).is_ok_and(|content| content == ORIGINAL.as_bytes()), "File untouched"),
+            false => expect_true(!Path::new(path).exists(), "No file created"),
+        };
+        return what;
+    }
+    let Some(mut file) = expect_ok(opened, "Open") else {
+        return what;
+    };
+
+    let initial = if existing && !flags.truncate { ORIGINAL } else { "" };
+    let length = file.metadata().map(|metadata| metadata.len());
+    if let Some(length) = expect_ok(length, "Length once open") {
+        expect_eq(length, initial.len() as u64, "Length once open");
+    }
+
+    let mut content = String::new();
+    let read = file.read_to_string(&mut content);
+    let what = format!("{}, read: {}", what, outcome(&read));
+    if !flags.read {
+        expect_true(read.is_err(), "Can't read without read");
+    } else if expect_ok(read, "Read").is_some() {
+        expect_eq(content.as_str(), initial, "Content read");
+    }
+
+    // Appending writes at the end wherever the file was sought to
+    let written = file.seek(SeekFrom::Start(0)).and_then(|_| file.write_all(WRITTEN.as_bytes()));
+    let what = format!("{}, write: {}", what, outcome(&written));
+    let expected = match (flags.writes(), flags.append) {
+        (false, _) => initial.to_string(),
+        (true, true) => format!("{}{}", initial, WRITTEN),
+        (true, false) => format!("{}{}", WRITTEN, initial.get(WRITTEN.len()..).unwrap_or("")),
+    };
+    if flags.writes() {
+        expect_ok(written, "Write at the start");
+    } else {
+        expect_true(written.is_err(), "Can't write without write or append");
+    }
+    drop(file);
+
+    let Some(content) = expect_ok(fs::read(path), "Read back") else {
+        return what;
+    };
+    expect_eq_bytes(&content, expected.as_bytes(), "Content afterwards");
+    format!("{}, left {:?}", what, String::from_utf8_lossy(&content))
+}
+
+pub(crate) fn test_open_flags(ctx: &TestCtx) {
+    use io::ErrorKind::{AlreadyExists, IsADirectory, NotADirectory, NotFound};
+
+    let mut outcomes = Vec::new();
+    for (index, flags) in Flags::all().into_iter().enumerate() {
+        for existing in [true, false] {
+            let (state, article) = if existing { ("existing", "an") } else { ("missing", "a") };
+            step!("Opening {} {} file with {}", article, state, flags);
+            let path = &ctx.path(&format!("open_{}_{}.txt", index, state));
+            if existing && expect_ok(fs::write(path, ORIGINAL), "Create the file").is_none() {
+                continue;
+            }
+            let outcome = try_flags(path, flags, existing);
+            outcomes.push(format!("{} on {} {} file: {}", flags, article, state, outcome));
+        }
+    }
+
+    step!("Opening names that aren't files");
+    let (file, dir) = (&ctx.path("file.txt"), &ctx.path("dir"));
+    if expect_ok(fs::write(file, ORIGINAL), "Create a file").is_none() {
+        return;
+    }
+    let cases: [(&str, io::Result<()>, io::ErrorKind); 6] = [
+        // read_dir opens with O_DIRECTORY
+        ("a file as a directory", fs::read_dir(file).map(drop), NotADirectory),
+        ("a file with a trailing slash", fs::File::open(format!("{}/", file)).map(drop), NotADirectory),
+        ("a name under a file", fs::File::open(format!("{}/inner", file)).map(drop), NotADirectory),
+        ("a directory to write", OpenOptions::new().write(true).open(dir).map(drop), IsADirectory),
+        ("over a directory", OpenOptions::new().write(true).create_new(true).open(dir).map(drop), AlreadyExists),
+        ("in a missing directory", fs::File::create(ctx.path("missing/file.txt")).map(drop), NotFound),
+    ];
+    for (what, result, kind) in cases {
+        outcomes.push(format!("{}: {}", what, outcome(&result)));
+        expect_err_kind(result, kind, &format!("Open {}", what));
+    }
+    snapshot::record("outcomes", outcomes.join("\n"));
+}
+
+pub(crate) fn test_append_mode(ctx: &TestCtx) {
+    let path = &ctx.path("append.txt");
+    let mut expected = ORIGINAL.to_string();
+    let open_append = || OpenOptions::new().read(true).append(true).open(path);
+
+    step!("Opening two appending handles");
+    let (Some(mut first), Some(mut second)) =
+        (expect_ok(open_append(), "Open the first handle"), expect_ok(open_append(), "Open the second handle"))
+    else {
+        return;
+    };
+
+    step!("Interleaving writes");
+    for (handle, data) in [(1, "first 1"), (2, "second 1"), (1, "first 2"), (2, "second 2")] {
+        let file = if handle == 1 { &mut first } else { &mut second };
+        if expect_ok(file.write_all(data.as_bytes()), &format!("Write {}", data)).is_none() {
+            return;
+        }
+        expected.push_str(data);
+    }
+    // Each write leaves its handle at the end of the file
+    if let Some(position) = expect_ok(second.stream_position(), "Position of the last handle written to") {
+        expect_eq(position, expected.len() as u64, "Position after the write");
+    }
+    if let Some(content) = expect_ok(fs::read(path), "Read back") {
+        expect_eq_bytes(&content, expected.as_bytes(), "Writes from both handles in order");
+    }
+
+    step!("Seeking to the start and writing again");
+    if expect_ok(first.seek(SeekFrom::Start(0)), "Seek to the start").is_some() {
+        let mut start = [0; 8];
+        if expect_ok(first.read_exact(&mut start), "Read from the start").is_some() {
+            expect_eq_bytes(&start, &ORIGINAL.as_bytes()[..8], "Reads aren't moved to the end");
+        }
+        if expect_ok(first.write_all(b"sought"), "Write after seeking").is_some() {
+            expected.push_str("sought");
+        }
+    }
+    if let Some(content) = expect_ok(fs::read(path), "Read back") {
+        expect_eq_bytes(&content, expected.as_bytes(), "Write landed at the end");
+    }
+
+    step!("Writing through a handle that doesn't append");
+    let Some(mut plain) = expect_ok(OpenOptions::new().write(true).open(path), "Open without append") else {
+        return;
+    };
+    if expect_ok(plain.write_all(b"OVER"), "Overwrite the start").is_some() {
+        expected.replace_range(..4, "OVER");
+    }
+    if expect_ok(second.write_all(b"after"), "Append after the overwrite").is_some() {
+        expected.push_str("after");
+    }
+    if let Some(content) = expect_ok(fs::read(path), "Read back") {
+        expect_eq_bytes(&content, expected.as_bytes(), "Overwrite in place, append at the end");
+    }
+
+    step!("Appending after the file shrinks");
+    if expect_ok(plain.set_len(4), "Truncate to 4 bytes").is_some() {
+        expected.truncate(4);
+    }
+    if expect_ok(first.write_all(b"+end"), "Append after truncating").is_some() {
+        expected.push_str("+end");
+    }
+    if let Some(content) = expect_ok(fs::read(path), "Read back") {
+        expect_eq_bytes(&content, expected.as_bytes(), "Appended at the new end, with no gap");
+    }
+}
+
+/// Creates `path` with `create_new` and writes `owner` to it, returning the
+/// kind of error if it couldn't.
+fn create_exclusively(path: &str, owner: &str) -> Result<(), io::ErrorKind> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path).map_err(|e| e.kind())?;
+    file.write_all(owner.as_bytes()).map_err(|e| e.kind())
+}
+
+pub(crate) fn test_create_new_exclusive(ctx: &TestCtx) {
+    let path = &ctx.path("exclusive.txt");
+
+    step!("Creating the same file {} times", ATTEMPTS);
+    let results: Vec<_> =
+        (0..ATTEMPTS).map(|attempt| create_exclusively(path, &format!("attempt {}", attempt))).collect();
+    expect_eq(results[0], Ok(()), "First attempt");
+    let refused = results[1..].iter().filter(|result| **result == Err(io::ErrorKind::AlreadyExists)).count();
+    expect_eq(refused, ATTEMPTS - 1, "Later attempts refused with AlreadyExists");
+    if let Some(content) = expect_ok(fs::read(path), "Read the file") {
+        expect_eq_bytes(&content, b"attempt 0", "Content from the first attempt");
+    }
+
+    step!("Creating the file again once it's gone");
+    if expect_ok(fs::remove_file(path), "Remove the file").is_some() {
+        expect_eq(create_exclusively(path, "again"), Ok(()), "Create after removing");
+        expect_eq(create_exclusively(path, "again"), Err(io::ErrorKind::AlreadyExists), "Create once more");
+    }
+}
+
+pub(crate) fn test_create_new_race(ctx: &TestCtx) {
+    step!("Racing {} threads to create each of {} files", RACERS, ROUNDS);
+    // Assertions are recorded per thread, so the racers only report back
+    let mut rounds = Vec::new();
+    for round in 0..ROUNDS {
+        let path = &ctx.path(&format!("race_{}.txt", round));
+        let start = Barrier::new(RACERS);
+        let results: Vec<_> = thread::scope(|scope| {
+            let racers: Vec<_> = (0..RACERS)
+                .map(|racer| {
+                    let start = &start;
+                    scope.spawn(move || {
+                        start.wait();
+                        create_exclusively(path, &format!("racer {}", racer))
+                    })
+                })
+                .collect();
+            racers.into_iter().map(|racer| racer.join().unwrap_or(Err(io::ErrorKind::Other))).collect()
+        });
+        let content = fs::read_to_string(path).unwrap_or_default();
+        rounds.push((round, results, content));
+    }
+
+    step!("Counting the winners");
+    let mut contested = Vec::new();
+    let mut unexpected = Vec::new();
+    for (round, results, content) in &rounds {
+        let winners: Vec<usize> = (0..RACERS).filter(|&racer| results[racer].is_ok()).collect();
+        if let [winner] = winners[..] {
+            if *content != format!("racer {}", winner) {
+                contested.push(format!("round {}: racer {} won, but the file holds {:?}", round, winner, content));
+            }
+        } else {
+            contested.push(format!("round {}: {} racers created the file", round, winners.len()));
+        }
+        for (racer, result) in results.iter().enumerate() {
+            match result {
+                Err(kind) if *kind != io::ErrorKind::AlreadyExists => {
+                    unexpected.push(format!("round {}, racer {}: {:?}", round, racer, kind));
+                }
+                _ => {}
+            }
+        }
+    }
+    expect_eq(contested, Vec::<String>::new(), "Exactly one racer creates each file");
+    expect_eq(unexpected, Vec::<String>::new(), "The others get AlreadyExists");
+}
@@ -0,0 +1,193 @@
+//! Tests of file modes and ownership: what chmod and chown change, and how
+//! the umask shapes the modes of new files and directories.
+//!
+//! WASI's filestat has neither a mode nor an owner, so there's nothing for
+//! these to read back on WASI targets, and they're skipped there. Run on a
+//! Unix host, they define the semantics the kernel's filesystem backends are
+//! expected to follow once modes and owners are visible to WASM programs.
+
+#[cfg(not(unix))]
+use crate::harness::context::TestCtx;
+
+#[cfg(unix)]
+mod unix {
+    use std::fmt;
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::{chown, DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+    use std::path::Path;
+
+    use crate::harness::assert::{expect_eq, expect_err_kind, expect_ok};
+    use crate::harness::context::TestCtx;
+
+    /// The modes `test_file_modes` sets, in order.
+    const MODES: [u32; 5] = [0o600, 0o644, 0o755, 0o400, 0o4755];
+
+    /// The permission bits of a mode, shown in octal when a check reports it.
+    #[derive(Clone, Copy, PartialEq)]
+    struct Mode(u32);
+
+    impl fmt::Debug for Mode {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{:o}", self.0)
+        }
+    }
+
+    /// The mode of a file, without its type.
+    fn mode_of(path: impl AsRef<Path>) -> io::Result<Mode> {
+        Ok(Mode(fs::metadata(path)?.permissions().mode() & 0o7777))
+    }
+
+    pub(crate) fn test_file_modes(ctx: &TestCtx) {
+        let test_file = &ctx.path("mode_test.txt");
+
+        for mode in MODES {
+            step!("Setting mode {:o}", mode);
+            if expect_ok(fs::set_permissions(test_file, fs::Permissions::from_mode(mode)), "Set mode").is_none() {
+                return;
+            }
+            if let Some(meta) = expect_ok(fs::metadata(test_file), "Get metadata") {
+                expect_eq(Mode(meta.permissions().mode() & 0o7777), Mode(mode), "Mode read back");
+                expect_eq(meta.permissions().readonly(), mode & 0o222 == 0, "Read-only flag");
+            }
+        }
+
+        // set_readonly only touches the write bits, for every class at once
+        step!("Setting the read-only flag");
+        let mut perms = fs::Permissions::from_mode(0o664);
+        perms.set_readonly(true);
+        if expect_ok(fs::set_permissions(test_file, perms), "Set permissions").is_some() {
+            if let Some(mode) = expect_ok(mode_of(test_file), "Get mode") {
+                expect_eq(mode, Mode(0o444), "Mode after setting read-only");
+            }
+        }
+
+        let test_dir = &ctx.path("mode_dir");
+        step!("Setting a directory's mode");
+        if expect_ok(fs::set_permissions(test_dir, fs::Permissions::from_mode(0o700)), "Set directory mode").is_some() {
+            if let Some(meta) = expect_ok(fs::metadata(test_dir), "Get directory metadata") {
+                expect_eq(Mode(meta.permissions().mode() & 0o7777), Mode(0o700), "Directory mode read back");
+                expect_eq(meta.is_dir(), true, "Still a directory");
+            }
+        }
+    }
+
+    pub(crate) fn test_creation_umask(ctx: &TestCtx) {
+        // There's no way to read the umask without changing it, so it's
+        // inferred from what it takes away from a file created with 777
+        step!("Creating a file with mode 777");
+        let probe = ctx.path("umask_probe");
+        let created = fs::OpenOptions::new().write(true).create_new(true).mode(0o777).open(&probe);
+        if expect_ok(created, "Create file").is_none() {
+            return;
+        }
+        let Some(mode) = expect_ok(mode_of(&probe), "Get mode") else {
+            return;
+        };
+        let umask = 0o777 & !mode.0;
+        detail!("umask: {:03o}", umask);
+
+        step!("Creating files with explicit modes");
+        for requested in [0o666, 0o644, 0o600, 0o755] {
+            let path = ctx.path(&format!("umask_file_{:o}", requested));
+            let created = fs::OpenOptions::new().write(true).create_new(true).mode(requested).open(&path);
+            if expect_ok(created, &format!("Create file with mode {:o}", requested)).is_some() {
+                if let Some(mode) = expect_ok(mode_of(&path), "Get mode") {
+                    expect_eq(mode, Mode(requested & !umask), &format!("Mode of file created with {:o}", requested));
+                }
+            }
+        }
+
+        step!("Creating directories with explicit modes");
+        for requested in [0o777, 0o755, 0o700] {
+            let path = ctx.path(&format!("umask_dir_{:o}", requested));
+            if expect_ok(fs::DirBuilder::new().mode(requested).create(&path), "Create directory").is_some() {
+                if let Some(mode) = expect_ok(mode_of(&path), "Get mode") {
+                    let what = format!("Mode of directory created with {:o}", requested);
+                    expect_eq(mode, Mode(requested & !umask), &what);
+                }
+            }
+        }
+
+        step!("Creating a file and a directory with the default modes");
+        let file = ctx.path("umask_default.txt");
+        if expect_ok(fs::write(&file, "umask test"), "Write file").is_some() {
+            if let Some(mode) = expect_ok(mode_of(&file), "Get mode") {
+                expect_eq(mode, Mode(0o666 & !umask), "Default file mode");
+            }
+        }
+        let dir = ctx.path("umask_default_dir");
+        if expect_ok(fs::create_dir(&dir), "Create directory").is_some() {
+            if let Some(mode) = expect_ok(mode_of(&dir), "Get mode") {
+                expect_eq(mode, Mode(0o777 & !umask), "Default directory mode");
+            }
+        }
+
+        step!("Checking chmod ignores the umask");
+        if expect_ok(fs::set_permissions(&file, fs::Permissions::from_mode(0o777)), "Set mode").is_some() {
+            if let Some(mode) = expect_ok(mode_of(&file), "Get mode") {
+                expect_eq(mode, Mode(0o777), "Mode after chmod");
+            }
+        }
+    }
+
+    pub(crate) fn test_file_ownership(ctx: &TestCtx) {
+        let test_file = &ctx.path("owner_test.txt");
+
+        // The test's directory was made by this process too, so a new file
+        // should have the same owner and group
+        step!("Comparing the owner of a new file with its directory's");
+        let Some(dir_meta) = expect_ok(fs::metadata(ctx.dir()), "Get directory metadata") else {
+            return;
+        };
+        let Some(meta) = expect_ok(fs::metadata(test_file), "Get file metadata") else {
+            return;
+        };
+        let (uid, gid) = (meta.uid(), meta.gid());
+        detail!("Owner: {}:{}", uid, gid);
+        expect_eq(uid, dir_meta.uid(), "Owner");
+        expect_eq(gid, dir_meta.gid(), "Group");
+
+        step!("Changing the owner to the current one");
+        if expect_ok(chown(test_file, Some(uid), Some(gid)), "Change owner").is_some() {
+            if let Some(meta) = expect_ok(fs::metadata(test_file), "Get metadata") {
+                expect_eq((meta.uid(), meta.gid()), (uid, gid), "Owner after chown");
+            }
+        }
+        expect_ok(chown(test_file, None, None), "Change nothing");
+        let missing = ctx.path("missing.txt");
+        expect_err_kind(chown(missing, Some(uid), None), io::ErrorKind::NotFound, "Change missing file");
+
+        // Only root may give a file away
+        let other = if uid == 65534 { 65533 } else { 65534 };
+        step!("Changing the owner to {}", other);
+        if uid == 0 {
+            if expect_ok(chown(test_file, Some(other), None), "Change owner").is_some() {
+                if let Some(meta) = expect_ok(fs::metadata(test_file), "Get metadata") {
+                    expect_eq(meta.uid(), other, "Owner after chown");
+                }
+                expect_ok(chown(test_file, Some(uid), None), "Restore owner");
+            }
+        } else {
+            expect_err_kind(chown(test_file, Some(other), None), io::ErrorKind::PermissionDenied, "Give file away");
+        }
+    }
+}
+
+#[cfg(unix)]
+pub(crate) use unix::{test_creation_umask, test_file_modes, test_file_ownership};
+
+#[cfg(not(unix))]
+pub(crate) fn test_file_modes(_ctx: &TestCtx) {
+    skip!("File modes aren't visible on this target");
+}
+
+#[cfg(not(unix))]
+pub(crate) fn test_creation_umask(_ctx: &TestCtx) {
+    skip!("File modes aren't visible on this target");
+}
+
+#[cfg(not(unix))]
+pub(crate) fn test_file_ownership(_ctx: &TestCtx) {
+    skip!("File owners aren't visible on this target");
+}
@@ -0,0 +1,227 @@
+//! Tests of where a descriptor is, asked for on its own.
+//!
+//! `stream_position` asks `fd_tell` where a file's descriptor is, where the
+//! `seek_operations` test only checks what `fd_seek` returns. These run
+//! reads, writes, appends, truncations and seeks, past the end of the file
+//! too, against a model of the file and its position, and after each one
+//! check that `fd_tell` agrees with the model, so a kernel that moves the
+//! position on some path and forgets to on another is caught where it
+//! happens.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::harness::assert::{expect_eq_bytes, expect_ok};
+use crate::harness::context::TestCtx;
+use crate::harness::random::Rng;
+
+pub(crate) const POSITION_CONTENT: &str = "0123456789ABCDEF";
+
+/// Operations drawn at random once the scripted ones are done.
+const RANDOM_OPERATIONS: usize = 300;
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    /// Read up to this many bytes.
+    Read(usize),
+    /// Write this many copies of a byte.
+    Write(usize, u8),
+    Seek(SeekFrom),
+    SetLen(u64),
+}
+
+/// What the file and the descriptor's position should be.
+struct Model {
+    content: Vec<u8>,
+    position: u64,
+    append: bool,
+}
+
+impl Model {
+    fn read(&mut self, len: usize) -> Vec<u8> {
+        let start = (self.position as usize).min(self.content.len());
+        let read = self.content[start..(start + len).min(self.content.len())].to_vec();
+        self.position += read.len() as u64;
+        read
+    }
+
+    fn write(&mut self, len: usize, byte: u8) {
+        if self.append {
+            self.position = self.content.len() as u64;
+        }
+        let start = self.position as usize;
+        // Writing past the end leaves a gap that reads as zeros
+        if self.content.len() < start + len {
+            self.content.resize(start + len, 0);
+        }
+        self.content[start..start + len].fill(byte);
+        self.position += len as u64;
+    }
+
+    /// Where a seek goes, or `None` if it goes before the start and should
+    /// fail without moving.
+    fn seek(&mut self, from: SeekFrom) -> Option<u64> {
+        let position = match from {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => (self.content.len() as u64).checked_add_signed(offset),
+        }?;
+        self.position = position;
+        Some(position)
+    }
+}
+
+/// Does `op` to the file and the model, then checks that they agree on what
+/// it gave and where the descriptor is.
+fn apply(file: &mut File, model: &mut Model, op: Op) -> Result<(), String> {
+    match op {
+        Op::Read(len) => {
+            let mut read = Vec::new();
+            Read::by_ref(file).take(len as u64).read_to_end(&mut read).map_err(|e| format!("read failed: {}", e))?;
+            let expected = model.read(len);
+            if read != expected {
+                return Err(format!("read {:?} where {:?} was expected", read, expected));
+            }
+        }
+        Op::Write(len, byte) => {
+            file.write_all(&vec![byte; len]).map_err(|e| format!("write failed: {}", e))?;
+            model.write(len, byte);
+        }
+        Op::Seek(from) => match (file.seek(from), model.seek(from)) {
+            (Ok(position), Some(expected)) if position == expected => {}
+            (Ok(position), Some(expected)) => {
+                return Err(format!("seek gave {} where {} was expected", position, expected))
+            }
+            (Ok(position), None) => return Err(format!("seek to before the start gave {}", position)),
+            (Err(e), Some(_)) => return Err(format!("seek failed: {}", e)),
+            (Err(_), None) => {}
+        },
+        Op::SetLen(len) => {
+            file.set_len(len).map_err(|e| format!("set_len failed: {}", e))?;
+            model.content.resize(len as usize, 0);
+        }
+    }
+    let position = file.stream_position().map_err(|e| format!("fd_tell failed: {}", e))?;
+    match position == model.position {
+        true => Ok(()),
+        false => Err(format!("fd_tell says {} where the model is at {}", position, model.position)),
+    }
+}
+
+/// Runs `ops` in order, stopping at the first that disagrees with the model.
+fn run_script(file: &mut File, model: &mut Model, ops: &[Op]) -> Option<()> {
+    for &op in ops {
+        match apply(file, model, op) {
+            Ok(()) => pass!("{:?}: at {}", op, model.position),
+            Err(message) => {
+                fail!("{:?}: {}", op, message);
+                return None;
+            }
+        }
+    }
+    Some(())
+}
+
+fn random_op(rng: &mut Rng, len: u64) -> Op {
+    match rng.below(6) {
+        0 | 1 => Op::Read(rng.below(64) as usize + 1),
+        2 => Op::Write(rng.below(64) as usize + 1, b'a' + rng.below(26) as u8),
+        3 => Op::Seek(SeekFrom::Start(rng.below(len + 64))),
+        4 => match rng.below(2) {
+            0 => Op::Seek(SeekFrom::Current(rng.below(128) as i64 - 64)),
+            _ => Op::Seek(SeekFrom::End(rng.below(96) as i64 - 64)),
+        },
+        _ => Op::SetLen(rng.below(len + 64)),
+    }
+}
+
+fn expect_content(path: &str, model: &Model) {
+    if let Some(content) = expect_ok(fs::read(path), "Read back") {
+        expect_eq_bytes(&content, &model.content, "Content matches the model");
+    }
+}
+
+pub(crate) fn test_fd_tell(ctx: &TestCtx) {
+    let path = &ctx.path("position.txt");
+    let mut model = Model { content: POSITION_CONTENT.as_bytes().to_vec(), position: 0, append: false };
+
+    step!("Reading, writing and seeking");
+    let Some(mut file) = expect_ok(OpenOptions::new().read(true).write(true).open(path), "Open") else {
+        return;
+    };
+    #[rustfmt::skip]
+    let script = [
+        Op::Seek(SeekFrom::Current(0)),
+        Op::Read(4),
+        Op::Read(100),
+        Op::Read(10),
+        Op::Seek(SeekFrom::Start(4)),
+        Op::Write(3, b'x'),
+        Op::Read(2),
+        Op::Seek(SeekFrom::Current(-5)),
+        Op::Seek(SeekFrom::Current(-30)),
+        Op::Seek(SeekFrom::End(-1)),
+        Op::Read(5),
+    ];
+    if run_script(&mut file, &mut model, &script).is_none() {
+        return;
+    }
+
+    step!("Going past the end");
+    #[rustfmt::skip]
+    let script = [
+        Op::Seek(SeekFrom::End(10)),
+        Op::Read(5),
+        Op::Write(2, b'y'),
+        Op::Seek(SeekFrom::Start(100)),
+        Op::SetLen(5),
+        Op::Seek(SeekFrom::Current(-98)),
+        Op::Read(10),
+        Op::SetLen(40),
+        Op::Read(10),
+    ];
+    if run_script(&mut file, &mut model, &script).is_none() {
+        return;
+    }
+    expect_content(path, &model);
+    drop(file);
+
+    step!("Appending");
+    let Some(mut file) = expect_ok(OpenOptions::new().read(true).append(true).open(path), "Open to append") else {
+        return;
+    };
+    model.position = 0;
+    model.append = true;
+    #[rustfmt::skip]
+    let script = [
+        Op::Seek(SeekFrom::Current(0)),
+        Op::Read(3),
+        Op::Write(3, b'z'),
+        Op::Seek(SeekFrom::Start(0)),
+        Op::Write(1, b'w'),
+        Op::Seek(SeekFrom::Start(2)),
+        Op::Read(2),
+    ];
+    if run_script(&mut file, &mut model, &script).is_none() {
+        return;
+    }
+    expect_content(path, &model);
+    drop(file);
+
+    step!("Doing {} operations at random", RANDOM_OPERATIONS);
+    let Some(mut file) = expect_ok(OpenOptions::new().read(true).write(true).open(path), "Open") else {
+        return;
+    };
+    model.position = 0;
+    model.append = false;
+    let mut rng = ctx.rng();
+    for index in 0..RANDOM_OPERATIONS {
+        let op = random_op(&mut rng, model.content.len() as u64);
+        if let Err(message) = apply(&mut file, &mut model, op) {
+            fail!("Operation {}, {:?}: {}", index + 1, op, message);
+            return;
+        }
+    }
+    pass!("Every position agreed with the model");
+    expect_content(path, &model);
+}
@@ -0,0 +1,252 @@
+//! Tests of positional reads and writes, WASI's `fd_pread` and `fd_pwrite`.
+//!
+//! A read or write at an offset has to leave the descriptor's position
+//! where it was, which is easy to get wrong when it's built out of a seek
+//! there and back. These read and write at offsets in, at and past the end
+//! of a file, check the position with `fd_tell` after each, and run a mix
+//! of them at random against a model of the file.
+//!
+//! std's `FileExt::{read_at, write_at}` only reach the calls on WASI behind
+//! an unstable feature, so there these call them directly; on Unix hosts
+//! they go through std. Other targets have neither, so they skip these.
+
+#[cfg(not(any(unix, target_os = "wasi")))]
+use crate::harness::context::TestCtx;
+
+pub(crate) const PREAD_CONTENT: &str = "0123456789ABCDEF";
+
+#[cfg(unix)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::fs::FileExt;
+
+    pub(super) fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        file.read_at(buf, offset)
+    }
+
+    pub(super) fn write_at(file: &File, buf: &[u8], offset: u64) -> io::Result<usize> {
+        file.write_at(buf, offset)
+    }
+}
+
+#[cfg(target_os = "wasi")]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsRawFd;
+
+    /// An `iovec` or `ciovec`: where a buffer is and how long.
+    #[repr(C)]
+    struct Iovec {
+        buf: *const u8,
+        len: usize,
+    }
+
+    #[link(wasm_import_module = "wasi_snapshot_preview1")]
+    extern "C" {
+        #[link_name = "fd_pread"]
+        fn wasi_fd_pread(fd: i32, iovs: *const Iovec, iovs_len: usize, offset: u64, nread: *mut usize) -> i32;
+        #[link_name = "fd_pwrite"]
+        fn wasi_fd_pwrite(fd: i32, iovs: *const Iovec, iovs_len: usize, offset: u64, nwritten: *mut usize) -> i32;
+    }
+
+    pub(super) fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let iovec = Iovec { buf: buf.as_mut_ptr(), len: buf.len() };
+        let mut read = 0;
+        // SAFETY: buf, iovec and read outlive the call, and the runtime
+        // writes no more than buf's length into buf
+        match unsafe { wasi_fd_pread(file.as_raw_fd(), &iovec, 1, offset, &mut read) } {
+            0 => Ok(read),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    pub(super) fn write_at(file: &File, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let iovec = Iovec { buf: buf.as_ptr(), len: buf.len() };
+        let mut written = 0;
+        // SAFETY: buf, iovec and written outlive the call, and the runtime
+        // only reads from buf
+        match unsafe { wasi_fd_pwrite(file.as_raw_fd(), &iovec, 1, offset, &mut written) } {
+            0 => Ok(written),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+}
+
+#[cfg(any(unix, target_os = "wasi"))]
+mod positional {
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    use super::sys::{read_at, write_at};
+    use super::PREAD_CONTENT;
+    use crate::harness::assert::{expect_eq, expect_eq_bytes, expect_ok, expect_true};
+    use crate::harness::context::TestCtx;
+
+    /// Reads and writes drawn at random, after the scripted ones.
+    const RANDOM_OPERATIONS: usize = 200;
+
+    /// Where `file`'s position starts, away from the start of the file so
+    /// that an offset taken as relative to it shows.
+    const POSITION: u64 = 3;
+
+    /// Checks that `file` is still at `expected` after `what`.
+    fn expect_position(file: &mut File, expected: u64, what: &str) {
+        if let Some(position) = expect_ok(file.stream_position(), "Get the position") {
+            expect_eq(position, expected, &format!("Position after {}", what));
+        }
+    }
+
+    /// Reads up to `len` bytes at `offset`, checking that they're `expected`
+    /// and that the position hasn't moved.
+    fn expect_read_at(file: &mut File, offset: u64, len: usize, expected: &[u8]) {
+        let mut buf = vec![0xff; len];
+        let what = format!("Read {} bytes at {}", len, offset);
+        if let Some(read) = expect_ok(read_at(file, &mut buf, offset), &what) {
+            expect_eq_bytes(&buf[..read], expected, &what);
+        }
+        expect_position(file, POSITION, "reading at an offset");
+    }
+
+    /// Writes `data` at `offset`, checking that all of it was written and
+    /// that the position hasn't moved.
+    fn expect_write_at(file: &mut File, offset: u64, data: &[u8]) {
+        let what = format!("Write {:?} at {}", String::from_utf8_lossy(data), offset);
+        if let Some(written) = expect_ok(write_at(file, data, offset), &what) {
+            expect_eq(written, data.len(), "Bytes written");
+        }
+        expect_position(file, POSITION, "writing at an offset");
+    }
+
+    fn expect_content(path: &str, expected: &[u8], what: &str) {
+        if let Some(content) = expect_ok(fs::read(path), "Read back") {
+            expect_eq_bytes(&content, expected, what);
+        }
+    }
+
+    pub(crate) fn test_pread_pwrite(ctx: &TestCtx) {
+        let path = &ctx.path("pread.txt");
+        let mut expected = PREAD_CONTENT.as_bytes().to_vec();
+        let Some(mut file) = expect_ok(OpenOptions::new().read(true).write(true).open(path), "Open") else {
+            return;
+        };
+        if expect_ok(file.seek(SeekFrom::Start(POSITION)), "Seek away from the start").is_none() {
+            return;
+        }
+
+        step!("Reading at offsets");
+        expect_read_at(&mut file, 10, 4, b"ABCD");
+        expect_read_at(&mut file, 0, 2, b"01");
+        expect_read_at(&mut file, 14, 8, b"EF");
+        expect_read_at(&mut file, 16, 8, b"");
+        expect_read_at(&mut file, 100, 8, b"");
+
+        step!("Writing at offsets");
+        expect_write_at(&mut file, 5, b"xy");
+        expected[5..7].copy_from_slice(b"xy");
+        expect_write_at(&mut file, 14, b"tail");
+        expected.truncate(14);
+        expected.extend_from_slice(b"tail");
+        expect_content(path, &expected, "Content after writing at offsets");
+
+        step!("Writing past the end");
+        expect_write_at(&mut file, 24, b"end");
+        // The gap reads as zeros
+        expected.resize(24, 0);
+        expected.extend_from_slice(b"end");
+        expect_read_at(&mut file, 16, 10, &expected[16..26]);
+        if let Some(metadata) = expect_ok(file.metadata(), "Metadata") {
+            expect_eq(metadata.len(), 27, "Size after writing past the end");
+        }
+
+        step!("Reading and writing at the position");
+        // Neither kind of call moved it, so these start from POSITION
+        let mut buf = [0; 2];
+        if expect_ok(file.read_exact(&mut buf), "Read from the position").is_some() {
+            expect_eq_bytes(&buf, &expected[3..5], "Read from where the position was left");
+        }
+        if expect_ok(file.write_all(b"!"), "Write at the position").is_some() {
+            expected[5] = b'!';
+            expect_content(path, &expected, "Write landed where the position was left");
+        }
+        if expect_ok(file.seek(SeekFrom::Start(POSITION)), "Seek back").is_none() {
+            return;
+        }
+
+        step!("Doing {} reads and writes at random", RANDOM_OPERATIONS);
+        let mut rng = ctx.rng();
+        for index in 0..RANDOM_OPERATIONS {
+            let offset = rng.below(expected.len() as u64 + 32);
+            let len = rng.below(32) as usize + 1;
+            let start = offset as usize;
+            if rng.below(2) == 0 {
+                let mut buf = vec![0; len];
+                let want = &expected[start.min(expected.len())..(start + len).min(expected.len())];
+                match read_at(&file, &mut buf, offset) {
+                    Ok(read) if buf[..read] == *want => {}
+                    Ok(read) => {
+                        fail!(
+                            "Read {}, {} bytes at {}: {:?} where {:?} was expected",
+                            index + 1,
+                            len,
+                            offset,
+                            &buf[..read],
+                            want
+                        );
+                        return;
+                    }
+                    Err(e) => {
+                        fail!(e => "Read {}, {} bytes at {}: {}", index + 1, len, offset, e);
+                        return;
+                    }
+                }
+            } else {
+                let data = vec![b'a' + rng.below(26) as u8; len];
+                match write_at(&file, &data, offset) {
+                    Ok(written) if written == len => {}
+                    Ok(written) => {
+                        fail!("Write {}, {} bytes at {}: only {} written", index + 1, len, offset, written);
+                        return;
+                    }
+                    Err(e) => {
+                        fail!(e => "Write {}, {} bytes at {}: {}", index + 1, len, offset, e);
+                        return;
+                    }
+                }
+                if expected.len() < start + len {
+                    expected.resize(start + len, 0);
+                }
+                expected[start..start + len].copy_from_slice(&data);
+            }
+        }
+        pass!("Every read matched the model");
+        expect_position(&mut file, POSITION, "reading and writing at random");
+        expect_content(path, &expected, "Content matches the model");
+        drop(file);
+
+        step!("Reading and writing where the descriptor can't");
+        if let Some(read_only) = expect_ok(File::open(path), "Open read-only") {
+            expect_true(
+                write_at(&read_only, b"no", 0).is_err(),
+                "Write at an offset through a read-only descriptor refused",
+            );
+        }
+        if let Some(write_only) = expect_ok(OpenOptions::new().write(true).open(path), "Open write-only") {
+            let mut buf = [0; 4];
+            expect_true(
+                read_at(&write_only, &mut buf, 0).is_err(),
+                "Read at an offset through a write-only descriptor refused",
+            );
+        }
+        expect_content(path, &expected, "File unchanged");
+    }
+}
+
+#[cfg(any(unix, target_os = "wasi"))]
+pub(crate) use positional::test_pread_pwrite;
+
+#[cfg(not(any(unix, target_os = "wasi")))]
+pub(crate) fn test_pread_pwrite(_ctx: &TestCtx) {
+    skip!("fd_pread and fd_pwrite are only reachable on WASI and Unix targets");
+}
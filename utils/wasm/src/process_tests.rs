@@ -0,0 +1,67 @@
+//! Tests of what a process gets from its runtime: arguments, environment
+//! and working directory.
+
+use std::env;
+use std::io;
+use std::path::Path;
+
+use crate::harness::assert::{expect_eq, expect_ok, expect_true};
+use crate::harness::context::TestCtx;
+
+pub(crate) fn test_command_line_args(_ctx: &TestCtx) {
+    let args: Vec<String> = env::args().collect();
+    step!("Number of arguments: {}", args.len());
+    for (i, arg) in args.iter().enumerate() {
+        step!("arg[{}]: {}", i, arg);
+    }
+    expect_true(!args.is_empty(), "Program name passed as argv[0]");
+}
+
+pub(crate) fn test_environment_variables(_ctx: &TestCtx) {
+    match env::var("PATH") {
+        Ok(val) => detail!("PATH: {}", val),
+        Err(_) => detail!("PATH: (not set)"),
+    }
+    
+    match env::var("HOME") {
+        Ok(val) => detail!("HOME: {}", val),
+        Err(_) => detail!("HOME: (not set)"),
+    }
+    
+    match env::var("USER") {
+        Ok(val) => detail!("USER: {}", val),
+        Err(_) => detail!("USER: (not set)"),
+    }
+}
+
+pub(crate) fn test_working_directory(ctx: &TestCtx) {
+    step!("Getting current working directory");
+    let Some(cwd) = expect_ok(env::current_dir(), "Get current directory") else {
+        return;
+    };
+    detail!("Current directory: {:?}", cwd);
+    
+    let test_dir = &ctx.path("cwd_test");
+    step!("Changing to test directory: {}", test_dir);
+    match env::set_current_dir(test_dir) {
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+            skip!("Changing directory isn't supported on this target")
+        }
+        result => {
+            if expect_ok(result, "Change directory").is_some() {
+                if let Some(new_cwd) = expect_ok(env::current_dir(), "Get new directory") {
+                    expect_eq(new_cwd.as_path(), Path::new(test_dir), "New directory");
+                }
+                expect_ok(env::set_current_dir(&cwd), "Restore directory");
+            }
+        }
+    }
+}
+
+/// Makes sure a failed working directory test doesn't leave the process in
+/// a directory that's about to be removed.
+pub(crate) fn leave_test_directory(ctx: &TestCtx) {
+    if env::current_dir().is_ok_and(|cwd| cwd.starts_with(ctx.dir())) {
+        let _ = env::set_current_dir("/");
+    }
+}
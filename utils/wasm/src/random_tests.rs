@@ -0,0 +1,22 @@
+//! Tests of random numbers.
+
+use crate::harness::assert::{expect_eq, expect_true};
+use crate::harness::context::TestCtx;
+
+pub(crate) fn test_random_operations(ctx: &TestCtx) {
+    let mut rng = ctx.rng();
+    let value = rng.next_u64();
+    pass!("Generated random value: {}", value);
+    expect_eq(ctx.rng().next_u64(), value, "Same value from the same seed");
+
+    step!("Testing numbers in a range");
+    let rolls: Vec<u64> = (0..1000).map(|_| rng.below(6)).collect();
+    expect_true(rolls.iter().all(|&roll| roll < 6), "Every number below the bound");
+    expect_true((0..6).all(|face| rolls.contains(&face)), "Every number in the range drawn");
+
+    step!("Testing random bytes");
+    let mut buffer = [0u8; 13];
+    rng.fill(&mut buffer);
+    detail!("{:02x?}", buffer);
+    expect_true(buffer.iter().any(|&byte| byte != 0), "Buffer filled");
+}
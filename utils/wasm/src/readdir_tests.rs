@@ -0,0 +1,207 @@
+//! Tests of reading directories through WASI's `fd_readdir` itself.
+//!
+//! std's `read_dir` asks for entries into a buffer big enough that most
+//! directories come back from one call, so a runtime that loses or repeats
+//! entries when a listing spans several calls goes unnoticed by the other
+//! tests. These call `fd_readdir` with buffers a few entries long, or
+//! shorter than one, and check that following the cookies gives every
+//! entry exactly once, in the same order however small the buffer.
+//!
+//! There's no `fd_readdir` to call on other targets, so they skip these.
+
+#[cfg(not(target_os = "wasi"))]
+use crate::harness::context::TestCtx;
+
+#[cfg(target_os = "wasi")]
+mod wasi {
+    use std::collections::BTreeMap;
+    use std::fs::{self, File};
+    use std::io;
+    use std::os::fd::AsRawFd;
+
+    use crate::harness::assert::{expect_eq, expect_ok, expect_true};
+    use crate::harness::context::TestCtx;
+
+    /// How many files `test_readdir_pagination` lists.
+    const ENTRIES: usize = 300;
+
+    /// The size of a dirent ahead of its name: the cookie of the entry after
+    /// it, the inode, the length of the name and the file type, padded.
+    const DIRENT_SIZE: usize = 24;
+
+    /// `filetype::regular_file`
+    const REGULAR_FILE: u8 = 4;
+
+    /// The sizes of buffer to read with: one entry exactly, entries split
+    /// across calls at different points, and a few entries at a time.
+    const BUFFER_SIZES: [usize; 5] = [DIRENT_SIZE + "entry_000".len(), 40, 64, 100, 256];
+
+    #[link(wasm_import_module = "wasi_snapshot_preview1")]
+    extern "C" {
+        #[link_name = "fd_readdir"]
+        fn wasi_fd_readdir(fd: i32, buf: i32, buf_len: i32, cookie: i64, bufused: i32) -> i32;
+    }
+
+    /// Fills `buf` with the entries of `dir` from `cookie` on, returning how
+    /// much of it was used.
+    fn fd_readdir(dir: &File, buf: &mut [u8], cookie: u64) -> io::Result<usize> {
+        let mut used: usize = 0;
+        // SAFETY: both buffers outlive the call, and the runtime writes no
+        // more than buf.len() bytes to buf
+        let errno = unsafe {
+            wasi_fd_readdir(
+                dir.as_raw_fd(),
+                buf.as_mut_ptr() as i32,
+                buf.len() as i32,
+                cookie as i64,
+                &mut used as *mut usize as i32,
+            )
+        };
+        match errno {
+            0 => Ok(used),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    /// An entry `fd_readdir` gave.
+    struct Dirent {
+        /// The cookie to read on from after this entry.
+        next: u64,
+        kind: u8,
+        name: String,
+    }
+
+    /// Reads the entries of `dir` from `cookie` on, `size` bytes at a time,
+    /// and how many calls it took. An entry cut off at the end of the buffer
+    /// is read again by the next call, from the cookie of the last whole one.
+    fn read_entries(dir: &File, size: usize, mut cookie: u64) -> io::Result<(Vec<Dirent>, usize)> {
+        let mut buf = vec![0; size];
+        let mut entries = Vec::new();
+        let mut calls = 0;
+        loop {
+            // Every call should give at least one entry, so more calls than
+            // that means the cookies are going round in circles
+            if calls > 4 * ENTRIES {
+                return Err(io::Error::other(format!("still reading after {} calls", calls)));
+            }
+            let used = fd_readdir(dir, &mut buf, cookie)?;
+            calls += 1;
+            let mut offset = 0;
+            while offset + DIRENT_SIZE <= used {
+                let header = &buf[offset..offset + DIRENT_SIZE];
+                let next = u64::from_le_bytes(header[0..8].try_into().unwrap());
+                let length = u32::from_le_bytes(header[16..20].try_into().unwrap()) as usize;
+                let end = offset + DIRENT_SIZE + length;
+                if end > used {
+                    break;
+                }
+                let name = String::from_utf8_lossy(&buf[offset + DIRENT_SIZE..end]).into_owned();
+                entries.push(Dirent { next, kind: header[20], name });
+                cookie = next;
+                offset = end;
+            }
+            // A buffer that isn't filled holds the end of the directory
+            if used < size {
+                return Ok((entries, calls));
+            }
+            if offset == 0 {
+                let message = format!("{} bytes didn't hold a whole entry", size);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+            }
+        }
+    }
+
+    /// Where `entries` first part from `expected`, if they do.
+    fn first_difference(entries: &[Dirent], expected: &[Dirent]) -> Option<String> {
+        let name = |entries: &[Dirent], index| entries.get(index).map(|entry: &Dirent| entry.name.clone());
+        let index =
+            (0..entries.len().max(expected.len())).find(|&index| name(entries, index) != name(expected, index))?;
+        Some(format!("entry {}: {:?} where {:?} was expected", index, name(entries, index), name(expected, index)))
+    }
+
+    /// Checks that `entries` are those of `expected`, each once, besides `.`
+    /// and `..`, which runtimes may or may not list.
+    fn expect_listing(entries: &[Dirent], expected: &[String]) {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for entry in entries {
+            *counts.entry(&entry.name).or_default() += 1;
+        }
+        let repeated: Vec<&str> = counts.iter().filter(|(_, &count)| count > 1).map(|(name, _)| *name).collect();
+        expect_eq(repeated, Vec::new(), "Entries listed more than once");
+        let missing: Vec<&str> =
+            expected.iter().map(String::as_str).filter(|name| !counts.contains_key(name)).collect();
+        expect_eq(missing, Vec::new(), "Entries missing");
+        let unexpected: Vec<&str> = counts
+            .keys()
+            .copied()
+            .filter(|name| !matches!(*name, "." | "..") && !expected.iter().any(|expected| expected == name))
+            .collect();
+        expect_eq(unexpected, Vec::new(), "Entries that weren't created");
+        let mistyped: Vec<&str> = entries
+            .iter()
+            .filter(|entry| !matches!(entry.name.as_str(), "." | "..") && entry.kind != REGULAR_FILE)
+            .map(|entry| entry.name.as_str())
+            .collect();
+        expect_eq(mistyped, Vec::new(), "Files not listed as regular files");
+    }
+
+    pub(crate) fn test_readdir_pagination(ctx: &TestCtx) {
+        let dir_path = &ctx.path("many");
+        let expected: Vec<String> = (0..ENTRIES).map(|index| format!("entry_{:03}", index)).collect();
+
+        step!("Creating {} files", ENTRIES);
+        let created = expected.iter().try_for_each(|name| fs::write(format!("{}/{}", dir_path, name), ""));
+        if expect_ok(created, "Create files").is_none() {
+            return;
+        }
+        let Some(dir) = expect_ok(File::open(dir_path), "Open the directory") else {
+            return;
+        };
+
+        step!("Reading the directory in one buffer");
+        let Some((all, _)) = expect_ok(read_entries(&dir, 64 * 1024, 0), "Read entries") else {
+            return;
+        };
+        expect_listing(&all, &expected);
+        if all.len() < ENTRIES {
+            return;
+        }
+
+        for size in BUFFER_SIZES {
+            step!("Reading {} bytes at a time", size);
+            if let Some((entries, calls)) = expect_ok(read_entries(&dir, size, 0), "Read entries") {
+                detail!("{} calls", calls);
+                expect_listing(&entries, &expected);
+                expect_eq(first_difference(&entries, &all), None, "Same order as in one buffer");
+            }
+        }
+
+        step!("Reading on from cookies partway through");
+        for index in [1, all.len() / 2, all.len() - 1, all.len()] {
+            let cookie = all[index - 1].next;
+            if let Some((rest, _)) = expect_ok(read_entries(&dir, 64, cookie), &format!("Read from cookie {}", cookie))
+            {
+                expect_eq(first_difference(&rest, &all[index..]), None, &format!("Entries after the first {}", index));
+            }
+        }
+
+        step!("Reading again after a change");
+        let added = format!("{}/entry_{:03}", dir_path, ENTRIES);
+        if expect_ok(fs::write(&added, ""), "Create another file").is_some() {
+            if let Some((entries, _)) = expect_ok(read_entries(&dir, 64, 0), "Read entries from the start") {
+                let mut expected = expected.clone();
+                expected.push(format!("entry_{:03}", ENTRIES));
+                expect_listing(&entries, &expected);
+            }
+        }
+        expect_true(fs::read_dir(dir_path).is_ok_and(|entries| entries.count() == ENTRIES + 1), "std agrees");
+    }
+}
+
+#[cfg(target_os = "wasi")]
+pub(crate) use wasi::test_readdir_pagination;
+
+#[cfg(not(target_os = "wasi"))]
+pub(crate) fn test_readdir_pagination(_ctx: &TestCtx) {
+    skip!("fd_readdir is only reachable on WASI targets");
+}
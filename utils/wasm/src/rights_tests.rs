@@ -0,0 +1,181 @@
+//! Tests that a file descriptor only allows what it was opened for.
+//!
+//! A handle opened to read mustn't write, one opened to write mustn't read,
+//! and on WASI a descriptor opened with a set of rights mustn't do anything
+//! outside them, nor pass on to the files opened through it rights it
+//! doesn't have to give. An fd layer that checks none of this passes every
+//! other test, so these check that each forbidden operation fails with an
+//! error that says it isn't allowed, rather than succeeding or failing for
+//! some other reason.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+
+use crate::harness::assert::{expect_eq, expect_eq_bytes, expect_ok, expect_true};
+use crate::harness::context::TestCtx;
+
+pub(crate) const RIGHTS_CONTENT: &str = "rights content";
+
+/// The errors that say a descriptor doesn't allow an operation: EBADF,
+/// EACCES, EPERM and ENOTCAPABLE, and EINVAL, which Linux gives when
+/// truncating through a handle not open for writing.
+#[cfg(target_os = "wasi")]
+const REFUSED: [i32; 5] = [8, 2, 63, 76, 28];
+#[cfg(not(target_os = "wasi"))]
+const REFUSED: [i32; 4] = [9, 13, 1, 22];
+
+/// Checks that an operation was refused because the descriptor doesn't
+/// allow it.
+fn expect_refused<T>(result: io::Result<T>, what: &str) {
+    match result {
+        Ok(_) => fail!("{}: allowed", what),
+        Err(e) if e.raw_os_error().is_some_and(|code| REFUSED.contains(&code)) => pass!(e => "{}: {}", what, e),
+        Err(e) => fail!(e => "{}: expected EBADF or EACCES, got {}", what, e),
+    }
+}
+
+fn expect_content(path: &str, expected: &str) {
+    if let Some(content) = expect_ok(fs::read(path), "Read the file back") {
+        expect_eq_bytes(&content, expected.as_bytes(), "Content of the file");
+    }
+}
+
+pub(crate) fn test_fd_access_modes(ctx: &TestCtx) {
+    let path = &ctx.path("rights.txt");
+
+    step!("Using a read-only handle");
+    if let Some(mut file) = expect_ok(File::open(path), "Open read-only") {
+        let mut content = String::new();
+        if expect_ok(file.read_to_string(&mut content), "Read").is_some() {
+            expect_eq(content.as_str(), RIGHTS_CONTENT, "Content read");
+        }
+        expect_refused(file.write_all(b"written"), "Write");
+        expect_refused(file.set_len(0), "Truncate");
+    }
+    expect_content(path, RIGHTS_CONTENT);
+
+    step!("Using a write-only handle");
+    if let Some(mut file) = expect_ok(OpenOptions::new().write(true).open(path), "Open write-only") {
+        expect_refused(file.read(&mut [0; 8]), "Read");
+        expect_ok(file.write_all(b"RIGHTS"), "Write");
+    }
+
+    step!("Using an append-only handle");
+    if let Some(mut file) = expect_ok(OpenOptions::new().append(true).open(path), "Open to append") {
+        expect_refused(file.read(&mut [0; 8]), "Read");
+        expect_ok(file.write_all(b"!"), "Append");
+    }
+    expect_content(path, &format!("{}!", RIGHTS_CONTENT.replacen("rights", "RIGHTS", 1)));
+
+    // Runtimes may say EISDIR rather than that the handle can't write
+    step!("Writing through a directory handle");
+    if let Some(mut dir) = expect_ok(File::open(ctx.dir()), "Open the directory") {
+        expect_true(dir.write_all(b"written").is_err(), "Write refused");
+    }
+}
+
+#[cfg(target_os = "wasi")]
+mod wasi {
+    use std::fs::File;
+    use std::io::{self, Read, Seek, SeekFrom, Write};
+    use std::os::fd::{AsRawFd, FromRawFd};
+
+    use super::{expect_content, expect_refused, RIGHTS_CONTENT};
+    use crate::harness::assert::{expect_eq, expect_ok};
+    use crate::harness::context::TestCtx;
+
+    const FD_READ: u64 = 1 << 1;
+    const FD_SEEK: u64 = 1 << 2;
+    const FD_WRITE: u64 = 1 << 6;
+    const PATH_OPEN: u64 = 1 << 13;
+    const FD_FILESTAT_GET: u64 = 1 << 21;
+
+    /// `oflags::directory`
+    const DIRECTORY: i32 = 1 << 1;
+
+    #[link(wasm_import_module = "wasi_snapshot_preview1")]
+    extern "C" {
+        #[link_name = "path_open"]
+        fn wasi_path_open(
+            fd: i32,
+            dirflags: i32,
+            path: i32,
+            path_len: i32,
+            oflags: i32,
+            rights_base: i64,
+            rights_inheriting: i64,
+            fdflags: i32,
+            opened: i32,
+        ) -> i32;
+    }
+
+    /// Opens `path` under `dir` with only the rights asked for: `base` for
+    /// the new descriptor and `inheriting` for those opened through it.
+    fn path_open(dir: &File, path: &str, oflags: i32, base: u64, inheriting: u64) -> io::Result<File> {
+        let mut fd: i32 = -1;
+        // SAFETY: the path and fd outlive the call, which only reads
+        // path.len() bytes of the path and writes the fd
+        let errno = unsafe {
+            wasi_path_open(
+                dir.as_raw_fd(),
+                0,
+                path.as_ptr() as i32,
+                path.len() as i32,
+                oflags,
+                base as i64,
+                inheriting as i64,
+                0,
+                &mut fd as *mut i32 as i32,
+            )
+        };
+        match errno {
+            // SAFETY: the descriptor was just opened and nothing else owns it
+            0 => Ok(unsafe { File::from_raw_fd(fd) }),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    pub(crate) fn test_fd_rights(ctx: &TestCtx) {
+        let Some(dir) = expect_ok(File::open(ctx.dir()), "Open the test's directory") else {
+            return;
+        };
+
+        step!("Opening with only the right to read");
+        if let Some(mut file) = expect_ok(path_open(&dir, "rights.txt", 0, FD_READ, 0), "Open") {
+            let mut content = String::new();
+            if expect_ok(file.read_to_string(&mut content), "Read").is_some() {
+                expect_eq(content.as_str(), RIGHTS_CONTENT, "Content read");
+            }
+            expect_refused(file.write_all(b"written"), "Write");
+            expect_refused(file.seek(SeekFrom::Start(0)), "Seek");
+            expect_refused(file.metadata(), "Get metadata");
+            expect_refused(file.set_len(0), "Truncate");
+        }
+
+        step!("Opening with only the rights to write and seek");
+        if let Some(mut file) = expect_ok(path_open(&dir, "rights.txt", 0, FD_WRITE | FD_SEEK, 0), "Open") {
+            expect_refused(file.read(&mut [0; 8]), "Read");
+            expect_ok(file.seek(SeekFrom::Start(0)), "Seek");
+            expect_refused(file.metadata(), "Get metadata");
+        }
+
+        step!("Opening through a directory that passes on only the right to read");
+        let opened = path_open(&dir, ".", DIRECTORY, PATH_OPEN, FD_READ | FD_FILESTAT_GET);
+        if let Some(limited) = expect_ok(opened, "Open the directory") {
+            expect_refused(path_open(&limited, "rights.txt", 0, FD_WRITE, 0), "Open to write");
+            expect_refused(path_open(&limited, "rights.txt", 0, FD_READ | FD_WRITE, 0), "Open to read and write");
+            if let Some(file) = expect_ok(path_open(&limited, "rights.txt", 0, FD_READ, 0), "Open to read") {
+                expect_ok(file.metadata(), "Get metadata, which was passed on");
+            }
+        }
+        expect_content(&ctx.path("rights.txt"), RIGHTS_CONTENT);
+    }
+}
+
+#[cfg(target_os = "wasi")]
+pub(crate) use wasi::test_fd_rights;
+
+#[cfg(not(target_os = "wasi"))]
+pub(crate) fn test_fd_rights(_ctx: &TestCtx) {
+    skip!("Descriptor rights are only asked for on WASI targets");
+}
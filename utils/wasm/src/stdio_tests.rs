@@ -0,0 +1,10 @@
+//! Tests of the standard streams.
+
+use crate::harness::context::TestCtx;
+
+pub(crate) fn test_stdout_stderr(_ctx: &TestCtx) {
+    eprintln!("This is stderr output");
+    println!("This is stdout output");
+    print!("Print without newline");
+    println!(" - continued");
+}
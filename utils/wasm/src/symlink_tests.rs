@@ -0,0 +1,203 @@
+//! Tests of symbolic links beyond the basics checked by the `symlinks` test:
+//! relative targets, chains and links to directories, a link's own metadata
+//! against its target's, links that point nowhere, and loops.
+//!
+//! The kernel's filesystems claim symlink support through ZenFS, and
+//! programs run into links in the paths they're given, so these check that
+//! links are resolved as POSIX says and that each operation acts on the
+//! link or on its target as it should.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::harness::assert::{expect_eq, expect_eq_bytes, expect_err_kind, expect_ok, expect_true};
+use crate::harness::context::TestCtx;
+
+pub(crate) const TARGET_CONTENT: &str = "symlink target";
+
+/// Creates a symbolic link at `link` that points to `target`.
+fn symlink(target: &str, link: &str) -> io::Result<()> {
+    // The only way to create a symlink that std offers on every target
+    #[allow(deprecated)]
+    fs::soft_link(target, link)
+}
+
+/// Checks that an operation failed because resolving the path went round a
+/// loop of links (ELOOP).
+fn expect_loop<T>(result: io::Result<T>, what: &str) {
+    match result {
+        Ok(_) => fail!("{}: unexpectedly succeeded", what),
+        // io::ErrorKind::FilesystemLoop isn't stable yet, but ELOOP is
+        // already decoded as it
+        Err(e) if format!("{:?}", e.kind()) == "FilesystemLoop" => pass!(e => "{}: {}", what, e),
+        Err(e) => fail!(e => "{}: expected a loop of links, got {}", what, e),
+    }
+}
+
+pub(crate) fn test_symlink_operations(ctx: &TestCtx) {
+    let target = &ctx.path("target.txt");
+
+    step!("Linking with a relative target");
+    let relative = &ctx.path("relative.txt");
+    let created = expect_ok(symlink("target.txt", relative), "Create relative symlink").is_some();
+    if created {
+        if let Some(read) = expect_ok(fs::read_link(relative), "Read link") {
+            expect_eq(read.as_path(), Path::new("target.txt"), "Target kept as given");
+        }
+        if let Some(content) = expect_ok(fs::read(relative), "Read through relative symlink") {
+            expect_eq_bytes(&content, TARGET_CONTENT.as_bytes(), "Resolved from the link's directory");
+        }
+    }
+
+    step!("Following a chain of links");
+    let (inner, outer) = (&ctx.path("chain_inner.txt"), &ctx.path("chain_outer.txt"));
+    if expect_ok(symlink(target, inner), "Link to the target").is_some()
+        && expect_ok(symlink(inner, outer), "Link to the link").is_some()
+    {
+        if let Some(read) = expect_ok(fs::read_link(outer), "Read the outer link") {
+            expect_eq(read.as_path(), Path::new(inner), "Outer link points at the inner one");
+        }
+        if let Some(content) = expect_ok(fs::read(outer), "Read through both links") {
+            expect_eq_bytes(&content, TARGET_CONTENT.as_bytes(), "Content at the end of the chain");
+        }
+    }
+
+    step!("Linking to a directory");
+    let dir_link = &ctx.path("dir_link");
+    if expect_ok(symlink("dir", dir_link), "Create symlink to directory").is_some() {
+        expect_true(Path::new(dir_link).is_dir(), "Link resolves to a directory");
+        if let Some(entries) = expect_ok(fs::read_dir(dir_link), "List through the link") {
+            let names: Vec<String> =
+                entries.filter_map(Result::ok).map(|entry| entry.file_name().to_string_lossy().into_owned()).collect();
+            expect_eq(names, vec!["inner.txt".to_string()], "Entries through the link");
+        }
+        if let Some(content) = expect_ok(fs::read(ctx.path("dir_link/inner.txt")), "Read a file through the link") {
+            expect_eq_bytes(&content, TARGET_CONTENT.as_bytes(), "Content through the link");
+        }
+        if expect_ok(fs::remove_file(dir_link), "Remove the directory link").is_some() {
+            expect_true(Path::new(&ctx.path("dir/inner.txt")).exists(), "Directory outlives the link");
+        }
+    }
+
+    if !created {
+        return;
+    }
+    step!("Renaming and removing a link");
+    let renamed = &ctx.path("renamed.txt");
+    if expect_ok(fs::rename(relative, renamed), "Rename the link").is_some() {
+        if let Some(read) = expect_ok(fs::read_link(renamed), "Read the renamed link") {
+            expect_eq(read.as_path(), Path::new("target.txt"), "Renamed link keeps its target");
+        }
+        if expect_ok(fs::remove_file(renamed), "Remove the link").is_some() {
+            expect_true(Path::new(target).is_file(), "Target outlives the link");
+        }
+    }
+}
+
+pub(crate) fn test_symlink_metadata(ctx: &TestCtx) {
+    let target = &ctx.path("target.txt");
+    let link = &ctx.path("link.txt");
+    if expect_ok(symlink(target, link), "Create symlink").is_none() {
+        return;
+    }
+
+    step!("Comparing the link's metadata with its target's");
+    let followed = expect_ok(fs::metadata(link), "Metadata through the link");
+    if let Some(followed) = &followed {
+        expect_true(followed.is_file(), "Followed: a file");
+        expect_eq(followed.len(), TARGET_CONTENT.len() as u64, "Followed: the target's size");
+    }
+    if let Some(own) = expect_ok(fs::symlink_metadata(link), "Metadata of the link itself") {
+        expect_true(own.file_type().is_symlink(), "Own: a symlink");
+        expect_true(!own.is_file(), "Own: not a file");
+        expect_eq(own.len(), target.len() as u64, "Own: the length of the path it holds");
+    }
+    if let (Some(followed), Some(direct)) = (followed, expect_ok(fs::metadata(target), "Metadata of the target")) {
+        expect_eq(followed.modified().ok(), direct.modified().ok(), "Same modification time as the target");
+    }
+
+    step!("Listing the directory");
+    if let Some(entries) = expect_ok(fs::read_dir(ctx.dir()), "List the directory") {
+        match entries
+            .filter_map(Result::ok)
+            .find(|entry| entry.file_name() == "link.txt")
+            .map(|entry| entry.file_type())
+        {
+            Some(Ok(file_type)) => {
+                expect_true(file_type.is_symlink(), "Listed as a symlink");
+            }
+            Some(Err(e)) => fail!(e => "Type of the listed link: {}", e),
+            None => fail!("link.txt isn't listed"),
+        }
+    }
+
+    step!("Writing through the link");
+    if expect_ok(fs::write(link, "rewritten"), "Write through the link").is_some() {
+        if let Some(content) = expect_ok(fs::read(target), "Read the target") {
+            expect_eq_bytes(&content, b"rewritten", "Target changed");
+        }
+        expect_true(fs::symlink_metadata(link).is_ok_and(|own| own.file_type().is_symlink()), "Link still a symlink");
+    }
+}
+
+pub(crate) fn test_dangling_symlinks(ctx: &TestCtx) {
+    let link = &ctx.path("dangling.txt");
+    let missing = &ctx.path("missing.txt");
+
+    step!("Linking to a file that doesn't exist");
+    if expect_ok(symlink(missing, link), "Create dangling symlink").is_none() {
+        return;
+    }
+    if let Some(read) = expect_ok(fs::read_link(link), "Read link") {
+        expect_eq(read.as_path(), Path::new(missing), "Link target");
+    }
+    expect_true(fs::symlink_metadata(link).is_ok_and(|own| own.file_type().is_symlink()), "Link itself exists");
+    expect_err_kind(fs::metadata(link), io::ErrorKind::NotFound, "Metadata through the link");
+    expect_true(!Path::new(link).exists(), "Doesn't exist when followed");
+    expect_err_kind(fs::read(link), io::ErrorKind::NotFound, "Read through the link");
+
+    step!("Creating a link where one exists");
+    expect_err_kind(symlink(missing, link), io::ErrorKind::AlreadyExists, "Link over an existing link");
+
+    step!("Writing through the link");
+    if expect_ok(fs::write(link, "created"), "Write through dangling link").is_some() {
+        if let Some(content) = expect_ok(fs::read(missing), "Read the new target") {
+            expect_eq_bytes(&content, b"created", "Target created where the link points");
+        }
+        expect_true(fs::symlink_metadata(link).is_ok_and(|own| own.file_type().is_symlink()), "Link still a symlink");
+    }
+
+    step!("Removing the link");
+    if expect_ok(fs::remove_file(link), "Remove the link").is_some() {
+        expect_err_kind(fs::symlink_metadata(link), io::ErrorKind::NotFound, "Link gone");
+    }
+}
+
+pub(crate) fn test_symlink_loops(ctx: &TestCtx) {
+    let (first, second, own) = (&ctx.path("loop_a"), &ctx.path("loop_b"), &ctx.path("loop_self"));
+
+    step!("Creating links that lead back to themselves");
+    if expect_ok(symlink(second, first), "Link a to b").is_none()
+        || expect_ok(symlink(first, second), "Link b to a").is_none()
+        || expect_ok(symlink(own, own), "Link to itself").is_none()
+    {
+        return;
+    }
+
+    for (link, target) in [(first, second), (own, own)] {
+        step!("Resolving {}", link);
+        if let Some(read) = expect_ok(fs::read_link(link), "Read link") {
+            expect_eq(read.as_path(), Path::new(target), "readlink doesn't follow");
+        }
+        expect_true(fs::symlink_metadata(link).is_ok(), "Metadata of the link itself");
+        expect_loop(fs::metadata(link), "Metadata through the link");
+        expect_loop(fs::read(link), "Read through the link");
+        expect_loop(fs::write(link, "loop"), "Write through the link");
+    }
+
+    step!("Removing the links");
+    for link in [first, second, own] {
+        expect_ok(fs::remove_file(link), &format!("Remove {}", link));
+    }
+}
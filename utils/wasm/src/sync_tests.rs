@@ -0,0 +1,117 @@
+//! Tests of `fd_sync` and `fd_datasync`, through `File::sync_all` and
+//! `File::sync_data`.
+//!
+//! ZenFS's OPFS and IndexedDB backends write asynchronously, so a sync is
+//! where the kernel has to wait for them. These write, sync and read back
+//! through a new descriptor, which is as much of durability as a program
+//! can see, and report how long each sync took, since one that returns at
+//! once on a backend that writes asynchronously may not have waited.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::time::{Duration, Instant};
+
+use crate::harness::assert::{expect_eq, expect_eq_bytes, expect_ok};
+use crate::harness::context::TestCtx;
+
+/// Size of the file synced whole.
+const FILE_SIZE: usize = 64 * 1024;
+
+/// Small writes each followed by a sync.
+const SMALL_WRITES: usize = 50;
+
+/// Bytes that differ from one offset to the next, so that a block read back
+/// from the wrong place doesn't match.
+fn pattern(len: usize, seed: u8) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8 ^ seed).collect()
+}
+
+/// Runs `sync`, reporting how long it took.
+fn timed_sync(what: &str, sync: impl FnOnce() -> io::Result<()>) -> Option<()> {
+    let start = Instant::now();
+    let result = sync();
+    let elapsed = start.elapsed();
+    expect_ok(result, what)?;
+    detail!("{} took {:.3} ms", what, elapsed.as_secs_f64() * 1000.0);
+    Some(())
+}
+
+/// Checks that the file at `path`, read through a new descriptor, holds
+/// `expected`.
+fn expect_content(path: &str, expected: &[u8], what: &str) {
+    if let Some(content) = expect_ok(fs::read(path), "Read back through a new descriptor") {
+        expect_eq_bytes(&content, expected, what);
+    }
+}
+
+pub(crate) fn test_fd_sync(ctx: &TestCtx) {
+    let path = &ctx.path("synced.bin");
+    let mut expected = pattern(FILE_SIZE, 0);
+
+    step!("Writing a new file and syncing it whole");
+    let Some(mut file) = expect_ok(File::create(path), "Create") else {
+        return;
+    };
+    if expect_ok(file.write_all(&expected), "Write").is_none() || timed_sync("sync_all", || file.sync_all()).is_none() {
+        return;
+    }
+    drop(file);
+    expect_content(path, &expected, "Content after sync_all");
+
+    step!("Overwriting a block and syncing the data");
+    let Some(mut file) = expect_ok(OpenOptions::new().write(true).open(path), "Open to write") else {
+        return;
+    };
+    let (offset, block) = (FILE_SIZE / 2 - 100, pattern(4096, 0x5a));
+    let written = file.seek(SeekFrom::Start(offset as u64)).and_then(|_| file.write_all(&block));
+    if expect_ok(written, "Overwrite a block across the middle").is_some()
+        && timed_sync("sync_data", || file.sync_data()).is_some()
+    {
+        expected[offset..offset + block.len()].copy_from_slice(&block);
+        expect_content(path, &expected, "Content after sync_data");
+    }
+
+    step!("Changing the size and syncing");
+    if expect_ok(file.set_len(FILE_SIZE as u64 * 2), "Extend").is_some()
+        && timed_sync("sync_all after extending", || file.sync_all()).is_some()
+    {
+        expected.resize(FILE_SIZE * 2, 0);
+        expect_content(path, &expected, "Content after extending");
+    }
+    // fdatasync still has to sync a change of size, which reads need
+    if expect_ok(file.set_len(100), "Truncate").is_some()
+        && timed_sync("sync_data after truncating", || file.sync_data()).is_some()
+    {
+        if let Some(metadata) = expect_ok(fs::metadata(path), "Metadata") {
+            expect_eq(metadata.len(), 100, "Size after truncating");
+        }
+        expect_content(path, &expected[..100], "Content after truncating");
+    }
+    drop(file);
+
+    step!("Syncing after each of {} small writes", SMALL_WRITES);
+    let log = &ctx.path("log.txt");
+    let Some(mut file) = expect_ok(OpenOptions::new().create(true).append(true).open(log), "Open to append") else {
+        return;
+    };
+    let mut lines = String::new();
+    let mut total = Duration::ZERO;
+    let mut slowest = Duration::ZERO;
+    for index in 0..SMALL_WRITES {
+        let line = format!("line {}\n", index);
+        let start = Instant::now();
+        let result = file.write_all(line.as_bytes()).and_then(|_| file.sync_data());
+        let elapsed = start.elapsed();
+        if let Err(e) = result {
+            fail!(e => "Write and sync line {}: {}", index, e);
+            return;
+        }
+        lines.push_str(&line);
+        total += elapsed;
+        slowest = slowest.max(elapsed);
+    }
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    detail!("{:.3} ms a write and sync on average, {:.3} ms at most", ms(total) / SMALL_WRITES as f64, ms(slowest));
+    drop(file);
+    expect_content(log, lines.as_bytes(), "Every line there after syncing");
+}
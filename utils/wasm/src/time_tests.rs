@@ -0,0 +1,24 @@
+//! Tests of the clock.
+
+use std::time::SystemTime;
+
+use crate::harness::assert::expect_true;
+use crate::harness::context::TestCtx;
+
+pub(crate) fn test_time_operations(_ctx: &TestCtx) {
+    use std::time::UNIX_EPOCH;
+    
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => {
+            pass!("Current timestamp: {} seconds", duration.as_secs());
+            detail!("Nanoseconds: {}", duration.subsec_nanos());
+        }
+        Err(e) => {
+            fail!("Failed to get time: {}", e);
+        }
+    }
+    
+    let now = SystemTime::now();
+    step!("SystemTime::now(): {:?}", now);
+    expect_true(SystemTime::now() >= now, "Clock doesn't go backwards");
+}